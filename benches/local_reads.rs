@@ -0,0 +1,120 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+//! Opening a large local file and querying its index, read through a buffer and mapped
+//! into memory with `--mmap`. Run with `cargo bench --bench local_reads`
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, atomic::AtomicUsize},
+    time::{Duration, Instant},
+};
+
+use fgbdump::{
+    dataset::{Dataset, OpenOptions, select_bbox},
+    projection::Bbox,
+};
+use flatgeobuf::{
+    FgbCrs, FgbWriter, FgbWriterOptions, GeometryType,
+    geozero::{ColumnValue, GeomProcessor, GeozeroGeometry, PropertyProcessor},
+};
+
+/// How many points across and down the grid of the fixture is
+const GRID: usize = 1000;
+
+struct Point(f64, f64);
+
+impl GeozeroGeometry for Point {
+    fn process_geom<P: GeomProcessor>(&self, processor: &mut P) -> geozero::error::Result<()> {
+        processor.point_begin(0)?;
+        processor.xy(self.0, self.1, 0)?;
+        processor.point_end(0)
+    }
+}
+
+/// A million points a hundredth of a degree apart, written once and kept between runs
+fn fixture() -> PathBuf {
+    let path = std::env::temp_dir().join(format!("fgbdump-bench-{GRID}x{GRID}.fgb"));
+    if path.exists() {
+        return path;
+    }
+    let options = FgbWriterOptions {
+        crs: FgbCrs {
+            code: 4326,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut fgb = FgbWriter::create_with_options("points", GeometryType::Point, options).unwrap();
+    fgb.add_column("id", flatgeobuf::ColumnType::ULong, |_, _| {});
+    for i in 0..GRID * GRID {
+        let point = Point((i % GRID) as f64 / 100.0, (i / GRID) as f64 / 100.0);
+        fgb.add_feature_geom(point, |feature| {
+            feature
+                .property(0, "id", &ColumnValue::ULong(i as u64))
+                .unwrap();
+        })
+        .unwrap();
+    }
+    let partial = path.with_extension("partial");
+    fgb.write(std::io::BufWriter::new(
+        std::fs::File::create(&partial).unwrap(),
+    ))
+    .unwrap();
+    std::fs::rename(partial, &path).unwrap();
+    path
+}
+
+/// The median time of `runs` runs of `f`
+fn median(runs: usize, mut f: impl FnMut()) -> Duration {
+    let mut times: Vec<Duration> = (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    times[runs / 2]
+}
+
+fn bench(runtime: &tokio::runtime::Runtime, path: &Path, mmap: bool) {
+    let source = path.to_str().unwrap();
+    let options = OpenOptions {
+        mmap,
+        ..Default::default()
+    };
+    let open = median(50, || {
+        runtime
+            .block_on(Dataset::open(source, Arc::default(), options.clone()))
+            .unwrap();
+    });
+    // a tenth of the grid across and down, so about ten thousand points
+    let bbox = Bbox::new(4.0, 4.0, 5.0, 5.0);
+    let mut found = 0;
+    let query = median(20, || {
+        let progress = AtomicUsize::new(0);
+        let features = runtime
+            .block_on(select_bbox(
+                source,
+                &bbox,
+                &progress,
+                Arc::default(),
+                options.clone(),
+            ))
+            .unwrap();
+        found = features.len();
+    });
+    let label = if mmap { "mapped" } else { "buffered" };
+    println!(
+        "{label:>8}: open header {open:>10.2?}, bbox query of {found} features {query:>10.2?}"
+    );
+}
+
+fn main() {
+    let path = fixture();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    for mmap in [false, true] {
+        bench(&runtime, &path, mmap);
+    }
+}
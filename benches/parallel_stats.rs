@@ -0,0 +1,122 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+//! Gathering the statistics of a large local file in one pass and on several threads. Run
+//! with `cargo bench --bench parallel_stats`
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, atomic::AtomicUsize},
+    time::{Duration, Instant},
+};
+
+use fgbdump::dataset::{Dataset, OpenOptions, scan_stats_parallel};
+use flatgeobuf::{
+    FgbCrs, FgbWriter, FgbWriterOptions, GeometryType,
+    geozero::{ColumnValue, GeomProcessor, GeozeroGeometry, PropertyProcessor},
+};
+
+/// How many points across and down the grid of the fixture is
+const GRID: usize = 1000;
+
+/// How many distinct values the category column has, few enough to count them all
+const CATEGORIES: usize = 50;
+
+struct Point(f64, f64);
+
+impl GeozeroGeometry for Point {
+    fn process_geom<P: GeomProcessor>(&self, processor: &mut P) -> geozero::error::Result<()> {
+        processor.point_begin(0)?;
+        processor.xy(self.0, self.1, 0)?;
+        processor.point_end(0)
+    }
+}
+
+/// A million points with a numeric and a string column, written once and kept between runs
+fn fixture() -> PathBuf {
+    let path = std::env::temp_dir().join(format!("fgbdump-bench-stats-{GRID}x{GRID}.fgb"));
+    if path.exists() {
+        return path;
+    }
+    let options = FgbWriterOptions {
+        crs: FgbCrs {
+            code: 4326,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut fgb = FgbWriter::create_with_options("points", GeometryType::Point, options).unwrap();
+    fgb.add_column("id", flatgeobuf::ColumnType::ULong, |_, _| {});
+    fgb.add_column("category", flatgeobuf::ColumnType::String, |_, _| {});
+    for i in 0..GRID * GRID {
+        let point = Point((i % GRID) as f64 / 100.0, (i / GRID) as f64 / 100.0);
+        let category = format!("category {}", i % CATEGORIES);
+        fgb.add_feature_geom(point, |feature| {
+            feature
+                .property(0, "id", &ColumnValue::ULong(i as u64))
+                .unwrap();
+            feature
+                .property(1, "category", &ColumnValue::String(&category))
+                .unwrap();
+        })
+        .unwrap();
+    }
+    let partial = path.with_extension("partial");
+    fgb.write(std::io::BufWriter::new(
+        std::fs::File::create(&partial).unwrap(),
+    ))
+    .unwrap();
+    std::fs::rename(partial, &path).unwrap();
+    path
+}
+
+/// The median time of `runs` runs of `f`
+fn median(runs: usize, mut f: impl FnMut()) -> Duration {
+    let mut times: Vec<Duration> = (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    times[runs / 2]
+}
+
+fn bench(runtime: &tokio::runtime::Runtime, path: &Path, threads: usize) {
+    let source = path.to_str().unwrap();
+    let options = OpenOptions::default();
+    let dataset = runtime
+        .block_on(Dataset::open(source, Arc::default(), options.clone()))
+        .unwrap();
+    let scan = median(5, || {
+        let progress = Arc::new(AtomicUsize::new(0));
+        runtime
+            .block_on(scan_stats_parallel(
+                source,
+                &dataset,
+                None,
+                threads,
+                &progress,
+                Arc::default(),
+                options.clone(),
+            ))
+            .unwrap();
+    });
+    println!(
+        "{threads:>2} threads: stats of {} features {scan:>10.2?}",
+        GRID * GRID
+    );
+}
+
+fn main() {
+    let path = fixture();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let cpus = std::thread::available_parallelism().map_or(1, std::num::NonZero::get);
+    let mut threads = vec![1, 2, 4, cpus];
+    threads.sort();
+    threads.dedup();
+    for threads in threads {
+        bench(&runtime, &path, threads);
+    }
+}
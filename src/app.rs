@@ -0,0 +1,3158 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use bytesize::ByteSize;
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Margin, Rect},
+    text::{Line, Span},
+    widgets::{
+        Bar, BarChart, Block, Borders, Cell, Clear, Paragraph, Row, ScrollbarState, Sparkline,
+        Table, TableState, Wrap, canvas::MapResolution,
+    },
+};
+use std::{
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    task::{Context, Poll, Waker},
+    time::Instant,
+};
+
+use crate::{
+    ColumnSort, ColumnSortField, ColumnsTableData, ColumnsTableState, FOOTER_HEIGHT, MapOptions,
+    MapOverlays, MapView, Scroll, SearchState, SelectedTab, TABS_HEIGHT, Theme,
+    clipboard::Clipboard,
+    column_detail_lines, coordinate_decimals,
+    dataset::{self, ColumnNameProblems, Dataset, OpenOptions},
+    feature::decimate,
+    highlight_line_match, highlight_match,
+    http::{FILE_CHANGED, TransferStats},
+    info_line, info_lines, is_remote_file,
+    json_tree::JsonTree,
+    keymap::{Action, Keymap},
+    line_text, make_map_with_bbox_overlay, make_scrollbar, make_tabs, map_legend, pretty_envelope,
+    pretty_geometry_type,
+    projection::{
+        Bbox, CrsInfo, RATATUI_MAP_CRS, describe_crs, is_map_crs, project_parts_to_ratatui_map_crs,
+    },
+    query::{BBOX_FIELDS, BboxForm, BboxQuery, FeatureLookup, QueryResults},
+    stats::{GeometryStats, GeometryTypes, Histogram, Range, Stats},
+    status_line, visible_column_range, wkt, wrap_text,
+};
+use tokio::task::JoinHandle;
+use unicode_width::UnicodeWidthStr;
+
+/// How much `+` and `-` zoom the map by, and how much of it panning moves across
+const MAP_ZOOM_STEP: f64 = 2.0;
+const MAP_PAN_FRACTION: f64 = 0.25;
+
+/// How many features are read to tally their geometry types, which is enough to show
+/// what a file holds without reading all of a huge one
+const GEOMETRY_TYPE_SAMPLE: usize = 100_000;
+
+/// How many ranges the histograms on the Stats tab split a column's values into
+const HISTOGRAM_BINS: usize = 20;
+
+const DISJOINT_QUERY: &str = "query bbox does not intersect dataset extent";
+
+/// A dataset being opened in the background
+struct Loading {
+    started: Instant,
+    task: JoinHandle<Result<Dataset, String>>,
+}
+
+/// All the state of the TUI, which can switch between several files
+/// Settings from the command line which apply to every file
+#[derive(Clone, Debug)]
+pub struct ViewOptions {
+    /// How many features `p` samples for the map
+    pub sample_size: usize,
+    /// Whether to check the envelope against the area of use of the CRS
+    pub crs_checks: bool,
+    /// How many files to open at once in the background
+    pub concurrency: usize,
+    pub open: OpenOptions,
+}
+
+pub struct App {
+    views: Vec<FileView>,
+    /// Index into `views` of the file being shown
+    current: usize,
+    /// How many files may be opening at once, and the index of the next to open ahead
+    /// of being switched to
+    concurrency: usize,
+    next_prefetch: usize,
+    clipboard: Clipboard,
+    theme: Theme,
+    keymap: Keymap,
+    /// Set once the user has asked to exit
+    pub quit: bool,
+    /// Set if that was with Ctrl-C, which exits with the status of an interrupt
+    pub interrupted: bool,
+}
+
+impl App {
+    /// Start opening `sources` in the background, the first straight away and the rest
+    /// a few at a time, or when switched to. Must be called from within a tokio runtime
+    pub fn new(sources: &[String], theme: Theme, keymap: Keymap, options: ViewOptions) -> Self {
+        let concurrency = options.concurrency;
+        let mut views: Vec<FileView> = sources
+            .iter()
+            .map(|s| FileView::new(s, options.clone()))
+            .collect();
+        if let Some(view) = views.first_mut() {
+            view.start_loading();
+        }
+        let mut app = Self {
+            views,
+            current: 0,
+            concurrency,
+            next_prefetch: 1,
+            clipboard: Clipboard::new(),
+            theme,
+            keymap,
+            quit: false,
+            interrupted: false,
+        };
+        app.prefetch();
+        app
+    }
+
+    /// Start opening the files after the first in order, while fewer than `concurrency`
+    /// are opening. Each is only tried once, so one which failed isn't retried until
+    /// switched to
+    fn prefetch(&mut self) {
+        while self.next_prefetch < self.views.len()
+            && self
+                .views
+                .iter()
+                .filter(|view| view.loading.is_some())
+                .count()
+                < self.concurrency
+        {
+            let view = &mut self.views[self.next_prefetch];
+            if view.dataset.is_none() && view.loading.is_none() {
+                view.start_loading();
+            }
+            self.next_prefetch += 1;
+        }
+    }
+
+    /// Whether the file being shown is still being opened or queried, in which
+    /// case the screen should be redrawn regularly to animate the progress
+    pub fn is_loading(&self) -> bool {
+        let view = &self.views[self.current];
+        view.loading.is_some()
+            || matches!(view.query, Some(BboxQuery::Running { .. }))
+            || matches!(view.feature_lookup, Some(FeatureLookup::Running { .. }))
+            || matches!(view.map_sample, Some(MapSample::Running { .. }))
+            || matches!(view.feature_count, Some(FeatureCount::Running { .. }))
+            || matches!(view.geometry_types, Some(GeometryTally::Running { .. }))
+            || matches!(view.stats, Some(StatsScan::Running { .. }))
+            || matches!(view.histogram, Some(HistogramView::Running { .. }))
+    }
+
+    /// Take the results of any background opens which have finished
+    pub fn poll_loading(&mut self) {
+        for view in &mut self.views {
+            view.poll_loading();
+        }
+        self.prefetch();
+    }
+
+    pub fn draw(&mut self, f: &mut Frame) {
+        let count = self.views.len();
+        let view = &mut self.views[self.current];
+        let source_label = if count > 1 {
+            format!("file {}/{}: {}", self.current + 1, count, view.source)
+        } else {
+            view.source.clone()
+        };
+        view.draw(f, &source_label, &self.theme, &self.keymap);
+    }
+
+    pub fn handle_event(&mut self, event: Event) {
+        // raw mode turns Ctrl-C into a key press rather than a signal
+        if let Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event
+            && modifiers.contains(KeyModifiers::CONTROL)
+        {
+            self.interrupted = true;
+        }
+        let view = &mut self.views[self.current];
+        match event {
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) if !view.is_modal()
+                && let Some(action @ (Action::NextFile | Action::PreviousFile)) =
+                    self.keymap.action(code, modifiers, view.selected_tab) =>
+            {
+                self.switch_file(action == Action::NextFile)
+            }
+            event => view.handle_event(event, &mut self.clipboard, &self.keymap),
+        }
+
+        if self.views[self.current].quit {
+            self.quit = true;
+            for view in &mut self.views {
+                if let Some(loading) = view.loading.take() {
+                    loading.task.abort();
+                }
+                if let Some(BboxQuery::Running { task, .. }) = view.query.take() {
+                    task.abort();
+                }
+                if let Some(FeatureLookup::Running { task, .. }) = view.feature_lookup.take() {
+                    task.abort();
+                }
+                if let Some(MapSample::Running { task, .. }) = view.map_sample.take() {
+                    task.abort();
+                }
+                if let Some(FeatureCount::Running { task, .. }) = view.feature_count.take() {
+                    task.abort();
+                }
+                if let Some(GeometryTally::Running { task, .. }) = view.geometry_types.take() {
+                    task.abort();
+                }
+                if let Some(StatsScan::Running { task, .. }) = view.stats.take() {
+                    task.abort();
+                }
+                if let Some(HistogramView::Running { task, .. }) = view.histogram.take() {
+                    task.abort();
+                }
+            }
+        }
+    }
+
+    /// Show the next or previous file, keeping the same tab selected
+    fn switch_file(&mut self, forward: bool) {
+        let len = self.views.len();
+        let selected_tab = self.views[self.current].selected_tab;
+        let raw_values = self.views[self.current].raw_values;
+        self.current = if forward {
+            (self.current + 1) % len
+        } else {
+            (self.current + len - 1) % len
+        };
+
+        let view = &mut self.views[self.current];
+        // files which haven't been loaded yet have every tab until they are
+        if view.tabs.contains(&selected_tab) {
+            view.selected_tab = selected_tab;
+        }
+        if view.raw_values != raw_values {
+            view.raw_values = raw_values;
+            view.metadata_lines = None;
+        }
+        view.status_message = None;
+        if view.dataset.is_none() && view.loading.is_none() {
+            view.start_loading();
+        }
+    }
+}
+
+/// The lines of the Metadata tab, which are relatively expensive to build
+struct MetadataLines {
+    /// The width the values were wrapped to
+    width: usize,
+    lines: Vec<Line<'static>>,
+    /// The plain text of each line, for searching
+    texts: Vec<String>,
+    /// The JSON tree node on each line, for expanding with Enter
+    paths: Vec<Option<String>>,
+    /// The row each line starts on once wrapped to `width`, and the number of rows
+    /// after the last line
+    row_starts: Vec<usize>,
+}
+
+/// A feature drawn over the bounding box on the Map tab
+struct MapFeature {
+    /// Which feature this is, for the map title
+    label: String,
+    /// The parts of its geometry, projected to EPSG:4326 and decimated
+    parts: Vec<Vec<(f64, f64)>>,
+}
+
+/// Representative points of a sample of the features, for the Map tab
+enum MapSample {
+    Running {
+        /// The number of features sampled so far
+        progress: Arc<AtomicUsize>,
+        task: JoinHandle<Result<Vec<(f64, f64)>, String>>,
+    },
+    /// Projected to EPSG:4326
+    Ready(Vec<(f64, f64)>),
+}
+
+/// The number of features found by reading them all, for files whose header doesn't say
+enum FeatureCount {
+    Running {
+        /// The number of features read so far
+        progress: Arc<AtomicUsize>,
+        task: JoinHandle<Result<usize, String>>,
+    },
+    Done(usize),
+}
+
+/// The geometry types of the features, tallied from the first of them
+enum GeometryTally {
+    Running {
+        /// The number of features read so far
+        progress: Arc<AtomicUsize>,
+        task: JoinHandle<Result<GeometryTypes, String>>,
+    },
+    Done(GeometryTypes),
+}
+
+/// Statistics from reading every feature, for the Stats tab
+enum StatsScan {
+    Running {
+        /// The number of features read so far
+        progress: Arc<AtomicUsize>,
+        task: JoinHandle<Result<Stats, String>>,
+    },
+    Done(Box<Stats>),
+}
+
+/// How the values of a numeric column are distributed, charted over the Stats tab
+enum HistogramView {
+    Running {
+        column: String,
+        /// The number of features read so far
+        progress: Arc<AtomicUsize>,
+        task: JoinHandle<Result<Histogram, String>>,
+    },
+    Done(Histogram),
+}
+
+/// The state of the TUI for one of the files being shown
+struct FileView {
+    source: String,
+    /// The opened file, once loading has finished successfully
+    dataset: Option<Dataset>,
+    loading: Option<Loading>,
+    quit: bool,
+
+    selected_tab: SelectedTab,
+    /// The tabs this file has what it takes to show
+    tabs: Vec<SelectedTab>,
+
+    // Scroll state for Metadata tab
+    metadata_scroll: usize,
+    metadata_scroll_state: ScrollbarState,
+
+    /// The envelope of the dataset in its own CRS
+    bbox: Result<Bbox, String>,
+    /// The CRS of the dataset as `org:code`, for projecting to the map
+    source_crs: Result<String, String>,
+    /// What PROJ knows about `source_crs`
+    crs_info: Result<CrsInfo, String>,
+    /// Signs that the CRS is mislabeled, unless `--no-crs-checks` was passed
+    crs_warnings: Vec<String>,
+    /// The envelope projected for the map along with the map title
+    map_bbox: Result<(Bbox, String), String>,
+
+    // rows of the Columns table are indices into the header columns
+    column_sort: ColumnSort,
+    column_order: Vec<usize>,
+    // names in display order, for searching
+    column_names: Vec<String>,
+    column_search: SearchState,
+    columns_table_state: ColumnsTableState,
+    columns_table_data: ColumnsTableData,
+    /// Duplicate and empty column names, warned about above the Columns table
+    column_problems: ColumnNameProblems,
+    columns_scroll_state: ScrollbarState,
+
+    // index into the header columns of the column shown in the detail popup, if open
+    column_detail: Option<usize>,
+    // whether the untruncated table values of the focused row are shown in a popup
+    show_row_values: bool,
+    // whether the HTTP requests made for the file are shown in a popup, in any tab
+    show_requests: bool,
+    popup_scroll: usize,
+
+    // where each sortable header cell of the Columns table was last drawn, for mouse clicks
+    column_header_cells: Vec<(Rect, ColumnSortField)>,
+    // index of the leftmost table column shown when the table is wider than the terminal
+    columns_hscroll: usize,
+    // whether to show the lesser used column fields as well
+    extended_columns: bool,
+
+    // the text `y` copies from the Metadata tab as of the last draw
+    metadata_yank_text: String,
+    metadata_search: SearchState,
+    // whether the CRS WKT is shown pretty-printed rather than folded away
+    wkt_expanded: bool,
+    /// Whether the Metadata tab shows the envelope, geometry type and custom metadata
+    /// as the header has them, as in bug reports, rather than formatted for reading.
+    /// Kept when switching files, like the selected tab
+    raw_values: bool,
+    /// The custom metadata, if it is JSON
+    metadata_tree: Option<JsonTree>,
+    /// Built when first drawn and kept until the header, the width or what is expanded changes
+    metadata_lines: Option<MetadataLines>,
+    // shown until the next key press
+    status_message: Option<String>,
+    /// The `b` bbox query, while its form, progress or results are open
+    query: Option<BboxQuery>,
+    /// The `:` prompt for a feature index, while it or the feature is open
+    feature_lookup: Option<FeatureLookup>,
+    /// A feature from the query results to draw on the map
+    map_feature: Option<MapFeature>,
+    /// Sampled once `p` is first pressed, and kept for the rest of the session
+    map_sample: Option<MapSample>,
+    /// Whether the sampled points are drawn on the map
+    show_sample: bool,
+    /// Counted with `c` when the header doesn't say how many features there are
+    feature_count: Option<FeatureCount>,
+    /// Tallied with `t` on the Metadata tab, and kept until the file is reloaded
+    geometry_types: Option<GeometryTally>,
+    /// Gathered when asked for on the Stats tab, since it reads the whole file
+    stats: Option<StatsScan>,
+    /// Rows of the Stats table are the header columns, in header order
+    stats_table_state: ColumnsTableState,
+    /// Shown with `h` on a numeric column of the Stats table, until closed
+    histogram: Option<HistogramView>,
+    /// Whether the Stats tab charts the sizes of the features by power of two rather
+    /// than in file order, switched with Enter once the stats are gathered
+    size_histogram: bool,
+    /// Whether the map reads the envelope with x and y swapped, to compare
+    /// against the file when its axes look swapped
+    swap_axes: bool,
+    options: ViewOptions,
+    /// Why the extent on the map isn't quite what the file says, if it isn't
+    map_warning: Option<String>,
+    /// The part of the world the map shows
+    map_view: MapView,
+    /// Whether lines of latitude and longitude are drawn on the map
+    show_graticule: bool,
+    /// The resolution of the world map once chosen with `m`, rather than
+    /// picked by how many degrees each terminal cell covers
+    map_resolution: Option<MapResolution>,
+    /// The resolution the map was last drawn with, which `m` switches away from
+    drawn_map_resolution: MapResolution,
+    /// Where the map canvas was last drawn, for mapping mouse positions onto it
+    map_area: Rect,
+    /// The longitude and latitude where a mouse drag on the map started and is now
+    map_drag: Option<((f64, f64), (f64, f64))>,
+    /// The requests made for the file if it is remote, counted across reloads and queries
+    transfer: Arc<TransferStats>,
+    /// A recoverable error shown in a modal until dismissed with Esc
+    error: Option<String>,
+
+    // Number of rows visible in the focused tab as of the last draw; used for paging
+    page_size: usize,
+}
+
+impl FileView {
+    fn new(source: &str, options: ViewOptions) -> Self {
+        Self {
+            source: source.to_string(),
+            dataset: None,
+            loading: None,
+            quit: false,
+            selected_tab: SelectedTab::Metadata,
+            tabs: SelectedTab::ALL.to_vec(),
+            metadata_scroll: 0,
+            metadata_scroll_state: ScrollbarState::default(),
+            bbox: Err("The file has not been loaded yet".to_string()),
+            source_crs: Err("The file has not been loaded yet".to_string()),
+            crs_info: Err("The file has not been loaded yet".to_string()),
+            crs_warnings: Vec::new(),
+            map_bbox: Err("The file has not been loaded yet".to_string()),
+            column_sort: ColumnSort::default(),
+            column_order: Vec::new(),
+            column_names: Vec::new(),
+            column_search: SearchState::default(),
+            columns_table_state: ColumnsTableState::new(),
+            columns_table_data: ColumnsTableData::default(),
+            column_problems: ColumnNameProblems::default(),
+            columns_scroll_state: ScrollbarState::default(),
+            column_detail: None,
+            show_row_values: false,
+            show_requests: false,
+            popup_scroll: 0,
+            column_header_cells: Vec::new(),
+            columns_hscroll: 0,
+            extended_columns: false,
+            metadata_yank_text: String::new(),
+            metadata_search: SearchState::default(),
+            wkt_expanded: false,
+            raw_values: false,
+            metadata_tree: None,
+            metadata_lines: None,
+            status_message: None,
+            query: None,
+            feature_lookup: None,
+            map_feature: None,
+            map_sample: None,
+            show_sample: false,
+            feature_count: None,
+            stats: None,
+            stats_table_state: ColumnsTableState::new(),
+            histogram: None,
+            size_histogram: false,
+            geometry_types: None,
+            swap_axes: false,
+            options,
+            map_warning: None,
+            map_view: MapView::WORLD,
+            show_graticule: false,
+            map_resolution: None,
+            drawn_map_resolution: MapResolution::High,
+            map_area: Rect::default(),
+            map_drag: None,
+            transfer: Arc::default(),
+            error: None,
+            page_size: 1,
+        }
+    }
+
+    fn start_loading(&mut self) {
+        let source = self.source.clone();
+        let transfer = self.transfer.clone();
+        let options = self.options.open.clone();
+        self.loading = Some(Loading {
+            started: Instant::now(),
+            task: tokio::spawn(async move { Dataset::open(&source, transfer, options).await }),
+        });
+    }
+
+    /// Take the result of the background open, query or sampling if it has finished
+    fn poll_loading(&mut self) {
+        self.poll_query();
+        self.poll_feature_lookup();
+        self.poll_sample();
+        self.poll_count();
+        self.poll_geometry_types();
+        self.poll_stats();
+        self.poll_histogram();
+        let Some(loading) = &mut self.loading else {
+            return;
+        };
+        let mut cx = Context::from_waker(Waker::noop());
+        let Poll::Ready(result) = Pin::new(&mut loading.task).poll(&mut cx) else {
+            return;
+        };
+        self.loading = None;
+        let error = match result {
+            Ok(Ok(dataset)) => return self.set_dataset(dataset),
+            Ok(Err(e)) => e,
+            Err(e) => format!("Failed to open {}: {e}", self.source),
+        };
+        // a failed reload keeps showing the header which was loaded before
+        if self.dataset.is_some() {
+            self.status_message = Some(format!("reload failed: {error}"));
+        } else {
+            self.error = Some(error);
+        }
+    }
+
+    fn poll_query(&mut self) {
+        let Some(BboxQuery::Running { bbox, task, .. }) = &mut self.query else {
+            return;
+        };
+        let mut cx = Context::from_waker(Waker::noop());
+        let Poll::Ready(result) = Pin::new(task).poll(&mut cx) else {
+            return;
+        };
+        let bbox = bbox.clone();
+        self.query = None;
+        match result {
+            Ok(Ok(features)) => {
+                self.query = Some(BboxQuery::Results(QueryResults::new(bbox, features)));
+            }
+            Ok(Err(e)) => self.error = Some(e),
+            Err(e) => self.error = Some(format!("Failed to query {}: {e}", self.source)),
+        }
+    }
+
+    fn poll_feature_lookup(&mut self) {
+        let Some(FeatureLookup::Running { index, task, .. }) = &mut self.feature_lookup else {
+            return;
+        };
+        let mut cx = Context::from_waker(Waker::noop());
+        let Poll::Ready(result) = Pin::new(task).poll(&mut cx) else {
+            return;
+        };
+        let index = *index;
+        self.feature_lookup = None;
+        match result {
+            Ok(Ok(feature)) => {
+                self.popup_scroll = 0;
+                self.feature_lookup = Some(FeatureLookup::Found { index, feature });
+            }
+            Ok(Err(e)) => self.error = Some(e),
+            Err(e) => self.error = Some(format!("Failed to read feature {index}: {e}")),
+        }
+    }
+
+    fn poll_sample(&mut self) {
+        let Some(MapSample::Running { task, .. }) = &mut self.map_sample else {
+            return;
+        };
+        let mut cx = Context::from_waker(Waker::noop());
+        let Poll::Ready(result) = Pin::new(task).poll(&mut cx) else {
+            return;
+        };
+        let points = match result {
+            Ok(result) => result.and_then(|points| {
+                let crs = self.source_crs.as_ref()?;
+                project_parts_to_ratatui_map_crs(&[points], crs).map(|parts| parts.concat())
+            }),
+            Err(e) => Err(format!("Failed to sample {}: {e}", self.source)),
+        };
+        match points {
+            Ok(points) => self.map_sample = Some(MapSample::Ready(points)),
+            Err(e) => {
+                self.map_sample = None;
+                self.show_sample = false;
+                self.status_message = Some(e);
+            }
+        }
+    }
+
+    fn poll_count(&mut self) {
+        let Some(FeatureCount::Running { task, .. }) = &mut self.feature_count else {
+            return;
+        };
+        let mut cx = Context::from_waker(Waker::noop());
+        let Poll::Ready(result) = Pin::new(task).poll(&mut cx) else {
+            return;
+        };
+        self.feature_count = None;
+        match result {
+            Ok(Ok(count)) => {
+                self.feature_count = Some(FeatureCount::Done(count));
+                self.status_message = Some(format!("counted {count} features"));
+                self.metadata_lines = None;
+            }
+            Ok(Err(e)) => self.error = Some(e),
+            Err(e) => {
+                self.error = Some(format!(
+                    "Failed to count the features of {}: {e}",
+                    self.source
+                ))
+            }
+        }
+    }
+
+    fn poll_geometry_types(&mut self) {
+        let Some(GeometryTally::Running { task, .. }) = &mut self.geometry_types else {
+            return;
+        };
+        let mut cx = Context::from_waker(Waker::noop());
+        let Poll::Ready(result) = Pin::new(task).poll(&mut cx) else {
+            return;
+        };
+        self.geometry_types = None;
+        self.metadata_lines = None;
+        match result {
+            Ok(Ok(types)) => self.geometry_types = Some(GeometryTally::Done(types)),
+            Ok(Err(e)) => self.error = Some(e),
+            Err(e) => self.error = Some(format!("Failed to read {}: {e}", self.source)),
+        }
+    }
+
+    /// Start tallying the geometry types of the first features in the background
+    fn start_geometry_tally(&mut self) {
+        let progress = Arc::new(AtomicUsize::new(0));
+        let task = tokio::spawn({
+            let source = self.source.clone();
+            let progress = progress.clone();
+            let transfer = self.transfer.clone();
+            let options = self.pinned_options();
+            async move {
+                dataset::scan_sample(
+                    &source,
+                    Some(GEOMETRY_TYPE_SAMPLE),
+                    &progress,
+                    transfer,
+                    options,
+                )
+                .await
+                .map(|sample| sample.types)
+            }
+        });
+        self.geometry_types = Some(GeometryTally::Running { progress, task });
+    }
+
+    fn poll_stats(&mut self) {
+        let Some(StatsScan::Running { task, .. }) = &mut self.stats else {
+            return;
+        };
+        let mut cx = Context::from_waker(Waker::noop());
+        let Poll::Ready(result) = Pin::new(task).poll(&mut cx) else {
+            return;
+        };
+        self.stats = None;
+        match result {
+            Ok(Ok(stats)) => self.stats = Some(StatsScan::Done(Box::new(stats))),
+            Ok(Err(e)) => self.error = Some(e),
+            Err(e) => self.error = Some(format!("Failed to read {}: {e}", self.source)),
+        }
+    }
+
+    fn poll_histogram(&mut self) {
+        let Some(HistogramView::Running { task, .. }) = &mut self.histogram else {
+            return;
+        };
+        let mut cx = Context::from_waker(Waker::noop());
+        let Poll::Ready(result) = Pin::new(task).poll(&mut cx) else {
+            return;
+        };
+        self.histogram = None;
+        match result {
+            Ok(Ok(histogram)) => self.histogram = Some(HistogramView::Done(histogram)),
+            Ok(Err(e)) => self.error = Some(e),
+            Err(e) => self.error = Some(format!("Failed to read {}: {e}", self.source)),
+        }
+    }
+
+    /// Start binning the values of the focused column of the Stats table in the
+    /// background, between the smallest and largest values the stats scan found
+    fn start_histogram(&mut self) {
+        let Some(StatsScan::Done(stats)) = &self.stats else {
+            return;
+        };
+        let selected = self.stats_table_state.state.selected().unwrap_or(0);
+        let Some(column) = stats.columns.get(selected) else {
+            return;
+        };
+        let range = match column.range {
+            Some(Range::Integer { min, max }) => (min as f64, max as f64),
+            Some(Range::Float { min, max }) => (min, max),
+            None if column.is_numeric() => {
+                self.status_message = Some(format!("{} has no values to chart", column.name));
+                return;
+            }
+            _ => {
+                self.status_message = Some("Histograms are only drawn of numeric columns".into());
+                return;
+            }
+        };
+        let name = column.name.clone();
+        let progress = Arc::new(AtomicUsize::new(0));
+        let task = tokio::spawn({
+            let source = self.source.clone();
+            let name = name.clone();
+            let progress = progress.clone();
+            let transfer = self.transfer.clone();
+            let options = self.pinned_options();
+            async move {
+                dataset::scan_histogram(
+                    &source,
+                    &name,
+                    HISTOGRAM_BINS,
+                    Some(range),
+                    &progress,
+                    transfer,
+                    options,
+                )
+                .await
+            }
+        });
+        self.histogram = Some(HistogramView::Running {
+            column: name,
+            progress,
+            task,
+        });
+    }
+
+    /// Close the histogram, stopping the scan for it if it's still running
+    fn close_histogram(&mut self) {
+        if let Some(HistogramView::Running { task, .. }) = self.histogram.take() {
+            task.abort();
+        }
+    }
+
+    /// Start gathering statistics in the background
+    fn start_stats_scan(&mut self) {
+        let progress = Arc::new(AtomicUsize::new(0));
+        let task = tokio::spawn({
+            let source = self.source.clone();
+            let progress = progress.clone();
+            let transfer = self.transfer.clone();
+            let options = self.pinned_options();
+            async move { dataset::scan_stats(&source, None, &progress, transfer, options).await }
+        });
+        self.stats = Some(StatsScan::Running { progress, task });
+    }
+
+    /// Start counting the features in the background
+    fn start_counting(&mut self) {
+        let progress = Arc::new(AtomicUsize::new(0));
+        let task = tokio::spawn({
+            let source = self.source.clone();
+            let progress = progress.clone();
+            let transfer = self.transfer.clone();
+            let options = self.pinned_options();
+            async move { dataset::count_features(&source, &progress, transfer, options).await }
+        });
+        self.feature_count = Some(FeatureCount::Running { progress, task });
+    }
+
+    /// Start sampling the features in the background
+    fn start_sampling(&mut self) {
+        let progress = Arc::new(AtomicUsize::new(0));
+        let task = tokio::spawn({
+            let source = self.source.clone();
+            let sample_size = self.options.sample_size;
+            let progress = progress.clone();
+            let transfer = self.transfer.clone();
+            let options = self.pinned_options();
+            async move {
+                dataset::sample_points(&source, sample_size, &progress, transfer, options).await
+            }
+        });
+        self.map_sample = Some(MapSample::Running { progress, task });
+    }
+
+    /// The options for reading the file again to query or sample it, which must read the
+    /// same version of a remote file as was opened, rather than one which replaced it since
+    fn pinned_options(&self) -> OpenOptions {
+        let mut options = self.options.open.clone();
+        options.http.version = self.dataset.as_ref().and_then(|d| d.version.clone());
+        options
+    }
+
+    /// Re-open the file to pick up any changes, keeping the current view where possible
+    fn reload(&mut self) {
+        if self.loading.is_none() {
+            self.start_loading();
+        }
+    }
+
+    /// Project the envelope for the map, read with its axes swapped if `x` was pressed
+    fn update_map_bbox(&mut self) {
+        let envelope = self.bbox.clone().map(|bbox| {
+            if self.swap_axes {
+                bbox.swapped_axes()
+            } else {
+                bbox
+            }
+        });
+        self.map_bbox = envelope.clone().and_then(|bbox| {
+            let crs = self.source_crs.as_ref()?;
+            bbox.project_to_ratatui_map_crs(crs)
+                .map_err(|e| format!("Failed to project bbox: {e}"))
+        });
+        // a bogus envelope or a bad projection would otherwise draw nothing at all. Without
+        // proj every other CRS would fail the same way, and the Map tab says so instead
+        self.map_warning = None;
+        if cfg!(feature = "proj")
+            && let (Ok(bbox), Err(e)) = (envelope, &self.map_bbox)
+        {
+            self.map_warning = Some(format!("{e}; drawn unprojected"));
+            self.map_bbox = Ok((bbox, "Extent of data, unprojected".to_string()));
+        }
+        if self.swap_axes
+            && let Ok((_, title)) = &mut self.map_bbox
+        {
+            title.push_str(", axes swapped");
+        }
+        if let Ok((bbox, _)) = &mut self.map_bbox
+            && bbox.validate_geographic().is_err()
+        {
+            let hint = if bbox.looks_axis_swapped() {
+                "; latitude and longitude may be swapped in the file"
+            } else {
+                ""
+            };
+            let warning = format!(
+                "envelope outside valid {RATATUI_MAP_CRS} range, clamped for display — source values: {bbox}{hint}"
+            );
+            self.map_warning = Some(match self.map_warning.take() {
+                Some(previous) => format!("{previous}; {warning}"),
+                None => warning,
+            });
+            *bbox = bbox.clamp_geographic();
+        }
+    }
+
+    fn set_dataset(&mut self, dataset: Dataset) {
+        let header = dataset.header();
+        self.bbox = dataset.envelope();
+        self.source_crs = dataset.crs();
+        self.crs_info = self
+            .source_crs
+            .clone()
+            .and_then(|crs| describe_crs(&crs).map_err(|e| e.to_string()));
+        self.update_map_bbox();
+        self.crs_warnings = if self.options.crs_checks {
+            dataset.crs_warnings()
+        } else {
+            Vec::new()
+        };
+        // without an envelope there is nothing to draw, so the Map tab is left out
+        // rather than reporting an error; the metadata shows the envelope is undefined
+        self.tabs = SelectedTab::ALL
+            .into_iter()
+            .filter(|&tab| tab != SelectedTab::Map || self.bbox.is_ok())
+            .collect();
+        if !self.tabs.contains(&self.selected_tab) {
+            self.selected_tab = SelectedTab::Metadata;
+        }
+        // a failed projection falls back to the unprojected envelope above
+        // or is explained on the Map tab
+        self.error = None;
+
+        self.metadata_tree = header.metadata().and_then(JsonTree::parse);
+        self.metadata_lines = None;
+        // the feature may have changed or gone
+        self.map_feature = None;
+        // as may the number of features
+        if let Some(FeatureCount::Running { task, .. }) = self.feature_count.take() {
+            task.abort();
+        }
+        // or their types
+        if let Some(GeometryTally::Running { task, .. }) = self.geometry_types.take() {
+            task.abort();
+        }
+        if let Some(StatsScan::Running { task, .. }) = self.stats.take() {
+            task.abort();
+        }
+        self.stats_table_state = ColumnsTableState::new();
+        self.close_histogram();
+        self.size_histogram = false;
+
+        let columns: Vec<flatgeobuf::Column> =
+            header.columns().unwrap_or_default().iter().collect();
+        self.columns_table_data = ColumnsTableData::new(&columns);
+        self.column_problems = dataset.column_name_problems();
+
+        self.dataset = Some(dataset);
+        self.sort_columns();
+
+        // the file may have fewer columns than before a reload
+        self.column_detail = None;
+        self.show_row_values = false;
+        self.clamp_scroll();
+    }
+
+    /// Keep the scroll positions within the content, which can shrink
+    /// when the file is reloaded or the terminal is resized
+    fn clamp_scroll(&mut self) {
+        if let Some(selected) = self.columns_table_state.state.selected()
+            && selected >= self.column_order.len()
+        {
+            self.columns_table_state
+                .state
+                .select(self.column_order.len().checked_sub(1));
+        }
+        if let Some(cached) = &self.metadata_lines {
+            self.metadata_scroll = self.metadata_scroll.min(cached.lines.len());
+            self.metadata_scroll_state = self
+                .metadata_scroll_state
+                .content_length(cached.lines.len() + 1)
+                .position(self.metadata_scroll);
+        }
+    }
+
+    /// The columns of the header in file order
+    fn columns(&self) -> Vec<flatgeobuf::Column<'_>> {
+        self.dataset
+            .as_ref()
+            .and_then(|dataset| dataset.header().columns())
+            .map(|columns| columns.iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// Recompute the display order of the columns after the sort or dataset changes
+    fn sort_columns(&mut self) {
+        let columns = self.columns();
+        let column_order = self.column_sort.order(&columns);
+        let column_names = column_order
+            .iter()
+            .map(|&i| columns[i].name().to_string())
+            .collect();
+        self.column_order = column_order;
+        self.column_names = column_names;
+    }
+
+    /// Apply a change of the sort, keeping the same column focused rather than the same row
+    fn resort_columns(&mut self) {
+        let focused_column = self.focused_column();
+        self.sort_columns();
+        if let Some(row) =
+            focused_column.and_then(|column| self.column_order.iter().position(|&i| i == column))
+        {
+            self.columns_table_state.state.select(Some(row));
+        }
+    }
+
+    fn draw(&mut self, f: &mut Frame, source_label: &str, theme: &Theme, keymap: &Keymap) {
+        let size = f.area();
+
+        // without room for at least one line of content, the layout
+        // can't fit, so just say so until the terminal grows again
+        if size.height < TABS_HEIGHT + FOOTER_HEIGHT + 1 {
+            f.render_widget(
+                Paragraph::new("Terminal too small; q to quit").wrap(Wrap { trim: true }),
+                size,
+            );
+            return;
+        }
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(TABS_HEIGHT),
+                Constraint::Min(0),
+                Constraint::Length(FOOTER_HEIGHT),
+            ])
+            .split(size);
+
+        f.render_widget(make_tabs(&self.tabs, self.selected_tab, theme), chunks[0]);
+
+        let content_area = chunks[1];
+
+        let tab_hints = self.selected_tab.key_hints(keymap);
+        let name = self
+            .dataset
+            .as_ref()
+            .and_then(|dataset| dataset.header().name())
+            .unwrap_or_default();
+        // counted live, so that the cost of opening, querying and sampling shows as it grows
+        let name = match self.transfer.requests() {
+            0 => name.to_string(),
+            _ if name.is_empty() => self.transfer.summary(),
+            _ => format!("{name} · {}", self.transfer.summary()),
+        };
+        let counting = match (&self.feature_count, &self.geometry_types) {
+            (Some(FeatureCount::Running { progress, .. }), _) => Some(format!(
+                "counting features: {} read",
+                progress.load(Ordering::Relaxed)
+            )),
+            (_, Some(GeometryTally::Running { progress, .. })) => Some(format!(
+                "tallying geometry types: {} read",
+                progress.load(Ordering::Relaxed)
+            )),
+            _ => None,
+        };
+        f.render_widget(
+            status_line(
+                source_label,
+                &name,
+                match &self.query {
+                    Some(BboxQuery::Form(_)) => "tab next field  ⏎ run  esc cancel",
+                    Some(BboxQuery::Running { .. }) => "esc cancel",
+                    Some(BboxQuery::Results(_)) => {
+                        "j/k select  ⏎ details  m show on map  y copy geojson  b new query  esc close"
+                    }
+                    None => match &self.feature_lookup {
+                        Some(FeatureLookup::Prompt { .. }) => "⏎ go  esc cancel",
+                        Some(FeatureLookup::Running { .. }) => "esc cancel",
+                        Some(FeatureLookup::Found { .. }) => {
+                            "j/k scroll  y copy geojson  : another  esc close"
+                        }
+                        None => &tab_hints,
+                    },
+                },
+                counting.as_deref().or(self.status_message.as_deref()),
+                chunks[2].width,
+                theme,
+            ),
+            chunks[2],
+        );
+
+        if let Some(loading) = &self.loading {
+            draw_loading(f, content_area, &self.source, loading.started, theme);
+        } else if self.dataset.is_none() {
+            let message = Paragraph::new("Nothing to show; press q to quit")
+                .block(Block::default().borders(Borders::ALL));
+            f.render_widget(message, content_area);
+        } else {
+            match self.selected_tab {
+                SelectedTab::Metadata => self.draw_metadata(f, content_area, theme, keymap),
+                SelectedTab::Columns => self.draw_columns(f, content_area, theme),
+                SelectedTab::Stats => self.draw_stats(f, content_area, theme, keymap),
+                SelectedTab::Map => self.draw_map(f, content_area, theme),
+            }
+        }
+
+        if let Some(i) = self.column_detail
+            && let Some(column) = self.columns().get(i)
+        {
+            render_popup(
+                f,
+                content_area,
+                &format!("Column: {}", column.name()),
+                column_detail_lines(column, theme),
+                &mut self.popup_scroll,
+            );
+        }
+
+        if self.query.is_some() {
+            self.draw_query(f, content_area, theme);
+        }
+
+        if self.feature_lookup.is_some() {
+            self.draw_feature_lookup(f, content_area, theme);
+        }
+
+        if self.show_requests {
+            self.draw_request_log(f, content_area, theme);
+        }
+
+        if let Some(error) = &self.error {
+            let hint = if error.contains(FILE_CHANGED) {
+                format!("Esc to dismiss · {} to reload", keymap.key(Action::Reload))
+            } else {
+                "Esc to dismiss".to_string()
+            };
+            let area = content_area.centered(Constraint::Percentage(60), Constraint::Length(5));
+            let modal = Paragraph::new(error.as_str())
+                .wrap(Wrap { trim: true })
+                .style(theme.error)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(theme.error)
+                        .title("Error")
+                        .title_bottom(hint),
+                );
+            f.render_widget(Clear, area);
+            f.render_widget(modal, area);
+        }
+    }
+
+    /// The sizes of the file and its header, and for a remote file how much has been
+    /// fetched so far, to judge how expensive it is to keep exploring
+    fn size_summary(&self) -> String {
+        let Some(dataset) = &self.dataset else {
+            return String::new();
+        };
+        let mut parts = Vec::new();
+        match (dataset.header_size, dataset.byte_size) {
+            (Some(header), Some(file)) => {
+                parts.push(format!("header {} of {}", ByteSize(header), ByteSize(file)))
+            }
+            (Some(header), None) => parts.push(format!("header {}", ByteSize(header))),
+            (None, Some(file)) => parts.push(format!("file {}", ByteSize(file))),
+            (None, None) => {}
+        }
+        if let Some(transfer) = &dataset.transfer {
+            parts.push(transfer.summary());
+            if transfer.retries() > 0 {
+                parts.push(format!("{} retried", transfer.retries()));
+            }
+        }
+        parts.join(" · ")
+    }
+
+    fn build_metadata_lines(
+        &self,
+        theme: &Theme,
+        keymap: &Keymap,
+        width: usize,
+    ) -> Option<MetadataLines> {
+        let dataset = self.dataset.as_ref()?;
+        let header = dataset.header();
+        let column_count = header.columns().map(|c| c.len()).unwrap_or(0);
+        let mut envelope = match header.envelope() {
+            None => "Undefined".to_string(),
+            Some(envelope) if self.raw_values => format!("{envelope:?}"),
+            Some(envelope) => Bbox::from_flatgeobuf_envelope(&envelope)
+                .map_or_else(|_| format!("{envelope:?}"), |bbox| pretty_envelope(&bbox)),
+        };
+        if self.bbox.as_ref().is_ok_and(Bbox::crosses_antimeridian) {
+            envelope.push_str(" (crosses antimeridian)");
+        }
+
+        let index_node_size = match header.index_node_size() {
+            0 => "No Spatial Index".to_string(),
+            _ => format!("{}", header.index_node_size()),
+        };
+        // from the feature count and node size, so reading the index isn't needed
+        let index_layout = dataset.index_layout().map(|layout| {
+            format!(
+                "{} in {} levels of {} nodes",
+                ByteSize(layout.bytes),
+                layout.levels,
+                layout.nodes
+            )
+        });
+
+        let byte_size_str = dataset.byte_size.map(|s| ByteSize(s).to_string());
+        let features_count = match (dataset.features_count(), &self.feature_count) {
+            (Some(count), _) => count.to_string(),
+            (None, Some(FeatureCount::Done(count))) => format!("{count} (counted)"),
+            (None, Some(FeatureCount::Running { .. })) => "unknown (counting…)".to_string(),
+            (None, None) => format!(
+                "unknown (press {} to count)",
+                keymap.key(Action::CountFeatures)
+            ),
+        };
+        let declared = header.geometry_type();
+        let declared_name = if self.raw_values {
+            format!("{declared:?}")
+        } else {
+            pretty_geometry_type(declared)
+        };
+        let geometry_type = match &self.geometry_types {
+            Some(GeometryTally::Done(types)) => {
+                let mut text = format!("{declared_name}: {} in {}", types.summary(), types.read());
+                if !types.mismatched().is_empty() {
+                    text.push_str(" (other types than the header says)");
+                }
+                text
+            }
+            Some(GeometryTally::Running { .. }) => format!("{declared_name} (tallying…)"),
+            None if declared == flatgeobuf::GeometryType::Unknown => format!(
+                "{declared_name} (press {} to tally the types of the features)",
+                keymap.key(Action::GeometryTypes)
+            ),
+            None => declared_name,
+        };
+        let column_count = column_count.to_string();
+
+        let mut pairs = vec![
+            ("Name", header.name().unwrap_or("")),
+            (
+                "File Size",
+                byte_size_str.as_deref().unwrap_or("Unknown File Size"),
+            ),
+            ("Description", header.description().unwrap_or("")),
+            ("Features", &features_count),
+            ("Bounds", &envelope),
+            ("Geometry Type", &geometry_type),
+            ("Columns", &column_count),
+            ("Spatial Index R-Tree Node Size", &index_node_size),
+        ];
+        if let Some(index_layout) = &index_layout {
+            pairs.push(("Spatial Index Size", index_layout));
+        }
+        let mut lines = info_lines(theme, &pairs, width);
+
+        lines.push(Line::default());
+        lines.extend(info_lines(
+            theme,
+            &[
+                ("Has M Dimension", &header.has_m().to_string()),
+                ("Has Z Dimension", &header.has_z().to_string()),
+                ("Has T Dimension", &header.has_t().to_string()),
+                ("Has TM Dimension", &header.has_tm().to_string()),
+            ],
+            width,
+        ));
+
+        if let Some(crs) = header.crs() {
+            let wkt = crs.wkt().filter(|wkt| !wkt.is_empty());
+            let wkt_label = match wkt {
+                Some(_) if self.wkt_expanded => "(press w to collapse)",
+                Some(_) => "(press w to expand)",
+                None => "Undefined",
+            };
+            lines.push(Line::default());
+            lines.extend(info_lines(
+                theme,
+                &[
+                    ("CRS Code", &crs.code().to_string()),
+                    ("CRS Name", crs.name().unwrap_or_default()),
+                    ("CRS Code String", crs.code_string().unwrap_or_default()),
+                    ("CRS Description", crs.description().unwrap_or_default()),
+                    ("CRS Organization", crs.org().unwrap_or_default()),
+                    ("CRS WKT", wkt_label),
+                ],
+                width,
+            ));
+            match &self.crs_info {
+                Ok(info) => lines.extend(info_lines(
+                    theme,
+                    &[
+                        ("CRS Resolved", &info.name),
+                        ("CRS Type", info.kind),
+                        ("CRS Units", &info.units),
+                    ],
+                    width,
+                )),
+                Err(e) => lines.extend(info_lines(theme, &[("CRS Resolved", e)], width)),
+            }
+            for warning in &self.crs_warnings {
+                lines.extend(
+                    wrap_text(&format!("Warning: {warning}"), width)
+                        .into_iter()
+                        .map(|row| Line::styled(row, theme.message)),
+                );
+            }
+            if let Some(wkt) = wkt
+                && self.wkt_expanded
+            {
+                lines.extend(wkt::pretty_print(wkt).into_iter().map(|line| {
+                    Line::from(vec![
+                        Span::raw("  ".repeat(line.depth + 1)),
+                        Span::styled(line.keyword, theme.keyword),
+                        Span::raw(line.rest),
+                    ])
+                }));
+            }
+        } else {
+            lines.push(info_line(theme, "CRS", "Undefined"));
+        }
+
+        lines.push(Line::default());
+        let mut line_paths = vec![None; lines.len()];
+        match &self.metadata_tree {
+            Some(tree) if !self.raw_values => {
+                lines.push(info_line(theme, "Custom Metadata", "JSON"));
+                line_paths.push(None);
+                for (line, path) in tree.lines(theme) {
+                    lines.push(line);
+                    line_paths.push(path);
+                }
+            }
+            _ => {
+                lines.extend(info_lines(
+                    theme,
+                    &[("Custom Metadata", header.metadata().unwrap_or("Undefined"))],
+                    width,
+                ));
+                line_paths.resize(lines.len(), None);
+            }
+        }
+
+        Some(MetadataLines {
+            width,
+            texts: lines.iter().map(line_text).collect(),
+            row_starts: row_starts(&lines, width),
+            lines,
+            paths: line_paths,
+        })
+    }
+
+    fn draw_metadata(&mut self, f: &mut Frame, content_area: Rect, theme: &Theme, keymap: &Keymap) {
+        // values are wrapped within the borders of the paragraph
+        let width = content_area.width.saturating_sub(2) as usize;
+        if self
+            .metadata_lines
+            .as_ref()
+            .is_none_or(|cached| cached.width != width)
+        {
+            self.metadata_lines = self.build_metadata_lines(theme, keymap, width);
+        }
+        let (Some(dataset), Some(cached)) = (&self.dataset, &self.metadata_lines) else {
+            return;
+        };
+
+        let max_scroll = cached.lines.len();
+        let lines: Vec<Line> = cached
+            .lines
+            .iter()
+            .map(|line| highlight_line_match(line.clone(), &self.metadata_search.query, theme))
+            .collect();
+
+        // while searching, the bottom line of the tab is the search prompt
+        let (content_area, search_area) = if self.metadata_search.editing {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(1)])
+                .split(content_area);
+            (chunks[0], Some(chunks[1]))
+        } else {
+            (content_area, None)
+        };
+
+        // yanking on any of the CRS lines copies the full WKT, since
+        // that is the value most worth getting out of the terminal
+        let focused_line = cached
+            .texts
+            .get(self.metadata_scroll.min(max_scroll))
+            .cloned()
+            .unwrap_or_default();
+        self.metadata_yank_text = match dataset.header().crs() {
+            Some(crs) if focused_line.starts_with("CRS") => {
+                crs.wkt().unwrap_or_default().to_string()
+            }
+            _ => focused_line,
+        };
+        // the paragraph block has a border on the top and bottom
+        self.page_size = content_area.height.saturating_sub(2).max(1) as usize;
+
+        self.metadata_scroll = self.metadata_scroll.min(max_scroll);
+        self.metadata_scroll_state = self
+            .metadata_scroll_state
+            .content_length(max_scroll + 1)
+            .position(self.metadata_scroll);
+
+        // the scroll position is a line, so that copying, searching and expanding act on
+        // the line at the top however many rows the lines above it wrap onto
+        let top_row = cached.row_starts[self.metadata_scroll];
+        let body = Paragraph::new(lines)
+            // without trimming so that indented lines keep their indentation
+            .wrap(Wrap { trim: false })
+            .scroll((top_row as u16, 0))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Metadata")
+                    .title_bottom(Line::from(self.size_summary()).right_aligned()),
+            );
+
+        f.render_widget(body, content_area);
+
+        f.render_stateful_widget(
+            make_scrollbar(theme),
+            content_area,
+            &mut self.metadata_scroll_state,
+        );
+
+        if let Some(search_area) = search_area {
+            render_search_prompt(f, search_area, &self.metadata_search.query);
+        }
+    }
+
+    fn draw_columns(&mut self, f: &mut Frame, content_area: Rect, theme: &Theme) {
+        let data = &self.columns_table_data;
+        let total_rows = data.cells.len();
+
+        // while searching, the bottom line of the tab is the search prompt
+        let (table_area, search_area) = if self.column_search.editing {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(1)])
+                .split(content_area);
+            (chunks[0], Some(chunks[1]))
+        } else {
+            (content_area, None)
+        };
+        // names loaders can't tell apart are warned about above the table
+        let warnings: Vec<Line> = self
+            .column_problems
+            .warnings()
+            .into_iter()
+            .flat_map(|warning| {
+                wrap_text(&format!("Warning: {warning}"), table_area.width as usize)
+            })
+            .map(|row| Line::styled(row, theme.message))
+            .collect();
+        let [warning_area, table_area] = Layout::vertical([
+            Constraint::Length((warnings.len() as u16).min(table_area.height / 2)),
+            Constraint::Fill(1),
+        ])
+        .areas(table_area);
+        f.render_widget(Paragraph::new(warnings), warning_area);
+
+        const TABLE_CHROME_ROWS: u16 = 3; // top border + header + bottom border
+        let visible_rows = table_area.height.saturating_sub(TABLE_CHROME_ROWS) as usize;
+
+        let max_scroll = total_rows.saturating_sub(visible_rows);
+        self.page_size = visible_rows.max(1);
+
+        let selected = self.columns_table_state.state.selected().unwrap_or(0);
+        let scroll_pos = selected.min(max_scroll);
+
+        self.columns_scroll_state = self
+            .columns_scroll_state
+            .content_length(max_scroll + 1)
+            .position(scroll_pos);
+
+        let fields = data.shown_fields(self.extended_columns);
+        let widths = fields.iter().map(|&i| data.widths[i]).collect::<Vec<_>>();
+
+        // scroll horizontally through the table columns which don't fit;
+        // the borders and highlight symbol also take up space
+        const TABLE_CHROME_COLUMNS: u16 = 2 + 3;
+        const COLUMN_SPACING: u16 = 1;
+        self.columns_hscroll = self.columns_hscroll.min(fields.len().saturating_sub(1));
+        let visible = visible_column_range(
+            &widths,
+            self.columns_hscroll,
+            table_area.width.saturating_sub(TABLE_CHROME_COLUMNS),
+            COLUMN_SPACING,
+        );
+        let total_table_columns = fields.len();
+        let visible_fields = &fields[visible.clone()];
+        let widths_in_view = widths[visible.clone()].to_vec();
+        let widths = widths_in_view
+            .iter()
+            .map(|w| Constraint::Length(*w))
+            .collect::<Vec<_>>();
+
+        let header_cells = visible_fields
+            .iter()
+            .map(|&i| {
+                Cell::from(
+                    self.column_sort
+                        .header_label(data.headers[i], &theme.symbols),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let table_header = Row::new(header_cells).height(1);
+
+        // only build the rows on screen, since some files have thousands of columns
+        let window = self
+            .columns_table_state
+            .visible_window(self.column_order.len(), visible_rows);
+        let rows = self.column_order[window.clone()].iter().map(|&row| {
+            let cells = visible_fields
+                .iter()
+                .map(|&i| {
+                    let value = data.truncated_cells[row][i].clone();
+                    if data.headers[i] == "Name" {
+                        Cell::from(highlight_match(value, &self.column_search.query, theme))
+                    } else {
+                        Cell::from(value)
+                    }
+                })
+                .collect::<Vec<_>>();
+            let line = Row::new(cells).height(1);
+            if self.column_problems.affects(row) {
+                line.style(theme.message)
+            } else {
+                line
+            }
+        });
+
+        let table = Table::new(rows, &widths)
+            .header(table_header)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(if self.column_sort.is_active() {
+                        format!(
+                            "Columns (Focused {} of {}, {})",
+                            selected + 1,
+                            total_rows,
+                            self.column_sort.description(&theme.symbols)
+                        )
+                    } else {
+                        format!("Columns (Focused {} of {})", selected + 1, total_rows)
+                    })
+                    .title_bottom(format!(
+                        "cols {}–{} of {} (h/l to scroll)",
+                        visible.start + 1,
+                        visible.end,
+                        total_table_columns
+                    )),
+            )
+            .row_highlight_style(theme.highlight)
+            .highlight_symbol(theme.symbols.highlight);
+
+        let mut window_state =
+            TableState::default().with_selected(selected.checked_sub(window.start));
+
+        // the header row is just inside the top border, with the cells after the
+        // highlight symbol, which is only given room while a row is selected
+        let mut x = table_area.x
+            + 1
+            + window_state
+                .selected()
+                .map_or(0, |_| theme.symbols.highlight.width() as u16);
+        self.column_header_cells.clear();
+        for (&i, &width) in visible_fields.iter().zip(&widths_in_view) {
+            if let Some(field) = ColumnSortField::from_header(data.headers[i]) {
+                let cell = Rect::new(x, table_area.y + 1, width, 1).intersection(table_area);
+                self.column_header_cells.push((cell, field));
+            }
+            x = x.saturating_add(width + COLUMN_SPACING);
+        }
+
+        f.render_stateful_widget(table, table_area, &mut window_state);
+        f.render_stateful_widget(
+            make_scrollbar(theme),
+            table_area,
+            &mut self.columns_scroll_state,
+        );
+
+        if self.show_row_values
+            && let Some(&row) = self.column_order.get(selected)
+        {
+            let values = &data.cells[row];
+            let lines: Vec<Line> = fields
+                .iter()
+                .map(|&i| info_line(theme, data.headers[i], &values[i]))
+                .collect();
+            render_popup(
+                f,
+                content_area,
+                &format!("Values: {}", values[0]),
+                lines,
+                &mut self.popup_scroll,
+            );
+        }
+
+        if let Some(search_area) = search_area {
+            render_search_prompt(f, search_area, &self.column_search.query);
+        }
+    }
+
+    fn draw_stats(&mut self, f: &mut Frame, content_area: Rect, theme: &Theme, keymap: &Keymap) {
+        let stats = match &self.stats {
+            Some(StatsScan::Done(stats)) => stats,
+            state => {
+                let text = match state {
+                    Some(StatsScan::Running { progress, .. }) => {
+                        let read = progress.load(Ordering::Relaxed);
+                        match self.dataset.as_ref().and_then(Dataset::features_count) {
+                            Some(total) => format!("Reading features: {read} of {total}"),
+                            None => format!("Reading features: {read}"),
+                        }
+                    }
+                    _ => format!(
+                        "Press {} to read every feature and gather statistics about the columns",
+                        keymap.key(Action::Select)
+                    ),
+                };
+                let message = Paragraph::new(text)
+                    .wrap(Wrap { trim: true })
+                    .block(Block::default().borders(Borders::ALL).title("Stats"));
+                f.render_widget(message, content_area);
+                return;
+            }
+        };
+
+        let mut geometry_lines = vec![
+            info_line(theme, "Vertices", &stats.geometry.vertices.text()),
+            info_line(theme, "Bytes", &stats.geometry.bytes.text()),
+        ];
+        if let Some(sortedness) = &stats.sortedness {
+            geometry_lines.push(info_line(theme, "Spatially sorted", &sortedness.text()));
+        }
+        // a single type is already given by the header or the tally, so isn't charted
+        let types = if stats.geometry_types.len() > 1 {
+            stats.geometry_types.len() as u16 + 2
+        } else {
+            0
+        };
+        let sizes_height = if self.size_histogram { 12 } else { 5 };
+        let [geometry_area, types_area, sizes_area, content_area] = Layout::vertical([
+            Constraint::Length(geometry_lines.len() as u16 + 2),
+            Constraint::Length(types),
+            Constraint::Length(sizes_height),
+            Constraint::Fill(1),
+        ])
+        .areas(content_area);
+        let geometry = Paragraph::new(geometry_lines)
+            .block(Block::default().borders(Borders::ALL).title("Geometry"));
+        f.render_widget(geometry, geometry_area);
+        if types > 0 {
+            draw_geometry_types(f, types_area, stats, theme);
+        }
+        if self.size_histogram {
+            draw_size_histogram(f, sizes_area, stats, theme);
+        } else {
+            draw_sizes(f, sizes_area, stats, theme);
+        }
+
+        const TABLE_CHROME_ROWS: u16 = 3; // top border + header + bottom border
+        let visible_rows = content_area.height.saturating_sub(TABLE_CHROME_ROWS) as usize;
+        self.page_size = visible_rows.max(1);
+        let total_rows = stats.columns.len();
+        let selected = self.stats_table_state.state.selected().unwrap_or(0);
+
+        let window = self
+            .stats_table_state
+            .visible_window(total_rows, visible_rows);
+        let rows = stats.columns[window.clone()].iter().map(|column| {
+            let (min, max) = column
+                .range
+                .as_ref()
+                .map_or_else(Default::default, Range::bounds);
+            let row = Row::new([
+                column.name.clone(),
+                column.column_type.clone(),
+                stats.nulls(column).to_string(),
+                format!("{:.1}%", stats.null_percent(column)),
+                min,
+                max,
+                column
+                    .values
+                    .as_ref()
+                    .map(|values| values.summary(3))
+                    .unwrap_or_default(),
+                column.notes(),
+            ]);
+            // a column without any values is usually a bug in whatever wrote the file
+            if stats.all_null(column) {
+                row.style(theme.error)
+            } else {
+                row
+            }
+        });
+        // long names and values are cut off rather than squeezing out the other columns
+        let width = |values: &mut dyn Iterator<Item = usize>, max| {
+            Constraint::Length(values.max().unwrap_or(0).clamp(4, max) as u16)
+        };
+        let bounds: Vec<(String, String)> = stats
+            .columns
+            .iter()
+            .filter_map(|column| column.range.as_ref().map(Range::bounds))
+            .collect();
+        let widths = [
+            width(&mut stats.columns.iter().map(|c| c.name.width()), 30),
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Length(7),
+            width(&mut bounds.iter().map(|(min, _)| min.width()), 29),
+            width(&mut bounds.iter().map(|(_, max)| max.width()), 29),
+            Constraint::Fill(3),
+            Constraint::Fill(1),
+        ];
+        let table = Table::new(rows, widths)
+            .header(Row::new([
+                "Name",
+                "Type",
+                "Nulls",
+                "Null %",
+                "Min",
+                "Max",
+                "Top values",
+                "Notes",
+            ]))
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                "Stats of {} features (Focused {} of {})",
+                stats.features,
+                selected + 1,
+                total_rows
+            )))
+            .row_highlight_style(theme.highlight)
+            .highlight_symbol(theme.symbols.highlight);
+        let mut window_state =
+            TableState::default().with_selected(selected.checked_sub(window.start));
+        f.render_stateful_widget(table, content_area, &mut window_state);
+        self.draw_histogram(f, content_area, theme);
+    }
+
+    fn draw_histogram(&self, f: &mut Frame, content_area: Rect, theme: &Theme) {
+        let area = content_area.inner(Margin::new(2, 1));
+        let histogram = match &self.histogram {
+            None => return,
+            Some(HistogramView::Running {
+                column, progress, ..
+            }) => {
+                let read = progress.load(Ordering::Relaxed);
+                let text = match self.dataset.as_ref().and_then(Dataset::features_count) {
+                    Some(total) => format!("Reading features: {read} of {total}"),
+                    None => format!("Reading features: {read}"),
+                };
+                let message = Paragraph::new(text).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(format!("Histogram of {column}"))
+                        .title_bottom("esc close"),
+                );
+                let area = area.centered_vertically(Constraint::Length(3));
+                f.render_widget(Clear, area);
+                f.render_widget(message, area);
+                return;
+            }
+            Some(HistogramView::Done(histogram)) => histogram,
+        };
+        let bars: Vec<Bar> = histogram
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| Bar::with_label(histogram.label(i), count as u64))
+            .collect();
+        let footer = match histogram.excluded() {
+            Some(excluded) => format!("{excluded} · esc close"),
+            None => "esc close".to_string(),
+        };
+        let chart = BarChart::horizontal(bars)
+            .bar_width(1)
+            .bar_gap(0)
+            .bar_style(theme.highlight)
+            .label_style(theme.label)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Histogram of {}", histogram.column))
+                    .title_bottom(footer),
+            );
+        // a row for each bin, cut off at the bottom if they don't all fit
+        let height = (histogram.counts.len() as u16 + 2).min(area.height);
+        let area = area.centered_vertically(Constraint::Length(height));
+        f.render_widget(Clear, area);
+        f.render_widget(chart, area);
+    }
+
+    /// Draw the latest HTTP requests made for the file, newest at the bottom
+    fn draw_request_log(&mut self, f: &mut Frame, content_area: Rect, theme: &Theme) {
+        let area = content_area.centered(Constraint::Percentage(80), Constraint::Percentage(80));
+        let log = self.transfer.request_log();
+        let logged = self.transfer.requests_logged();
+        let title = if logged > log.len() {
+            format!("HTTP requests (the last {} of {logged})", log.len())
+        } else {
+            "HTTP requests".to_string()
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_bottom("j/k scroll  esc close");
+        if log.is_empty() {
+            let text = if is_remote_file(&self.source) {
+                "No requests yet"
+            } else {
+                "No network activity; the file is read from disk"
+            };
+            f.render_widget(Clear, area);
+            f.render_widget(Paragraph::new(text).block(block), area);
+            return;
+        }
+
+        const TABLE_CHROME_ROWS: usize = 3; // top border + header + bottom border
+        let visible_rows = (area.height as usize).saturating_sub(TABLE_CHROME_ROWS);
+        self.popup_scroll = self
+            .popup_scroll
+            .min(log.len().saturating_sub(visible_rows));
+        let rows = log.iter().skip(self.popup_scroll).map(|entry| {
+            let status = entry
+                .status
+                .map_or("failed".to_string(), |status| status.as_u16().to_string());
+            let row = Row::new([
+                entry.method.to_string(),
+                entry.range.clone().unwrap_or_else(|| "—".to_string()),
+                status,
+                ByteSize(entry.bytes).to_string(),
+                format!("{} ms", entry.duration.as_millis()),
+            ]);
+            if entry.status.is_none_or(|status| !status.is_success()) {
+                row.style(theme.error)
+            } else {
+                row
+            }
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(6),
+                Constraint::Fill(1),
+                Constraint::Length(6),
+                Constraint::Length(11),
+                Constraint::Length(9),
+            ],
+        )
+        .header(Row::new(["Method", "Range", "Status", "Size", "Duration"]).style(theme.label))
+        .block(block);
+        f.render_widget(Clear, area);
+        f.render_widget(table, area);
+    }
+
+    fn draw_query(&mut self, f: &mut Frame, content_area: Rect, theme: &Theme) {
+        match &mut self.query {
+            None => {}
+            Some(BboxQuery::Form(form)) => {
+                let area = content_area.centered(Constraint::Length(48), Constraint::Length(8));
+                let mut lines: Vec<Line> = BBOX_FIELDS
+                    .iter()
+                    .zip(&form.values)
+                    .enumerate()
+                    .map(|(i, (label, value))| {
+                        let line = info_line(theme, label, value);
+                        if i == form.focused {
+                            line.patch_style(theme.highlight)
+                        } else {
+                            line
+                        }
+                    })
+                    .collect();
+                if let Some(error) = &form.error {
+                    lines.push(Line::styled(error.clone(), theme.error));
+                }
+                let popup = Paragraph::new(lines).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Bbox query")
+                        .title_bottom("in the CRS of the file"),
+                );
+                f.render_widget(Clear, area);
+                f.render_widget(popup, area);
+
+                // after the border and the `label: ` prefix
+                let label = BBOX_FIELDS[form.focused];
+                let value = &form.values[form.focused];
+                f.set_cursor_position((
+                    area.x + 1 + (label.width() + 2 + value.width()) as u16,
+                    area.y + 1 + form.focused as u16,
+                ));
+            }
+            Some(BboxQuery::Running {
+                bbox,
+                started,
+                progress,
+                ..
+            }) => {
+                let area = content_area.centered(Constraint::Percentage(60), Constraint::Length(4));
+                let spinner = theme.symbols.spinner;
+                let elapsed = started.elapsed();
+                let frame = spinner[(elapsed.as_millis() / 100) as usize % spinner.len()];
+                let message = Paragraph::new(format!(
+                    "{frame} Querying {bbox}… {} features read in {:.1}s",
+                    progress.load(Ordering::Relaxed),
+                    elapsed.as_secs_f64()
+                ))
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Bbox query")
+                        .title_bottom("Esc to cancel"),
+                );
+                f.render_widget(Clear, area);
+                f.render_widget(message, area);
+            }
+            Some(BboxQuery::Results(results)) => {
+                const TABLE_CHROME_ROWS: u16 = 3; // top border + header + bottom border
+                let visible_rows = content_area.height.saturating_sub(TABLE_CHROME_ROWS) as usize;
+                self.page_size = visible_rows.max(1);
+
+                let total = results.features.len();
+                let selected = results.table.state.selected().unwrap_or(0);
+                let window = results.table.visible_window(total, visible_rows);
+                let rows = window
+                    .clone()
+                    .map(|i| Row::new(results.row(i).into_iter().map(Cell::from)));
+                let widths = results
+                    .widths
+                    .iter()
+                    .map(|w| Constraint::Length(*w))
+                    .collect::<Vec<_>>();
+                let title = if total == 0 {
+                    format!("No features in {}", results.bbox)
+                } else {
+                    format!(
+                        "Features in {} (Focused {} of {})",
+                        results.bbox,
+                        selected + 1,
+                        total
+                    )
+                };
+                let table = Table::new(rows, &widths)
+                    .header(Row::new(
+                        results.headers.iter().map(|h| Cell::from(h.as_str())),
+                    ))
+                    .block(Block::default().borders(Borders::ALL).title(title))
+                    .row_highlight_style(theme.highlight)
+                    .highlight_symbol(theme.symbols.highlight);
+
+                let mut window_state =
+                    TableState::default().with_selected(selected.checked_sub(window.start));
+                f.render_widget(Clear, content_area);
+                f.render_stateful_widget(table, content_area, &mut window_state);
+
+                if results.show_detail
+                    && let Some(feature) = results.selected()
+                {
+                    let lines = feature.detail_lines(theme);
+                    render_popup(
+                        f,
+                        content_area,
+                        &format!("Feature {} of {}", selected + 1, total),
+                        lines,
+                        &mut self.popup_scroll,
+                    );
+                }
+            }
+        }
+    }
+
+    fn draw_feature_lookup(&mut self, f: &mut Frame, content_area: Rect, theme: &Theme) {
+        match &self.feature_lookup {
+            None => {}
+            Some(FeatureLookup::Prompt { input, error }) => {
+                let area = content_area.centered(Constraint::Length(48), Constraint::Length(4));
+                let mut lines = vec![Line::from(format!(":{input}"))];
+                if let Some(error) = error {
+                    lines.push(Line::styled(error.clone(), theme.error));
+                }
+                let hint = match self.feature_count() {
+                    Some(count) => format!("from 0 to {}", count - 1),
+                    None => "counting from 0".to_string(),
+                };
+                let popup = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Go to feature")
+                        .title_bottom(hint),
+                );
+                f.render_widget(Clear, area);
+                f.render_widget(popup, area);
+                // after the border and the `:`
+                f.set_cursor_position((area.x + 2 + input.width() as u16, area.y + 1));
+            }
+            Some(FeatureLookup::Running { index, started, .. }) => {
+                let area = content_area.centered(Constraint::Percentage(60), Constraint::Length(3));
+                let spinner = theme.symbols.spinner;
+                let elapsed = started.elapsed();
+                let frame = spinner[(elapsed.as_millis() / 100) as usize % spinner.len()];
+                let message = Paragraph::new(format!(
+                    "{frame} Reading feature {index}… {:.1}s",
+                    elapsed.as_secs_f64()
+                ))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Go to feature")
+                        .title_bottom("Esc to cancel"),
+                );
+                f.render_widget(Clear, area);
+                f.render_widget(message, area);
+            }
+            Some(FeatureLookup::Found { index, feature }) => {
+                render_popup(
+                    f,
+                    content_area,
+                    &format!("Feature {index}"),
+                    feature.detail_lines(theme),
+                    &mut self.popup_scroll,
+                );
+            }
+        }
+    }
+
+    fn draw_map(&mut self, f: &mut Frame, content_area: Rect, theme: &Theme) {
+        match &self.map_bbox {
+            Ok((bbox, title)) => {
+                let warning_height = u16::from(self.map_warning.is_some());
+                let [content_area, warning_area, info_area] = Layout::vertical([
+                    Constraint::Min(3),
+                    Constraint::Length(warning_height),
+                    Constraint::Length(1),
+                ])
+                .areas(content_area);
+                if let Some(warning) = &self.map_warning {
+                    f.render_widget(
+                        Paragraph::new(warning.as_str()).style(theme.message),
+                        warning_area,
+                    );
+                }
+                self.map_area = content_area;
+                f.render_widget(
+                    Paragraph::new(info_line(theme, "Envelope", &envelope_dimensions(bbox))),
+                    info_area,
+                );
+
+                let mut title = title.clone();
+                let (x, y) = bbox.center();
+                let decimals = coordinate_decimals(bbox);
+                title.push_str(&format!(", centroid {x:.decimals$}, {y:.decimals$}"));
+                if let Some(kind) = bbox.degenerate_kind() {
+                    title.push_str(&format!(", {kind}"));
+                }
+                if let Some(feature) = &self.map_feature {
+                    title.push_str(&format!(", showing {}", feature.label));
+                }
+                let sample_points = match &self.map_sample {
+                    Some(MapSample::Running { progress, .. }) if self.show_sample => {
+                        title.push_str(&format!(
+                            ", sampling features {}/{}",
+                            progress.load(Ordering::Relaxed),
+                            self.options.sample_size
+                        ));
+                        &[][..]
+                    }
+                    Some(MapSample::Ready(points)) if self.show_sample => {
+                        title.push_str(&format!(", {} sampled points", points.len()));
+                        points.as_slice()
+                    }
+                    _ => &[][..],
+                };
+                title.push_str(&format!(", {}", self.map_view.description()));
+                let feature_parts = self
+                    .map_feature
+                    .as_ref()
+                    .map_or(&[][..], |feature| feature.parts.as_slice());
+                let resolution = self.map_resolution.unwrap_or_else(|| {
+                    MapOptions::auto_resolution(self.map_view.bounds(), content_area)
+                });
+                self.drawn_map_resolution = resolution;
+                let overlays = MapOverlays {
+                    feature_parts,
+                    sample_points,
+                };
+                let options = MapOptions {
+                    resolution,
+                    graticule: self.show_graticule,
+                };
+                let canvas = make_map_with_bbox_overlay(
+                    &title,
+                    content_area,
+                    self.map_view.bounds(),
+                    bbox,
+                    overlays,
+                    options,
+                    theme,
+                );
+                f.render_widget(canvas, content_area);
+
+                // in the top right corner, where it is least likely to cover the extent
+                let legend = map_legend(overlays, options, theme);
+                let width = legend.iter().map(Line::width).max().unwrap_or(0) as u16;
+                let inner = content_area.inner(Margin::new(1, 1));
+                let legend_area = Rect::new(
+                    inner.right().saturating_sub(width),
+                    inner.y,
+                    width,
+                    legend.len() as u16,
+                )
+                .intersection(inner);
+                f.render_widget(Clear, legend_area);
+                f.render_widget(Paragraph::new(legend), legend_area);
+            }
+            Err(e) => {
+                self.map_area = content_area;
+                let message = Paragraph::new(format!("No map can be rendered: {e}"))
+                    .wrap(Wrap { trim: true })
+                    .style(theme.error)
+                    .block(Block::default().borders(Borders::ALL).title("Map"));
+                f.render_widget(message, content_area);
+            }
+        }
+    }
+
+    /// Whether the file is in the same CRS as the map, so its coordinates are longitudes and latitudes
+    fn geographic(&self) -> bool {
+        self.source_crs.as_deref().is_ok_and(is_map_crs)
+    }
+
+    /// Whether a popup, prompt or error currently has the keyboard to itself
+    fn is_modal(&self) -> bool {
+        self.error.is_some()
+            || self.query.is_some()
+            || self.feature_lookup.is_some()
+            || self.column_detail.is_some()
+            || self.show_row_values
+            || self.show_requests
+            || self.histogram.is_some()
+            || self.column_search.editing
+            || self.metadata_search.editing
+    }
+
+    fn handle_event(&mut self, event: Event, clipboard: &mut Clipboard, keymap: &Keymap) {
+        if let Event::Key(_) = event {
+            self.status_message = None;
+        }
+
+        let scroll = match event {
+            Event::Key(KeyEvent {
+                code,
+                kind: KeyEventKind::Press,
+                modifiers,
+                ..
+            }) => {
+                if code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL) {
+                    self.quit = true;
+                    None
+                } else if let Some(error) = &self.error {
+                    if code == KeyCode::Esc {
+                        self.error = None;
+                    } else if error.contains(FILE_CHANGED)
+                        && keymap.action(code, modifiers, self.selected_tab) == Some(Action::Reload)
+                    {
+                        self.error = None;
+                        self.reload();
+                    }
+                    None
+                } else if self.query.is_some() {
+                    self.handle_query_key(code, clipboard);
+                    None
+                } else if self.feature_lookup.is_some() {
+                    self.handle_feature_lookup_key(code, clipboard);
+                    None
+                } else if self.show_requests {
+                    if code == KeyCode::Esc
+                        || keymap.action(code, modifiers, self.selected_tab)
+                            == Some(Action::RequestLog)
+                    {
+                        self.show_requests = false;
+                    } else {
+                        self.handle_popup_key(code);
+                    }
+                    None
+                } else if self.column_detail.is_some() || self.show_row_values {
+                    self.handle_popup_key(code);
+                    None
+                } else if self.histogram.is_some() {
+                    if code == KeyCode::Esc
+                        || keymap.action(code, modifiers, self.selected_tab)
+                            == Some(Action::Histogram)
+                    {
+                        self.close_histogram();
+                    }
+                    None
+                } else if self.column_search.editing || self.metadata_search.editing {
+                    self.handle_search_key(code);
+                    None
+                } else {
+                    keymap
+                        .action(code, modifiers, self.selected_tab)
+                        .and_then(|action| self.handle_key(action, clipboard))
+                }
+            }
+            Event::Mouse(MouseEvent {
+                kind, column, row, ..
+            }) => match kind {
+                MouseEventKind::Down(MouseButton::Left) if row < TABS_HEIGHT => {
+                    if let Some(tab) = SelectedTab::at_column(&self.tabs, column) {
+                        self.selected_tab = tab;
+                    }
+                    None
+                }
+                MouseEventKind::Down(MouseButton::Left)
+                    if self.selected_tab == SelectedTab::Columns && !self.is_modal() =>
+                {
+                    if let Some(&(_, field)) = self
+                        .column_header_cells
+                        .iter()
+                        .find(|(cell, _)| cell.contains((column, row).into()))
+                    {
+                        self.column_sort.toggle(field);
+                        self.resort_columns();
+                    }
+                    None
+                }
+                MouseEventKind::Down(MouseButton::Left)
+                    if self.selected_tab == SelectedTab::Map
+                        && !self.is_modal()
+                        && self.map_bbox.is_ok()
+                        && self.map_area.contains((column, row).into()) =>
+                {
+                    let start = self
+                        .map_view
+                        .bounds()
+                        .cell_to_lon_lat(self.map_area, column, row);
+                    self.map_drag = Some((start, start));
+                    None
+                }
+                MouseEventKind::Drag(MouseButton::Left) => {
+                    if let Some((start, _)) = self.map_drag {
+                        let end =
+                            self.map_view
+                                .bounds()
+                                .cell_to_lon_lat(self.map_area, column, row);
+                        self.map_drag = Some((start, end));
+                        self.status_message =
+                            Some(format!("bbox {} (release to query)", drag_bbox(start, end)));
+                    }
+                    None
+                }
+                MouseEventKind::Up(MouseButton::Left) => {
+                    if let Some((start, end)) = self.map_drag.take() {
+                        self.finish_map_drag(start, end);
+                    }
+                    None
+                }
+                MouseEventKind::ScrollDown if row >= TABS_HEIGHT => Some(Scroll::Down),
+                MouseEventKind::ScrollUp if row >= TABS_HEIGHT => Some(Scroll::Up),
+                _ => None,
+            },
+            Event::Resize(..) => {
+                // the next draw, straight after this event, rewraps
+                // the metadata and recomputes the page size
+                self.clamp_scroll();
+                None
+            }
+            _ => None,
+        };
+
+        if let Some(scroll) = scroll {
+            self.scroll(scroll);
+        }
+    }
+
+    /// Query the features within the bbox dragged out on the map
+    fn finish_map_drag(&mut self, start: (f64, f64), end: (f64, f64)) {
+        // a click, or a drag within a single row or column, has no area to query
+        let dragged = drag_bbox(start, end);
+        if dragged.is_degenerate() {
+            self.status_message = None;
+            return;
+        }
+        let bbox = self
+            .source_crs
+            .clone()
+            .and_then(|crs| dragged.project_from_ratatui_map_crs(&crs));
+        match bbox.and_then(|bbox| self.run_query(bbox)) {
+            Ok(()) => self.status_message = None,
+            Err(e) => self.status_message = Some(e),
+        }
+    }
+
+    /// Query the features within `bbox`, unless it is nowhere near the data,
+    /// in which case there is no point reading the index
+    fn run_query(&mut self, bbox: Bbox) -> Result<(), String> {
+        if let Ok(envelope) = &self.bbox
+            && !envelope.intersects(&bbox)
+        {
+            return Err(DISJOINT_QUERY.to_string());
+        }
+        self.query = Some(BboxQuery::run(
+            &self.source,
+            bbox,
+            self.transfer.clone(),
+            self.pinned_options(),
+        ));
+        Ok(())
+    }
+
+    fn handle_query_key(&mut self, code: KeyCode, clipboard: &mut Clipboard) {
+        let geographic = self.geographic();
+        let Some(query) = &mut self.query else {
+            return;
+        };
+        match query {
+            BboxQuery::Form(form) => match code {
+                KeyCode::Esc => self.query = None,
+                KeyCode::Enter => match form.bbox() {
+                    Ok(bbox) if self.bbox.as_ref().is_ok_and(|e| !e.intersects(&bbox)) => {
+                        form.error = Some(DISJOINT_QUERY.to_string());
+                    }
+                    Ok(bbox) => {
+                        self.query = Some(BboxQuery::run(
+                            &self.source,
+                            bbox,
+                            self.transfer.clone(),
+                            self.pinned_options(),
+                        ))
+                    }
+                    Err(e) => form.error = Some(e),
+                },
+                KeyCode::Tab | KeyCode::Down => form.next_field(),
+                KeyCode::BackTab | KeyCode::Up => form.previous_field(),
+                KeyCode::Backspace => form.pop(),
+                KeyCode::Char(c) => form.push(c),
+                _ => {}
+            },
+            BboxQuery::Running { task, .. } => {
+                if code == KeyCode::Esc {
+                    task.abort();
+                    self.query = None;
+                    self.status_message = Some("query cancelled".to_string());
+                }
+            }
+            BboxQuery::Results(results) => {
+                let len = results.features.len();
+                match code {
+                    KeyCode::Esc | KeyCode::Enter if results.show_detail => {
+                        results.show_detail = false;
+                    }
+                    KeyCode::Down | KeyCode::Char('j') if results.show_detail => {
+                        self.popup_scroll = self.popup_scroll.saturating_add(1);
+                    }
+                    KeyCode::Up | KeyCode::Char('k') if results.show_detail => {
+                        self.popup_scroll = self.popup_scroll.saturating_sub(1);
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') => self.query = None,
+                    KeyCode::Enter if results.selected().is_some() => {
+                        results.show_detail = true;
+                        self.popup_scroll = 0;
+                    }
+                    KeyCode::Char('y') => {
+                        let text = results
+                            .selected()
+                            .map(|feature| feature.to_geojson().to_string());
+                        self.status_message = Some(match text.map(|text| clipboard.copy(&text)) {
+                            Some(Ok(())) => "copied!".to_string(),
+                            Some(Err(e)) => format!("failed to copy: {e}"),
+                            None => "nothing to copy".to_string(),
+                        });
+                    }
+                    KeyCode::Char('b') => {
+                        *query = BboxQuery::Form(BboxForm::new(Some(&results.bbox), geographic));
+                    }
+                    KeyCode::Char('m') if !self.tabs.contains(&SelectedTab::Map) => {
+                        self.status_message =
+                            Some("there is no map without an envelope in the header".to_string());
+                    }
+                    KeyCode::Char('m') => {
+                        let Some(feature) = results.selected() else {
+                            return;
+                        };
+                        // the canvas slows down with tens of thousands of line segments
+                        const MAX_MAP_POSITIONS: usize = 5000;
+                        let selected = results.table.state.selected().unwrap_or(0);
+                        let parts = self.source_crs.clone().and_then(|crs| {
+                            project_parts_to_ratatui_map_crs(&feature.parts(), &crs)
+                        });
+                        match parts {
+                            Ok(parts) => {
+                                self.map_feature = Some(MapFeature {
+                                    label: format!(
+                                        "feature {} of {} in {}",
+                                        selected + 1,
+                                        len,
+                                        results.bbox
+                                    ),
+                                    parts: decimate(parts, MAX_MAP_POSITIONS),
+                                });
+                                self.query = None;
+                                self.selected_tab = SelectedTab::Map;
+                            }
+                            Err(e) => self.status_message = Some(e),
+                        }
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => results.table.next(len),
+                    KeyCode::Up | KeyCode::Char('k') => results.table.previous(len),
+                    KeyCode::PageDown => results.table.page_down(self.page_size, len),
+                    KeyCode::PageUp => results.table.page_up(self.page_size),
+                    KeyCode::Home | KeyCode::Char('g') => results.table.first(),
+                    KeyCode::End | KeyCode::Char('G') => results.table.last(len),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn handle_feature_lookup_key(&mut self, code: KeyCode, clipboard: &mut Clipboard) {
+        let count = self.feature_count();
+        let Some(lookup) = &mut self.feature_lookup else {
+            return;
+        };
+        match lookup {
+            FeatureLookup::Prompt { input, error } => match code {
+                KeyCode::Esc => self.feature_lookup = None,
+                KeyCode::Enter => match FeatureLookup::index(input, count) {
+                    Ok(index) => {
+                        self.feature_lookup = Some(FeatureLookup::run(
+                            &self.source,
+                            index,
+                            self.transfer.clone(),
+                            self.pinned_options(),
+                        ))
+                    }
+                    Err(e) => *error = Some(e),
+                },
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) if c.is_ascii_digit() => input.push(c),
+                _ => {}
+            },
+            FeatureLookup::Running { task, .. } => {
+                if code == KeyCode::Esc {
+                    task.abort();
+                    self.feature_lookup = None;
+                    self.status_message = Some("cancelled".to_string());
+                }
+            }
+            FeatureLookup::Found { feature, .. } => match code {
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => self.feature_lookup = None,
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.popup_scroll = self.popup_scroll.saturating_add(1);
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.popup_scroll = self.popup_scroll.saturating_sub(1);
+                }
+                KeyCode::Char('y') => {
+                    self.status_message =
+                        Some(match clipboard.copy(&feature.to_geojson().to_string()) {
+                            Ok(()) => "copied!".to_string(),
+                            Err(e) => format!("failed to copy: {e}"),
+                        });
+                }
+                KeyCode::Char(':') => *lookup = FeatureLookup::prompt(),
+                _ => {}
+            },
+        }
+    }
+
+    /// How many features the header says there are, if it does
+    fn feature_count(&self) -> Option<usize> {
+        self.dataset
+            .as_ref()
+            .map(|dataset| dataset.header().features_count() as usize)
+            .filter(|&count| count > 0)
+    }
+
+    fn handle_popup_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('v') => {
+                self.column_detail = None;
+                self.show_row_values = false;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.popup_scroll = self.popup_scroll.saturating_add(1);
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.popup_scroll = self.popup_scroll.saturating_sub(1);
+            }
+            _ => {}
+        }
+    }
+
+    /// The search of the selected tab along with the text of the lines it searches
+    fn search(&mut self) -> Option<(&mut SearchState, &[String])> {
+        match self.selected_tab {
+            SelectedTab::Metadata => Some((
+                &mut self.metadata_search,
+                self.metadata_lines
+                    .as_ref()
+                    .map_or(&[][..], |cached| &cached.texts),
+            )),
+            SelectedTab::Columns => Some((&mut self.column_search, &self.column_names)),
+            SelectedTab::Stats | SelectedTab::Map => None,
+        }
+    }
+
+    /// The line of the selected tab which searches start from
+    fn search_position(&self) -> Option<usize> {
+        match self.selected_tab {
+            SelectedTab::Metadata => Some(self.metadata_scroll),
+            SelectedTab::Columns => self.columns_table_state.state.selected(),
+            SelectedTab::Stats | SelectedTab::Map => None,
+        }
+    }
+
+    fn select_search_result(&mut self, i: Option<usize>) {
+        match self.selected_tab {
+            SelectedTab::Metadata => self.metadata_scroll = i.unwrap_or(0),
+            SelectedTab::Columns => self.columns_table_state.state.select(i),
+            SelectedTab::Stats | SelectedTab::Map => {}
+        }
+    }
+
+    fn handle_search_key(&mut self, code: KeyCode) {
+        let Some((search, names)) = self.search() else {
+            return;
+        };
+        let mut selection = None;
+        match code {
+            KeyCode::Esc => selection = Some(search.cancel()),
+            KeyCode::Enter => search.confirm(),
+            KeyCode::Backspace => {
+                search.query.pop();
+            }
+            KeyCode::Char(c) => search.query.push(c),
+            _ => {}
+        }
+        // jump to the first match as the query is typed
+        if search.editing
+            && let Some(i) = search.find(names, 0, true)
+        {
+            selection = Some(Some(i));
+        }
+        if let Some(selection) = selection {
+            self.select_search_result(selection);
+        }
+    }
+
+    /// Handle a key press in normal navigation, returning any scrolling it asks for
+    fn handle_key(&mut self, action: Action, clipboard: &mut Clipboard) -> Option<Scroll> {
+        let selected_tab = self.selected_tab;
+        match action {
+            Action::NextTab => self.selected_tab = selected_tab.next(&self.tabs),
+            Action::PreviousTab => self.selected_tab = selected_tab.previous(&self.tabs),
+            Action::Quit => self.quit = true,
+            Action::Search if selected_tab != SelectedTab::Map => {
+                let position = self.search_position();
+                if let Some((search, _)) = self.search() {
+                    search.start(position);
+                }
+            }
+            Action::NextMatch | Action::PreviousMatch if selected_tab != SelectedTab::Map => {
+                let selected = self.search_position().unwrap_or(0);
+                let found = self.search().and_then(|(search, names)| {
+                    let len = names.len();
+                    if search.query.is_empty() {
+                        None
+                    } else if action == Action::NextMatch {
+                        search.find(names, selected + 1, true)
+                    } else {
+                        search.find(names, selected + len - 1, false)
+                    }
+                });
+                if found.is_some() {
+                    self.select_search_result(found);
+                }
+            }
+            Action::ToggleWkt if selected_tab == SelectedTab::Metadata => {
+                self.wkt_expanded = !self.wkt_expanded;
+                self.metadata_lines = None;
+            }
+            Action::RawValues if selected_tab == SelectedTab::Metadata => {
+                self.raw_values = !self.raw_values;
+                self.metadata_lines = None;
+            }
+            Action::Select if selected_tab == SelectedTab::Metadata => {
+                // the top visible line is the one acted on, as with copying
+                if let Some(cached) = self.metadata_lines.take()
+                    && let Some(Some(path)) = cached.paths.get(self.metadata_scroll)
+                    && let Some(tree) = self.metadata_tree.as_mut()
+                {
+                    tree.toggle(path);
+                }
+            }
+            Action::Select if selected_tab == SelectedTab::Columns => {
+                self.column_detail = self.focused_column();
+                self.popup_scroll = 0;
+            }
+            Action::Select
+                if selected_tab == SelectedTab::Stats
+                    && self.stats.is_none()
+                    && self.dataset.is_some() =>
+            {
+                self.start_stats_scan();
+            }
+            Action::Select
+                if selected_tab == SelectedTab::Stats
+                    && matches!(self.stats, Some(StatsScan::Done(_))) =>
+            {
+                self.size_histogram = !self.size_histogram;
+            }
+            Action::Histogram if selected_tab == SelectedTab::Stats => self.start_histogram(),
+            Action::Sort | Action::ReverseSort if selected_tab == SelectedTab::Columns => {
+                if action == Action::Sort {
+                    self.column_sort.field = self.column_sort.field.next();
+                } else {
+                    self.column_sort.descending = !self.column_sort.descending;
+                }
+                self.resort_columns();
+            }
+            Action::CycleOrder if selected_tab == SelectedTab::Columns => {
+                self.column_sort = ColumnSort {
+                    field: self.column_sort.field.next_order(),
+                    descending: false,
+                };
+                self.resort_columns();
+            }
+            Action::ZoomExtent if selected_tab == SelectedTab::Map => {
+                self.map_view = match &self.map_bbox {
+                    Ok((bbox, _)) if self.map_view == MapView::WORLD => {
+                        MapView::around(bbox, self.map_area)
+                    }
+                    _ => MapView::WORLD,
+                };
+            }
+            Action::Graticule => self.show_graticule = !self.show_graticule,
+            Action::SwapAxes if selected_tab == SelectedTab::Map => {
+                self.swap_axes = !self.swap_axes;
+                self.update_map_bbox();
+                if self.map_view != MapView::WORLD
+                    && let Ok((bbox, _)) = &self.map_bbox
+                {
+                    self.map_view = MapView::around(bbox, self.map_area);
+                }
+            }
+            Action::CountFeatures
+                if self.feature_count.is_none()
+                    && self
+                        .dataset
+                        .as_ref()
+                        .is_some_and(|dataset| dataset.features_count().is_none()) =>
+            {
+                self.start_counting();
+                self.metadata_lines = None;
+            }
+            Action::GeometryTypes
+                if selected_tab == SelectedTab::Metadata
+                    && self.geometry_types.is_none()
+                    && self.dataset.is_some() =>
+            {
+                self.start_geometry_tally();
+                self.metadata_lines = None;
+            }
+            Action::SamplePoints if selected_tab == SelectedTab::Map => {
+                if self.map_sample.is_none() {
+                    self.start_sampling();
+                }
+                self.show_sample = !self.show_sample;
+            }
+            Action::ToggleResolution if selected_tab == SelectedTab::Map => {
+                self.map_resolution = Some(match self.drawn_map_resolution {
+                    MapResolution::High => MapResolution::Low,
+                    MapResolution::Low => MapResolution::High,
+                });
+            }
+            Action::ZoomIn if selected_tab == SelectedTab::Map => self.map_view.zoom(MAP_ZOOM_STEP),
+            Action::ZoomOut if selected_tab == SelectedTab::Map => {
+                self.map_view.zoom(1.0 / MAP_ZOOM_STEP)
+            }
+            Action::ScrollLeft if selected_tab == SelectedTab::Map => {
+                self.map_view.pan(-MAP_PAN_FRACTION, 0.0)
+            }
+            Action::ScrollRight if selected_tab == SelectedTab::Map => {
+                self.map_view.pan(MAP_PAN_FRACTION, 0.0)
+            }
+            Action::ScrollDown if selected_tab == SelectedTab::Map => {
+                self.map_view.pan(0.0, -MAP_PAN_FRACTION)
+            }
+            Action::ScrollUp if selected_tab == SelectedTab::Map => {
+                self.map_view.pan(0.0, MAP_PAN_FRACTION)
+            }
+            Action::Reload => self.reload(),
+            Action::Query if self.dataset.is_some() => {
+                self.query = Some(BboxQuery::Form(BboxForm::new(
+                    self.bbox.as_ref().ok(),
+                    self.geographic(),
+                )));
+            }
+            Action::GoToFeature if self.dataset.is_some() => {
+                self.feature_lookup = Some(FeatureLookup::prompt());
+            }
+            Action::Copy => {
+                let text = match selected_tab {
+                    SelectedTab::Metadata => Some(self.metadata_yank_text.clone()),
+                    SelectedTab::Columns => self
+                        .focused_column()
+                        .and_then(|i| self.columns().get(i).map(|c| c.name().to_string())),
+                    SelectedTab::Stats => match &self.stats {
+                        Some(StatsScan::Done(stats)) => Some(format!("{:#}", stats.to_json())),
+                        _ => None,
+                    },
+                    SelectedTab::Map => self.bbox.as_ref().ok().map(|bbox| bbox.to_string()),
+                };
+                self.status_message = Some(match text.map(|text| clipboard.copy(&text)) {
+                    Some(Ok(())) => "copied!".to_string(),
+                    Some(Err(e)) => format!("failed to copy: {e}"),
+                    None => "nothing to copy".to_string(),
+                });
+            }
+            Action::RequestLog => {
+                self.show_requests = true;
+                // scrolled to the newest requests at the bottom once drawn
+                self.popup_scroll = usize::MAX;
+            }
+            Action::RowValues if selected_tab == SelectedTab::Columns => {
+                self.show_row_values = true;
+                self.popup_scroll = 0;
+            }
+            Action::ExtendedColumns if selected_tab == SelectedTab::Columns => {
+                self.extended_columns = !self.extended_columns;
+            }
+            Action::ScrollLeft if selected_tab == SelectedTab::Columns => {
+                self.columns_hscroll = self.columns_hscroll.saturating_sub(1);
+            }
+            Action::ScrollRight if selected_tab == SelectedTab::Columns => {
+                // clamped against the number of table columns when drawing
+                self.columns_hscroll = self.columns_hscroll.saturating_add(1);
+            }
+            Action::ScrollDown => return Some(Scroll::Down),
+            Action::ScrollUp => return Some(Scroll::Up),
+            Action::PageDown => return Some(Scroll::PageDown),
+            Action::PageUp => return Some(Scroll::PageUp),
+            Action::Top => return Some(Scroll::Top),
+            Action::Bottom => return Some(Scroll::Bottom),
+            _ => {}
+        }
+        None
+    }
+
+    /// Index into the header columns of the column on the focused table row
+    fn focused_column(&self) -> Option<usize> {
+        self.columns_table_state
+            .state
+            .selected()
+            .and_then(|row| self.column_order.get(row).copied())
+    }
+
+    fn scroll(&mut self, scroll: Scroll) {
+        let page_size = self.page_size;
+        let column_count = self.column_order.len();
+        match self.selected_tab {
+            SelectedTab::Metadata => {
+                // drawing clamps the metadata scroll against the current
+                // content length, so it is safe to overshoot here
+                self.metadata_scroll = match scroll {
+                    Scroll::Down => self.metadata_scroll.saturating_add(1),
+                    Scroll::Up => self.metadata_scroll.saturating_sub(1),
+                    Scroll::PageDown => self.metadata_scroll.saturating_add(page_size),
+                    Scroll::PageUp => self.metadata_scroll.saturating_sub(page_size),
+                    Scroll::Top => 0,
+                    Scroll::Bottom => usize::MAX,
+                };
+                self.metadata_scroll_state =
+                    self.metadata_scroll_state.position(self.metadata_scroll);
+            }
+            SelectedTab::Columns => {
+                let table = &mut self.columns_table_state;
+                match scroll {
+                    Scroll::Down => table.next(column_count),
+                    Scroll::Up => table.previous(column_count),
+                    Scroll::PageDown => table.page_down(page_size, column_count),
+                    Scroll::PageUp => table.page_up(page_size),
+                    Scroll::Top => table.first(),
+                    Scroll::Bottom => table.last(column_count),
+                }
+            }
+            SelectedTab::Stats => {
+                let count = match &self.stats {
+                    Some(StatsScan::Done(stats)) => stats.columns.len(),
+                    _ => 0,
+                };
+                let table = &mut self.stats_table_state;
+                match scroll {
+                    Scroll::Down => table.next(count),
+                    Scroll::Up => table.previous(count),
+                    Scroll::PageDown => table.page_down(page_size, count),
+                    Scroll::PageUp => table.page_up(page_size),
+                    Scroll::Top => table.first(),
+                    Scroll::Bottom => table.last(count),
+                }
+            }
+            SelectedTab::Map => {}
+        }
+    }
+}
+
+/// The bbox with two opposite corners at `start` and `end`
+fn drag_bbox(start: (f64, f64), end: (f64, f64)) -> Bbox {
+    Bbox::new(
+        start.0.min(end.0),
+        start.1.min(end.1),
+        start.0.max(end.0),
+        start.1.max(end.1),
+    )
+}
+
+/// The size of a bbox on the map in degrees, and roughly in kilometers
+fn envelope_dimensions(bbox: &Bbox) -> String {
+    let (width_km, height_km) = bbox.approx_dimensions_km(bbox.center().1);
+    format!(
+        "{:.4}° × {:.4}°, approx. {width_km:.1} × {height_km:.1} km, {:.1} km²",
+        bbox.width(),
+        bbox.height(),
+        bbox.approx_area_km2()
+    )
+}
+
+/// Draw how many features there are of each geometry type as bars labelled with the
+/// count and percentage, or as lines of text where there isn't room for the bars
+fn draw_geometry_types(f: &mut Frame, area: Rect, stats: &Stats, theme: &Theme) {
+    // the fewest columns worth drawing as bars, beyond the labels and counts
+    const MIN_BAR_WIDTH: u16 = 10;
+    let counts: Vec<(&str, String, &GeometryStats)> = stats
+        .geometry_types
+        .iter()
+        .map(|(name, types)| {
+            let count = types.vertices.features;
+            let percent = 100.0 * count as f64 / stats.features.max(1) as f64;
+            (name.as_str(), format!("{count} ({percent:.1}%)"), types)
+        })
+        .collect();
+    // the labels, the counts, a space between them and the borders
+    let label_width = counts.iter().map(|(name, ..)| name.width()).max();
+    let count_width = counts.iter().map(|(_, count, _)| count.width()).max();
+    let chrome = label_width.unwrap_or(0) + count_width.unwrap_or(0) + 3;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Geometry types");
+    if area.width < chrome as u16 + MIN_BAR_WIDTH {
+        let lines: Vec<Line> = counts
+            .iter()
+            .map(|(name, count, types)| {
+                let text = format!(
+                    "{count}, mean {:.1} vertices, mean {:.1} bytes",
+                    types.vertices.mean(),
+                    types.bytes.mean()
+                );
+                info_line(theme, name, &text)
+            })
+            .collect();
+        f.render_widget(Paragraph::new(lines).block(block), area);
+        return;
+    }
+    let bars: Vec<Bar> = counts
+        .into_iter()
+        .map(|(name, count, types)| {
+            Bar::with_label(name, types.vertices.features as u64).text_value(count)
+        })
+        .collect();
+    let chart = BarChart::horizontal(bars)
+        .bar_width(1)
+        .bar_gap(0)
+        .bar_style(theme.highlight)
+        .label_style(theme.label)
+        .block(block);
+    f.render_widget(chart, area);
+}
+
+/// Draw the size of the features in file order, with each column of the sparkline the
+/// largest of a run of features, so that a few huge features stand out from the rest
+fn draw_sizes(f: &mut Frame, area: Rect, stats: &Stats, theme: &Theme) {
+    let sizes = &stats.sizes;
+    let buckets = sizes.buckets(area.width.saturating_sub(2).into());
+    let summary = match sizes.median() {
+        Some(median) => format!(
+            "min {} · median {} · max {}",
+            ByteSize(stats.geometry.bytes.min),
+            ByteSize(median.into()),
+            ByteSize(stats.geometry.bytes.max)
+        ),
+        None => "no features".to_string(),
+    };
+    let per_column = match buckets.len() {
+        0 => String::new(),
+        columns => format!(", about {} per column", sizes.len().div_ceil(columns)),
+    };
+    let sparkline = Sparkline::default()
+        .data(&buckets)
+        .style(theme.highlight)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Feature sizes in file order{per_column}"))
+                .title_bottom(summary),
+        );
+    f.render_widget(sparkline, area);
+}
+
+/// Draw how many features are 1 B, 2 B, 4 B and so on up to the largest, with the
+/// bars spread across the width of `area`
+fn draw_size_histogram(f: &mut Frame, area: Rect, stats: &Stats, theme: &Theme) {
+    let classes = &stats.geometry.size_classes;
+    // from the smallest features rather than from 1 B, which none are
+    let first = classes.iter().position(|&count| count > 0).unwrap_or(0);
+    let bars: Vec<Bar> = classes[first..]
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| Bar::with_label(short_size(1 << (first + i)), count as u64))
+        .collect();
+    let width = area.width.saturating_sub(2) / bars.len().max(1) as u16;
+    let chart = BarChart::vertical(bars)
+        .bar_width(width.saturating_sub(1).max(1))
+        .bar_gap(1)
+        .bar_style(theme.highlight)
+        .label_style(theme.label)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Features by size in powers of two"),
+        );
+    f.render_widget(chart, area);
+}
+
+/// Like `512`, `4K` or `2G`, to fit under a narrow bar
+fn short_size(bytes: u64) -> String {
+    match bytes.ilog2() / 10 {
+        0 => bytes.to_string(),
+        1 => format!("{}K", bytes >> 10),
+        2 => format!("{}M", bytes >> 20),
+        _ => format!("{}G", bytes >> 30),
+    }
+}
+
+/// The row each of `lines` starts on once a paragraph wraps them to `width`, followed by
+/// the number of rows of all of them
+fn row_starts(lines: &[Line], width: usize) -> Vec<usize> {
+    let mut rows = 0;
+    let mut starts = Vec::with_capacity(lines.len() + 1);
+    for line in lines {
+        starts.push(rows);
+        rows += Paragraph::new(line.clone())
+            .wrap(Wrap { trim: false })
+            .line_count(width as u16)
+            .max(1);
+    }
+    starts.push(rows);
+    starts
+}
+
+/// Draw a spinner while the file is being opened
+fn draw_loading(f: &mut Frame, area: Rect, source: &str, started: Instant, theme: &Theme) {
+    let spinner = theme.symbols.spinner;
+    let elapsed = started.elapsed();
+    let frame = spinner[(elapsed.as_millis() / 100) as usize % spinner.len()];
+    let verb = if is_remote_file(source) {
+        "Fetching"
+    } else {
+        "Reading"
+    };
+
+    let message = Paragraph::new(format!(
+        "{frame} {verb} header from {source}… {:.1}s",
+        elapsed.as_secs_f64()
+    ))
+    .wrap(Wrap { trim: true })
+    .block(Block::default().borders(Borders::ALL).title("Loading"));
+    f.render_widget(message, area);
+}
+
+/// Draw the `/` search prompt with the cursor at the end of the query
+fn render_search_prompt(f: &mut Frame, area: Rect, query: &str) {
+    let prompt = format!("/{query}");
+    f.set_cursor_position((area.x + prompt.chars().count() as u16, area.y));
+    f.render_widget(Paragraph::new(prompt), area);
+}
+
+/// Draw a scrollable, wrapping popup centered over `area`
+fn render_popup(f: &mut Frame, area: Rect, title: &str, lines: Vec<Line>, scroll: &mut usize) {
+    let popup_area = area.centered(Constraint::Percentage(70), Constraint::Percentage(70));
+    *scroll = (*scroll).min(lines.len().saturating_sub(1));
+
+    let popup = Paragraph::new(lines)
+        // without trimming so that indented lines keep their indentation
+        .wrap(Wrap { trim: false })
+        .scroll((*scroll as u16, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title.to_string()),
+        );
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup, popup_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_starts_count_the_rows_lines_wrap_onto() {
+        let lines = [
+            Line::from("short"),
+            Line::from("a line long enough to wrap onto three rows"),
+            Line::default(),
+            Line::from("last"),
+        ];
+        assert_eq!(row_starts(&lines, 16), [0, 1, 4, 5, 6]);
+        // nothing wraps when there's room
+        assert_eq!(row_starts(&lines, 80), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn drag_bbox_orders_the_corners() {
+        let bbox = drag_bbox((-105.0, 40.0), (-102.0, 38.0));
+        assert_eq!(bbox.to_string(), "-105,38,-102,40");
+        let bbox = drag_bbox((-102.0, 38.0), (-105.0, 40.0));
+        assert_eq!(bbox.to_string(), "-105,38,-102,40");
+        // a click without a drag has no area to query
+        assert!(drag_bbox((-105.0, 40.0), (-105.0, 40.0)).is_degenerate());
+    }
+
+    fn view_options() -> ViewOptions {
+        ViewOptions {
+            sample_size: 100,
+            crs_checks: true,
+            concurrency: 1,
+            open: OpenOptions::default(),
+        }
+    }
+
+    /// A view of `testdata/<name>` with its dataset loaded
+    async fn loaded_view(name: &str) -> FileView {
+        let source = format!("testdata/{name}");
+        let mut view = FileView::new(&source, view_options());
+        let dataset = Dataset::open(&source, Arc::default(), OpenOptions::default())
+            .await
+            .unwrap();
+        view.set_dataset(dataset);
+        view
+    }
+
+    #[tokio::test]
+    async fn queries_outside_the_envelope_are_skipped() {
+        let mut view = loaded_view("colorado_subset.fgb").await;
+        // the data is all in the south west corner of Colorado
+        let denver = Bbox::new(-105.1, 39.6, -104.6, 39.9);
+        assert_eq!(view.run_query(denver), Err(DISJOINT_QUERY.to_string()));
+        assert!(view.query.is_none());
+        let four_corners = Bbox::new(-109.5, 37.0, -109.0, 37.3);
+        assert_eq!(view.run_query(four_corners), Ok(()));
+        assert!(view.query.is_some());
+    }
+
+    #[tokio::test]
+    async fn files_are_opened_ahead_a_few_at_a_time() {
+        let sources = vec!["testdata/colorado_subset.fgb".to_string(); 5];
+        let options = ViewOptions {
+            concurrency: 2,
+            ..view_options()
+        };
+        let mut app = App::new(&sources, Theme::default(), Keymap::default(), options);
+        let loading = |app: &App| app.views.iter().filter(|v| v.loading.is_some()).count();
+        assert_eq!(loading(&app), 2);
+        // as files finish opening, the next are started, until every one has been
+        let deadline = Instant::now() + std::time::Duration::from_secs(10);
+        while app.views.iter().any(|view| view.dataset.is_none()) {
+            assert!(
+                Instant::now() < deadline,
+                "the files never finished opening"
+            );
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            app.poll_loading();
+            assert!(loading(&app) <= 2);
+        }
+        assert_eq!(app.next_prefetch, sources.len());
+    }
+
+    #[tokio::test]
+    async fn the_reload_key_reopens_a_file_changed_on_the_server() {
+        let keymap = Keymap::default();
+        let mut clipboard = Clipboard::new();
+        let press = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        let reload = KeyCode::Char('r');
+        assert_eq!(
+            keymap.action(reload, KeyModifiers::NONE, SelectedTab::Metadata),
+            Some(Action::Reload)
+        );
+
+        let mut view = loaded_view("colorado_subset.fgb").await;
+        view.error = Some("the request timed out".to_string());
+        view.handle_event(press(reload), &mut clipboard, &keymap);
+        // other errors keep the modal up until dismissed
+        assert!(view.error.is_some() && view.loading.is_none());
+
+        view.error = Some(crate::http::file_changed("https://example.com/data.fgb"));
+        view.handle_event(press(reload), &mut clipboard, &keymap);
+        assert!(view.error.is_none());
+        assert!(view.loading.is_some());
+    }
+
+    #[tokio::test]
+    async fn the_go_to_feature_prompt_shows_the_feature() {
+        let keymap = Keymap::default();
+        let mut clipboard = Clipboard::new();
+        let press = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        let mut view = loaded_view("colorado_subset.fgb").await;
+        let go_to = |index: &str| {
+            let keys = std::iter::once(':').chain(index.chars()).map(KeyCode::Char);
+            keys.chain([KeyCode::Enter]).map(press).collect::<Vec<_>>()
+        };
+
+        // past the end of the file is caught before anything is read
+        for event in go_to("370") {
+            view.handle_event(event, &mut clipboard, &keymap);
+        }
+        let Some(FeatureLookup::Prompt { error, .. }) = &view.feature_lookup else {
+            panic!("the prompt should stay open");
+        };
+        assert_eq!(
+            error.as_deref(),
+            Some("there are 370 features, from 0 to 369")
+        );
+        view.handle_event(press(KeyCode::Esc), &mut clipboard, &keymap);
+        assert!(view.feature_lookup.is_none());
+
+        for event in go_to("369") {
+            view.handle_event(event, &mut clipboard, &keymap);
+        }
+        assert!(view.is_modal());
+        let deadline = Instant::now() + std::time::Duration::from_secs(10);
+        while matches!(view.feature_lookup, Some(FeatureLookup::Running { .. })) {
+            assert!(Instant::now() < deadline, "the feature was never read");
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            view.poll_loading();
+        }
+        let Some(FeatureLookup::Found { index, feature }) = &view.feature_lookup else {
+            panic!("{:?}", view.error);
+        };
+        assert_eq!(*index, 369);
+        let expected = dataset::feature_at(
+            "testdata/colorado_subset.fgb",
+            369,
+            Arc::default(),
+            OpenOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(feature.to_geojson(), expected.to_geojson());
+        view.handle_event(press(KeyCode::Esc), &mut clipboard, &keymap);
+        assert!(view.feature_lookup.is_none());
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The responses to the range requests made while reading the header of a remote file,
+/// which are replayed instead of fetched again while the server says the file is unchanged
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct CachedHeader {
+    url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub content_length: Option<u64>,
+    ranges: Vec<CachedRange>,
+    /// The bodies of all of `ranges`, one after another
+    #[serde(skip)]
+    body: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CachedRange {
+    /// As requested, like `bytes=8-11`
+    range: String,
+    /// The Content-Range of the response, which holds the size of the whole file
+    content_range: Option<String>,
+    offset: usize,
+    len: usize,
+}
+
+impl CachedHeader {
+    /// An empty entry for `url`, if the server sent a validator to check it against later
+    pub fn new(
+        url: &str,
+        etag: Option<String>,
+        last_modified: Option<String>,
+        content_length: Option<u64>,
+    ) -> Option<Self> {
+        if etag.is_none() && last_modified.is_none() {
+            return None;
+        }
+        Some(Self {
+            url: url.to_string(),
+            etag,
+            last_modified,
+            content_length,
+            ..Default::default()
+        })
+    }
+
+    /// Remember the response to `range`
+    pub fn record(&mut self, range: &str, content_range: Option<String>, bytes: &[u8]) {
+        self.ranges.push(CachedRange {
+            range: range.to_string(),
+            content_range,
+            offset: self.body.len(),
+            len: bytes.len(),
+        });
+        self.body.extend_from_slice(bytes);
+    }
+
+    /// The Content-Range and body of the remembered response to `range`, if any
+    pub fn response(&self, range: &str) -> Option<(Option<&str>, Bytes)> {
+        let cached = self.ranges.iter().find(|cached| cached.range == range)?;
+        let body = self.body.get(cached.offset..cached.offset + cached.len)?;
+        Some((
+            cached.content_range.as_deref(),
+            Bytes::copy_from_slice(body),
+        ))
+    }
+}
+
+/// `$XDG_CACHE_HOME/fgbdump/headers`, falling back to `~/.cache`
+pub fn default_dir() -> Option<PathBuf> {
+    let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(cache_dir.join("fgbdump").join("headers"))
+}
+
+/// The file name for `url`, from its 64 bit FNV-1a hash, which unlike the standard
+/// library's hasher is the same from one build to the next
+fn file_name(url: &str) -> String {
+    let hash = url.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{hash:016x}.header")
+}
+
+/// The cached header of `url` in `dir`, if there is a readable one. A different URL
+/// with the same hash, or a file from another version of fgbdump, counts as a miss
+pub fn load(dir: &Path, url: &str) -> Option<CachedHeader> {
+    let data = std::fs::read(dir.join(file_name(url))).ok()?;
+    let split = data.iter().position(|&byte| byte == b'\n')?;
+    let mut entry: CachedHeader = serde_json::from_slice(&data[..split]).ok()?;
+    entry.body = data[split + 1..].to_vec();
+    let complete = entry
+        .ranges
+        .iter()
+        .all(|cached| cached.offset + cached.len <= entry.body.len());
+    (entry.url == url && complete).then_some(entry)
+}
+
+/// Save `entry` in `dir` as a line of JSON followed by the bodies. It is written to a
+/// temporary file first and renamed into place, so that another fgbdump reading or
+/// writing the same entry at the same time sees either the old or the new one whole
+pub fn store(dir: &Path, entry: &CachedHeader) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let name = file_name(&entry.url);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.subsec_nanos());
+    let temp = dir.join(format!("{name}.{}.{nanos}.tmp", std::process::id()));
+    let write = || {
+        let mut file = std::fs::File::create(&temp)?;
+        serde_json::to_writer(&mut file, entry)?;
+        file.write_all(b"\n")?;
+        file.write_all(&entry.body)?;
+        file.sync_all()?;
+        std::fs::rename(&temp, dir.join(&name))
+    };
+    write().inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })
+}
+
+/// Delete every cached header in `dir`, and any temporary file left by a [`store`] that
+/// was cut short, returning how many headers there were. Anything else is left alone
+pub fn clear(dir: &Path) -> std::io::Result<usize> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let mut removed = 0;
+    for entry in entries {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let path = entry.path();
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("header") => removed += 1,
+            Some("tmp") => {}
+            _ => continue,
+        }
+        std::fs::remove_file(path)?;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory of its own for `test`
+    fn cache_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("fgbdump-{}-cache-{test}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    const URL: &str = "https://example.com/data.fgb";
+
+    fn entry(url: &str) -> CachedHeader {
+        let mut entry = CachedHeader::new(
+            url,
+            Some("\"v1\"".to_string()),
+            Some("Fri, 16 Oct 2026 00:00:00 GMT".to_string()),
+            Some(1234),
+        )
+        .unwrap();
+        entry.record(
+            "bytes=0-7",
+            Some("bytes 0-7/1234".to_string()),
+            b"fgb\x03fgb\x01",
+        );
+        entry.record("bytes=8-11", None, &[16, 0, 0, 0]);
+        entry
+    }
+
+    #[test]
+    fn entries_need_a_validator() {
+        assert!(CachedHeader::new(URL, None, None, Some(1234)).is_none());
+        assert!(CachedHeader::new(URL, Some("\"v1\"".to_string()), None, None).is_some());
+    }
+
+    #[test]
+    fn stored_headers_load_as_they_were() {
+        let dir = cache_dir("round-trip");
+        assert!(load(&dir, URL).is_none());
+        store(&dir, &entry(URL)).unwrap();
+        let loaded = load(&dir, URL).unwrap();
+        assert_eq!(loaded.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(
+            loaded.last_modified.as_deref(),
+            Some("Fri, 16 Oct 2026 00:00:00 GMT")
+        );
+        assert_eq!(loaded.content_length, Some(1234));
+        assert_eq!(
+            loaded.response("bytes=0-7"),
+            Some((
+                Some("bytes 0-7/1234"),
+                Bytes::from_static(b"fgb\x03fgb\x01")
+            ))
+        );
+        assert_eq!(
+            loaded.response("bytes=8-11"),
+            Some((None, Bytes::from_static(&[16, 0, 0, 0])))
+        );
+        assert_eq!(loaded.response("bytes=12-15"), None);
+        // stored again over the first, without leaving temporary files behind
+        store(&dir, &entry(URL)).unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn another_url_with_the_same_hash_is_a_miss() {
+        let dir = cache_dir("collision");
+        let other = "https://example.com/other.fgb";
+        store(&dir, &entry(other)).unwrap();
+        // as if the hash of the other URL were the same
+        std::fs::rename(dir.join(file_name(other)), dir.join(file_name(URL))).unwrap();
+        assert!(load(&dir, URL).is_none());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn truncated_entries_are_a_miss() {
+        let dir = cache_dir("truncated");
+        store(&dir, &entry(URL)).unwrap();
+        let path = dir.join(file_name(URL));
+        let data = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &data[..data.len() - 1]).unwrap();
+        assert!(load(&dir, URL).is_none());
+        // cut off in the middle of the JSON
+        std::fs::write(&path, &data[..20]).unwrap();
+        assert!(load(&dir, URL).is_none());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn clearing_deletes_only_headers_and_temporary_files() {
+        let dir = cache_dir("clear");
+        assert_eq!(clear(&dir).unwrap(), 0);
+        store(&dir, &entry(URL)).unwrap();
+        store(&dir, &entry("https://example.com/other.fgb")).unwrap();
+        let temp = dir.join(format!("{}.1.2.tmp", file_name(URL)));
+        std::fs::write(&temp, b"{").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"mine").unwrap();
+        std::fs::create_dir(dir.join("nested.header")).unwrap();
+
+        assert_eq!(clear(&dir).unwrap(), 2);
+        assert!(load(&dir, URL).is_none());
+        assert!(!temp.exists());
+        assert!(dir.join("notes.txt").exists());
+        assert!(dir.join("nested.header").is_dir());
+        assert_eq!(clear(&dir).unwrap(), 0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,445 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use bytes::Bytes;
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    dataset::{self, Dataset, IndexLayout, NODE_ITEM_SIZE, OpenOptions},
+    http::TransferStats,
+    source::{RangeSource, open_range_source},
+    stats::Sample,
+};
+
+/// How many features `check` reads without `--full`
+pub const SAMPLE_FEATURES: usize = 1000;
+
+/// How much of the feature section is read at a time while walking it, so that a remote
+/// file takes a request per chunk rather than per feature
+const WALK_CHUNK: u64 = 1 << 20;
+
+/// What `check` found wrong with a file
+#[derive(Debug, Default)]
+pub struct Report {
+    /// One message for each problem found
+    pub problems: Vec<String>,
+    /// Problems which don't fail the check, with `--lenient`
+    pub warnings: Vec<String>,
+    /// What else was found which isn't a problem as such, like whether the features
+    /// are spatially sorted
+    pub notes: Vec<String>,
+    /// Which features were read, like `the first 1000 features`
+    pub read: String,
+}
+
+/// Look for problems with `source`, reading only its first features unless `full`.
+/// With `lenient`, duplicate and empty column names are only warnings. `progress` counts
+/// the features read so far
+pub async fn check(
+    source: &str,
+    full: bool,
+    lenient: bool,
+    progress: &AtomicUsize,
+    transfer: Arc<TransferStats>,
+    options: OpenOptions,
+) -> Result<Report, String> {
+    let dataset = Dataset::open(source, transfer.clone(), options.clone()).await?;
+    let mut options = options;
+    options.http.version = dataset.version.clone();
+    let mut report = Report::default();
+
+    let column_names = dataset.column_name_problems().warnings();
+    if lenient {
+        report.warnings.extend(column_names);
+    } else {
+        report.problems.extend(column_names);
+    }
+
+    let features = dataset.header().features_count();
+    let layout = dataset.index_layout();
+    // walking the features with --full decodes them too, so that they're read only once
+    let mut walked = None;
+    if let Some(header_size) = dataset.header_size
+        && (layout.is_some() || full)
+    {
+        let range_source = open_range_source(source, transfer.clone(), &options).await?;
+        if let Some(layout) = layout {
+            report
+                .problems
+                .extend(check_index(&*range_source, header_size, layout, features).await?);
+        }
+        // the header is trusted otherwise
+        if full {
+            let start = 12 + header_size + layout.map_or(0, |layout| layout.bytes);
+            let mut sample = Sample::new(&dataset.header());
+            report.problems.extend(
+                walk_features(&*range_source, start, features, &mut sample, progress).await?,
+            );
+            walked = Some(sample);
+        }
+    }
+
+    let sample = match walked {
+        Some(sample) => sample,
+        None => {
+            let limit = (!full).then_some(SAMPLE_FEATURES);
+            dataset::scan_sample(source, limit, progress, transfer, options).await?
+        }
+    };
+    let types = sample.types;
+    report.read = types.read();
+    if let Some(sortedness) = sample.sortedness {
+        report
+            .notes
+            .push(format!("spatially sorted: {}", sortedness.text()));
+    }
+    for (name, count) in types.mismatched() {
+        report.problems.push(format!(
+            "The header says the features are {:?}, but {count} of {} are {name}",
+            types.declared,
+            types.read()
+        ));
+    }
+    Ok(report)
+}
+
+/// Whether the index takes up the bytes its layout says between the header and the
+/// first feature, which readers rely on to find the features
+async fn check_index(
+    source: &dyn RangeSource,
+    header_size: u64,
+    layout: IndexLayout,
+    features: u64,
+) -> Result<Vec<String>, String> {
+    // the magic bytes and the length prefix of the header come before it
+    let start = 12 + header_size;
+    let end = start + layout.bytes;
+    if let Some(size) = source.total_size()
+        && size < end
+    {
+        return Ok(vec![format!(
+            "The index of {features} features with a node size of {} should take up {} bytes \
+             after the header, but the file ends {} bytes after it",
+            layout.node_size,
+            layout.bytes,
+            size.saturating_sub(start)
+        )]);
+    }
+    let mut problems = Vec::new();
+    // the leaves come last, in the same order as the features, so the first of them
+    // points at the start of the features
+    let first_leaf = end - features * NODE_ITEM_SIZE;
+    let leaf = source.read_range(first_leaf, NODE_ITEM_SIZE).await?;
+    if let Some(offset) = leaf.get(32..40)
+        && let Ok(offset) = <[u8; 8]>::try_from(offset)
+        && u64::from_le_bytes(offset) != 0
+    {
+        problems.push(format!(
+            "The first leaf of the index, at byte {first_leaf}, points {} bytes into the \
+             features rather than at the first of them",
+            u64::from_le_bytes(offset)
+        ));
+    }
+    let prefix = source.read_range(end, 4).await?;
+    let Ok(prefix) = <[u8; 4]>::try_from(&prefix[..]) else {
+        problems.push(format!(
+            "The index should end at byte {end}, {} bytes after the header, but there are no \
+             features after it",
+            layout.bytes
+        ));
+        return Ok(problems);
+    };
+    let length = u64::from(u32::from_le_bytes(prefix));
+    if length == 0 {
+        problems.push(format!(
+            "The first feature, at byte {end} after an index of {} bytes, has a length of 0, \
+             so the index may not be the size its node size and feature count say",
+            layout.bytes
+        ));
+    } else if let Some(size) = source.total_size()
+        && end + 4 + length > size
+    {
+        problems.push(format!(
+            "The first feature, at byte {end} after an index of {} bytes, says it's {length} \
+             bytes long, which runs past the end of the file at byte {size}",
+            layout.bytes
+        ));
+    }
+    Ok(problems)
+}
+
+/// Walk the features from `start` by their length prefixes, checking that there are
+/// `expected` of them, or any number if the header says 0, that none has a length of 0,
+/// runs past the end of the file or isn't a feature, and that the last ends where the
+/// file does. Each feature is added to `sample` on the way; after a problem which stops
+/// the walk, `sample` has those before it. `progress` counts the features walked so far
+async fn walk_features(
+    source: &dyn RangeSource,
+    start: u64,
+    expected: u64,
+    sample: &mut Sample,
+    progress: &AtomicUsize,
+) -> Result<Vec<String>, String> {
+    let size = source.total_size();
+    let mut chunk = Bytes::new();
+    let mut chunk_start = start;
+    let mut offset = start;
+    let mut count: u64 = 0;
+    let stop = |sample: &mut Sample, problem: String| {
+        sample.types.sampled = true;
+        Ok(vec![problem])
+    };
+    loop {
+        // the next length prefix, from the chunk if it's in it
+        if (offset - chunk_start) as usize + 4 > chunk.len() {
+            chunk = source.read_range(offset, WALK_CHUNK).await?;
+            chunk_start = offset;
+        }
+        let at = (offset - chunk_start) as usize;
+        let prefix = chunk.get(at..chunk.len().min(at + 4)).unwrap_or_default();
+        if prefix.is_empty() {
+            break;
+        }
+        let Ok(prefix) = <[u8; 4]>::try_from(prefix) else {
+            return stop(
+                sample,
+                format!(
+                    "The file ends partway through the length of feature {count}, at byte \
+                     {offset}, {} bytes after the last feature",
+                    prefix.len()
+                ),
+            );
+        };
+        let length = u64::from(u32::from_le_bytes(prefix));
+        if length == 0 {
+            return stop(
+                sample,
+                format!("Feature {count}, at byte {offset}, has a length of 0"),
+            );
+        }
+        let end = offset + 4 + length;
+        if let Some(size) = size
+            && end > size
+        {
+            return stop(
+                sample,
+                format!(
+                    "Feature {count}, at byte {offset}, says it's {length} bytes long, which \
+                     runs past the end of the file at byte {size}"
+                ),
+            );
+        }
+        // the whole feature, which may run past the chunk
+        if (end - chunk_start) as usize > chunk.len() {
+            chunk = source
+                .read_range(offset, WALK_CHUNK.max(4 + length))
+                .await?;
+            chunk_start = offset;
+        }
+        let at = (offset - chunk_start) as usize;
+        let Some(bytes) = chunk.get(at..at + 4 + length as usize) else {
+            return stop(
+                sample,
+                format!(
+                    "Feature {count}, at byte {offset}, says it's {length} bytes long, but the \
+                     file ends {} bytes into it",
+                    chunk.len() - at - 4
+                ),
+            );
+        };
+        match flatgeobuf::size_prefixed_root_as_feature(bytes) {
+            Ok(feature) => sample.add_geometry(feature.geometry().as_ref()),
+            Err(e) => {
+                return stop(
+                    sample,
+                    format!("Feature {count}, at byte {offset}, isn't a feature: {e}"),
+                );
+            }
+        }
+        progress.fetch_add(1, Ordering::Relaxed);
+        offset = end;
+        count += 1;
+    }
+    let mut problems = Vec::new();
+    if expected > 0 && count != expected {
+        problems.push(format!(
+            "The header says there are {expected} features, but there are {count}, the last \
+             of which ends at byte {offset}"
+        ));
+    }
+    if let Some(size) = size
+        && offset != size
+    {
+        problems.push(format!(
+            "The last of {count} features ends at byte {offset}, but the file is {size} bytes"
+        ));
+    }
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = "testdata/colorado_subset.fgb";
+
+    /// The bytes of the fixture, and where each of its features starts
+    fn fixture() -> (Vec<u8>, Vec<usize>) {
+        let bytes = std::fs::read(FIXTURE).unwrap();
+        let header_size = u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize;
+        let layout = IndexLayout::new(370, 16).unwrap();
+        let mut offset = 12 + header_size + layout.bytes as usize;
+        let mut offsets = Vec::new();
+        while offset < bytes.len() {
+            offsets.push(offset);
+            offset +=
+                4 + u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize;
+        }
+        assert_eq!(offsets.len(), 370);
+        (bytes, offsets)
+    }
+
+    /// The problems `check --full` finds in a file of `bytes`
+    async fn problems(name: &str, bytes: &[u8]) -> Vec<String> {
+        let path = std::env::temp_dir().join(format!("fgbdump-{}-{name}.fgb", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        let progress = AtomicUsize::new(0);
+        let source = path.to_str().unwrap();
+        let report = check(
+            source,
+            true,
+            false,
+            &progress,
+            Arc::default(),
+            OpenOptions::default(),
+        )
+        .await
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        report.problems
+    }
+
+    #[tokio::test]
+    async fn the_fixture_passes() {
+        let (bytes, _) = fixture();
+        assert_eq!(
+            problems("check-fixture", &bytes).await,
+            Vec::<String>::new()
+        );
+    }
+
+    #[tokio::test]
+    async fn a_truncated_length_prefix() {
+        let (bytes, offsets) = fixture();
+        let last = offsets[369];
+        assert_eq!(
+            problems("check-truncated-prefix", &bytes[..last + 2]).await,
+            [format!(
+                "The file ends partway through the length of feature 369, at byte {last}, 2 \
+                 bytes after the last feature"
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn a_zero_length_feature() {
+        let (mut bytes, offsets) = fixture();
+        let fifth = offsets[5];
+        bytes[fifth..fifth + 4].copy_from_slice(&[0; 4]);
+        assert_eq!(
+            problems("check-zero-length", &bytes).await,
+            [format!("Feature 5, at byte {fifth}, has a length of 0")]
+        );
+    }
+
+    #[tokio::test]
+    async fn a_feature_running_past_the_end() {
+        let (bytes, offsets) = fixture();
+        let last = offsets[369];
+        let length = bytes.len() - last - 4;
+        let size = bytes.len() - 10;
+        assert_eq!(
+            problems("check-past-the-end", &bytes[..size]).await,
+            [format!(
+                "Feature 369, at byte {last}, says it's {length} bytes long, which runs past \
+                 the end of the file at byte {size}"
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn fewer_features_than_the_header_says() {
+        let (bytes, offsets) = fixture();
+        let last = offsets[369];
+        assert_eq!(
+            problems("check-count", &bytes[..last]).await,
+            [format!(
+                "The header says there are 370 features, but there are 369, the last of which \
+                 ends at byte {last}"
+            )]
+        );
+    }
+
+    /// The bytes of a file whose size isn't known until reading past the end, as with a
+    /// server which doesn't say
+    struct UnsizedSource(Bytes);
+
+    #[async_trait::async_trait]
+    impl RangeSource for UnsizedSource {
+        async fn read_range(&self, offset: u64, length: u64) -> Result<Bytes, String> {
+            let start = (offset as usize).min(self.0.len());
+            let end = (offset + length).min(self.0.len() as u64) as usize;
+            Ok(self.0.slice(start..end))
+        }
+
+        fn total_size(&self) -> Option<u64> {
+            None
+        }
+    }
+
+    /// Walk the first `end` of `bytes` as a file of unknown size, giving the problems
+    /// found and which features were read
+    async fn walk_unsized(bytes: &[u8], start: u64, end: usize) -> (Vec<String>, String) {
+        let source = UnsizedSource(Bytes::copy_from_slice(&bytes[..end]));
+        let header = flatgeobuf::size_prefixed_root_as_header(&bytes[8..]).unwrap();
+        let mut sample = Sample::new(&header);
+        let progress = AtomicUsize::new(0);
+        let problems = walk_features(&source, start, 370, &mut sample, &progress)
+            .await
+            .unwrap();
+        (problems, sample.types.read())
+    }
+
+    #[tokio::test]
+    async fn truncation_is_found_without_knowing_the_size() {
+        let (bytes, offsets) = fixture();
+        let last = offsets[369];
+        let start = offsets[0] as u64;
+        let walk = |end: usize| walk_unsized(&bytes, start, end);
+        assert_eq!(
+            walk(bytes.len()).await,
+            (vec![], "all 370 features".to_string())
+        );
+        assert_eq!(
+            walk(last + 3).await,
+            (
+                vec![format!(
+                    "The file ends partway through the length of feature 369, at byte {last}, \
+                     3 bytes after the last feature"
+                )],
+                "the first 369 features".to_string()
+            )
+        );
+        assert_eq!(
+            walk(last + 100).await.0,
+            [format!(
+                "Feature 369, at byte {last}, says it's {} bytes long, but the file ends 96 \
+                 bytes into it",
+                bytes.len() - last - 4
+            )]
+        );
+    }
+}
@@ -0,0 +1,35 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use crossterm::{clipboard::CopyToClipboard, execute};
+
+/// Copies text to the system clipboard; when there is no display clipboard,
+/// such as in an SSH session, the terminal is asked to do it with an OSC 52 escape sequence
+pub struct Clipboard {
+    // kept alive for the whole session since on some platforms
+    // the copied text is lost once the clipboard handle is dropped
+    system: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    pub fn new() -> Self {
+        Self {
+            system: arboard::Clipboard::new().ok(),
+        }
+    }
+
+    pub fn copy(&mut self, text: &str) -> std::io::Result<()> {
+        if let Some(system) = self.system.as_mut()
+            && system.set_text(text).is_ok()
+        {
+            return Ok(());
+        }
+        execute!(std::io::stdout(), CopyToClipboard::to_clipboard_from(text))
+    }
+}
+
+impl Default for Clipboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
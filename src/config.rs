@@ -0,0 +1,46 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::Deserialize;
+use std::{collections::HashMap, path::PathBuf};
+
+/// The settings read from the TOML config file
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Keys to bind to each action, replacing its default keys,
+    /// such as `scroll_down = ["j", "down"]`
+    #[serde(default)]
+    pub keys: HashMap<String, Vec<String>>,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/fgbdump/config.toml`, falling back to `~/.config`
+    pub fn default_path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("fgbdump").join("config.toml"))
+    }
+
+    /// Read the config file at `path`, or at the default path if none is given.
+    /// Only a missing default config file is fine; it means every setting is the default
+    pub fn load(path: Option<&str>) -> Result<Self, String> {
+        let (path, explicit) = match path {
+            Some(path) => (PathBuf::from(path), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if !explicit && e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(e) => return Err(format!("Failed to read {}: {e}", path.display())),
+        };
+        toml::from_str(&text).map_err(|e| format!("Invalid config file {}: {e}", path.display()))
+    }
+}
@@ -0,0 +1,1628 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use flatgeobuf::{FallibleStreamingIterator, FgbFeature, FgbReader, HttpFgbReader};
+use memmap2::Mmap;
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    ops::ControlFlow,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use crate::{
+    export::FeatureSink,
+    feature::Feature,
+    http::{FileVersion, HttpOptions, TransferStats},
+    is_remote_file,
+    projection::{Bbox, area_of_use, check_crs, crs_label, describe_crs},
+    source::{ChunkSource, SourceClient, open_range_source, range_client},
+    stats::{Histogram, Sample, Stats, feature_number, numeric_column},
+};
+
+/// How to open files, from the command line
+#[derive(Clone, Debug, Default)]
+pub struct OpenOptions {
+    /// Map local files into memory rather than reading them through a buffer
+    pub mmap: bool,
+    pub http: HttpOptions,
+}
+
+/// A local file, mapped into memory or read through a buffer
+enum LocalFile {
+    Mapped(MappedFile),
+    Buffered(BufReader<File>),
+}
+
+/// A file mapped into memory, which reads copy out of. Touching a page of the map past
+/// the end of the file, as after another process truncates it, would crash fgbdump, so
+/// the length of the file is checked before every read
+struct MappedFile {
+    file: File,
+    map: Mmap,
+    position: u64,
+}
+
+impl MappedFile {
+    fn new(file: File) -> std::io::Result<Self> {
+        // SAFETY: the map is only read by `read`, which checks first that the file
+        // still covers all of it, and fgbdump never writes to the file
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Self {
+            file,
+            map,
+            position: 0,
+        })
+    }
+}
+
+impl Read for MappedFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mapped = self.map.len() as u64;
+        if self.file.metadata()?.len() < mapped {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "the file was truncated while it was mapped into memory",
+            ));
+        }
+        let start = self.position.min(mapped) as usize;
+        let len = buf.len().min(self.map.len() - start);
+        buf[..len].copy_from_slice(&self.map[start..start + len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl Seek for MappedFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+            SeekFrom::End(delta) => (self.map.len() as u64).checked_add_signed(delta),
+        };
+        self.position = position.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "seek to before the start of the file",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+impl LocalFile {
+    /// Map the file if asked to, falling back to buffered reads where it can't be
+    /// mapped, as on some network filesystems
+    fn open(source: &str, options: &OpenOptions) -> Result<Self, String> {
+        let file = File::open(source).map_err(|e| format!("Failed to open {source}: {e}"))?;
+        if options.mmap
+            && let Ok(mapped) = file.try_clone().and_then(MappedFile::new)
+        {
+            return Ok(Self::Mapped(mapped));
+        }
+        Ok(Self::Buffered(BufReader::new(file)))
+    }
+}
+
+impl Read for LocalFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Mapped(map) => map.read(buf),
+            Self::Buffered(file) => file.read(buf),
+        }
+    }
+}
+
+impl Seek for LocalFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        match self {
+            Self::Mapped(map) => map.seek(pos),
+            Self::Buffered(file) => file.seek(pos),
+        }
+    }
+}
+
+/// A local file read as though the features from `chunk_start` on came straight after
+/// the index, so that a reader opened on it reads only from there. Everything before the
+/// features is read as it is, so the header and index still check out
+struct ChunkReader {
+    file: LocalFile,
+    /// Where the features start in the file, and so where the chunk starts in what's read
+    features_start: u64,
+    chunk_start: u64,
+    /// Where reading is up to, as the reader sees it
+    position: u64,
+}
+
+impl ChunkReader {
+    fn new(file: LocalFile, features_start: u64, chunk_start: u64) -> Self {
+        Self {
+            file,
+            features_start,
+            chunk_start,
+            position: 0,
+        }
+    }
+
+    /// Where `position` is in the file
+    fn in_file(&self, position: u64) -> u64 {
+        match position.checked_sub(self.features_start) {
+            Some(into_features) => self.chunk_start + into_features,
+            None => position,
+        }
+    }
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // a read only goes up to the start of the features, which the chunk then follows
+        let len = match self.features_start.checked_sub(self.position) {
+            Some(left) if left > 0 => buf.len().min(left as usize),
+            _ => buf.len(),
+        };
+        let read = self.file.read(&mut buf[..len])?;
+        self.position += read as u64;
+        if read > 0 && self.position == self.features_start {
+            self.file.seek(SeekFrom::Start(self.chunk_start))?;
+        }
+        Ok(read)
+    }
+}
+
+impl Seek for ChunkReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+            SeekFrom::End(delta) => {
+                let end = self.file.seek(SeekFrom::End(0))?;
+                (end - self.chunk_start + self.features_start).checked_add_signed(delta)
+            }
+        };
+        let position = position.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "seek to before the start of the file",
+            )
+        })?;
+        self.file.seek(SeekFrom::Start(self.in_file(position)))?;
+        self.position = position;
+        Ok(position)
+    }
+}
+
+/// Open a local file and read its header
+fn open_local(source: &str, options: &OpenOptions) -> Result<FgbReader<LocalFile>, String> {
+    FgbReader::open(LocalFile::open(source, options)?)
+        .map_err(|e| format!("Failed to read the header of {source}: {e}"))
+}
+
+enum Reader {
+    Local(FgbReader<LocalFile>),
+    Remote(HttpFgbReader<SourceClient>),
+}
+
+/// The size of a node of the spatial index: its bbox as four f64s, then the offset of
+/// its first child, or for a leaf of its feature, as a u64
+pub const NODE_ITEM_SIZE: u64 = 40;
+
+/// The shape of a packed Hilbert R-tree, which follows from the number of features and
+/// the node size alone: a leaf for each feature, then levels of parents with up to
+/// node size children each, up to a single root
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexLayout {
+    pub node_size: u16,
+    /// Including the leaves
+    pub levels: usize,
+    /// Including the leaves
+    pub nodes: u64,
+    /// The size of the index, which lies between the header and the first feature
+    pub bytes: u64,
+}
+
+impl IndexLayout {
+    /// The layout of the index of `features` features with nodes of `node_size`, or
+    /// `None` when there's no index, as for a node size of 0
+    pub fn new(features: u64, node_size: u16) -> Option<Self> {
+        if features == 0 || node_size < 2 {
+            return None;
+        }
+        let mut level = features;
+        let mut nodes = features;
+        let mut levels = 1;
+        // as in the reference implementation, even a single leaf gets a root above it
+        loop {
+            level = level.div_ceil(u64::from(node_size));
+            nodes = nodes.checked_add(level)?;
+            levels += 1;
+            if level == 1 {
+                break;
+            }
+        }
+        Some(Self {
+            node_size,
+            levels,
+            nodes,
+            bytes: nodes.checked_mul(NODE_ITEM_SIZE)?,
+        })
+    }
+
+    pub fn to_json(self) -> serde_json::Value {
+        serde_json::json!({
+            "node_size": self.node_size,
+            "levels": self.levels,
+            "nodes": self.nodes,
+            "bytes": self.bytes,
+        })
+    }
+}
+
+/// An opened flatgeobuf file, either on disk or over HTTP
+pub struct Dataset {
+    reader: Reader,
+    /// The size of the whole file, if known
+    pub byte_size: Option<u64>,
+    /// The size of the header, not counting the magic bytes and length prefix before it
+    pub header_size: Option<u64>,
+    /// The requests made for a remote file, which `transfer` passed to [`Dataset::open`]
+    /// keeps counting across reloads and queries
+    pub transfer: Option<Arc<TransferStats>>,
+    /// The version of a remote file, which queries and sampling check they still read
+    pub version: Option<FileVersion>,
+}
+
+impl Dataset {
+    /// Open the file and read its header. Any requests made for a remote file are
+    /// recorded in `transfer`
+    pub async fn open(
+        source: &str,
+        transfer: Arc<TransferStats>,
+        options: OpenOptions,
+    ) -> Result<Self, String> {
+        if is_remote_file(source) {
+            let deadline = options.http.header_deadline();
+            let read_header = async {
+                let range_source = open_range_source(source, transfer.clone(), &options).await?;
+                // the length of the header, which follows the 8 magic bytes
+                let prefix = range_source.read_range(8, 4).await?;
+                let prefix: [u8; 4] = prefix[..]
+                    .try_into()
+                    .map_err(|_| format!("{source} is too short to be a flatgeobuf file"))?;
+                let fgb = HttpFgbReader::new(range_client(range_source.clone()))
+                    .await
+                    .map_err(|e| format!("Failed to read the header of {source}: {e}"))?;
+                range_source.finish_header();
+                let header_size = u64::from(u32::from_le_bytes(prefix));
+                let size = range_source.total_size();
+                Ok::<_, String>((fgb, size, header_size, range_source.version()))
+            };
+            let (fgb, byte_size, header_size, version) =
+                tokio::time::timeout(deadline, read_header)
+                    .await
+                    .map_err(|_| {
+                        HttpOptions::timed_out("reading the header of", source, deadline)
+                    })??;
+            Ok(Self {
+                reader: Reader::Remote(fgb),
+                byte_size,
+                header_size: Some(header_size),
+                transfer: Some(transfer),
+                version,
+            })
+        } else {
+            // Local file: use metadata to get size
+            let mut file =
+                File::open(source).map_err(|e| format!("Failed to open {source}: {e}"))?;
+            let byte_size = file.metadata().ok().map(|metadata| metadata.len());
+            let header_size = local_header_size(&mut file);
+
+            let fgb = open_local(source, &options)?;
+            Ok(Self {
+                reader: Reader::Local(fgb),
+                byte_size,
+                header_size,
+                transfer: None,
+                version: None,
+            })
+        }
+    }
+
+    pub fn header(&self) -> flatgeobuf::Header<'_> {
+        match &self.reader {
+            Reader::Local(fgb) => fgb.header(),
+            Reader::Remote(fgb) => fgb.header(),
+        }
+    }
+
+    /// The number of features the header gives, or `None` where it says 0 but there is
+    /// data after the header, since the spec allows 0 to mean it wasn't known when writing
+    pub fn features_count(&self) -> Option<u64> {
+        let count = self.header().features_count();
+        if count > 0 {
+            return Some(count);
+        }
+        // without a count there can be no index, so the features follow the header
+        match (self.byte_size, self.header_size) {
+            (Some(file), Some(header)) if file <= header + 12 => Some(0),
+            _ => None,
+        }
+    }
+
+    /// The size and shape of the spatial index, if the file has one
+    pub fn index_layout(&self) -> Option<IndexLayout> {
+        let header = self.header();
+        IndexLayout::new(header.features_count(), header.index_node_size())
+    }
+
+    /// The extent of the data in its own CRS
+    pub fn envelope(&self) -> Result<Bbox, String> {
+        self.header()
+            .envelope()
+            .ok_or("No bbox extent envelope found in the flatgeobuf metadata".to_string())
+            .and_then(|envelope| Bbox::from_flatgeobuf_envelope(&envelope))
+    }
+
+    /// The ways the header describes its CRS, best first: `org:code`, such as `EPSG:3857`,
+    /// then the WKT, then the code string, for files which leave out the ones before
+    fn crs_candidates(&self) -> Vec<(&'static str, String)> {
+        let Some(crs) = self.header().crs() else {
+            return Vec::new();
+        };
+        let mut candidates = Vec::new();
+        // a code of 0 means the code is unknown
+        if let Some(org) = crs.org().filter(|org| !org.is_empty())
+            && crs.code() != 0
+        {
+            candidates.push(("org and code", format!("{org}:{}", crs.code())));
+        }
+        if let Some(wkt) = crs.wkt().filter(|wkt| !wkt.is_empty()) {
+            candidates.push(("WKT", wkt.to_string()));
+        }
+        if let Some(code_string) = crs.code_string().filter(|code| !code.is_empty()) {
+            candidates.push(("code string", code_string.to_string()));
+        }
+        candidates
+    }
+
+    /// The CRS of the data, as the first way the header describes it which can be projected
+    /// to the map, along with a note for each way tried before it
+    pub fn resolve_crs(&self) -> (Result<String, String>, Vec<String>) {
+        let mut notes = Vec::new();
+        for (kind, crs) in self.crs_candidates() {
+            match check_crs(&crs) {
+                Ok(()) => {
+                    notes.push(format!("using the CRS {kind}: {}", crs_label(&crs)));
+                    return (Ok(crs), notes);
+                }
+                Err(e) => notes.push(format!("skipping the CRS {kind}: {e}")),
+            }
+        }
+        let error = if notes.is_empty() {
+            "No crs org and code, WKT, or code string found in the flatgeobuf metadata".to_string()
+        } else {
+            format!(
+                "No usable crs in the flatgeobuf metadata: {}",
+                notes.join("; ")
+            )
+        };
+        (Err(error), notes)
+    }
+
+    pub fn crs(&self) -> Result<String, String> {
+        self.resolve_crs().0
+    }
+
+    /// Problems with the CRS which suggest the data is mislabeled, such as an envelope
+    /// nowhere near the area the CRS is meant for
+    pub fn crs_warnings(&self) -> Vec<String> {
+        let Ok(crs) = self.crs() else {
+            return Vec::new();
+        };
+        let mut warnings = Vec::new();
+        // longitudes and latitudes would be a few hundred meters from the origin of a
+        // projected CRS, which for many CRSs is still within their area of use
+        if let (Ok(envelope), Ok(info)) = (self.envelope(), describe_crs(&crs))
+            && info.kind == "projected"
+            && envelope.validate_geographic().is_ok()
+        {
+            warnings.push(format!(
+                "The envelope {envelope} looks like longitudes and latitudes, \
+                 but {} is projected; the CRS may be mislabeled",
+                crs_label(&crs)
+            ));
+        }
+        // a tiny extent fits within the range of latitudes either way around
+        if let (Ok(envelope), Ok(info)) = (self.envelope(), describe_crs(&crs))
+            && info.kind == "geographic"
+            && envelope.looks_axis_swapped()
+        {
+            warnings.push(format!(
+                "The envelope {envelope} may have swapped axes, with latitudes in x \
+                 and longitudes in y"
+            ));
+        }
+        if let (Ok((envelope, _)), Some(area)) = (self.map_envelope(), area_of_use(&crs))
+            && !area
+                .expand_by_fraction(AREA_OF_USE_TOLERANCE)
+                .intersects(&envelope)
+        {
+            warnings.push(format!(
+                "The envelope lies outside the area of use of {} ({area}); \
+                 the CRS may be mislabeled",
+                crs_label(&crs)
+            ));
+        }
+        warnings
+    }
+
+    /// Columns whose names loaders may not be able to tell apart
+    pub fn column_name_problems(&self) -> ColumnNameProblems {
+        ColumnNameProblems::of(&column_names(&self.header()))
+    }
+
+    /// The extent of the data projected for the map, with a title saying what it was projected from
+    pub fn map_envelope(&self) -> Result<(Bbox, String), String> {
+        self.envelope().and_then(|bbox| {
+            bbox.project_to_ratatui_map_crs(&self.crs()?)
+                .map_err(|e| format!("Failed to project bbox: {e}"))
+        })
+    }
+}
+
+/// How far past the area of use of its CRS the envelope may lie, as a fraction of
+/// the size of the area, since data along the edges commonly overshoots it a little
+const AREA_OF_USE_TOLERANCE: f64 = 0.1;
+
+/// Read the length prefix of the header, which follows the 8 magic bytes, and go back
+/// to the start. A file too short to have one fails to open anyway, with a better error
+fn local_header_size(file: &mut File) -> Option<u64> {
+    let mut prefix = [0; 12];
+    let read = file.read_exact(&mut prefix);
+    file.rewind().ok()?;
+    read.ok()?;
+    Some(u32::from_le_bytes([prefix[8], prefix[9], prefix[10], prefix[11]]).into())
+}
+
+/// Columns whose names are the same as another's, or differ only in case, or are empty,
+/// which loaders may silently drop columns over
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ColumnNameProblems {
+    /// The name of each group of columns with the same name, with their indices
+    pub duplicates: Vec<(String, Vec<usize>)>,
+    /// The names of each group of columns whose names differ only in case, with their
+    /// indices
+    pub case_duplicates: Vec<(Vec<String>, Vec<usize>)>,
+    /// The indices of columns with an empty name
+    pub empty: Vec<usize>,
+}
+
+impl ColumnNameProblems {
+    pub fn of(names: &[String]) -> Self {
+        let group = |key: &dyn Fn(&str) -> String| {
+            let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+            for (i, name) in names
+                .iter()
+                .enumerate()
+                .filter(|(_, name)| !name.is_empty())
+            {
+                let key = key(name);
+                match groups.iter_mut().find(|(other, _)| *other == key) {
+                    Some((_, indices)) => indices.push(i),
+                    None => groups.push((key, vec![i])),
+                }
+            }
+            groups.retain(|(_, indices)| indices.len() > 1);
+            groups
+        };
+        let duplicates = group(&|name| name.to_string());
+        let case_duplicates = group(&|name| name.to_lowercase())
+            .into_iter()
+            .filter_map(|(_, indices)| {
+                let mut spellings: Vec<String> =
+                    indices.iter().map(|&i| names[i].clone()).collect();
+                spellings.sort();
+                spellings.dedup();
+                // the same name every time is only a duplicate
+                (spellings.len() > 1).then_some((spellings, indices))
+            })
+            .collect();
+        Self {
+            duplicates,
+            case_duplicates,
+            empty: (0..names.len()).filter(|&i| names[i].is_empty()).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.duplicates.is_empty() && self.case_duplicates.is_empty() && self.empty.is_empty()
+    }
+
+    /// Whether the column at `index` is one of those with a problem
+    pub fn affects(&self, index: usize) -> bool {
+        self.duplicates
+            .iter()
+            .any(|(_, indices)| indices.contains(&index))
+            || self
+                .case_duplicates
+                .iter()
+                .any(|(_, indices)| indices.contains(&index))
+            || self.empty.contains(&index)
+    }
+
+    /// A warning for each problem, numbering the columns from 1 in header order
+    pub fn warnings(&self) -> Vec<String> {
+        let positions = |indices: &[usize]| {
+            let positions: Vec<String> = indices.iter().map(|i| (i + 1).to_string()).collect();
+            positions.join(", ")
+        };
+        let mut warnings: Vec<String> = self
+            .duplicates
+            .iter()
+            .map(|(name, indices)| {
+                format!(
+                    "{} columns are named \"{name}\" (columns {}); loaders may keep only one",
+                    indices.len(),
+                    positions(indices)
+                )
+            })
+            .collect();
+        warnings.extend(self.case_duplicates.iter().map(|(names, indices)| {
+            let names: Vec<String> = names.iter().map(|name| format!("\"{name}\"")).collect();
+            format!(
+                "The names {} differ only in case (columns {}); case-insensitive loaders \
+                 may keep only one",
+                names.join(", "),
+                positions(indices)
+            )
+        }));
+        match self.empty.len() {
+            0 => {}
+            1 => warnings.push(format!("Column {} has an empty name", self.empty[0] + 1)),
+            count => warnings.push(format!(
+                "{count} columns have an empty name (columns {})",
+                positions(&self.empty)
+            )),
+        }
+        warnings
+    }
+}
+
+/// The names of the columns in the header, in file order
+fn column_names(header: &flatgeobuf::Header) -> Vec<String> {
+    header
+        .columns()
+        .map(|columns| columns.iter().map(|c| c.name().to_string()).collect())
+        .unwrap_or_default()
+}
+
+fn query_error(source: &str, e: flatgeobuf::Error) -> String {
+    match e {
+        flatgeobuf::Error::NoIndex => {
+            format!("{source} has no spatial index, which is needed for bbox queries")
+        }
+        e => format!("Failed to query {source}: {e}"),
+    }
+}
+
+/// Which features of a file a scan reads
+#[derive(Clone, Copy)]
+enum Selection<'a> {
+    All,
+    /// Those whose bounding boxes intersect the bbox, which is in the CRS of the file
+    Bbox(&'a Bbox),
+    /// Those from the one at the index on. The leaves of the spatial index give where it
+    /// is, so reading starts there; without one, reading starts from the first
+    From(usize),
+}
+
+/// Open `source`, make what the scan keeps with `start` from the header, and hand each
+/// feature selected to `visit` along with its position, until there are no more or
+/// `visit` breaks. The position is in the file, or among those a bbox selects. A scan
+/// opens the file again rather than using the reader of a [`Dataset`], since selecting
+/// consumes a reader
+async fn scan_features<T>(
+    source: &str,
+    selection: Selection<'_>,
+    transfer: Arc<TransferStats>,
+    options: OpenOptions,
+    start: impl FnOnce(&flatgeobuf::Header) -> Result<T, String>,
+    mut visit: impl FnMut(&mut T, usize, &FgbFeature) -> Result<ControlFlow<()>, String>,
+) -> Result<T, String> {
+    let read_error = |e| match selection {
+        Selection::Bbox(_) => query_error(source, e),
+        _ => format!("Failed to read {source}: {e}"),
+    };
+    if is_remote_file(source) {
+        let range_source = open_range_source(source, transfer, &options).await?;
+        // the length of the header says where the index is, to find a feature in it
+        let header_size = match selection {
+            Selection::From(_) => {
+                let prefix = range_source.read_range(8, 4).await?;
+                <[u8; 4]>::try_from(&prefix[..])
+                    .map(|prefix| u64::from(u32::from_le_bytes(prefix)))
+                    .ok()
+            }
+            _ => None,
+        };
+        let fgb = HttpFgbReader::new(range_client(range_source.clone()))
+            .await
+            .map_err(|e| format!("Failed to read the header of {source}: {e}"))?;
+        range_source.finish_header();
+        let mut state = start(&fgb.header())?;
+        let (features, mut i) = match selection {
+            Selection::All => (fgb.select_all().await, 0),
+            Selection::Bbox(bbox) => (
+                fgb.select_bbox(bbox.xmin, bbox.ymin, bbox.xmax, bbox.ymax)
+                    .await,
+                0,
+            ),
+            Selection::From(index) => {
+                let count = fgb.header().features_count();
+                let layout = IndexLayout::new(count, fgb.header().index_node_size());
+                match (header_size, layout) {
+                    (Some(header_size), Some(layout)) if (index as u64) < count => {
+                        let features_start = 12 + header_size + layout.bytes;
+                        let position = leaf_offset_position(count, features_start, index as u64);
+                        let offset = range_source.read_range(position, 8).await?;
+                        let offset = <[u8; 8]>::try_from(&offset[..])
+                            .map(u64::from_le_bytes)
+                            .map_err(|_| {
+                                format!("Failed to read the index of {source}: it ends early")
+                            })?;
+                        let chunk =
+                            ChunkSource::new(range_source, features_start, features_start + offset);
+                        let fgb = HttpFgbReader::new(range_client(Arc::new(chunk)))
+                            .await
+                            .map_err(|e| format!("Failed to read the header of {source}: {e}"))?;
+                        (fgb.select_all().await, index)
+                    }
+                    _ => (fgb.select_all().await, 0),
+                }
+            }
+        };
+        let mut features = features.map_err(read_error)?;
+        while let Some(feature) = features.next().await.map_err(read_error)? {
+            if visit(&mut state, i, feature)?.is_break() {
+                break;
+            }
+            i += 1;
+        }
+        Ok(state)
+    } else {
+        let fgb = open_local(source, &options)?;
+        let mut state = start(&fgb.header())?;
+        match selection {
+            Selection::All => {
+                let features = fgb.select_all().map_err(read_error)?;
+                visit_local(features, 0, &mut state, &mut visit, read_error).await?;
+            }
+            Selection::Bbox(bbox) => {
+                let features = fgb
+                    .select_bbox(bbox.xmin, bbox.ymin, bbox.xmax, bbox.ymax)
+                    .map_err(read_error)?;
+                visit_local(features, 0, &mut state, &mut visit, read_error).await?;
+            }
+            Selection::From(index) => match local_chunk(source, &options, &fgb.header(), index)? {
+                Some(chunk) => {
+                    let fgb = FgbReader::open(chunk)
+                        .map_err(|e| format!("Failed to read the header of {source}: {e}"))?;
+                    let features = fgb.select_all().map_err(read_error)?;
+                    visit_local(features, index, &mut state, &mut visit, read_error).await?;
+                }
+                None => {
+                    let features = fgb.select_all().map_err(read_error)?;
+                    visit_local(features, 0, &mut state, &mut visit, read_error).await?;
+                }
+            },
+        }
+        Ok(state)
+    }
+}
+
+/// Hand each feature of a local file to `visit` as for [`scan_features`], counting their
+/// positions from `first`. Reading a local file never waits, so this yields now and then
+/// to give aborting the task a chance to take effect
+async fn visit_local<T>(
+    mut features: impl FallibleStreamingIterator<Item = FgbFeature, Error = flatgeobuf::Error>,
+    first: usize,
+    state: &mut T,
+    visit: &mut impl FnMut(&mut T, usize, &FgbFeature) -> Result<ControlFlow<()>, String>,
+    read_error: impl Fn(flatgeobuf::Error) -> String,
+) -> Result<(), String> {
+    let mut i = first;
+    while let Some(feature) = features.next().map_err(&read_error)? {
+        if visit(state, i, feature)?.is_break() {
+            break;
+        }
+        i += 1;
+        if (i - first).is_multiple_of(1000) {
+            tokio::task::yield_now().await;
+        }
+    }
+    Ok(())
+}
+
+/// A local file read from feature `index` on, if its header and index say where that is
+fn local_chunk(
+    source: &str,
+    options: &OpenOptions,
+    header: &flatgeobuf::Header,
+    index: usize,
+) -> Result<Option<ChunkReader>, String> {
+    let count = header.features_count();
+    let header_size = File::open(source)
+        .ok()
+        .and_then(|mut file| local_header_size(&mut file));
+    let layout = IndexLayout::new(count, header.index_node_size());
+    let (Some(header_size), Some(layout)) = (header_size, layout) else {
+        return Ok(None);
+    };
+    if index as u64 >= count {
+        return Ok(None);
+    }
+    let features_start = 12 + header_size + layout.bytes;
+    let mut file = LocalFile::open(source, options)?;
+    let mut offset = [0; 8];
+    file.seek(SeekFrom::Start(leaf_offset_position(
+        count,
+        features_start,
+        index as u64,
+    )))
+    .and_then(|_| file.read_exact(&mut offset))
+    .and_then(|_| file.rewind())
+    .map_err(|e| format!("Failed to read the index of {source}: {e}"))?;
+    let chunk_start = features_start + u64::from_le_bytes(offset);
+    Ok(Some(ChunkReader::new(file, features_start, chunk_start)))
+}
+
+/// Read the features whose bounding boxes intersect `bbox`, which is in the CRS of
+/// the file. `progress` counts the features read so far
+pub async fn select_bbox(
+    source: &str,
+    bbox: &Bbox,
+    progress: &AtomicUsize,
+    transfer: Arc<TransferStats>,
+    options: OpenOptions,
+) -> Result<Vec<Feature>, String> {
+    let mut results = Vec::new();
+    scan_features(
+        source,
+        Selection::Bbox(bbox),
+        transfer,
+        options,
+        |header| Ok(column_names(header)),
+        |columns, _, feature| {
+            results.push(Feature::from_fgb(feature, columns)?);
+            progress.fetch_add(1, Ordering::Relaxed);
+            Ok(ControlFlow::Continue(()))
+        },
+    )
+    .await?;
+    Ok(results)
+}
+
+/// Read every feature of `source`, or only those whose bounding boxes intersect `bbox`,
+/// handing each to `sink` as it's read rather than keeping them. `progress` counts the
+/// features read so far
+pub async fn read_features(
+    source: &str,
+    bbox: Option<&Bbox>,
+    sink: &mut dyn FeatureSink,
+    progress: &AtomicUsize,
+    transfer: Arc<TransferStats>,
+    options: OpenOptions,
+) -> Result<(), String> {
+    let selection = bbox.map_or(Selection::All, Selection::Bbox);
+    let (_, sink) = scan_features(
+        source,
+        selection,
+        transfer,
+        options,
+        // the scan keeps the sink, since it's started from the header
+        move |header| {
+            let columns = column_names(header);
+            sink.start(&columns)?;
+            Ok((columns, sink))
+        },
+        |(columns, sink), _, feature| {
+            sink.feature(&Feature::from_fgb(feature, columns)?)?;
+            progress.fetch_add(1, Ordering::Relaxed);
+            Ok(ControlFlow::Continue(()))
+        },
+    )
+    .await?;
+    sink.finish()
+}
+
+/// Take a representative point from each of up to `max` features spread evenly through
+/// the file. A remote file only has its first `max` features read, so as not to download
+/// all of it. `progress` counts the points taken so far
+pub async fn sample_points(
+    source: &str,
+    max: usize,
+    progress: &AtomicUsize,
+    transfer: Arc<TransferStats>,
+    options: OpenOptions,
+) -> Result<Vec<(f64, f64)>, String> {
+    let remote = is_remote_file(source);
+    let mut points = Vec::new();
+    let mut taken = 0;
+    scan_features(
+        source,
+        Selection::All,
+        transfer,
+        options,
+        |header| {
+            let count = header.features_count() as usize;
+            Ok(if remote {
+                1
+            } else {
+                count.div_ceil(max.max(1)).max(1)
+            })
+        },
+        |&mut step, i, feature| {
+            if i % step == 0 && taken < max {
+                if let Some(point) = Feature::geometry_from_fgb(feature)?.representative_point() {
+                    points.push(point);
+                }
+                taken += 1;
+                progress.fetch_add(1, Ordering::Relaxed);
+            }
+            // the count in the header is zero if it wasn't known when writing
+            Ok(match taken < max {
+                true => ControlFlow::Continue(()),
+                false => ControlFlow::Break(()),
+            })
+        },
+    )
+    .await?;
+    Ok(points)
+}
+
+/// Count the features by reading every one of them, for files whose header doesn't say
+/// how many there are. `progress` counts the features read so far
+pub async fn count_features(
+    source: &str,
+    progress: &AtomicUsize,
+    transfer: Arc<TransferStats>,
+    options: OpenOptions,
+) -> Result<usize, String> {
+    scan_features(
+        source,
+        Selection::All,
+        transfer,
+        options,
+        |_| Ok(0),
+        |count, _, _| {
+            *count += 1;
+            progress.fetch_add(1, Ordering::Relaxed);
+            Ok(ControlFlow::Continue(()))
+        },
+    )
+    .await
+}
+
+/// Read the feature at `index`, counting from 0 in the order they are stored. With a
+/// spatial index, its leaf for the feature gives where the feature is, so only that feature
+/// is read; without one, features are read in order up to it, and no further
+pub async fn feature_at(
+    source: &str,
+    index: usize,
+    transfer: Arc<TransferStats>,
+    options: OpenOptions,
+) -> Result<Feature, String> {
+    let out_of_range = |count: usize| match count {
+        0 => format!("{source} has no features"),
+        _ => format!(
+            "There is no feature {index} in {source}; it has {count}, from 0 to {}",
+            count - 1
+        ),
+    };
+    let mut found = None;
+    // how many features there are, if the scan got to the end of them
+    let mut read = 0;
+    let (_, count) = scan_features(
+        source,
+        Selection::From(index),
+        transfer,
+        options,
+        |header| {
+            // 0 if it wasn't known when writing, in which case reading past the end says so
+            let count = header.features_count() as usize;
+            if count > 0 && index >= count {
+                return Err(out_of_range(count));
+            }
+            Ok((column_names(header), count))
+        },
+        |(columns, _), i, feature| {
+            read = i + 1;
+            if i < index {
+                return Ok(ControlFlow::Continue(()));
+            }
+            found = Some(Feature::from_fgb(feature, columns)?);
+            Ok(ControlFlow::Break(()))
+        },
+    )
+    .await?;
+    match found {
+        Some(feature) => Ok(feature),
+        // the header says there are more than were read, which an index which points past
+        // the last feature also leads to
+        None if read == 0 && count > 0 => Err(format!(
+            "Failed to read {source}: feature {index} isn't where its index says"
+        )),
+        None => Err(out_of_range(read)),
+    }
+}
+
+/// Where the leaf of the index for feature `i` of `features` keeps the offset of the
+/// feature from the first. The leaves come last before `features_start`, one for each
+/// feature in the same order as the features
+fn leaf_offset_position(features: u64, features_start: u64, i: u64) -> u64 {
+    features_start - (features - i) * NODE_ITEM_SIZE + 32
+}
+
+/// Gather [`Stats`] by reading every feature once, counting the most frequent values of
+/// `value_columns` or else the default columns. `progress` counts the features read so far
+pub async fn scan_stats(
+    source: &str,
+    value_columns: Option<&[String]>,
+    progress: &AtomicUsize,
+    transfer: Arc<TransferStats>,
+    options: OpenOptions,
+) -> Result<Stats, String> {
+    scan_features(
+        source,
+        Selection::All,
+        transfer,
+        options,
+        |header| Stats::new(header, value_columns),
+        |stats, _, feature| {
+            stats.add(feature)?;
+            progress.fetch_add(1, Ordering::Relaxed);
+            Ok(ControlFlow::Continue(()))
+        },
+    )
+    .await
+}
+/// Like [`scan_stats`], but reading `threads` runs of the features of a local file at once
+/// and merging what was found in each, for files too big to read in one pass quickly.
+/// The leaves of the spatial index give where each run starts, so remote files and files
+/// without an index are read in one pass as before, as are files where a run after the
+/// first has more distinct values of a column than are counted. `progress` counts the
+/// features read so far, by all the threads together
+pub async fn scan_stats_parallel(
+    source: &str,
+    dataset: &Dataset,
+    value_columns: Option<&[String]>,
+    threads: usize,
+    progress: &Arc<AtomicUsize>,
+    transfer: Arc<TransferStats>,
+    options: OpenOptions,
+) -> Result<Stats, String> {
+    let features = dataset.header().features_count();
+    let (Some(header_size), Some(layout)) = (dataset.header_size, dataset.index_layout()) else {
+        return scan_stats(source, value_columns, progress, transfer, options).await;
+    };
+    if is_remote_file(source) || threads < 2 {
+        return scan_stats(source, value_columns, progress, transfer, options).await;
+    }
+    let features_start = 12 + header_size + layout.bytes;
+    let chunks = feature_chunks(source, &options, features, features_start, threads)?;
+    let tasks = chunks.into_iter().map(|(chunk_start, count)| {
+        let source = source.to_string();
+        let value_columns = value_columns.map(<[String]>::to_vec);
+        let progress = progress.clone();
+        let options = options.clone();
+        tokio::task::spawn_blocking(move || {
+            let read_error = |e| format!("Failed to read {source}: {e}");
+            let file = LocalFile::open(&source, &options)?;
+            let fgb = FgbReader::open(ChunkReader::new(file, features_start, chunk_start))
+                .map_err(|e| format!("Failed to read the header of {source}: {e}"))?;
+            let mut stats = Stats::new(&fgb.header(), value_columns.as_deref())?;
+            let mut features = fgb.select_all().map_err(read_error)?;
+            for _ in 0..count {
+                let Some(feature) = features.next().map_err(read_error)? else {
+                    break;
+                };
+                stats.add(feature)?;
+                progress.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok::<_, String>(stats)
+        })
+    });
+    let mut runs = Vec::new();
+    // in the order of the runs, so that merging sees the features in file order
+    for task in tasks.collect::<Vec<_>>() {
+        runs.push(
+            task.await
+                .map_err(|e| format!("Failed to read {source}: {e}"))??,
+        );
+    }
+    // a run after the first with too many distinct values to count them all may have
+    // left out some which one pass counts, so only one pass gives the same counts
+    if !runs.iter().skip(1).all(Stats::values_complete) {
+        progress.store(0, Ordering::Relaxed);
+        return scan_stats(source, value_columns, progress, transfer, options).await;
+    }
+    let mut runs = runs.into_iter();
+    let mut merged = runs
+        .next()
+        .ok_or_else(|| format!("{source} has no features"))?;
+    for stats in runs {
+        merged.merge(stats);
+    }
+    Ok(merged)
+}
+
+/// Where each of up to `threads` runs of about the same number of the `features`
+/// features starts in a local file, and how many features it has, from the leaves of the
+/// index for the first feature of each
+fn feature_chunks(
+    source: &str,
+    options: &OpenOptions,
+    features: u64,
+    features_start: u64,
+    threads: usize,
+) -> Result<Vec<(u64, u64)>, String> {
+    let read_error = |e| format!("Failed to read the index of {source}: {e}");
+    let mut file = LocalFile::open(source, options)?;
+    let threads = threads as u64;
+    let mut firsts: Vec<u64> = (0..threads).map(|i| features * i / threads).collect();
+    // fewer features than threads would give some runs none
+    firsts.dedup();
+    let mut chunks = Vec::new();
+    for (i, &first) in firsts.iter().enumerate() {
+        let mut offset = [0; 8];
+        file.seek(SeekFrom::Start(leaf_offset_position(
+            features,
+            features_start,
+            first,
+        )))
+        .map_err(read_error)?;
+        file.read_exact(&mut offset).map_err(read_error)?;
+        let end = firsts.get(i + 1).copied().unwrap_or(features);
+        chunks.push((features_start + u64::from_le_bytes(offset), end - first));
+    }
+    Ok(chunks)
+}
+
+/// Tally the geometry types of the features and see whether they're spatially sorted,
+/// reading only the first `limit` of them if given. `progress` counts the features read
+/// so far
+pub async fn scan_sample(
+    source: &str,
+    limit: Option<usize>,
+    progress: &AtomicUsize,
+    transfer: Arc<TransferStats>,
+    options: OpenOptions,
+) -> Result<Sample, String> {
+    let limit = limit.unwrap_or(usize::MAX);
+    scan_features(
+        source,
+        Selection::All,
+        transfer,
+        options,
+        |header| Ok(Sample::new(header)),
+        |sample, _, feature| {
+            if sample.types.features == limit {
+                sample.types.sampled = true;
+                return Ok(ControlFlow::Break(()));
+            }
+            sample.add(feature);
+            progress.fetch_add(1, Ordering::Relaxed);
+            Ok(ControlFlow::Continue(()))
+        },
+    )
+    .await
+}
+
+/// Read every value of the numeric column `column` into a histogram with `bins` bins
+/// spanning `range`, or else from the smallest to the largest value, which means holding
+/// on to every value until the end. `progress` counts the features read so far
+pub async fn scan_histogram(
+    source: &str,
+    column: &str,
+    bins: usize,
+    range: Option<(f64, f64)>,
+    progress: &AtomicUsize,
+    transfer: Arc<TransferStats>,
+    options: OpenOptions,
+) -> Result<Histogram, String> {
+    let mut histogram = range.map(|(min, max)| Histogram::new(column, min, max, bins));
+    let mut values = Vec::new();
+    let mut nulls = 0;
+    scan_features(
+        source,
+        Selection::All,
+        transfer,
+        options,
+        |header| numeric_column(header, column),
+        |&mut index, _, feature| {
+            match (feature_number(feature, index)?, &mut histogram) {
+                (None, _) => nulls += 1,
+                (Some(value), Some(histogram)) => histogram.add(value),
+                (Some(value), None) => values.push(value),
+            }
+            progress.fetch_add(1, Ordering::Relaxed);
+            Ok(ControlFlow::Continue(()))
+        },
+    )
+    .await?;
+    let mut histogram = histogram.unwrap_or_else(|| Histogram::of_values(column, &values, bins));
+    histogram.nulls = nulls;
+    Ok(histogram)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// How the header of a fixture describes its CRS
+    #[derive(Default)]
+    struct CrsFixture {
+        org: Option<&'static str>,
+        code: i32,
+        wkt: Option<&'static str>,
+        code_string: Option<&'static str>,
+    }
+
+    /// Write a flatgeobuf file with no features and a header with `crs`, if any
+    fn write_crs_fixture(name: &str, crs: Option<CrsFixture>) -> std::path::PathBuf {
+        let mut fbb = flatbuffers::FlatBufferBuilder::new();
+        let crs = crs.map(|crs| {
+            let args = flatgeobuf::CrsArgs {
+                org: crs.org.map(|org| fbb.create_string(org)),
+                code: crs.code,
+                wkt: crs.wkt.map(|wkt| fbb.create_string(wkt)),
+                code_string: crs.code_string.map(|code| fbb.create_string(code)),
+                ..Default::default()
+            };
+            flatgeobuf::Crs::create(&mut fbb, &args)
+        });
+        let header = flatgeobuf::Header::create(
+            &mut fbb,
+            &flatgeobuf::HeaderArgs {
+                geometry_type: flatgeobuf::GeometryType::Point,
+                crs,
+                index_node_size: 0,
+                ..Default::default()
+            },
+        );
+        fbb.finish_size_prefixed(header, None);
+        let path = std::env::temp_dir().join(format!("fgbdump-{}-{name}.fgb", std::process::id()));
+        let mut bytes = b"fgb\x03fgb\x00".to_vec();
+        bytes.extend_from_slice(fbb.finished_data());
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    async fn open_fixture(path: &std::path::Path) -> Dataset {
+        let source = path.to_str().unwrap();
+        Dataset::open(source, Arc::default(), OpenOptions::default())
+            .await
+            .unwrap()
+    }
+
+    /// A copy of `testdata/<name>` which a test can change without affecting the others
+    fn copy_testdata(name: &str, test: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("fgbdump-{}-{test}.fgb", std::process::id()));
+        std::fs::copy(std::path::Path::new("testdata").join(name), &path).unwrap();
+        path
+    }
+
+    #[test]
+    fn mapped_files_read_and_seek_like_buffered_ones() {
+        let path = std::path::Path::new("testdata/colorado_subset.fgb");
+        let mut mapped = MappedFile::new(File::open(path).unwrap()).unwrap();
+        let mut buffered = BufReader::new(File::open(path).unwrap());
+        let mut read = |pos| {
+            let mut from_map = [0; 100];
+            let mut from_file = [0; 100];
+            let at = mapped.seek(pos).unwrap();
+            assert_eq!(buffered.seek(pos).unwrap(), at);
+            let len = mapped.read(&mut from_map).unwrap();
+            buffered.read_exact(&mut from_file[..len]).unwrap();
+            assert_eq!(from_map[..len], from_file[..len]);
+            len
+        };
+        assert_eq!(read(SeekFrom::Start(0)), 100);
+        assert_eq!(read(SeekFrom::Current(1000)), 100);
+        assert_eq!(read(SeekFrom::End(-40)), 40);
+        // reads past the end read nothing
+        assert_eq!(read(SeekFrom::End(10)), 0);
+        assert!(mapped.seek(SeekFrom::Current(-1_000_000)).is_err());
+    }
+
+    #[test]
+    fn truncating_a_mapped_file_fails_reads_rather_than_crashing() {
+        let path = copy_testdata("colorado_subset.fgb", "truncated-map");
+        let options = OpenOptions {
+            mmap: true,
+            ..Default::default()
+        };
+        let mut file = LocalFile::open(path.to_str().unwrap(), &options).unwrap();
+        assert!(matches!(file, LocalFile::Mapped(_)));
+        let mut magic = [0; 8];
+        file.read_exact(&mut magic).unwrap();
+        assert_eq!(&magic[..3], b"fgb");
+
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(1000)
+            .unwrap();
+        file.seek(SeekFrom::Start(500_000)).unwrap();
+        let error = file.read(&mut [0; 100]).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        // even where the file still has data, since the rest of the map might be read next
+        file.seek(SeekFrom::Start(0)).unwrap();
+        assert!(file.read(&mut magic).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    async fn open_testdata(name: &str) -> Dataset {
+        open_fixture(std::path::Path::new("testdata").join(name).as_path()).await
+    }
+
+    #[tokio::test]
+    async fn map_envelope_of_data_in_the_map_crs() {
+        let dataset = open_testdata("colorado_subset.fgb").await;
+        assert_eq!(dataset.crs().unwrap(), "EPSG:4326");
+        let (bbox, title) = dataset.map_envelope().unwrap();
+        assert_eq!(bbox.to_string(), dataset.envelope().unwrap().to_string());
+        assert_eq!(title, "Extent of data in EPSG:4326");
+    }
+
+    #[tokio::test]
+    async fn map_envelope_of_data_in_another_crs() {
+        let dataset = open_testdata("colorado_subset_epsg8857.fgb").await;
+        assert_eq!(dataset.crs_candidates()[0].1, "EPSG:8857");
+        let map_envelope = dataset.map_envelope();
+        #[cfg(feature = "proj")]
+        {
+            let (bbox, title) = map_envelope.unwrap();
+            assert_eq!(title, "Extent of data in EPSG:8857 projected to EPSG:4326");
+            assert!(bbox.validate_geographic().is_ok());
+            assert!(bbox.contains_point(-105.0, 39.0));
+        }
+        // the Map tab says why rather than drawing the envelope in the wrong place
+        #[cfg(not(feature = "proj"))]
+        assert_eq!(
+            map_envelope.unwrap_err(),
+            "No usable crs in the flatgeobuf metadata: skipping the CRS org and code: \
+             reprojection of EPSG:8857 unavailable — built without proj; skipping the CRS \
+             WKT: reprojection of WGS 84 / Equal Earth Greenwich unavailable — built without proj"
+        );
+    }
+
+    #[tokio::test]
+    async fn crs_from_org_and_code() {
+        let path = write_crs_fixture(
+            "org-and-code",
+            Some(CrsFixture {
+                org: Some("EPSG"),
+                code: 4326,
+                code_string: Some("OGC:CRS84"),
+                ..Default::default()
+            }),
+        );
+        let dataset = open_fixture(&path).await;
+        let (crs, notes) = dataset.resolve_crs();
+        assert_eq!(crs.unwrap(), "EPSG:4326");
+        assert_eq!(notes, ["using the CRS org and code: EPSG:4326"]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn crs_falls_back_past_an_unknown_code_to_the_code_string() {
+        // a code of 0 is no code at all
+        let path = write_crs_fixture(
+            "code-string",
+            Some(CrsFixture {
+                org: Some("EPSG"),
+                code: 0,
+                code_string: Some("OGC:CRS84"),
+                ..Default::default()
+            }),
+        );
+        let dataset = open_fixture(&path).await;
+        let (crs, notes) = dataset.resolve_crs();
+        assert_eq!(crs.unwrap(), "OGC:CRS84");
+        assert_eq!(notes, ["using the CRS code string: OGC:CRS84"]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    const NAD83_UTM_15N: &str = r#"PROJCS["NAD83 / UTM zone 15N",GEOGCS["NAD83",DATUM["North_American_Datum_1983",SPHEROID["GRS 1980",6378137,298.257222101]],PRIMEM["Greenwich",0],UNIT["degree",0.0174532925199433]],PROJECTION["Transverse_Mercator"],PARAMETER["latitude_of_origin",0],PARAMETER["central_meridian",-93],PARAMETER["scale_factor",0.9996],PARAMETER["false_easting",500000],PARAMETER["false_northing",0],UNIT["metre",1],AUTHORITY["EPSG","26915"]]"#;
+
+    #[tokio::test]
+    async fn crs_from_wkt_without_an_authority_code() {
+        let path = write_crs_fixture(
+            "wkt",
+            Some(CrsFixture {
+                wkt: Some(NAD83_UTM_15N),
+                code_string: Some("OGC:CRS84"),
+                ..Default::default()
+            }),
+        );
+        let dataset = open_fixture(&path).await;
+        let (crs, notes) = dataset.resolve_crs();
+        // the WKT is used where PROJ can read it, and otherwise noted and skipped
+        #[cfg(feature = "proj")]
+        {
+            assert_eq!(crs.unwrap(), NAD83_UTM_15N);
+            assert_eq!(notes, ["using the CRS WKT: NAD83 / UTM zone 15N"]);
+        }
+        #[cfg(not(feature = "proj"))]
+        {
+            assert_eq!(crs.unwrap(), "OGC:CRS84");
+            assert_eq!(
+                notes,
+                [
+                    "skipping the CRS WKT: reprojection of NAD83 / UTM zone 15N unavailable — \
+                     built without proj",
+                    "using the CRS code string: OGC:CRS84",
+                ]
+            );
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn crs_errors_when_nothing_can_be_used() {
+        let path = write_crs_fixture(
+            "unusable",
+            Some(CrsFixture {
+                org: Some("EPSG"),
+                code: 999_999,
+                ..Default::default()
+            }),
+        );
+        let dataset = open_fixture(&path).await;
+        let (crs, notes) = dataset.resolve_crs();
+        assert!(crs.unwrap_err().starts_with(
+            "No usable crs in the flatgeobuf metadata: skipping the CRS org and code"
+        ));
+        assert_eq!(notes.len(), 1);
+        std::fs::remove_file(path).unwrap();
+
+        let path = write_crs_fixture("no-crs", None);
+        let dataset = open_fixture(&path).await;
+        let (crs, notes) = dataset.resolve_crs();
+        assert_eq!(
+            crs.unwrap_err(),
+            "No crs org and code, WKT, or code string found in the flatgeobuf metadata"
+        );
+        assert!(notes.is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// A server for `file` which honours If-Match against the ETag it's set to, so that
+    /// changing the ETag stands in for the file being replaced
+    fn versioned_server(file: &str) -> (String, Arc<std::sync::Mutex<&'static str>>) {
+        use std::io::Write;
+
+        let data = Arc::new(std::fs::read(file).unwrap());
+        let etag = Arc::new(std::sync::Mutex::new("\"v1\""));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/data.fgb", listener.local_addr().unwrap());
+        let current = etag.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buf[..read]),
+                    }
+                }
+                let request = String::from_utf8(request).unwrap().to_lowercase();
+                let etag = *current.lock().unwrap();
+                let header = |name: &str| {
+                    request
+                        .lines()
+                        .find_map(|line| line.strip_prefix(name))
+                        .map(str::trim)
+                };
+                let response = match (header("if-match:"), header("range: bytes=")) {
+                    (Some(wanted), _) if wanted != etag => {
+                        b"HTTP/1.1 412 Precondition Failed\r\nContent-Length: 0\r\n\r\n".to_vec()
+                    }
+                    (_, Some(range)) => {
+                        let (start, end) = range.split_once('-').unwrap();
+                        let start: usize = start.parse().unwrap();
+                        let end = end.parse::<usize>().unwrap().min(data.len() - 1);
+                        let mut response = format!(
+                            "HTTP/1.1 206 Partial Content\r\nETag: {etag}\r\n\
+                             Content-Range: bytes {start}-{end}/{}\r\nContent-Length: {}\r\n\r\n",
+                            data.len(),
+                            end + 1 - start
+                        )
+                        .into_bytes();
+                        response.extend_from_slice(&data[start..=end]);
+                        response
+                    }
+                    _ => format!(
+                        "HTTP/1.1 200 OK\r\nETag: {etag}\r\nContent-Length: {}\r\n\r\n",
+                        data.len()
+                    )
+                    .into_bytes(),
+                };
+                let _ = stream.write_all(&response);
+            }
+        });
+        (url, etag)
+    }
+
+    #[tokio::test]
+    async fn remote_file_replaced_mid_session() {
+        let (url, etag) = versioned_server("testdata/colorado_subset.fgb");
+        let dataset = Dataset::open(&url, Arc::default(), OpenOptions::default())
+            .await
+            .unwrap();
+        assert!(dataset.version.is_some());
+        // as the TUI runs queries, pinned to the version the header was read from
+        let mut options = OpenOptions::default();
+        options.http.version = dataset.version.clone();
+        let four_corners = Bbox::new(-109.5, 37.0, -109.0, 37.3);
+        let progress = AtomicUsize::new(0);
+        let query = || {
+            select_bbox(
+                &url,
+                &four_corners,
+                &progress,
+                Arc::default(),
+                options.clone(),
+            )
+        };
+        assert!(!query().await.unwrap().is_empty());
+
+        *etag.lock().unwrap() = "\"v2\"";
+        let error = query().await.unwrap_err();
+        assert!(error.contains(crate::http::FILE_CHANGED), "{error}");
+        // reopening it reads the new version
+        assert!(
+            Dataset::open(&url, Arc::default(), OpenOptions::default())
+                .await
+                .is_ok()
+        );
+    }
+
+    /// Feature `index` of `source`, read by going through the features in order
+    fn nth_feature(source: &str, index: usize) -> Feature {
+        let fgb = open_local(source, &OpenOptions::default()).unwrap();
+        let columns = column_names(&fgb.header());
+        let mut features = fgb.select_all().unwrap();
+        for _ in 0..index {
+            features.next().unwrap();
+        }
+        Feature::from_fgb(features.next().unwrap().unwrap(), &columns).unwrap()
+    }
+
+    #[tokio::test]
+    async fn feature_at_reads_the_feature_the_index_points_to() {
+        let source = "testdata/colorado_subset.fgb";
+        // either side of where the leaves fill a node of 16
+        for index in [0, 1, 15, 16, 17, 200, 369] {
+            let feature = feature_at(source, index, Arc::default(), OpenOptions::default())
+                .await
+                .unwrap();
+            assert_eq!(
+                feature.to_geojson(),
+                nth_feature(source, index).to_geojson(),
+                "{index}"
+            );
+        }
+        let error = feature_at(source, 370, Arc::default(), OpenOptions::default())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error,
+            format!("There is no feature 370 in {source}; it has 370, from 0 to 369")
+        );
+    }
+
+    #[tokio::test]
+    async fn remote_feature_at_reads_only_around_the_feature() {
+        let (url, _) = versioned_server("testdata/colorado_subset.fgb");
+        let transfer = Arc::new(TransferStats::default());
+        let feature = feature_at(&url, 369, transfer.clone(), OpenOptions::default())
+            .await
+            .unwrap();
+        let expected = nth_feature("testdata/colorado_subset.fgb", 369);
+        assert_eq!(feature.to_geojson(), expected.to_geojson());
+        // the file is 602152 bytes, which reading up to the last feature would all be
+        assert!(transfer.bytes() < 100_000, "{} bytes", transfer.bytes());
+    }
+
+    /// Write points with one string column, whose value for the `i`th feature written is
+    /// `value(i)`
+    fn write_values_fixture(
+        name: &str,
+        features: usize,
+        value: impl Fn(usize) -> String,
+    ) -> std::path::PathBuf {
+        use flatgeobuf::{
+            ColumnType, FgbWriter, GeometryType,
+            geozero::{ColumnValue, PropertyProcessor},
+        };
+        use geozero::geojson::GeoJson;
+
+        let mut fgb = FgbWriter::create(name, GeometryType::Point).unwrap();
+        fgb.add_column("value", ColumnType::String, |_, _| {});
+        for i in 0..features {
+            let point = format!(
+                r#"{{"type": "Point", "coordinates": [{}, {}]}}"#,
+                i % 100,
+                i / 100
+            );
+            fgb.add_feature_geom(GeoJson(&point), |feature| {
+                feature
+                    .property(0, "value", &ColumnValue::String(&value(i)))
+                    .unwrap();
+            })
+            .unwrap();
+        }
+        let path = std::env::temp_dir().join(format!("fgbdump-{}-{name}.fgb", std::process::id()));
+        fgb.write(std::io::BufWriter::new(File::create(&path).unwrap()))
+            .unwrap();
+        path
+    }
+
+    /// The statistics of `source` read on `threads` threads, and how many features the
+    /// progress counted
+    async fn parallel_stats(source: &str, threads: usize) -> (serde_json::Value, usize) {
+        let dataset = Dataset::open(source, Arc::default(), OpenOptions::default())
+            .await
+            .unwrap();
+        let progress = Arc::new(AtomicUsize::new(0));
+        let stats = scan_stats_parallel(
+            source,
+            &dataset,
+            None,
+            threads,
+            &progress,
+            Arc::default(),
+            OpenOptions::default(),
+        )
+        .await
+        .unwrap();
+        (stats.to_json(), progress.load(Ordering::Relaxed))
+    }
+
+    #[tokio::test]
+    async fn stats_read_on_several_threads_are_those_of_one_pass() {
+        let colorado = "testdata/colorado_subset.fgb";
+        // each value twice, so that the runs together have more distinct values than are
+        // counted but none has on its own, and the merge decides which are
+        let repeated =
+            write_values_fixture("repeated-values", 24_000, |i| (i % 12_000).to_string());
+        // runs with more distinct values than are counted, which leave out some values
+        // that the runs before counted, so that the file is read in one pass instead
+        let crowded = write_values_fixture("crowded-values", 44_000, |i| {
+            // scattered, so that the runs don't leave out the same values
+            (((i as u64).wrapping_mul(2_654_435_761) >> 8) & 0x3fff).to_string()
+        });
+        for (source, features) in [
+            (colorado, 370),
+            (repeated.to_str().unwrap(), 24_000),
+            (crowded.to_str().unwrap(), 44_000),
+        ] {
+            let (one_pass, _) = parallel_stats(source, 1).await;
+            assert_eq!(one_pass["features"], features, "{source}");
+            for threads in [2, 3, 4, 7] {
+                let (stats, progress) = parallel_stats(source, threads).await;
+                assert_eq!(stats, one_pass, "{source} on {threads} threads");
+                assert_eq!(progress, features, "{source} on {threads} threads");
+            }
+        }
+        std::fs::remove_file(repeated).unwrap();
+        std::fs::remove_file(crowded).unwrap();
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    fmt::Display,
+    process::ExitCode,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Whether `--quiet` was given, which leaves nothing on stderr but errors. Everything
+/// else written to stderr goes through here or [`crate::progress::report`], which
+/// checks it, so that nothing slips past it
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Print an error to stderr, even when quiet
+pub fn error(message: &str) {
+    eprintln!("{message}");
+}
+
+/// Print `warning: {message}` to stderr, unless quiet
+pub fn warn(message: &str) {
+    if !quiet() {
+        eprintln!("warning: {message}");
+    }
+}
+
+/// Print something which is neither a warning nor an error to stderr, unless quiet, such
+/// as a summary at the end of an export or what `--verbose` asked for
+pub fn note(message: &str) {
+    if !quiet() {
+        eprintln!("{message}");
+    }
+}
+
+/// The exit status after Ctrl-C, as shells use for SIGINT
+pub const INTERRUPTED: u8 = 130;
+
+/// Why fgbdump is exiting unsuccessfully. Returned all the way up to `main`, which
+/// reports it once with [`report`], so that destructors run on the way out
+#[derive(Debug)]
+pub struct Failure {
+    /// Printed as an error, unless empty because what went wrong was already printed
+    message: String,
+    code: u8,
+}
+
+impl Failure {
+    /// What went wrong was already printed, as `check` prints the problems it finds
+    pub fn reported() -> Self {
+        Self {
+            message: String::new(),
+            code: 1,
+        }
+    }
+
+    /// Ctrl-C, after which `message` says what was left undone, if anything
+    pub fn interrupted(message: &str) -> Self {
+        Self {
+            message: message.to_string(),
+            code: INTERRUPTED,
+        }
+    }
+}
+
+// errors print in one line, where the debug output main would print for them is hard
+// to read
+impl<E: Display> From<E> for Failure {
+    fn from(error: E) -> Self {
+        Self {
+            message: error.to_string(),
+            code: 1,
+        }
+    }
+}
+
+/// Print the error of a failure, if it has one, giving the status to exit with
+pub fn report(failure: Failure) -> ExitCode {
+    if !failure.message.is_empty() {
+        error(&failure.message);
+    }
+    ExitCode::from(failure.code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failures_exit_with_their_status() {
+        assert_eq!(report(Failure::reported()), ExitCode::FAILURE);
+        assert_eq!(
+            report(Failure::interrupted("")),
+            ExitCode::from(INTERRUPTED)
+        );
+        assert_eq!(report("no such file".into()), ExitCode::FAILURE);
+    }
+}
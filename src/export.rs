@@ -0,0 +1,1105 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use flatgeobuf::{ColumnType, FgbCrs, FgbWriter, FgbWriterOptions, GeometryType, Header};
+use geozero::{ColumnValue, PropertyProcessor, geojson::GeoJson};
+use serde_json::{Number, Value, json};
+use std::{cmp::Ordering, io::Write, str::FromStr};
+
+use crate::{
+    feature::Feature,
+    projection::{Reprojection, crs_urn, is_map_crs},
+    properties::{Properties, PropertyValue},
+};
+
+/// Where features go as they're read, one at a time, so that a large file can be
+/// written out without keeping its features in memory
+pub trait FeatureSink {
+    /// Called once with the names of the columns in the header, before any feature
+    fn start(&mut self, columns: &[String]) -> Result<(), String>;
+
+    fn feature(&mut self, feature: &Feature) -> Result<(), String>;
+
+    /// Called once after the last feature
+    fn finish(&mut self) -> Result<(), String>;
+}
+
+/// Which columns to write: the positions of those named in `select`, or of all of them
+/// if it's empty, in header order either way
+fn selected_columns(select: &[String], columns: &[String]) -> Result<Vec<usize>, String> {
+    if let Some(name) = select.iter().find(|name| !columns.contains(name)) {
+        return Err(format!("There is no column named {name}"));
+    }
+    Ok((0..columns.len())
+        .filter(|&i| select.is_empty() || select.contains(&columns[i]))
+        .collect())
+}
+
+/// Only the properties at `columns`
+fn selected_properties(columns: &[usize], properties: &Properties) -> Properties {
+    Properties(
+        columns
+            .iter()
+            .filter_map(|&i| properties.0.get(i).cloned())
+            .collect(),
+    )
+}
+
+fn write_error(e: std::io::Error) -> String {
+    format!("Failed to write the features: {e}")
+}
+
+/// Writes a row for each feature with its properties, then its geometry as WKT unless
+/// `geometry` is false, after a row naming the columns
+pub struct CsvWriter<W> {
+    out: W,
+    select: Vec<String>,
+    geometry: bool,
+    /// The positions of the columns written, once the header is known
+    columns: Vec<usize>,
+    /// How many features have been written so far
+    written: usize,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// Write only the columns named in `select`, or all of them if it's empty
+    pub fn new(out: W, select: Vec<String>, geometry: bool) -> Self {
+        Self {
+            out,
+            select,
+            geometry,
+            columns: Vec::new(),
+            written: 0,
+        }
+    }
+
+    fn write_row<'a>(&mut self, fields: impl Iterator<Item = &'a str>) -> Result<(), String> {
+        let row = fields.map(csv_field).collect::<Vec<_>>().join(",");
+        writeln!(self.out, "{row}").map_err(write_error)
+    }
+}
+
+impl<W: Write> FeatureSink for CsvWriter<W> {
+    fn start(&mut self, columns: &[String]) -> Result<(), String> {
+        self.columns = selected_columns(&self.select, columns)?;
+        let mut names: Vec<&str> = self.columns.iter().map(|&i| columns[i].as_str()).collect();
+        if self.geometry {
+            names.push("geometry");
+        }
+        self.write_row(names.into_iter())
+    }
+
+    fn feature(&mut self, feature: &Feature) -> Result<(), String> {
+        let properties = &feature.properties.0;
+        let mut fields: Vec<String> = self
+            .columns
+            .iter()
+            .map(|&i| match properties.get(i) {
+                Some((_, Some(value))) => value.to_string(),
+                // an empty field, as spreadsheets take for a missing value
+                _ => String::new(),
+            })
+            .collect();
+        if self.geometry {
+            // an empty field for a missing geometry, but not for one that can't be written
+            let wkt = match feature.wkt() {
+                Some(wkt) => wkt,
+                None if feature.geometry.is_null() => String::new(),
+                None => {
+                    return Err(format!(
+                        "Failed to write the features: the geometry of feature {} can't be \
+                         written as WKT",
+                        self.written
+                    ));
+                }
+            };
+            fields.push(wkt);
+        }
+        self.write_row(fields.iter().map(String::as_str))?;
+        self.written += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        self.out.flush().map_err(write_error)
+    }
+}
+
+/// `field` quoted if it has a comma, quote or line break in it, with its quotes doubled,
+/// as RFC 4180 and spreadsheets expect. WKT with more than one position always has commas
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Writes each feature as a GeoJSON Feature on a line of its own, with only the
+/// properties named in `select`, or all of them if it's empty, and a null geometry
+/// unless `geometry` is true
+pub struct NdjsonWriter<W> {
+    out: W,
+    select: Vec<String>,
+    geometry: bool,
+    /// The positions of the columns written, once the header is known
+    columns: Vec<usize>,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    pub fn new(out: W, select: Vec<String>, geometry: bool) -> Self {
+        Self {
+            out,
+            select,
+            geometry,
+            columns: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> FeatureSink for NdjsonWriter<W> {
+    fn start(&mut self, columns: &[String]) -> Result<(), String> {
+        self.columns = selected_columns(&self.select, columns)?;
+        Ok(())
+    }
+
+    fn feature(&mut self, feature: &Feature) -> Result<(), String> {
+        let properties = selected_properties(&self.columns, &feature.properties);
+        let geometry = if self.geometry {
+            feature.geometry.clone()
+        } else {
+            Value::Null
+        };
+        let feature = json!({
+            "type": "Feature",
+            "geometry": geometry,
+            "properties": properties.to_json(),
+        });
+        writeln!(self.out, "{feature}").map_err(write_error)
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        self.out.flush().map_err(write_error)
+    }
+}
+
+/// Writes the features as a single GeoJSON FeatureCollection, one feature per line,
+/// with only the properties named in `select`, or all of them if it's empty. The
+/// collection is only closed by `finish`, so output cut short by an error or an
+/// interruption is missing its closing bracket and won't parse
+pub struct GeojsonWriter<W> {
+    out: W,
+    select: Vec<String>,
+    /// The positions of the columns written, once the header is known
+    columns: Vec<usize>,
+    /// The CRS of the coordinates, named in the collection unless it's EPSG:4326
+    crs: Option<String>,
+    /// Whether a feature has been written, which the next needs a comma before
+    written: bool,
+}
+
+impl<W: Write> GeojsonWriter<W> {
+    pub fn new(out: W, select: Vec<String>, crs: Option<String>) -> Self {
+        Self {
+            out,
+            select,
+            columns: Vec::new(),
+            crs,
+            written: false,
+        }
+    }
+}
+
+impl<W: Write> FeatureSink for GeojsonWriter<W> {
+    fn start(&mut self, columns: &[String]) -> Result<(), String> {
+        self.columns = selected_columns(&self.select, columns)?;
+        write!(self.out, r#"{{"type":"FeatureCollection","#).map_err(write_error)?;
+        // RFC 7946 took out the crs member, leaving EPSG:4326 as the only CRS, but
+        // GDAL and QGIS still read it for the others
+        if let Some(crs) = self.crs.as_deref().filter(|crs| !is_map_crs(crs)) {
+            let crs = json!({"type": "name", "properties": {"name": crs_urn(crs)}});
+            write!(self.out, r#""crs":{crs},"#).map_err(write_error)?;
+        }
+        writeln!(self.out, r#""features":["#).map_err(write_error)
+    }
+
+    fn feature(&mut self, feature: &Feature) -> Result<(), String> {
+        let separator = if self.written { ",\n" } else { "" };
+        self.written = true;
+        let feature = Feature {
+            geometry: feature.geometry.clone(),
+            properties: selected_properties(&self.columns, &feature.properties),
+        };
+        write!(self.out, "{separator}{}", feature.to_geojson()).map_err(write_error)
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        writeln!(self.out, "\n]}}").map_err(write_error)?;
+        self.out.flush().map_err(write_error)
+    }
+}
+
+/// A column as the header describes it, kept to write the same column to a new file
+#[derive(Clone, Debug)]
+pub struct ColumnSchema {
+    pub name: String,
+    pub column_type: ColumnType,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub width: i32,
+    pub precision: i32,
+    pub scale: i32,
+    pub nullable: bool,
+    pub unique: bool,
+    pub primary_key: bool,
+    pub metadata: Option<String>,
+}
+
+/// What a FlatGeobuf written by [`FgbFileWriter`] keeps of the header of the file read,
+/// owned so that it outlives the reader
+#[derive(Clone, Debug)]
+pub struct FgbSchema {
+    pub name: String,
+    pub geometry_type: GeometryType,
+    pub has_z: bool,
+    pub has_m: bool,
+    pub has_t: bool,
+    pub has_tm: bool,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub metadata: Option<String>,
+    pub crs: CrsSchema,
+    pub columns: Vec<ColumnSchema>,
+}
+
+/// The CRS of a header, with the same fields
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CrsSchema {
+    pub org: Option<String>,
+    pub code: i32,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub wkt: Option<String>,
+    pub code_string: Option<String>,
+}
+
+impl CrsSchema {
+    /// The CRS `crs` names, as an `org:code` like `EPSG:4326` or else as WKT
+    pub fn named(crs: &str) -> Self {
+        match crs.split_once(':') {
+            Some((org, code)) if !crs.contains(char::is_whitespace) => match code.parse() {
+                Ok(code) => Self {
+                    org: Some(org.to_string()),
+                    code,
+                    ..Default::default()
+                },
+                Err(_) => Self {
+                    org: Some(org.to_string()),
+                    code_string: Some(code.to_string()),
+                    ..Default::default()
+                },
+            },
+            _ => Self {
+                wkt: Some(crs.to_string()),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl FgbSchema {
+    pub fn of(header: &Header) -> Self {
+        let owned = |text: Option<&str>| text.map(String::from);
+        let crs = header
+            .crs()
+            .map_or_else(CrsSchema::default, |crs| CrsSchema {
+                org: owned(crs.org()),
+                code: crs.code(),
+                name: owned(crs.name()),
+                description: owned(crs.description()),
+                wkt: owned(crs.wkt()),
+                code_string: owned(crs.code_string()),
+            });
+        let columns = header.columns().map_or_else(Vec::new, |columns| {
+            columns
+                .iter()
+                .map(|column| ColumnSchema {
+                    name: column.name().to_string(),
+                    column_type: column.type_(),
+                    title: owned(column.title()),
+                    description: owned(column.description()),
+                    width: column.width(),
+                    precision: column.precision(),
+                    scale: column.scale(),
+                    nullable: column.nullable(),
+                    unique: column.unique(),
+                    primary_key: column.primary_key(),
+                    metadata: owned(column.metadata()),
+                })
+                .collect()
+        });
+        Self {
+            name: header.name().unwrap_or_default().to_string(),
+            geometry_type: header.geometry_type(),
+            has_z: header.has_z(),
+            has_m: header.has_m(),
+            has_t: header.has_t(),
+            has_tm: header.has_tm(),
+            title: owned(header.title()),
+            description: owned(header.description()),
+            metadata: owned(header.metadata()),
+            crs,
+            columns,
+        }
+    }
+}
+
+/// Writes the features to a new FlatGeobuf with a spatial index, with only the columns
+/// named in `select`, or all of them if it's empty, described as the header of the file
+/// read describes them. The features are sorted along the index as they're written by
+/// `finish`, so until then nothing is written to `out`
+pub struct FgbFileWriter<W> {
+    out: Option<W>,
+    select: Vec<String>,
+    schema: FgbSchema,
+    /// The positions of the columns written, once the header is known
+    columns: Vec<usize>,
+    fgb: Option<FgbWriter<'static>>,
+    /// How many features have been written so far
+    written: usize,
+}
+
+impl<W: Write> FgbFileWriter<W> {
+    pub fn new(out: W, select: Vec<String>, schema: FgbSchema) -> Self {
+        Self {
+            out: Some(out),
+            select,
+            schema,
+            columns: Vec::new(),
+            fgb: None,
+            written: 0,
+        }
+    }
+}
+
+fn fgb_error(e: impl std::fmt::Display) -> String {
+    format!("Failed to write the features: {e}")
+}
+
+impl<W: Write> FeatureSink for FgbFileWriter<W> {
+    fn start(&mut self, columns: &[String]) -> Result<(), String> {
+        self.columns = selected_columns(&self.select, columns)?;
+        let schema = &self.schema;
+        let options = FgbWriterOptions {
+            write_index: true,
+            // the geometry type and its dimensions stay as the header has them
+            detect_type: false,
+            promote_to_multi: false,
+            crs: FgbCrs {
+                org: schema.crs.org.as_deref(),
+                code: schema.crs.code,
+                name: schema.crs.name.as_deref(),
+                description: schema.crs.description.as_deref(),
+                wkt: schema.crs.wkt.as_deref(),
+                code_string: schema.crs.code_string.as_deref(),
+            },
+            has_z: schema.has_z,
+            has_m: schema.has_m,
+            has_t: schema.has_t,
+            has_tm: schema.has_tm,
+            title: schema.title.as_deref(),
+            description: schema.description.as_deref(),
+            metadata: schema.metadata.as_deref(),
+        };
+        let mut fgb = FgbWriter::create_with_options(&schema.name, schema.geometry_type, options)
+            .map_err(fgb_error)?;
+        for &i in &self.columns {
+            let column = &schema.columns[i];
+            fgb.add_column(&column.name, column.column_type, |fbb, args| {
+                args.title = column
+                    .title
+                    .as_deref()
+                    .map(|title| fbb.create_string(title));
+                args.description = column
+                    .description
+                    .as_deref()
+                    .map(|description| fbb.create_string(description));
+                args.width = column.width;
+                args.precision = column.precision;
+                args.scale = column.scale;
+                args.nullable = column.nullable;
+                args.unique = column.unique;
+                args.primary_key = column.primary_key;
+                args.metadata = column
+                    .metadata
+                    .as_deref()
+                    .map(|metadata| fbb.create_string(metadata));
+            });
+        }
+        self.fgb = Some(fgb);
+        Ok(())
+    }
+
+    fn feature(&mut self, feature: &Feature) -> Result<(), String> {
+        let mut values = Vec::new();
+        for (j, &i) in self.columns.iter().enumerate() {
+            let column = &self.schema.columns[i];
+            let Some((_, Some(value))) = feature.properties.0.get(i) else {
+                continue;
+            };
+            let value = column_value(value, column.column_type).ok_or_else(|| {
+                format!(
+                    "Failed to write the features: {value} doesn't fit the {:?} column {}",
+                    column.column_type, column.name
+                )
+            })?;
+            values.push((j, column.name.as_str(), value));
+        }
+        // the writer always writes a geometry, and an empty one trips up readers
+        if feature.geometry.is_null() {
+            return Err(format!(
+                "Failed to write the features: feature {} has no geometry, which can't be \
+                 written to FlatGeobuf",
+                self.written
+            ));
+        }
+        let geometry = feature.geometry.to_string();
+        let fgb = self
+            .fgb
+            .as_mut()
+            .expect("start is called before any feature");
+        fgb.add_feature_geom(GeoJson(&geometry), |writer| {
+            write_properties(writer, &values)
+        })
+        .map_err(fgb_error)?;
+        self.written += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        let (Some(fgb), Some(mut out)) = (self.fgb.take(), self.out.take()) else {
+            return Ok(());
+        };
+        fgb.write(&mut out).map_err(fgb_error)?;
+        out.flush().map_err(write_error)
+    }
+}
+
+/// Add each of `values` to the feature being written, at its position among the columns
+fn write_properties(writer: &mut impl PropertyProcessor, values: &[(usize, &str, ColumnValue)]) {
+    for (i, name, value) in values {
+        // writing a property can't fail, but the trait allows for it
+        let _ = writer.property(*i, name, value);
+    }
+}
+
+/// `value` as the type of a column, which it was decoded from and so fits unless the
+/// column has a type other than the one the value came from
+fn column_value(value: &PropertyValue, column_type: ColumnType) -> Option<ColumnValue<'_>> {
+    use PropertyValue as P;
+    Some(match (column_type, value) {
+        (ColumnType::Byte, P::Int(v)) => ColumnValue::Byte(i8::try_from(*v).ok()?),
+        (ColumnType::UByte, P::Int(v)) => ColumnValue::UByte(u8::try_from(*v).ok()?),
+        (ColumnType::Bool, P::Bool(v)) => ColumnValue::Bool(*v),
+        (ColumnType::Short, P::Int(v)) => ColumnValue::Short(i16::try_from(*v).ok()?),
+        (ColumnType::UShort, P::Int(v)) => ColumnValue::UShort(u16::try_from(*v).ok()?),
+        (ColumnType::Int, P::Int(v)) => ColumnValue::Int(i32::try_from(*v).ok()?),
+        (ColumnType::UInt, P::Int(v)) => ColumnValue::UInt(u32::try_from(*v).ok()?),
+        (ColumnType::Long, P::Int(v)) => ColumnValue::Long(*v),
+        (ColumnType::ULong, P::UInt(v)) => ColumnValue::ULong(*v),
+        (ColumnType::Float, P::Float(v)) => ColumnValue::Float(*v as f32),
+        (ColumnType::Double, P::Float(v)) => ColumnValue::Double(*v),
+        (ColumnType::String, P::String(v)) => ColumnValue::String(v),
+        (ColumnType::Json, P::Json(v)) => ColumnValue::Json(v),
+        (ColumnType::DateTime, P::DateTime(v)) => ColumnValue::DateTime(v),
+        (ColumnType::Binary, P::Binary(v)) => ColumnValue::Binary(v),
+        _ => return None,
+    })
+}
+
+/// A condition on a property, like `state=NY` or `population>=10000`, which features
+/// without a value for the property never meet
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+    pub column: String,
+    pub operator: Operator,
+    pub value: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Condition {
+    /// Whether `value` meets the condition: numbers are compared as numbers if the
+    /// condition's value is one, and anything else as text
+    pub fn matches(&self, value: Option<&PropertyValue>) -> bool {
+        let Some(value) = value else {
+            return false;
+        };
+        let number = match value {
+            PropertyValue::Int(v) => Some(*v as f64),
+            PropertyValue::UInt(v) => Some(*v as f64),
+            PropertyValue::Float(v) => Some(*v),
+            _ => None,
+        };
+        let ordering = match (number, self.value.parse::<f64>()) {
+            (Some(number), Ok(wanted)) => number.partial_cmp(&wanted),
+            _ => Some(value.to_string().as_str().cmp(self.value.as_str())),
+        };
+        // NaN meets no condition but !=
+        let Some(ordering) = ordering else {
+            return self.operator == Operator::NotEqual;
+        };
+        match self.operator {
+            Operator::Equal => ordering == Ordering::Equal,
+            Operator::NotEqual => ordering != Ordering::Equal,
+            Operator::Less => ordering == Ordering::Less,
+            Operator::LessOrEqual => ordering != Ordering::Greater,
+            Operator::Greater => ordering == Ordering::Greater,
+            Operator::GreaterOrEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    /// The column name, then one of `=`, `!=`, `<`, `<=`, `>` or `>=`, then the value
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid condition '{text}'; expected a column, an operator such as = or >=, and a value"
+            )
+        };
+        let at = text.find(['=', '!', '<', '>']).ok_or_else(invalid)?;
+        let (column, rest) = text.split_at(at);
+        let (operator, value) = [
+            ("!=", Operator::NotEqual),
+            ("<=", Operator::LessOrEqual),
+            (">=", Operator::GreaterOrEqual),
+            ("=", Operator::Equal),
+            ("<", Operator::Less),
+            (">", Operator::Greater),
+        ]
+        .into_iter()
+        .find_map(|(symbol, operator)| Some((operator, rest.strip_prefix(symbol)?)))
+        .ok_or_else(invalid)?;
+        let column = column.trim();
+        if column.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            column: column.to_string(),
+            operator,
+            value: value.trim().to_string(),
+        })
+    }
+}
+
+/// Passes on to `inner` only the features which meet every one of `conditions`
+pub struct Filtered<'a> {
+    conditions: Vec<Condition>,
+    inner: &'a mut dyn FeatureSink,
+    /// How many features have been passed on so far
+    pub matched: usize,
+}
+
+impl<'a> Filtered<'a> {
+    pub fn new(conditions: Vec<Condition>, inner: &'a mut dyn FeatureSink) -> Self {
+        Self {
+            conditions,
+            inner,
+            matched: 0,
+        }
+    }
+}
+
+impl FeatureSink for Filtered<'_> {
+    fn start(&mut self, columns: &[String]) -> Result<(), String> {
+        if let Some(condition) = self
+            .conditions
+            .iter()
+            .find(|condition| !columns.contains(&condition.column))
+        {
+            return Err(format!("There is no column named {}", condition.column));
+        }
+        self.inner.start(columns)
+    }
+
+    fn feature(&mut self, feature: &Feature) -> Result<(), String> {
+        let properties = &feature.properties;
+        if self
+            .conditions
+            .iter()
+            .all(|condition| condition.matches(properties.get(&condition.column)))
+        {
+            self.inner.feature(feature)?;
+            self.matched += 1;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        self.inner.finish()
+    }
+}
+
+/// Projects the geometry of each feature before passing it on to `inner`, failing with
+/// the index of the feature, counting from 0 in the order they were read, if any of
+/// its positions can't be projected
+pub struct Reprojected<'a> {
+    reprojection: Reprojection,
+    inner: &'a mut dyn FeatureSink,
+    /// How many features have been read so far
+    index: usize,
+}
+
+impl<'a> Reprojected<'a> {
+    pub fn new(reprojection: Reprojection, inner: &'a mut dyn FeatureSink) -> Self {
+        Self {
+            reprojection,
+            inner,
+            index: 0,
+        }
+    }
+}
+
+impl FeatureSink for Reprojected<'_> {
+    fn start(&mut self, columns: &[String]) -> Result<(), String> {
+        self.inner.start(columns)
+    }
+
+    fn feature(&mut self, feature: &Feature) -> Result<(), String> {
+        let mut feature = feature.clone();
+        reproject_geometry(&mut feature.geometry, &self.reprojection).map_err(|e| {
+            format!(
+                "Failed to project feature {} to {}: {e}",
+                self.index, self.reprojection.to
+            )
+        })?;
+        self.index += 1;
+        self.inner.feature(&feature)
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        self.inner.finish()
+    }
+}
+
+/// Project every position in a GeoJSON geometry, including those of the members of a
+/// GeometryCollection, leaving any Z as it is
+fn reproject_geometry(geometry: &mut Value, reprojection: &Reprojection) -> Result<(), String> {
+    match geometry {
+        // a position
+        Value::Array(items) if items.first().is_some_and(Value::is_number) => {
+            let (Some(x), Some(y)) = (items[0].as_f64(), items.get(1).and_then(Value::as_f64))
+            else {
+                return Err(format!("{geometry} isn't a position"));
+            };
+            let (x, y) = reprojection.convert((x, y)).map_err(|e| e.to_string())?;
+            // PROJ gives infinity rather than failing for some positions out of range
+            let (Some(x), Some(y)) = (Number::from_f64(x), Number::from_f64(y)) else {
+                return Err(format!("({x}, {y}) isn't a valid position"));
+            };
+            items[0] = Value::Number(x);
+            items[1] = Value::Number(y);
+            Ok(())
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .try_for_each(|item| reproject_geometry(item, reprojection)),
+        Value::Object(object) => object
+            .iter_mut()
+            .filter(|(key, _)| *key == "coordinates" || *key == "geometries")
+            .try_for_each(|(_, value)| reproject_geometry(value, reprojection)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flatgeobuf::{FallibleStreamingIterator, FgbReader};
+
+    fn column(name: &str, column_type: ColumnType) -> ColumnSchema {
+        ColumnSchema {
+            name: name.to_string(),
+            column_type,
+            title: None,
+            description: None,
+            width: -1,
+            precision: -1,
+            scale: -1,
+            nullable: true,
+            unique: false,
+            primary_key: false,
+            metadata: None,
+        }
+    }
+
+    fn feature(geometry: Value, properties: Vec<(&str, Option<PropertyValue>)>) -> Feature {
+        Feature {
+            geometry,
+            properties: Properties(
+                properties
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value))
+                    .collect(),
+            ),
+        }
+    }
+
+    #[test]
+    fn fgb_output_keeps_the_selected_columns_as_described() {
+        let id = ColumnSchema {
+            title: Some("Identifier".to_string()),
+            description: Some("the catchment".to_string()),
+            nullable: false,
+            unique: true,
+            primary_key: true,
+            metadata: Some(r#"{"source": "NHDPlus"}"#.to_string()),
+            ..column("id", ColumnType::Long)
+        };
+        let area = ColumnSchema {
+            description: Some("square kilometres".to_string()),
+            width: 12,
+            precision: 10,
+            scale: 4,
+            ..column("area", ColumnType::Float)
+        };
+        let schema = FgbSchema {
+            name: "catchments".to_string(),
+            geometry_type: GeometryType::Point,
+            has_z: false,
+            has_m: false,
+            has_t: false,
+            has_tm: false,
+            title: Some("Catchments".to_string()),
+            description: None,
+            metadata: Some("{}".to_string()),
+            crs: CrsSchema::named("EPSG:4326"),
+            columns: vec![id, column("name", ColumnType::String), area],
+        };
+        let names: Vec<String> = schema.columns.iter().map(|c| c.name.clone()).collect();
+        let select = vec!["area".to_string(), "id".to_string()];
+        let mut out = Vec::new();
+        let mut writer = FgbFileWriter::new(&mut out, select, schema.clone());
+        writer.start(&names).unwrap();
+        writer
+            .feature(&feature(
+                json!({"type": "Point", "coordinates": [-108.5, 37.25]}),
+                vec![
+                    ("id", Some(PropertyValue::Int(7))),
+                    ("name", Some(PropertyValue::String("Mancos".to_string()))),
+                    ("area", Some(PropertyValue::Float(2.5))),
+                ],
+            ))
+            .unwrap();
+        let no_geometry = feature(Value::Null, vec![]);
+        assert_eq!(
+            writer.feature(&no_geometry).unwrap_err(),
+            "Failed to write the features: feature 1 has no geometry, which can't be \
+             written to FlatGeobuf"
+        );
+        // no value for area
+        writer
+            .feature(&feature(
+                json!({"type": "Point", "coordinates": [-108.25, 37.5]}),
+                vec![
+                    ("id", Some(PropertyValue::Int(8))),
+                    ("name", None),
+                    ("area", None),
+                ],
+            ))
+            .unwrap();
+        writer.finish().unwrap();
+
+        let fgb = FgbReader::open(std::io::Cursor::new(&out)).unwrap();
+        let header = fgb.header();
+        assert_eq!(header.features_count(), 2);
+        assert_eq!(header.title(), Some("Catchments"));
+        let crs = header.crs().unwrap();
+        assert_eq!((crs.org(), crs.code()), (Some("EPSG"), 4326));
+        let written = FgbSchema::of(&header);
+        // in the order of the file, rather than of --select
+        let expected = [&schema.columns[0], &schema.columns[2]];
+        assert_eq!(written.columns.len(), expected.len());
+        for (written, expected) in written.columns.iter().zip(expected) {
+            assert_eq!(format!("{written:?}"), format!("{expected:?}"));
+        }
+
+        let columns: Vec<String> = written.columns.iter().map(|c| c.name.clone()).collect();
+        let mut features = fgb.select_all_seq().unwrap();
+        let mut read = Vec::new();
+        while let Some(feature) = features.next().unwrap() {
+            read.push(Feature::from_fgb(feature, &columns).unwrap());
+        }
+        read.sort_by_key(|feature| feature.properties.get("id").map(ToString::to_string));
+        assert_eq!(
+            read[0].properties.0,
+            [
+                ("id".to_string(), Some(PropertyValue::Int(7))),
+                ("area".to_string(), Some(PropertyValue::Float(2.5)))
+            ]
+        );
+        assert_eq!(read[0].geometry["coordinates"], json!([-108.5, 37.25]));
+        assert_eq!(
+            read[1].properties.0,
+            [
+                ("id".to_string(), Some(PropertyValue::Int(8))),
+                ("area".to_string(), None)
+            ]
+        );
+    }
+
+    #[test]
+    fn values_must_fit_their_column() {
+        let fits = |value, column_type| column_value(&value, column_type).is_some();
+        assert!(fits(PropertyValue::Int(-128), ColumnType::Byte));
+        assert!(!fits(PropertyValue::Int(128), ColumnType::Byte));
+        assert!(!fits(PropertyValue::Int(-1), ColumnType::UInt));
+        assert!(fits(PropertyValue::UInt(u64::MAX), ColumnType::ULong));
+        assert!(!fits(
+            PropertyValue::String("1".to_string()),
+            ColumnType::Int
+        ));
+    }
+
+    #[test]
+    fn crs_schemas_by_name() {
+        let named = CrsSchema::named("EPSG:3857");
+        assert_eq!((named.org.as_deref(), named.code), (Some("EPSG"), 3857));
+        let named = CrsSchema::named("OGC:CRS84");
+        assert_eq!(named.code_string.as_deref(), Some("CRS84"));
+        let wkt = r#"GEOGCRS["WGS 84", CS[ellipsoidal, 2]]"#;
+        assert_eq!(CrsSchema::named(wkt).wkt.as_deref(), Some(wkt));
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_they_need_to_be() {
+        assert_eq!(csv_field("Mancos"), "Mancos");
+        assert_eq!(csv_field(""), "");
+        assert_eq!(csv_field("Mancos, CO"), "\"Mancos, CO\"");
+        assert_eq!(csv_field("the \"Mancos\""), "\"the \"\"Mancos\"\"\"");
+        assert_eq!(csv_field("one\ntwo"), "\"one\ntwo\"");
+        assert_eq!(csv_field("one\r\ntwo"), "\"one\r\ntwo\"");
+        assert_eq!(
+            csv_field("LINESTRING (0 0, 1 1)"),
+            "\"LINESTRING (0 0, 1 1)\""
+        );
+        assert_eq!(csv_field("POINT (0 0)"), "POINT (0 0)");
+    }
+
+    #[test]
+    fn csv_rows_follow_the_header_order_with_the_geometry_last() {
+        let names = ["id", "name", "area"].map(String::from);
+        let mut out = Vec::new();
+        let select = vec!["area".to_string(), "id".to_string()];
+        let mut writer = CsvWriter::new(&mut out, select, true);
+        writer.start(&names).unwrap();
+        writer
+            .feature(&feature(
+                json!({"type": "LineString", "coordinates": [[0.0, 0.0], [1.5, 1.0]]}),
+                vec![
+                    ("id", Some(PropertyValue::Int(7))),
+                    ("name", Some(PropertyValue::String("Mancos".to_string()))),
+                    ("area", Some(PropertyValue::Float(2.5))),
+                ],
+            ))
+            .unwrap();
+        writer
+            .feature(&feature(
+                Value::Null,
+                vec![
+                    ("id", Some(PropertyValue::Int(8))),
+                    ("name", None),
+                    ("area", None),
+                ],
+            ))
+            .unwrap();
+        writer.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "id,area,geometry\n7,2.5,\"LINESTRING (0.0 0.0, 1.5 1.0)\"\n8,,\n"
+        );
+
+        let mut out = Vec::new();
+        let mut writer = CsvWriter::new(&mut out, Vec::new(), false);
+        writer.start(&names).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "id,name,area\n");
+    }
+
+    #[test]
+    fn csv_geometries_which_cant_be_written_as_wkt_are_errors() {
+        let mut writer = CsvWriter::new(Vec::new(), Vec::new(), true);
+        writer.start(&[]).unwrap();
+        writer
+            .feature(&feature(
+                json!({"type": "Point", "coordinates": [1.0, 2.0]}),
+                vec![],
+            ))
+            .unwrap();
+        let curve = feature(json!({"type": "CircularString", "coordinates": []}), vec![]);
+        assert_eq!(
+            writer.feature(&curve).unwrap_err(),
+            "Failed to write the features: the geometry of feature 1 can't be written as WKT"
+        );
+    }
+
+    /// Two catchments, the second with no name or area
+    fn catchments(sink: &mut dyn FeatureSink) -> Result<(), String> {
+        sink.start(&["id", "name", "area"].map(String::from))?;
+        sink.feature(&feature(
+            json!({"type": "Point", "coordinates": [-108.5, 37.25]}),
+            vec![
+                ("id", Some(PropertyValue::Int(7))),
+                ("name", Some(PropertyValue::String("Mancos".to_string()))),
+                ("area", Some(PropertyValue::Float(2.5))),
+            ],
+        ))?;
+        sink.feature(&feature(
+            json!({"type": "Point", "coordinates": [-108.25, 37.5]}),
+            vec![
+                ("id", Some(PropertyValue::Int(8))),
+                ("name", None),
+                ("area", None),
+            ],
+        ))?;
+        sink.finish()
+    }
+
+    fn json_lines(out: &[u8]) -> Vec<Value> {
+        let out = std::str::from_utf8(out).unwrap();
+        out.lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn ndjson_writes_a_feature_per_line() {
+        let mut out = Vec::new();
+        let select = vec!["name".to_string(), "id".to_string()];
+        catchments(&mut NdjsonWriter::new(&mut out, select, true)).unwrap();
+        assert_eq!(
+            json_lines(&out),
+            [
+                json!({
+                    "type": "Feature",
+                    "geometry": {"type": "Point", "coordinates": [-108.5, 37.25]},
+                    "properties": {"id": 7, "name": "Mancos"},
+                }),
+                json!({
+                    "type": "Feature",
+                    "geometry": {"type": "Point", "coordinates": [-108.25, 37.5]},
+                    "properties": {"id": 8, "name": null},
+                }),
+            ]
+        );
+
+        let mut out = Vec::new();
+        catchments(&mut NdjsonWriter::new(&mut out, Vec::new(), false)).unwrap();
+        let features = json_lines(&out);
+        assert_eq!(features.len(), 2);
+        for feature in &features {
+            assert_eq!(feature["type"], "Feature");
+            assert_eq!(feature["geometry"], Value::Null);
+        }
+        assert_eq!(
+            features[0]["properties"],
+            json!({"id": 7, "name": "Mancos", "area": 2.5})
+        );
+    }
+
+    #[test]
+    fn geojson_wraps_the_features_in_a_collection() {
+        let mut out = Vec::new();
+        let select = vec!["id".to_string()];
+        catchments(&mut GeojsonWriter::new(&mut out, select, None)).unwrap();
+        let collection: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(
+            collection,
+            json!({
+                "type": "FeatureCollection",
+                "features": [
+                    {
+                        "type": "Feature",
+                        "geometry": {"type": "Point", "coordinates": [-108.5, 37.25]},
+                        "properties": {"id": 7},
+                    },
+                    {
+                        "type": "Feature",
+                        "geometry": {"type": "Point", "coordinates": [-108.25, 37.5]},
+                        "properties": {"id": 8},
+                    },
+                ],
+            })
+        );
+
+        let crs = |crs: &str| {
+            let mut out = Vec::new();
+            let mut writer = GeojsonWriter::new(&mut out, Vec::new(), Some(crs.to_string()));
+            writer.start(&[]).unwrap();
+            writer.finish().unwrap();
+            serde_json::from_slice::<Value>(&out).unwrap()
+        };
+        assert_eq!(
+            crs("EPSG:26913"),
+            json!({
+                "type": "FeatureCollection",
+                "crs": {"type": "name", "properties": {"name": "urn:ogc:def:crs:EPSG::26913"}},
+                "features": [],
+            })
+        );
+        assert_eq!(crs("EPSG:4326").get("crs"), None);
+    }
+
+    #[test]
+    fn geojson_cut_short_doesnt_parse() {
+        let mut out = Vec::new();
+        let mut writer = GeojsonWriter::new(&mut out, Vec::new(), None);
+        writer.start(&[]).unwrap();
+        writer
+            .feature(&feature(
+                json!({"type": "Point", "coordinates": [1.0, 2.0]}),
+                vec![],
+            ))
+            .unwrap();
+        assert!(serde_json::from_slice::<Value>(&out).is_err());
+    }
+
+    #[test]
+    fn filtered_passes_on_only_features_meeting_every_condition() {
+        let filter = |conditions: &[&str]| {
+            let conditions = conditions.iter().map(|c| c.parse().unwrap()).collect();
+            let mut out = Vec::new();
+            let mut writer = NdjsonWriter::new(&mut out, vec!["id".to_string()], false);
+            let mut filtered = Filtered::new(conditions, &mut writer);
+            catchments(&mut filtered).map(|()| {
+                let matched = filtered.matched;
+                let ids: Vec<Value> = json_lines(&out)
+                    .iter()
+                    .map(|feature| feature["properties"]["id"].clone())
+                    .collect();
+                assert_eq!(ids.len(), matched);
+                ids
+            })
+        };
+        assert_eq!(filter(&[]), Ok(vec![json!(7), json!(8)]));
+        assert_eq!(filter(&["id>=8"]), Ok(vec![json!(8)]));
+        assert_eq!(filter(&["id>=7", "area<3"]), Ok(vec![json!(7)]));
+        // a missing value meets no condition, not even !=
+        assert_eq!(filter(&["name!=Dolores"]), Ok(vec![json!(7)]));
+        assert_eq!(filter(&["name=Mancos", "id=8"]), Ok(vec![]));
+        assert_eq!(
+            filter(&["population>100"]),
+            Err("There is no column named population".to_string())
+        );
+    }
+}
@@ -0,0 +1,379 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use flatgeobuf::FgbFeature;
+use geozero::{CoordDimensions, GeozeroGeometry, geojson::GeoJsonWriter};
+use ratatui::text::{Line, Span};
+use serde_json::{Value, json};
+
+use crate::{Theme, info_line, projection::Bbox, properties::Properties};
+
+/// A decoded feature, so that it can outlive the reader it was read with
+#[derive(Clone, Debug)]
+pub struct Feature {
+    /// The GeoJSON geometry, or null if the feature has none
+    pub geometry: Value,
+    /// The value of every column in the header, in header order
+    pub properties: Properties,
+}
+
+impl Feature {
+    pub fn from_fgb(feature: &FgbFeature, columns: &[String]) -> Result<Self, String> {
+        Ok(Self {
+            geometry: decode_geometry(feature)?,
+            properties: Properties::decode(feature, columns)?,
+        })
+    }
+
+    /// Decode only the geometry, for when the properties aren't needed
+    pub fn geometry_from_fgb(feature: &FgbFeature) -> Result<Self, String> {
+        Ok(Self {
+            geometry: decode_geometry(feature)?,
+            properties: Properties::default(),
+        })
+    }
+
+    /// The GeoJSON type of the geometry, such as `Polygon`
+    pub fn geometry_type(&self) -> Option<&str> {
+        self.geometry["type"].as_str()
+    }
+
+    /// Every position in the geometry as x and y, including repeated ones like
+    /// the closing positions of polygon rings
+    pub fn positions(&self) -> Vec<(f64, f64)> {
+        self.parts().concat()
+    }
+
+    /// The connected runs of positions in the geometry: each line string and polygon
+    /// ring, or a single position for each point
+    pub fn parts(&self) -> Vec<Vec<(f64, f64)>> {
+        let mut parts = Vec::new();
+        push_parts(&self.geometry, &mut parts);
+        parts
+    }
+
+    /// The bounding box of the geometry, if it has any positions
+    pub fn bbox(&self) -> Option<Bbox> {
+        let positions = self.positions();
+        let (&(x, y), rest) = positions.split_first()?;
+        Some(rest.iter().fold(Bbox::new(x, y, x, y), |bbox, &(x, y)| {
+            Bbox::new(
+                bbox.xmin.min(x),
+                bbox.ymin.min(y),
+                bbox.xmax.max(x),
+                bbox.ymax.max(y),
+            )
+        }))
+    }
+
+    /// A single position standing in for the geometry: a point itself,
+    /// or the center of the bounding box of anything else
+    pub fn representative_point(&self) -> Option<(f64, f64)> {
+        match self.geometry_type() {
+            Some("Point") => as_position(&self.geometry["coordinates"]),
+            _ => self.bbox().map(|bbox| bbox.center()),
+        }
+    }
+
+    /// The geometry summary followed by every property, for the feature detail popup.
+    /// Properties without a value are shown as a dimmed `null`
+    pub fn detail_lines(&self, theme: &Theme) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            info_line(theme, "Geometry", self.geometry_type().unwrap_or("—")),
+            info_line(theme, "Vertices", &self.positions().len().to_string()),
+            info_line(
+                theme,
+                "Bbox",
+                &self.bbox().map_or("—".to_string(), |bbox| bbox.to_string()),
+            ),
+            Line::default(),
+        ];
+        lines.extend(self.properties.0.iter().map(|(name, value)| match value {
+            None => Line::from(vec![
+                Span::styled(format!("{name}: "), theme.label),
+                Span::styled("null", theme.hints),
+            ]),
+            Some(value) => info_line(theme, name, &value.to_string()),
+        }));
+        lines
+    }
+
+    /// The geometry as WKT, like `POINT (1 2)`, or `None` if the feature has none. M values
+    /// are already gone from geometries decoded from a file, which GeoJSON has no place for
+    pub fn wkt(&self) -> Option<String> {
+        geometry_wkt(&self.geometry)
+    }
+
+    /// The feature as a GeoJSON Feature object
+    pub fn to_geojson(&self) -> Value {
+        json!({
+            "type": "Feature",
+            "geometry": self.geometry,
+            "properties": self.properties.to_json(),
+        })
+    }
+}
+
+/// The geometry of a feature as GeoJSON, or null if it has none. Z values are kept,
+/// which `ToJson` would leave out
+fn decode_geometry(feature: &FgbFeature) -> Result<Value, String> {
+    if feature.geometry().is_none() {
+        return Ok(Value::Null);
+    }
+    let mut json = Vec::new();
+    feature
+        .process_geom(&mut GeoJsonWriter::with_dims(
+            &mut json,
+            CoordDimensions::xyz(),
+        ))
+        .map_err(|e| format!("Failed to decode a geometry: {e}"))?;
+    serde_json::from_slice(&json).map_err(|e| format!("Failed to decode a geometry: {e}"))
+}
+
+/// A GeoJSON geometry as WKT, with a Z if its positions have a third coordinate and ZM if
+/// they have a fourth
+fn geometry_wkt(geometry: &Value) -> Option<String> {
+    let kind = geometry["type"].as_str()?;
+    if kind == "GeometryCollection" {
+        let geometries = geometry["geometries"].as_array()?;
+        if geometries.is_empty() {
+            return Some("GEOMETRYCOLLECTION EMPTY".to_string());
+        }
+        let geometries: Option<Vec<String>> = geometries.iter().map(geometry_wkt).collect();
+        return Some(format!("GEOMETRYCOLLECTION ({})", geometries?.join(", ")));
+    }
+    // how deeply positions are nested in the coordinates
+    let depth = match kind {
+        "Point" => 0,
+        "LineString" | "MultiPoint" => 1,
+        "Polygon" | "MultiLineString" => 2,
+        "MultiPolygon" => 3,
+        _ => return None,
+    };
+    let coordinates = &geometry["coordinates"];
+    if coordinates.as_array().is_none_or(Vec::is_empty) {
+        return Some(format!("{} EMPTY", kind.to_uppercase()));
+    }
+    let mut first = coordinates;
+    for _ in 0..depth {
+        first = &first[0];
+    }
+    let z = match first.as_array().map_or(0, Vec::len) {
+        ..=2 => "",
+        3 => " Z",
+        _ => " ZM",
+    };
+    let text = coordinates_wkt(coordinates, depth);
+    // a point's single position still goes in parentheses
+    Some(match depth {
+        0 => format!("{}{z} ({text})", kind.to_uppercase()),
+        _ => format!("{}{z} {text}", kind.to_uppercase()),
+    })
+}
+
+/// Positions as space separated numbers, and each level of nesting above them as a
+/// parenthesized list
+fn coordinates_wkt(coordinates: &Value, depth: usize) -> String {
+    let items = coordinates
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let items: Vec<String> = match depth {
+        0 => items.iter().map(Value::to_string).collect(),
+        _ => items
+            .iter()
+            .map(|item| coordinates_wkt(item, depth - 1))
+            .collect(),
+    };
+    match depth {
+        0 => items.join(" "),
+        _ => format!("({})", items.join(", ")),
+    }
+}
+
+/// Collect the parts of a GeoJSON geometry, recursing into geometry collections
+fn push_parts(geometry: &Value, parts: &mut Vec<Vec<(f64, f64)>>) {
+    match &geometry["geometries"] {
+        Value::Array(geometries) => {
+            for geometry in geometries {
+                push_parts(geometry, parts);
+            }
+        }
+        _ => push_coordinates(&geometry["coordinates"], parts),
+    }
+}
+
+/// A position is an array of numbers; coordinates nest arrays of positions to any depth
+fn as_position(value: &Value) -> Option<(f64, f64)> {
+    Some((value.get(0)?.as_f64()?, value.get(1)?.as_f64()?))
+}
+
+fn push_coordinates(coordinates: &Value, parts: &mut Vec<Vec<(f64, f64)>>) {
+    let Value::Array(items) = coordinates else {
+        return;
+    };
+    if let Some(position) = as_position(coordinates) {
+        parts.push(vec![position]);
+    } else if items.first().and_then(as_position).is_some() {
+        parts.push(items.iter().filter_map(as_position).collect());
+    } else {
+        for item in items {
+            push_coordinates(item, parts);
+        }
+    }
+}
+
+/// Thin out `parts` to roughly `max_positions` positions in total by keeping every nth
+/// position of each part, along with its last so that rings stay closed
+pub fn decimate(parts: Vec<Vec<(f64, f64)>>, max_positions: usize) -> Vec<Vec<(f64, f64)>> {
+    let total: usize = parts.iter().map(Vec::len).sum();
+    if total <= max_positions {
+        return parts;
+    }
+    let step = total.div_ceil(max_positions.max(1));
+    parts
+        .into_iter()
+        .map(|part| {
+            let last = part.len().saturating_sub(1);
+            part.into_iter()
+                .enumerate()
+                .filter(|&(i, _)| i % step == 0 || i == last)
+                .map(|(_, position)| position)
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wkt(geometry: Value) -> Option<String> {
+        geometry_wkt(&geometry)
+    }
+
+    #[test]
+    fn points_and_lines() {
+        assert_eq!(
+            wkt(json!({"type": "Point", "coordinates": [-108.5, 37.25]})).as_deref(),
+            Some("POINT (-108.5 37.25)")
+        );
+        assert_eq!(
+            wkt(json!({"type": "MultiPoint", "coordinates": [[1, 2], [3, 4]]})).as_deref(),
+            Some("MULTIPOINT (1 2, 3 4)")
+        );
+        assert_eq!(
+            wkt(json!({"type": "LineString", "coordinates": [[1, 2], [3, 4]]})).as_deref(),
+            Some("LINESTRING (1 2, 3 4)")
+        );
+    }
+
+    #[test]
+    fn multipolygons_with_holes() {
+        let outer = json!([[0, 0], [10, 0], [10, 10], [0, 10], [0, 0]]);
+        let hole = json!([[2, 2], [4, 2], [4, 4], [2, 2]]);
+        let island = json!([[20, 20], [21, 20], [21, 21], [20, 20]]);
+        assert_eq!(
+            wkt(json!({"type": "MultiPolygon", "coordinates": [[outer, hole], [island]]}))
+                .as_deref(),
+            Some(
+                "MULTIPOLYGON (((0 0, 10 0, 10 10, 0 10, 0 0), (2 2, 4 2, 4 4, 2 2)), \
+                 ((20 20, 21 20, 21 21, 20 20)))"
+            )
+        );
+        assert_eq!(
+            wkt(json!({"type": "Polygon", "coordinates": [outer, hole]})).as_deref(),
+            Some("POLYGON ((0 0, 10 0, 10 10, 0 10, 0 0), (2 2, 4 2, 4 4, 2 2))")
+        );
+    }
+
+    #[test]
+    fn empty_geometries() {
+        // as geozero writes an empty point
+        assert_eq!(
+            wkt(json!({"type": "Point", "coordinates": []})).as_deref(),
+            Some("POINT EMPTY")
+        );
+        assert_eq!(
+            wkt(json!({"type": "MultiPolygon", "coordinates": []})).as_deref(),
+            Some("MULTIPOLYGON EMPTY")
+        );
+        assert_eq!(
+            wkt(json!({"type": "GeometryCollection", "geometries": []})).as_deref(),
+            Some("GEOMETRYCOLLECTION EMPTY")
+        );
+        assert_eq!(
+            wkt(json!({"type": "GeometryCollection", "geometries": [
+                {"type": "Point", "coordinates": [1, 2]},
+                {"type": "LineString", "coordinates": []},
+            ]}))
+            .as_deref(),
+            Some("GEOMETRYCOLLECTION (POINT (1 2), LINESTRING EMPTY)")
+        );
+        // no geometry at all, or one WKT has no name for
+        assert_eq!(wkt(Value::Null), None);
+        assert_eq!(
+            wkt(json!({"type": "CircularString", "coordinates": [[1, 2]]})),
+            None
+        );
+    }
+
+    #[test]
+    fn z_and_m_coordinates() {
+        assert_eq!(
+            wkt(json!({"type": "Point", "coordinates": [1, 2, 3]})).as_deref(),
+            Some("POINT Z (1 2 3)")
+        );
+        assert_eq!(
+            wkt(json!({"type": "Polygon", "coordinates": [[[0, 0, 5], [1, 0, 5], [0, 1, 6], [0, 0, 5]]]}))
+                .as_deref(),
+            Some("POLYGON Z ((0 0 5, 1 0 5, 0 1 6, 0 0 5))")
+        );
+        assert_eq!(
+            wkt(json!({"type": "LineString", "coordinates": [[1, 2, 3, 4], [5, 6, 7, 8]]}))
+                .as_deref(),
+            Some("LINESTRING ZM (1 2 3 4, 5 6 7 8)")
+        );
+    }
+
+    #[test]
+    fn z_values_are_kept_and_m_values_dropped_when_decoding() {
+        use flatgeobuf::{
+            FallibleStreamingIterator, FgbReader, FgbWriter, FgbWriterOptions, GeometryType,
+        };
+
+        struct PointZm;
+        impl GeozeroGeometry for PointZm {
+            fn process_geom<P: geozero::GeomProcessor>(
+                &self,
+                processor: &mut P,
+            ) -> geozero::error::Result<()> {
+                processor.point_begin(0)?;
+                processor.coordinate(1.0, 2.0, Some(3.0), Some(4.0), None, None, 0)?;
+                processor.point_end(0)
+            }
+
+            fn dims(&self) -> CoordDimensions {
+                CoordDimensions::xyzm()
+            }
+        }
+
+        let options = FgbWriterOptions {
+            has_z: true,
+            has_m: true,
+            ..Default::default()
+        };
+        let mut fgb = FgbWriter::create_with_options("zm", GeometryType::Point, options).unwrap();
+        fgb.add_feature_geom(PointZm, |_| {}).unwrap();
+        let mut out = Vec::new();
+        fgb.write(&mut out).unwrap();
+
+        let mut reader = FgbReader::open(std::io::Cursor::new(out))
+            .unwrap()
+            .select_all_seq()
+            .unwrap();
+        let feature = reader.next().unwrap().unwrap();
+        let feature = Feature::geometry_from_fgb(feature).unwrap();
+        assert_eq!(feature.wkt().as_deref(), Some("POINT Z (1 2 3)"));
+    }
+}
@@ -0,0 +1,1220 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use bytes::Bytes;
+use bytesize::ByteSize;
+use reqwest::{
+    Method, RequestBuilder, Response, StatusCode,
+    header::{
+        AUTHORIZATION, CONTENT_LENGTH, CONTENT_RANGE, ETAG, HeaderMap, HeaderName, HeaderValue,
+        IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_UNMODIFIED_SINCE, LAST_MODIFIED, LOCATION,
+        RANGE,
+    },
+    redirect::{Attempt, Policy},
+};
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    cache::{self, CachedHeader},
+    diagnostics,
+    s3::S3Object,
+    source::Scheme,
+};
+
+/// How long to wait on a server before giving up, unless `--timeout` says otherwise
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Reading the header of a remote file takes a few requests, which may take up to
+/// this many timeouts all together before opening the file gives up
+const HEADER_DEADLINE_TIMEOUTS: u32 = 4;
+
+/// How many times to retry a request which may have failed only for the moment,
+/// unless `--retries` says otherwise
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// How long to wait before the first retry, which doubles for each retry after it
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+
+/// How many redirects a request follows before failing, as many as reqwest's default
+const MAX_REDIRECTS: usize = 10;
+
+/// How many of the latest requests [`TransferStats`] keeps the details of
+pub const REQUEST_LOG_SIZE: usize = 500;
+
+/// Settings for the requests made for remote files, from the command line
+#[derive(Clone, Debug)]
+pub struct HttpOptions {
+    /// For connecting, and for each request
+    pub timeout: Duration,
+    /// How many times to retry range requests which fail with a 5xx or 429
+    /// status, a connection error, a timeout or a body cut short
+    pub retries: u32,
+    /// Print each retry to stderr, which only makes sense outside the TUI
+    pub verbose: bool,
+    /// Sent with every request, from `--header` and `--bearer-token`
+    pub headers: HeaderMap,
+    /// Send every request through this proxy instead of any from `HTTP_PROXY` or
+    /// `HTTPS_PROXY`, which reqwest reads otherwise
+    pub proxy: Option<String>,
+    /// Where to cache the headers of remote files, unless `--no-cache` was given
+    pub cache_dir: Option<PathBuf>,
+    /// The region of the bucket for `s3://` URLs, rather than looking it up
+    pub aws_region: Option<String>,
+    /// Accept any certificate, even expired or self-signed ones
+    pub insecure: bool,
+    /// A PEM bundle of CA certificates to trust as well as the system's
+    pub cacert: Option<PathBuf>,
+    /// The version of the file which was opened first, which later requests must be for
+    pub version: Option<FileVersion>,
+    /// Read the whole file from servers which don't support range requests, rather than
+    /// failing
+    pub allow_full_download: bool,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            retries: DEFAULT_RETRIES,
+            verbose: false,
+            headers: HeaderMap::new(),
+            proxy: None,
+            cache_dir: None,
+            aws_region: None,
+            insecure: false,
+            cacert: None,
+            version: None,
+            allow_full_download: false,
+        }
+    }
+}
+
+impl HttpOptions {
+    /// How long opening a remote file may take to read its header
+    pub fn header_deadline(&self) -> Duration {
+        self.timeout * HEADER_DEADLINE_TIMEOUTS
+    }
+
+    /// Send `headers` with every request, along with an `Authorization: Bearer` header
+    /// for `token` if given, which replaces any authorization in `headers`
+    pub fn with_headers(
+        mut self,
+        headers: Vec<RequestHeader>,
+        token: Option<&str>,
+    ) -> Result<Self, String> {
+        for RequestHeader(name, value) in headers {
+            self.headers.append(name, value);
+        }
+        if let Some(token) = token {
+            let value = HeaderValue::try_from(format!("Bearer {}", token.trim()))
+                .map_err(|_| "the bearer token contains characters not allowed in a header")?;
+            self.headers.insert(AUTHORIZATION, sensitive(value));
+        }
+        Ok(self)
+    }
+
+    /// The error for `phase` of reading `url` taking longer than `limit`
+    pub fn timed_out(phase: &str, url: &str, limit: Duration) -> String {
+        format!(
+            "Timed out after {}s {phase} {url}; try again, or allow longer with --timeout",
+            limit.as_secs_f64()
+        )
+    }
+}
+
+/// How errors say that a remote file was replaced while it was open, for the TUI to
+/// offer reloading it
+pub const FILE_CHANGED: &str = "changed on the server since it was opened";
+
+/// Which version of a remote file was read, from the validators the server sent for it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileVersion {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl FileVersion {
+    fn new(etag: Option<String>, last_modified: Option<String>) -> Option<Self> {
+        (etag.is_some() || last_modified.is_some()).then_some(Self {
+            etag,
+            last_modified,
+        })
+    }
+
+    /// Whether `other` may be the same version, comparing whichever validator both have
+    fn matches(&self, other: &Self) -> bool {
+        match (
+            &self.etag,
+            &other.etag,
+            &self.last_modified,
+            &other.last_modified,
+        ) {
+            (Some(a), Some(b), _, _) => a == b,
+            (_, _, Some(a), Some(b)) => a == b,
+            _ => true,
+        }
+    }
+
+    /// Make `request` fail with 412 Precondition Failed if the file is no longer this
+    /// version. A weak ETag never satisfies If-Match, so only a strong one is sent
+    fn condition(&self, request: RequestBuilder) -> RequestBuilder {
+        match (&self.etag, &self.last_modified) {
+            (Some(etag), _) if !etag.starts_with("W/") => request.header(IF_MATCH, etag),
+            (_, Some(last_modified)) => request.header(IF_UNMODIFIED_SINCE, last_modified),
+            _ => request,
+        }
+    }
+}
+
+/// The error for `url` having been replaced while it was open
+pub(crate) fn file_changed(url: &str) -> String {
+    format!("{url} {FILE_CHANGED}; reload it to read the new version")
+}
+
+/// A header to send with every request, given as `Name: value`
+#[derive(Debug)]
+pub struct RequestHeader(HeaderName, HeaderValue);
+
+impl std::str::FromStr for RequestHeader {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (name, value) = spec
+            .split_once(':')
+            .ok_or_else(|| format!("expected 'Name: value' for a header, got '{spec}'"))?;
+        let name = HeaderName::try_from(name.trim())
+            .map_err(|_| format!("'{}' is not a valid header name", name.trim()))?;
+        // the value may be a secret, so it is left out of the error
+        let value = HeaderValue::try_from(value.trim())
+            .map_err(|_| format!("the value of header '{name}' contains invalid characters"))?;
+        Ok(Self(name, sensitive(value)))
+    }
+}
+
+/// Mark a header value as sensitive, so that it shows as `Sensitive` in debug output
+/// and is never printed in logs or errors
+fn sensitive(mut value: HeaderValue) -> HeaderValue {
+    value.set_sensitive(true);
+    value
+}
+
+/// One request made for a remote file, including each attempt of one which was retried
+#[derive(Clone, Debug)]
+pub struct RequestLogEntry {
+    pub method: Method,
+    /// Like `bytes=0-8191`, for range requests
+    pub range: Option<String>,
+    /// `None` if the request failed without a response, like when it timed out
+    pub status: Option<StatusCode>,
+    /// Of the body read, which is 0 for a server which ignored the range asked for,
+    /// since reading stops before the body
+    pub bytes: u64,
+    pub duration: Duration,
+}
+
+/// How many range requests have been made for a remote file and how many bytes they
+/// returned, along with the details of the latest requests
+#[derive(Default, Debug)]
+pub struct TransferStats {
+    requests: AtomicUsize,
+    bytes: AtomicU64,
+    retries: AtomicUsize,
+    ranges_ignored: AtomicBool,
+    /// The last [`REQUEST_LOG_SIZE`] requests, oldest first
+    log: Mutex<VecDeque<RequestLogEntry>>,
+    /// Every request logged, including those since forgotten
+    logged: AtomicUsize,
+}
+
+impl TransferStats {
+    /// Keep the details of a request, forgetting the oldest past [`REQUEST_LOG_SIZE`]
+    pub fn log_request(&self, entry: RequestLogEntry) {
+        let mut log = self.log.lock().unwrap();
+        if log.len() == REQUEST_LOG_SIZE {
+            log.pop_front();
+        }
+        log.push_back(entry);
+        self.logged.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// How many requests have been logged, which is more than [`Self::request_log`]
+    /// returns once the oldest are forgotten
+    pub fn requests_logged(&self) -> usize {
+        self.logged.load(Ordering::Relaxed)
+    }
+
+    /// The latest requests, oldest first
+    pub fn request_log(&self) -> Vec<RequestLogEntry> {
+        self.log.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn record(&self, bytes: u64) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_retry(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn retries(&self) -> usize {
+        self.retries.load(Ordering::Relaxed)
+    }
+
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// Note that the server answered a range request with the whole file
+    pub fn record_ranges_ignored(&self) {
+        self.ranges_ignored.store(true, Ordering::Relaxed);
+    }
+
+    /// Like `3 HTTP requests, 182.0 KiB transferred`, noting if the server ignored the
+    /// ranges asked for
+    pub fn summary(&self) -> String {
+        let requests = self.requests();
+        let summary = format!(
+            "{requests} HTTP request{}, {} transferred",
+            if requests == 1 { "" } else { "s" },
+            ByteSize(self.bytes())
+        );
+        if self.ranges_ignored.load(Ordering::Relaxed) {
+            format!("{summary}, range requests unsupported")
+        } else {
+            summary
+        }
+    }
+}
+
+/// Why a range request failed
+#[derive(Debug)]
+pub(crate) enum RangeError {
+    /// The request failed, after `attempts` tries
+    Failed {
+        error: reqwest::Error,
+        attempts: u32,
+    },
+    /// The server ignored the range and started sending the whole file, of `size` bytes
+    /// if it said, which was left unread
+    Ignored { size: Option<u64> },
+}
+
+/// The value of header `name` as a string, if present and readable
+fn header_string(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Whether a failed request might succeed if tried again: server errors, rate
+/// limiting, failing to connect, timeouts and bodies cut short, but not client errors
+/// or requests which couldn't be sent as they were
+fn is_transient(error: &reqwest::Error) -> bool {
+    match error.status() {
+        Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+        None => {
+            // a body cut short comes wrapped in a decoding error when read whole
+            let body = std::iter::successors(Some(error as &dyn std::error::Error), |e| e.source())
+                .filter_map(|e| e.downcast_ref::<reqwest::Error>())
+                .any(reqwest::Error::is_body);
+            error.is_connect() || error.is_timeout() || body
+        }
+    }
+}
+
+/// How long to wait before retry number `retry`, counting from 1: exponential
+/// backoff with up to half again as long added at random, so that clients
+/// which failed together don't all retry together
+fn retry_delay(retry: u32) -> Duration {
+    let delay = RETRY_BASE_DELAY * 2u32.saturating_pow(retry - 1);
+    delay + delay.mul_f64(fastrand::f64() / 2.0)
+}
+
+/// What to do with the header cache while reading the header of a remote file
+#[derive(Default)]
+enum CacheState {
+    /// Not reading the header, or not caching it
+    #[default]
+    Off,
+    /// The server said the cached header is current, so answer the requests from it
+    Replaying(CachedHeader),
+    /// Remember the responses, to save once the header has been read
+    Recording(CachedHeader),
+}
+
+/// Follow redirects within the origin first requested, and stop at one to another
+/// origin, for [`CountingClient::send`] to follow without the headers meant for the first
+fn same_origin_redirects(attempt: Attempt) -> reqwest::redirect::Action {
+    let first = &attempt.previous()[0];
+    if attempt.url().origin() != first.origin() {
+        attempt.stop()
+    } else if attempt.previous().len() > MAX_REDIRECTS {
+        attempt.error("too many redirects")
+    } else {
+        attempt.follow()
+    }
+}
+
+/// A reqwest client which records every range request it makes in [`TransferStats`]
+#[derive(Clone)]
+pub struct CountingClient {
+    /// Stops at redirects to another origin
+    client: reqwest::Client,
+    /// Follows redirects anywhere, for requests which no longer carry the headers from
+    /// `--header` and `--bearer-token` or an S3 signature
+    redirected_client: reqwest::Client,
+    stats: Arc<TransferStats>,
+    options: HttpOptions,
+    /// Shared with the clones of the client given to the reader
+    cache: Arc<Mutex<CacheState>>,
+    /// The object requests are signed for, if the file is in S3
+    s3: Option<Arc<S3Object>>,
+    /// The version of the file first read, which every range request is made conditional on
+    version: Arc<Mutex<Option<FileVersion>>>,
+}
+
+impl CountingClient {
+    pub fn new(stats: Arc<TransferStats>, options: &HttpOptions) -> Result<Self, String> {
+        let certificates = match &options.cacert {
+            Some(path) => {
+                let pem = std::fs::read(path)
+                    .map_err(|e| format!("Failed to read --cacert {}: {e}", path.display()))?;
+                let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+                    .map_err(|e| format!("Invalid certificates in {}: {e}", path.display()))?;
+                if certificates.is_empty() {
+                    return Err(format!("No certificates in --cacert {}", path.display()));
+                }
+                certificates
+            }
+            None => Vec::new(),
+        };
+        let proxy = match &options.proxy {
+            // the URL may hold a password, so it is left out of the error
+            Some(proxy) => Some(
+                reqwest::Proxy::all(proxy)
+                    .map_err(|e| format!("Invalid --proxy URL: {e}"))?
+                    .no_proxy(reqwest::NoProxy::from_env()),
+            ),
+            None => None,
+        };
+        let build = |redirects: Policy| {
+            let mut builder = reqwest::Client::builder()
+                .connect_timeout(options.timeout)
+                .timeout(options.timeout)
+                .danger_accept_invalid_certs(options.insecure)
+                .redirect(redirects);
+            for certificate in &certificates {
+                builder = builder.add_root_certificate(certificate.clone());
+            }
+            if let Some(proxy) = &proxy {
+                builder = builder.proxy(proxy.clone());
+            }
+            builder
+                .build()
+                .map_err(|e| format!("Failed to set up the HTTP client: {e}"))
+        };
+        // reqwest drops Authorization, but not other custom headers, when redirected to
+        // another host or port, so redirects there are followed by `send` instead
+        Ok(Self {
+            client: build(Policy::custom(same_origin_redirects))?,
+            redirected_client: build(Policy::limited(MAX_REDIRECTS))?,
+            stats,
+            options: options.clone(),
+            cache: Arc::default(),
+            s3: None,
+            version: Arc::new(Mutex::new(options.version.clone())),
+        })
+    }
+
+    /// The URL to request `source` from. For `s3://bucket/key`, this finds the region and
+    /// credentials, and every request made by the client after is signed for the object
+    pub async fn resolve(&mut self, source: &str) -> Result<String, String> {
+        if Scheme::of(source) != Scheme::S3 {
+            return Ok(source.to_string());
+        }
+        let object =
+            S3Object::resolve(&self.client, source, self.options.aws_region.as_deref()).await?;
+        let url = object.url();
+        self.s3 = Some(Arc::new(object));
+        Ok(url)
+    }
+
+    /// The region of S3 bucket `bucket`, from `--aws-region` or else looked up, for
+    /// reading it through object_store
+    #[cfg(feature = "aws")]
+    pub(crate) async fn s3_region(&self, bucket: &str) -> Result<String, String> {
+        crate::s3::bucket_region(&self.client, bucket, self.options.aws_region.as_deref()).await
+    }
+
+    /// A request for `url` with the headers from the command line, signed if the file is in S3
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let request = self
+            .client
+            .request(method.clone(), url)
+            .headers(self.options.headers.clone());
+        match &self.s3 {
+            Some(object) => request.headers(object.sign(method.as_str())),
+            None => request,
+        }
+    }
+
+    /// Send a request for `url`, with headers added by `build`. A redirect to another
+    /// origin is followed without the headers from the command line or the S3 signature,
+    /// which aren't meant for any other server, but with the ones from `build`
+    async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        build: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> Result<Response, reqwest::Error> {
+        let response = build(self.request(method.clone(), url)).send().await?;
+        let location = Some(&response)
+            .filter(|response| response.status().is_redirection())
+            .and_then(|response| response.headers().get(LOCATION))
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| response.url().join(location).ok());
+        let Some(location) = location else {
+            return Ok(response);
+        };
+        // a 303 says to GET the other URL instead, though a HEAD stays a HEAD
+        let method = match response.status() {
+            StatusCode::SEE_OTHER if method != Method::HEAD => Method::GET,
+            _ => method,
+        };
+        build(self.redirected_client.request(method, location))
+            .send()
+            .await
+    }
+
+    /// Describe a failed request made while `phase`, saying so if it timed out
+    fn request_error(&self, phase: &str, url: &str, e: reqwest::Error) -> String {
+        if e.is_timeout() {
+            HttpOptions::timed_out(phase, url, self.options.timeout)
+        } else {
+            // reqwest's own message leaves out the cause, such as why a certificate failed
+            let mut message = format!("Failed to request {url}: {e}");
+            let mut source = std::error::Error::source(&e);
+            while let Some(cause) = source {
+                let cause_text = cause.to_string();
+                if !message.contains(&cause_text) {
+                    message.push_str(&format!(": {cause_text}"));
+                }
+                source = cause.source();
+            }
+            if message.contains("certificate") && !self.options.insecure {
+                message.push_str(
+                    "; pass --cacert with the CA which signed it, or --insecure to not check it",
+                );
+            }
+            message
+        }
+    }
+
+    /// Describe a range request which failed even after retrying
+    pub(crate) fn range_error(&self, phase: &str, url: &str, e: RangeError) -> String {
+        let (error, attempts) = match e {
+            RangeError::Failed { error, attempts } => (error, attempts),
+            RangeError::Ignored { size } => {
+                let size = size.map_or("an unknown size".to_string(), |s| ByteSize(s).to_string());
+                return format!(
+                    "{url} doesn't support range requests, so reading any of it means \
+                     downloading the whole file, which is {size}; pass --allow-full-download \
+                     to do that anyway"
+                );
+            }
+        };
+        if error.status() == Some(StatusCode::PRECONDITION_FAILED) {
+            return file_changed(url);
+        }
+        let message = self.request_error(phase, url, error);
+        match attempts {
+            1 => message,
+            attempts => format!("{message} (gave up after {attempts} attempts)"),
+        }
+    }
+
+    /// Whether to print what the client finds out about the server, like whether it
+    /// supports range requests
+    pub(crate) fn verbose(&self) -> bool {
+        self.options.verbose
+    }
+
+    /// Request `range` of `url`, retrying failures which may be transient,
+    /// and return the response headers along with the body
+    pub(crate) async fn fetch_range(
+        &self,
+        url: &str,
+        range: &str,
+    ) -> Result<(StatusCode, HeaderMap, Bytes), RangeError> {
+        if let CacheState::Replaying(entry) = &*self.cache.lock().unwrap()
+            && let Some((content_range, bytes)) = entry.response(range)
+        {
+            let mut headers = HeaderMap::new();
+            if let Some(value) = content_range.and_then(|value| value.try_into().ok()) {
+                headers.insert(CONTENT_RANGE, value);
+            }
+            return Ok((StatusCode::PARTIAL_CONTENT, headers, bytes));
+        }
+        let mut attempts = 1;
+        loop {
+            let started = Instant::now();
+            let result: Result<_, reqwest::Error> = async {
+                let version = self.version.lock().unwrap().clone();
+                let resp = self
+                    .send(Method::GET, url, |request| {
+                        let request = request.header(RANGE, range);
+                        match &version {
+                            Some(version) => version.condition(request),
+                            None => request,
+                        }
+                    })
+                    .await?
+                    .error_for_status()?;
+                let (status, headers) = (resp.status(), resp.headers().clone());
+                // checked before reading the body, which could be the whole of a huge file
+                if status == StatusCode::OK && !self.options.allow_full_download {
+                    return Ok(Err(resp.content_length()));
+                }
+                Ok(Ok((status, headers, resp.bytes().await?)))
+            }
+            .await;
+            let (status, bytes) = match &result {
+                Ok(Err(_)) => (Some(StatusCode::OK), 0),
+                Ok(Ok((status, _, body))) => (Some(*status), body.len() as u64),
+                Err(error) => (error.status(), 0),
+            };
+            self.stats.log_request(RequestLogEntry {
+                method: Method::GET,
+                range: Some(range.to_string()),
+                status,
+                bytes,
+                duration: started.elapsed(),
+            });
+            match result {
+                Ok(Err(size)) => {
+                    self.stats.record_ranges_ignored();
+                    return Err(RangeError::Ignored { size });
+                }
+                Ok(Ok(response)) => {
+                    if response.0 == StatusCode::OK {
+                        self.stats.record_ranges_ignored();
+                    }
+                    self.stats.record(response.2.len() as u64);
+                    if let CacheState::Recording(entry) = &mut *self.cache.lock().unwrap()
+                        && response.0 == StatusCode::PARTIAL_CONTENT
+                    {
+                        entry.record(
+                            range,
+                            header_string(&response.1, CONTENT_RANGE),
+                            &response.2,
+                        );
+                    }
+                    return Ok(response);
+                }
+                Err(error) if attempts <= self.options.retries && is_transient(&error) => {
+                    let delay = retry_delay(attempts);
+                    if self.options.verbose {
+                        diagnostics::note(&format!(
+                            "Retrying {range} of {url} in {delay:.1?} after attempt {attempts} failed: {error}"
+                        ));
+                    }
+                    self.stats.record_retry();
+                    tokio::time::sleep(delay).await;
+                    attempts += 1;
+                }
+                Err(error) => return Err(RangeError::Failed { error, attempts }),
+            }
+        }
+    }
+
+    /// The size of the file from a HEAD request, if the server says. With a cache, the
+    /// request is conditional on the cached header, which answers the requests for reading
+    /// the header if the file hasn't changed, and is replaced by them otherwise
+    pub async fn content_length(&self, url: &str) -> Result<Option<u64>, String> {
+        let cached = self
+            .options
+            .cache_dir
+            .as_deref()
+            .and_then(|dir| cache::load(dir, url));
+        let conditional = |mut request: RequestBuilder| {
+            if let Some(entry) = &cached {
+                if let Some(etag) = &entry.etag {
+                    request = request.header(IF_NONE_MATCH, etag);
+                }
+                if let Some(last_modified) = &entry.last_modified {
+                    request = request.header(IF_MODIFIED_SINCE, last_modified);
+                }
+            }
+            request
+        };
+        let started = Instant::now();
+        let resp = self.send(Method::HEAD, url, conditional).await;
+        self.stats.log_request(RequestLogEntry {
+            method: Method::HEAD,
+            range: None,
+            status: resp
+                .as_ref()
+                .map_or_else(reqwest::Error::status, |resp| Some(resp.status())),
+            bytes: 0,
+            duration: started.elapsed(),
+        });
+        let resp = resp.map_err(|e| self.request_error("requesting the size of", url, e))?;
+        if resp.status() == StatusCode::NOT_MODIFIED
+            && let Some(entry) = cached
+        {
+            self.check_version(
+                url,
+                FileVersion::new(entry.etag.clone(), entry.last_modified.clone()),
+            )?;
+            let content_length = entry.content_length;
+            *self.cache.lock().unwrap() = CacheState::Replaying(entry);
+            return Ok(content_length);
+        }
+        let headers = resp.headers();
+        self.check_version(
+            url,
+            FileVersion::new(
+                header_string(headers, ETAG),
+                header_string(headers, LAST_MODIFIED),
+            ),
+        )?;
+        let content_length = header_string(headers, CONTENT_LENGTH).and_then(|s| s.parse().ok());
+        if self.options.cache_dir.is_some()
+            && let Some(entry) = CachedHeader::new(
+                url,
+                header_string(headers, ETAG),
+                header_string(headers, LAST_MODIFIED),
+                content_length,
+            )
+        {
+            *self.cache.lock().unwrap() = CacheState::Recording(entry);
+        }
+        Ok(content_length)
+    }
+
+    /// Remember `found` as the version of the file, or if one was already known,
+    /// check that `found` is still the same
+    fn check_version(&self, url: &str, found: Option<FileVersion>) -> Result<(), String> {
+        let mut version = self.version.lock().unwrap();
+        match (&*version, found) {
+            (Some(known), Some(found)) if !known.matches(&found) => Err(file_changed(url)),
+            (None, found) => {
+                *version = found;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The version of the file as first read, if the server identified it
+    pub fn version(&self) -> Option<FileVersion> {
+        self.version.lock().unwrap().clone()
+    }
+
+    /// Stop using the cache once the header has been read, saving the responses to the
+    /// requests made for it if they weren't already cached. The cache only saves requests,
+    /// so failing to write it is ignored
+    pub fn finish_header(&self) {
+        let state = std::mem::take(&mut *self.cache.lock().unwrap());
+        if let CacheState::Recording(entry) = state
+            && let Some(dir) = &self.options.cache_dir
+        {
+            let _ = cache::store(dir, &entry);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread::JoinHandle,
+    };
+
+    /// A server on a port of its own which answers one connection with each of
+    /// `responses` in turn, and gives back the requests it was sent
+    fn serve(responses: Vec<String>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let origin = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|response| {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let mut request = Vec::new();
+                    let mut buf = [0; 1024];
+                    while !request.ends_with(b"\r\n\r\n") {
+                        let read = stream.read(&mut buf).unwrap();
+                        request.extend_from_slice(&buf[..read]);
+                    }
+                    stream.write_all(response.as_bytes()).unwrap();
+                    String::from_utf8(request).unwrap().to_lowercase()
+                })
+                .collect()
+        });
+        (origin, server)
+    }
+
+    fn redirect(location: &str) -> String {
+        format!(
+            "HTTP/1.1 302 Found\r\nLocation: {location}\r\nContent-Length: 0\r\n\
+             Connection: close\r\n\r\n"
+        )
+    }
+
+    const PARTIAL: &str = "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 0-2/10\r\n\
+                           Content-Length: 3\r\nConnection: close\r\n\r\nfgb";
+
+    fn client() -> CountingClient {
+        let options = HttpOptions::default()
+            .with_headers(vec!["X-Api-Key: secret".parse().unwrap()], Some("token"))
+            .unwrap();
+        CountingClient::new(Arc::default(), &options).unwrap()
+    }
+
+    #[tokio::test]
+    async fn headers_are_not_sent_to_another_origin() {
+        let (elsewhere, other_server) = serve(vec![PARTIAL.to_string()]);
+        let (origin, server) = serve(vec![redirect(&format!("{elsewhere}/data.fgb"))]);
+        let (status, _, body) = client()
+            .fetch_range(&format!("{origin}/data.fgb"), "bytes=0-2")
+            .await
+            .unwrap();
+        assert_eq!(
+            (status, &body[..]),
+            (StatusCode::PARTIAL_CONTENT, &b"fgb"[..])
+        );
+
+        let first = &server.join().unwrap()[0];
+        assert!(first.contains("x-api-key: secret"));
+        assert!(first.contains("authorization: bearer token"));
+        let redirected = &other_server.join().unwrap()[0];
+        assert!(redirected.starts_with("get /data.fgb"));
+        assert!(!redirected.contains("x-api-key"));
+        assert!(!redirected.contains("authorization"));
+        // the range is part of the request, rather than meant for the first server
+        assert!(redirected.contains("range: bytes=0-2"));
+    }
+
+    #[tokio::test]
+    async fn headers_are_sent_on_redirects_within_the_origin() {
+        let listener_responses = vec![redirect("/moved/data.fgb"), PARTIAL.to_string()];
+        let (origin, server) = serve(listener_responses);
+        client()
+            .fetch_range(&format!("{origin}/data.fgb"), "bytes=0-2")
+            .await
+            .unwrap();
+        let requests = server.join().unwrap();
+        assert!(requests[1].starts_with("get /moved/data.fgb"));
+        assert!(requests[1].contains("x-api-key: secret"));
+        assert!(requests[1].contains("authorization: bearer token"));
+    }
+
+    #[tokio::test]
+    async fn size_requests_follow_redirects_to_another_origin() {
+        let size = "HTTP/1.1 200 OK\r\nContent-Length: 1234\r\nConnection: close\r\n\r\n";
+        let (elsewhere, other_server) = serve(vec![size.to_string()]);
+        let (origin, server) = serve(vec![redirect(&format!("{elsewhere}/data.fgb"))]);
+        let size = client()
+            .content_length(&format!("{origin}/data.fgb"))
+            .await
+            .unwrap();
+        assert_eq!(size, Some(1234));
+        server.join().unwrap();
+        let redirected = &other_server.join().unwrap()[0];
+        assert!(redirected.starts_with("head /data.fgb"));
+        assert!(!redirected.contains("x-api-key") && !redirected.contains("authorization"));
+    }
+
+    /// Like [`serve`] over TLS, with a certificate for 127.0.0.1 signed by the CA in
+    /// testdata/tls, made with `openssl req` and `openssl x509 -req` to last a century
+    fn serve_tls(responses: Vec<String>) -> (String, JoinHandle<Vec<String>>) {
+        let identity = native_tls::Identity::from_pkcs8(
+            &std::fs::read("testdata/tls/server.pem").unwrap(),
+            &std::fs::read("testdata/tls/server.key").unwrap(),
+        )
+        .unwrap();
+        let acceptor = native_tls::TlsAcceptor::new(identity).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let origin = format!("https://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|response| {
+                    // skipping clients which reject the certificate
+                    let mut stream = loop {
+                        if let Ok(stream) = acceptor.accept(listener.accept().unwrap().0) {
+                            break stream;
+                        }
+                    };
+                    let mut request = Vec::new();
+                    let mut buf = [0; 1024];
+                    while !request.ends_with(b"\r\n\r\n") {
+                        let read = stream.read(&mut buf).unwrap();
+                        request.extend_from_slice(&buf[..read]);
+                    }
+                    stream.write_all(response.as_bytes()).unwrap();
+                    String::from_utf8(request).unwrap().to_lowercase()
+                })
+                .collect()
+        });
+        (origin, server)
+    }
+
+    fn tls_client(options: HttpOptions) -> CountingClient {
+        CountingClient::new(Arc::default(), &options).unwrap()
+    }
+
+    #[tokio::test]
+    async fn private_cas_are_untrusted_by_default() {
+        let (origin, _server) = serve_tls(vec![PARTIAL.to_string()]);
+        let client = tls_client(HttpOptions {
+            retries: 0,
+            ..Default::default()
+        });
+        let error = client
+            .fetch_range(&format!("{origin}/data.fgb"), "bytes=0-2")
+            .await
+            .unwrap_err();
+        let message = client.range_error("reading", &origin, error);
+        assert!(message.contains("certificate verify failed"), "{message}");
+        assert!(
+            message.ends_with("or --insecure to not check it"),
+            "{message}"
+        );
+    }
+
+    #[tokio::test]
+    async fn cacert_trusts_a_private_ca() {
+        let (origin, server) = serve_tls(vec![PARTIAL.to_string()]);
+        let client = tls_client(HttpOptions {
+            cacert: Some("testdata/tls/ca.pem".into()),
+            ..Default::default()
+        });
+        let (_, _, body) = client
+            .fetch_range(&format!("{origin}/data.fgb"), "bytes=0-2")
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"fgb");
+        assert!(server.join().unwrap()[0].starts_with("get /data.fgb"));
+    }
+
+    #[tokio::test]
+    async fn cacert_is_used_for_retries() {
+        let unavailable = "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\
+                           Connection: close\r\n\r\n";
+        let (origin, server) = serve_tls(vec![unavailable.to_string(), PARTIAL.to_string()]);
+        let stats = Arc::new(TransferStats::default());
+        let options = HttpOptions {
+            cacert: Some("testdata/tls/ca.pem".into()),
+            ..Default::default()
+        };
+        let client = CountingClient::new(stats.clone(), &options).unwrap();
+        client
+            .fetch_range(&format!("{origin}/data.fgb"), "bytes=0-2")
+            .await
+            .unwrap();
+        assert_eq!(stats.retries(), 1);
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn insecure_accepts_any_certificate() {
+        let (origin, server) = serve_tls(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 1234\r\nConnection: close\r\n\r\n".to_string(),
+        ]);
+        let client = tls_client(HttpOptions {
+            insecure: true,
+            ..Default::default()
+        });
+        let size = client
+            .content_length(&format!("{origin}/data.fgb"))
+            .await
+            .unwrap();
+        assert_eq!(size, Some(1234));
+        assert!(server.join().unwrap()[0].starts_with("head /data.fgb"));
+    }
+
+    #[test]
+    fn cacert_must_hold_certificates() {
+        let error = CountingClient::new(
+            Arc::default(),
+            &HttpOptions {
+                cacert: Some("testdata/colorado_subset.fgb".into()),
+                ..Default::default()
+            },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            error,
+            "No certificates in --cacert testdata/colorado_subset.fgb"
+        );
+    }
+
+    /// A response to a HEAD request for a file of 10 bytes with `validators`
+    fn size_with(validators: &str) -> String {
+        format!("HTTP/1.1 200 OK\r\nContent-Length: 10\r\n{validators}Connection: close\r\n\r\n")
+    }
+
+    const PRECONDITION_FAILED: &str = "HTTP/1.1 412 Precondition Failed\r\n\
+                                       Content-Length: 0\r\nConnection: close\r\n\r\n";
+
+    const LAST_MODIFIED_AT: &str = "Fri, 16 Oct 2026 00:00:00 GMT";
+
+    #[tokio::test]
+    async fn range_requests_are_for_the_version_first_read() {
+        let validators = format!("ETag: \"v1\"\r\nLast-Modified: {LAST_MODIFIED_AT}\r\n");
+        let (origin, server) = serve(vec![
+            size_with(&validators),
+            PARTIAL.to_string(),
+            PRECONDITION_FAILED.to_string(),
+        ]);
+        let client = CountingClient::new(Arc::default(), &HttpOptions::default()).unwrap();
+        let url = format!("{origin}/data.fgb");
+        client.content_length(&url).await.unwrap();
+        assert_eq!(
+            client.version(),
+            FileVersion::new(
+                Some("\"v1\"".to_string()),
+                Some(LAST_MODIFIED_AT.to_string())
+            )
+        );
+        client.fetch_range(&url, "bytes=0-2").await.unwrap();
+        // the server answers 412 once the file is replaced
+        let error = client.fetch_range(&url, "bytes=0-2").await.unwrap_err();
+        assert_eq!(
+            client.range_error("reading", &url, error),
+            file_changed(&url)
+        );
+
+        let requests = server.join().unwrap();
+        assert!(requests[1].contains("if-match: \"v1\""), "{}", requests[1]);
+        assert!(requests[2].contains("if-match: \"v1\""), "{}", requests[2]);
+    }
+
+    #[tokio::test]
+    async fn weak_etags_fall_back_to_the_modification_time() {
+        let validators = format!("ETag: W/\"v1\"\r\nLast-Modified: {LAST_MODIFIED_AT}\r\n");
+        let (origin, server) = serve(vec![size_with(&validators), PARTIAL.to_string()]);
+        let client = CountingClient::new(Arc::default(), &HttpOptions::default()).unwrap();
+        let url = format!("{origin}/data.fgb");
+        client.content_length(&url).await.unwrap();
+        client.fetch_range(&url, "bytes=0-2").await.unwrap();
+        let requests = server.join().unwrap();
+        assert!(!requests[1].contains("if-match"));
+        assert!(
+            requests[1].contains(&format!(
+                "if-unmodified-since: {}",
+                LAST_MODIFIED_AT.to_lowercase()
+            )),
+            "{}",
+            requests[1]
+        );
+    }
+
+    #[tokio::test]
+    async fn a_new_version_is_noticed_when_reopening() {
+        let (origin, server) = serve(vec![
+            size_with("ETag: \"v1\"\r\n"),
+            size_with("ETag: \"v2\"\r\n"),
+        ]);
+        let url = format!("{origin}/data.fgb");
+        let first = CountingClient::new(Arc::default(), &HttpOptions::default()).unwrap();
+        first.content_length(&url).await.unwrap();
+        // a query later in the session, which has to read the version the header came from
+        let options = HttpOptions {
+            version: first.version(),
+            ..Default::default()
+        };
+        let later = CountingClient::new(Arc::default(), &options).unwrap();
+        assert_eq!(later.content_length(&url).await, Err(file_changed(&url)));
+        server.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn unchanged_headers_are_replayed_from_the_cache() {
+        let cache_dir = std::env::temp_dir().join(format!("fgbdump-{}-replay", std::process::id()));
+        let _ = std::fs::remove_dir_all(&cache_dir);
+        let options = HttpOptions {
+            cache_dir: Some(cache_dir.clone()),
+            ..Default::default()
+        };
+        let not_modified = "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n";
+        let (origin, server) = serve(vec![
+            size_with("ETag: \"v1\"\r\n"),
+            PARTIAL.to_string(),
+            not_modified.to_string(),
+        ]);
+        let url = format!("{origin}/data.fgb");
+        let first = CountingClient::new(Arc::default(), &options).unwrap();
+        first.content_length(&url).await.unwrap();
+        first.fetch_range(&url, "bytes=0-2").await.unwrap();
+        first.finish_header();
+
+        let stats = Arc::new(TransferStats::default());
+        let later = CountingClient::new(stats.clone(), &options).unwrap();
+        assert_eq!(later.content_length(&url).await, Ok(Some(10)));
+        let (status, headers, body) = later.fetch_range(&url, "bytes=0-2").await.unwrap();
+        assert_eq!(
+            (status, &body[..]),
+            (StatusCode::PARTIAL_CONTENT, &b"fgb"[..])
+        );
+        assert_eq!(
+            header_string(&headers, CONTENT_RANGE).as_deref(),
+            Some("bytes 0-2/10")
+        );
+        later.finish_header();
+        // the second time, only the HEAD request was sent
+        let requests = server.join().unwrap();
+        assert!(!requests[0].contains("if-none-match"));
+        assert!(requests[2].starts_with("head /data.fgb"));
+        assert!(
+            requests[2].contains("if-none-match: \"v1\""),
+            "{}",
+            requests[2]
+        );
+        assert_eq!(stats.requests(), 0);
+        std::fs::remove_dir_all(cache_dir).unwrap();
+    }
+
+    /// The error of a range request to a server which answers with `response`
+    async fn failure(response: &str) -> reqwest::Error {
+        let (origin, server) = serve(vec![response.to_string()]);
+        let client = tls_client(HttpOptions {
+            retries: 0,
+            ..Default::default()
+        });
+        let error = client
+            .fetch_range(&format!("{origin}/data.fgb"), "bytes=0-9")
+            .await
+            .unwrap_err();
+        server.join().unwrap();
+        match error {
+            RangeError::Failed { error, attempts } => {
+                assert_eq!(attempts, 1);
+                error
+            }
+            RangeError::Ignored { .. } => panic!("the range was ignored"),
+        }
+    }
+
+    fn status(status: &str) -> String {
+        format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+    }
+
+    #[tokio::test]
+    async fn server_errors_and_rate_limiting_are_transient() {
+        for transient in [
+            "500 Internal Server Error",
+            "503 Service Unavailable",
+            "429 Too Many Requests",
+        ] {
+            assert!(
+                is_transient(&failure(&status(transient)).await),
+                "{transient}"
+            );
+        }
+        for permanent in [
+            "400 Bad Request",
+            "403 Forbidden",
+            "404 Not Found",
+            "416 Range Not Satisfiable",
+        ] {
+            assert!(
+                !is_transient(&failure(&status(permanent)).await),
+                "{permanent}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn bodies_cut_short_are_transient() {
+        let short = "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 0-9/10\r\n\
+                     Content-Length: 10\r\nConnection: close\r\n\r\nfgb";
+        let error = failure(short).await;
+        assert!(is_transient(&error), "{error:?}");
+    }
+
+    #[tokio::test]
+    async fn connections_refused_or_timing_out_are_transient() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+        let client = tls_client(HttpOptions {
+            retries: 0,
+            ..Default::default()
+        });
+        let Err(RangeError::Failed { error, .. }) = client
+            .fetch_range(&format!("http://{address}/data.fgb"), "bytes=0-9")
+            .await
+        else {
+            panic!("a closed port answered");
+        };
+        assert!(error.is_connect(), "{error:?}");
+        assert!(is_transient(&error));
+
+        // connected, but never answered
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = tls_client(HttpOptions {
+            retries: 0,
+            timeout: Duration::from_millis(100),
+            ..Default::default()
+        });
+        let url = format!("http://{}/data.fgb", listener.local_addr().unwrap());
+        let Err(RangeError::Failed { error, .. }) = client.fetch_range(&url, "bytes=0-9").await
+        else {
+            panic!("a silent server answered");
+        };
+        assert!(error.is_timeout(), "{error:?}");
+        assert!(is_transient(&error));
+    }
+
+    #[tokio::test]
+    async fn connections_closed_without_an_answer_are_not_retried() {
+        let error = failure("").await;
+        assert!(error.is_request(), "{error:?}");
+        assert!(!is_transient(&error));
+    }
+
+    #[test]
+    fn retries_back_off_exponentially_with_jitter() {
+        for retry in 1..=6 {
+            let base = RETRY_BASE_DELAY * 2u32.pow(retry - 1);
+            for _ in 0..100 {
+                let delay = retry_delay(retry);
+                assert!(delay >= base && delay <= base * 3 / 2, "{retry}: {delay:?}");
+            }
+        }
+        // too many retries to double for stays as long as the longest
+        assert!(retry_delay(64) >= RETRY_BASE_DELAY * u32::MAX);
+    }
+
+    #[test]
+    fn versions_compare_whichever_validators_both_have() {
+        let etag = |etag: &str| FileVersion::new(Some(etag.to_string()), None).unwrap();
+        let modified = |at: &str| FileVersion::new(None, Some(at.to_string())).unwrap();
+        assert!(etag("\"a\"").matches(&etag("\"a\"")));
+        assert!(!etag("\"a\"").matches(&etag("\"b\"")));
+        assert!(!modified("Mon").matches(&modified("Tue")));
+        // with nothing to compare, it may be the same
+        assert!(etag("\"a\"").matches(&modified("Tue")));
+        assert_eq!(FileVersion::new(None, None), None);
+    }
+}
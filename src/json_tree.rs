@@ -0,0 +1,211 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use ratatui::text::{Line, Span};
+use serde_json::Value;
+use std::collections::HashSet;
+
+use crate::Theme;
+
+/// A JSON document shown as an indented tree whose objects and arrays can be expanded
+pub struct JsonTree {
+    value: Value,
+    /// JSON pointers of the expanded objects and arrays; the root is always expanded
+    expanded: HashSet<String>,
+}
+
+impl JsonTree {
+    /// Parse `text` as a tree if it is a JSON object or array
+    pub fn parse(text: &str) -> Option<Self> {
+        match serde_json::from_str(text) {
+            Ok(value @ (Value::Object(_) | Value::Array(_))) => Some(Self {
+                value,
+                expanded: HashSet::new(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Expand the node at `path` if it is collapsed and collapse it otherwise
+    pub fn toggle(&mut self, path: &str) {
+        if !self.expanded.remove(path) {
+            self.expanded.insert(path.to_string());
+        }
+    }
+
+    /// One line per visible node below the root, each paired with the path
+    /// of the node if it is an object or array which can be toggled
+    pub fn lines(&self, theme: &Theme) -> Vec<(Line<'static>, Option<String>)> {
+        let mut lines = Vec::new();
+        self.push_children(&self.value, "", 1, theme, &mut lines);
+        lines
+    }
+
+    fn push_children(
+        &self,
+        value: &Value,
+        path: &str,
+        depth: usize,
+        theme: &Theme,
+        lines: &mut Vec<(Line<'static>, Option<String>)>,
+    ) {
+        let children: Vec<(String, &Value)> = match value {
+            Value::Object(map) => map.iter().map(|(k, v)| (k.clone(), v)).collect(),
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (format!("[{i}]"), v))
+                .collect(),
+            _ => return,
+        };
+
+        let indent = "  ".repeat(depth);
+        for (key, child) in children {
+            // JSON pointer escaping, so keys containing `/` don't collide
+            let child_path = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+            let summary = match child {
+                Value::Object(map) => Some(format!("{{…}} {} keys", map.len())),
+                Value::Array(items) => Some(format!("[…] {} items", items.len())),
+                _ => None,
+            };
+            match summary {
+                Some(summary) => {
+                    let expanded = self.expanded.contains(&child_path);
+                    let marker = if expanded {
+                        theme.symbols.expanded
+                    } else {
+                        theme.symbols.collapsed
+                    };
+                    let mut spans = vec![
+                        Span::raw(format!("{indent}{marker}")),
+                        Span::styled(key, theme.label),
+                    ];
+                    if !expanded {
+                        spans.push(Span::raw(format!(": {summary}")));
+                    }
+                    lines.push((Line::from(spans), Some(child_path.clone())));
+                    if expanded {
+                        self.push_children(child, &child_path, depth + 1, theme, lines);
+                    }
+                }
+                None => {
+                    lines.push((
+                        Line::from(vec![
+                            // line up with the keys after the markers of their siblings
+                            Span::raw(format!("{indent}  ")),
+                            Span::styled(format!("{key}: "), theme.label),
+                            Span::raw(child.to_string()),
+                        ]),
+                        None,
+                    ));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Symbols;
+
+    const DOCUMENT: &str = r#"{
+        "name": "catchments",
+        "source": {"agency": "USGS", "a/b": {"c": 1}},
+        "tags": ["water", ["nested"]],
+        "empty": {}
+    }"#;
+
+    /// The visible lines as text, with + and - for collapsed and expanded nodes, and the
+    /// paths which can be toggled
+    fn render(tree: &JsonTree) -> (Vec<String>, Vec<Option<String>>) {
+        let theme = Theme {
+            symbols: Symbols::ascii(),
+            ..Theme::default()
+        };
+        tree.lines(&theme)
+            .into_iter()
+            .map(|(line, path)| {
+                let text: String = line
+                    .spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect();
+                (text, path)
+            })
+            .unzip()
+    }
+
+    #[test]
+    fn only_objects_and_arrays_are_trees() {
+        assert!(JsonTree::parse(DOCUMENT).is_some());
+        assert!(JsonTree::parse("[1, 2]").is_some());
+        for text in ["\"text\"", "12", "null", "{not json"] {
+            assert!(JsonTree::parse(text).is_none(), "{text}");
+        }
+    }
+
+    #[test]
+    fn nodes_start_collapsed_below_the_root() {
+        let tree = JsonTree::parse(DOCUMENT).unwrap();
+        let (lines, paths) = render(&tree);
+        assert_eq!(
+            lines,
+            [
+                "    name: \"catchments\"",
+                "  + source: {…} 2 keys",
+                "  + tags: […] 2 items",
+                "  + empty: {…} 0 keys",
+            ]
+        );
+        assert_eq!(
+            paths,
+            [
+                None,
+                Some("/source".to_string()),
+                Some("/tags".to_string()),
+                Some("/empty".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn expanding_and_collapsing() {
+        let mut tree = JsonTree::parse(DOCUMENT).unwrap();
+        tree.toggle("/source");
+        tree.toggle("/tags");
+        tree.toggle("/tags/[1]");
+        let (lines, paths) = render(&tree);
+        assert_eq!(
+            lines,
+            [
+                "    name: \"catchments\"",
+                "  - source",
+                "      agency: \"USGS\"",
+                "    + a/b: {…} 1 keys",
+                "  - tags",
+                "      [0]: \"water\"",
+                "    - [1]",
+                "        [0]: \"nested\"",
+                "  + empty: {…} 0 keys",
+            ]
+        );
+        // the slash in the key is escaped, so it isn't taken for a level of nesting
+        assert_eq!(paths[3].as_deref(), Some("/source/a~1b"));
+        assert_eq!(paths[6].as_deref(), Some("/tags/[1]"));
+
+        // collapsing a node hides what is under it, but remembers what was expanded
+        tree.toggle("/tags");
+        assert_eq!(render(&tree).0.len(), 6);
+        tree.toggle("/tags");
+        assert_eq!(render(&tree).0, lines);
+        tree.toggle("/source");
+        tree.toggle("/tags");
+        assert_eq!(render(&JsonTree::parse(DOCUMENT).unwrap()), render(&tree));
+        assert_eq!(tree.value()["source"]["agency"], "USGS");
+    }
+}
@@ -0,0 +1,459 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::collections::HashMap;
+
+use crate::SelectedTab;
+
+/// Something a key can be bound to outside of popups and text input, which keep fixed keys
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    NextTab,
+    PreviousTab,
+    ScrollDown,
+    ScrollUp,
+    PageDown,
+    PageUp,
+    Top,
+    Bottom,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    Select,
+    ToggleWkt,
+    Sort,
+    ReverseSort,
+    CycleOrder,
+    Reload,
+    Query,
+    ZoomExtent,
+    ZoomIn,
+    ZoomOut,
+    Graticule,
+    ToggleResolution,
+    SamplePoints,
+    SwapAxes,
+    CountFeatures,
+    GeometryTypes,
+    RawValues,
+    Histogram,
+    Copy,
+    RowValues,
+    ExtendedColumns,
+    ScrollLeft,
+    ScrollRight,
+    NextFile,
+    PreviousFile,
+    RequestLog,
+    GoToFeature,
+}
+
+impl Action {
+    /// Every action with its name in the `[keys]` section of the config file and its
+    /// default keys. The first key of each is the one shown in the footer
+    const ALL: [(Self, &'static str, &'static [&'static str]); 39] = [
+        (Self::Quit, "quit", &["q", "Q", "esc"]),
+        (Self::NextTab, "next_tab", &["right"]),
+        (Self::PreviousTab, "previous_tab", &["left"]),
+        (Self::ScrollDown, "scroll_down", &["j", "down"]),
+        (Self::ScrollUp, "scroll_up", &["k", "up"]),
+        (Self::PageDown, "page_down", &["pagedown"]),
+        (Self::PageUp, "page_up", &["pageup"]),
+        (Self::Top, "top", &["g", "home"]),
+        (Self::Bottom, "bottom", &["G", "end"]),
+        (Self::Search, "search", &["/"]),
+        (Self::NextMatch, "next_match", &["n"]),
+        (Self::PreviousMatch, "previous_match", &["N"]),
+        (Self::Select, "select", &["enter"]),
+        (Self::ToggleWkt, "toggle_wkt", &["w"]),
+        (Self::Sort, "sort", &["s"]),
+        (Self::ReverseSort, "reverse_sort", &["S"]),
+        (Self::CycleOrder, "cycle_order", &["o"]),
+        (Self::Reload, "reload", &["r"]),
+        (Self::Query, "query", &["b"]),
+        (Self::ZoomExtent, "zoom_extent", &["z"]),
+        (Self::ZoomIn, "zoom_in", &["+", "="]),
+        (Self::ZoomOut, "zoom_out", &["-"]),
+        (Self::Graticule, "graticule", &["g"]),
+        (Self::ToggleResolution, "toggle_resolution", &["m"]),
+        (Self::SamplePoints, "sample_points", &["p"]),
+        (Self::SwapAxes, "swap_axes", &["x"]),
+        (Self::CountFeatures, "count_features", &["c"]),
+        (Self::GeometryTypes, "geometry_types", &["t"]),
+        (Self::RawValues, "raw_values", &["T"]),
+        (Self::Histogram, "histogram", &["h"]),
+        (Self::Copy, "copy", &["y"]),
+        (Self::RowValues, "row_values", &["v"]),
+        (Self::ExtendedColumns, "extended_columns", &["e"]),
+        (Self::ScrollLeft, "scroll_left", &["h"]),
+        (Self::ScrollRight, "scroll_right", &["l"]),
+        (Self::NextFile, "next_file", &["]"]),
+        (Self::PreviousFile, "previous_file", &["["]),
+        (Self::RequestLog, "request_log", &["L"]),
+        (Self::GoToFeature, "go_to_feature", &[":"]),
+    ];
+
+    /// The action's name in the `[keys]` section
+    fn name(self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|&&(action, _, _)| action == self)
+            .map_or("", |&(_, name, _)| name)
+    }
+
+    /// The tab an action is limited to, if any. Its keys take precedence there over the
+    /// same keys bound to other actions, so that `g` can toggle the graticule on the Map
+    /// tab while still going to the top everywhere else
+    fn tab(self) -> Option<SelectedTab> {
+        match self {
+            Self::Graticule => Some(SelectedTab::Map),
+            Self::Histogram => Some(SelectedTab::Stats),
+            _ => None,
+        }
+    }
+}
+
+/// Names of keys other than single characters, as written in the config file
+const NAMED_KEYS: [(&str, KeyCode); 16] = [
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backtab", KeyCode::BackTab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("space", KeyCode::Char(' ')),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+];
+
+/// A key along with the modifiers held down with it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// The case of a character already says whether shift was held, as does back tab,
+    /// and terminals disagree on whether they also report the shift modifier, so it is ignored
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+}
+
+/// Parses keys like `j`, `G`, `ctrl-c`, `shift-tab`, `pagedown` or `f5`
+impl std::str::FromStr for Key {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = spec;
+        // a lone `-` is a key rather than a separator
+        while let Some((modifier, key)) = rest.split_once('-')
+            && !key.is_empty()
+        {
+            modifiers |= match modifier {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => {
+                    return Err(format!(
+                        "unknown modifier '{modifier}' in key '{spec}'; expected ctrl, alt, or shift"
+                    ));
+                }
+            };
+            rest = key;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match NAMED_KEYS.iter().find(|(name, _)| *name == rest) {
+                Some((_, KeyCode::Tab)) if modifiers.contains(KeyModifiers::SHIFT) => {
+                    KeyCode::BackTab
+                }
+                Some(&(_, code)) => code,
+                None => match rest.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => {
+                        let names: Vec<&str> = NAMED_KEYS.iter().map(|(name, _)| *name).collect();
+                        return Err(format!(
+                            "unknown key '{rest}' in '{spec}'; expected a single character, \
+                             f1 to f12, or one of {}",
+                            names.join(", ")
+                        ));
+                    }
+                },
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+/// Formats keys for the footer, with arrows for the arrow keys
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Enter => write!(f, "⏎"),
+            KeyCode::BackTab => write!(f, "shift-tab"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            code => match NAMED_KEYS.iter().find(|&&(_, named)| named == code) {
+                Some((name, _)) => write!(f, "{name}"),
+                None => write!(f, "{code:?}"),
+            },
+        }
+    }
+}
+
+/// Which action each key triggers
+pub struct Keymap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Keymap {
+    /// The default keys, with the keys of any action named in `overrides` replaced. A key
+    /// bound to two actions is an error, unless one is limited to a tab and the other isn't
+    pub fn new(overrides: &HashMap<String, Vec<String>>) -> Result<Self, String> {
+        if let Some(name) = overrides
+            .keys()
+            .find(|name| !Action::ALL.iter().any(|(_, known, _)| known == name))
+        {
+            let names: Vec<&str> = Action::ALL.iter().map(|(_, name, _)| *name).collect();
+            return Err(format!(
+                "unknown action '{name}' in [keys]; expected one of {}",
+                names.join(", ")
+            ));
+        }
+
+        let mut bindings: Vec<(Key, Action)> = Vec::new();
+        for (action, name, defaults) in Action::ALL {
+            let keys: Vec<&str> = match overrides.get(name) {
+                Some(keys) => keys.iter().map(String::as_str).collect(),
+                None => defaults.to_vec(),
+            };
+            for key in keys {
+                let key = key
+                    .parse()
+                    .map_err(|e| format!("invalid key for {name} in [keys]: {e}"))?;
+                if let Some((_, other)) = bindings.iter().find(|&&(bound, other)| {
+                    bound == key && other != action && other.tab() == action.tab()
+                }) {
+                    return Err(format!(
+                        "key '{key}' is bound to both {} and {name} in [keys]; \
+                         rebind one of them",
+                        other.name()
+                    ));
+                }
+                bindings.push((key, action));
+            }
+        }
+        Ok(Self { bindings })
+    }
+
+    /// The action bound to a key press on `tab`, if any
+    pub fn action(
+        &self,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+        tab: SelectedTab,
+    ) -> Option<Action> {
+        let key = Key::new(code, modifiers);
+        let mut bound = self
+            .bindings
+            .iter()
+            .filter(|(bound, _)| *bound == key)
+            .map(|&(_, action)| action);
+        bound
+            .clone()
+            .find(|action| action.tab() == Some(tab))
+            .or_else(|| bound.find(|action| action.tab().is_none()))
+    }
+
+    /// The first key bound to `action`, for showing in the footer
+    pub fn key(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|&&(_, bound)| bound == action)
+            .map_or("unbound".to_string(), |(key, _)| key.to_string())
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&HashMap::new()).expect("the default keys are valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(overrides: &[(&str, &[&str])]) -> Result<Keymap, String> {
+        let overrides = overrides
+            .iter()
+            .map(|(name, keys)| {
+                (
+                    name.to_string(),
+                    keys.iter().map(|k| k.to_string()).collect(),
+                )
+            })
+            .collect();
+        Keymap::new(&overrides)
+    }
+
+    #[test]
+    fn key_specs() {
+        let char = |c| KeyCode::Char(c);
+        for (spec, code, modifiers) in [
+            ("j", char('j'), KeyModifiers::NONE),
+            ("G", char('G'), KeyModifiers::NONE),
+            ("-", char('-'), KeyModifiers::NONE),
+            ("space", char(' '), KeyModifiers::NONE),
+            ("ctrl-c", char('c'), KeyModifiers::CONTROL),
+            ("ctrl--", char('-'), KeyModifiers::CONTROL),
+            (
+                "alt-ctrl-x",
+                char('x'),
+                KeyModifiers::CONTROL | KeyModifiers::ALT,
+            ),
+            // shift is the case of the character
+            ("shift-g", char('G'), KeyModifiers::NONE),
+            ("shift-tab", KeyCode::BackTab, KeyModifiers::NONE),
+            ("backtab", KeyCode::BackTab, KeyModifiers::NONE),
+            ("shift-up", KeyCode::Up, KeyModifiers::SHIFT),
+            ("pagedown", KeyCode::PageDown, KeyModifiers::NONE),
+            ("f5", KeyCode::F(5), KeyModifiers::NONE),
+            ("f12", KeyCode::F(12), KeyModifiers::NONE),
+        ] {
+            assert_eq!(spec.parse(), Ok(Key::new(code, modifiers)), "{spec}");
+        }
+    }
+
+    #[test]
+    fn invalid_key_specs() {
+        for (spec, error) in [
+            ("", "unknown key '' in ''"),
+            ("pgdn", "unknown key 'pgdn' in 'pgdn'"),
+            ("f13", "unknown key 'f13' in 'f13'"),
+            ("f0", "unknown key 'f0' in 'f0'"),
+            ("ctrl-", "unknown key 'ctrl-' in 'ctrl-'"),
+            (
+                "cmd-q",
+                "unknown modifier 'cmd' in key 'cmd-q'; expected ctrl, alt, or shift",
+            ),
+            ("control-q", "unknown modifier 'control' in key 'control-q'"),
+        ] {
+            let found = spec.parse::<Key>().unwrap_err();
+            assert!(found.starts_with(error), "{spec}: {found}");
+        }
+    }
+
+    #[test]
+    fn keys_for_the_footer() {
+        for (spec, shown) in [
+            ("ctrl-alt-x", "ctrl-alt-x"),
+            ("space", "space"),
+            ("down", "↓"),
+            ("enter", "⏎"),
+            ("shift-tab", "shift-tab"),
+            ("pagedown", "pagedown"),
+            ("f5", "f5"),
+        ] {
+            assert_eq!(spec.parse::<Key>().unwrap().to_string(), shown, "{spec}");
+        }
+    }
+
+    #[test]
+    fn overrides_replace_the_default_keys() {
+        let keymap = keys(&[("quit", &["ctrl-q"])]).unwrap();
+        let on = |code, modifiers| keymap.action(code, modifiers, SelectedTab::Metadata);
+        assert_eq!(
+            on(KeyCode::Char('q'), KeyModifiers::CONTROL),
+            Some(Action::Quit)
+        );
+        assert_eq!(on(KeyCode::Char('q'), KeyModifiers::NONE), None);
+        assert_eq!(on(KeyCode::Esc, KeyModifiers::NONE), None);
+        assert_eq!(keymap.key(Action::Quit), "ctrl-q");
+        assert_eq!(keys(&[("quit", &[])]).unwrap().key(Action::Quit), "unbound");
+        // terminals which report shift with an uppercase letter still match
+        assert_eq!(
+            keymap.action(
+                KeyCode::Char('G'),
+                KeyModifiers::SHIFT,
+                SelectedTab::Metadata
+            ),
+            Some(Action::Bottom)
+        );
+    }
+
+    #[test]
+    fn invalid_overrides() {
+        for (overrides, error) in [
+            (
+                &[("qiut", &["q"][..])][..],
+                "unknown action 'qiut' in [keys]; expected one of quit, next_tab,",
+            ),
+            (&[("Quit", &["q"])], "unknown action 'Quit' in [keys]"),
+            (
+                &[("quit", &["ctl-q"])],
+                "invalid key for quit in [keys]: unknown modifier 'ctl' in key 'ctl-q'",
+            ),
+            (
+                &[("quit", &["j"])],
+                "key 'j' is bound to both quit and scroll_down in [keys]; rebind one of them",
+            ),
+        ] {
+            let found = keys(overrides).err().unwrap();
+            assert!(found.starts_with(error), "{overrides:?}: {found}");
+        }
+    }
+
+    #[test]
+    fn keys_limited_to_a_tab_take_precedence_there() {
+        let keymap = Keymap::default();
+        let g = |tab| keymap.action(KeyCode::Char('g'), KeyModifiers::NONE, tab);
+        assert_eq!(g(SelectedTab::Map), Some(Action::Graticule));
+        assert_eq!(g(SelectedTab::Metadata), Some(Action::Top));
+        let h = |tab| keymap.action(KeyCode::Char('h'), KeyModifiers::NONE, tab);
+        assert_eq!(h(SelectedTab::Stats), Some(Action::Histogram));
+        assert_eq!(h(SelectedTab::Columns), Some(Action::ScrollLeft));
+        // actions limited to different tabs can share a key
+        let keymap = keys(&[("graticule", &["h"])]).unwrap();
+        assert_eq!(h(SelectedTab::Map), Some(Action::ScrollLeft));
+        let h = |tab| keymap.action(KeyCode::Char('h'), KeyModifiers::NONE, tab);
+        assert_eq!(h(SelectedTab::Map), Some(Action::Graticule));
+        assert_eq!(h(SelectedTab::Stats), Some(Action::Histogram));
+        // a key moved off one action can be bound to another
+        let keymap = keys(&[("scroll_down", &["ctrl-n"]), ("quit", &["j"])]).unwrap();
+        let j = keymap.action(KeyCode::Char('j'), KeyModifiers::NONE, SelectedTab::Map);
+        assert_eq!(j, Some(Action::Quit));
+    }
+}
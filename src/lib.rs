@@ -2,29 +2,61 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use ratatui::{
+    layout::Rect,
     style::{Color, Modifier, Style},
+    symbols::{Marker, line, scrollbar},
     text::{Line, Span},
     widgets::{
-        Block, Borders, TableState, Tabs, Widget,
-        canvas::{Canvas, Map, MapResolution},
+        Block, Borders, Scrollbar, ScrollbarOrientation, TableState, Tabs, Widget,
+        canvas::{Canvas, Map, MapResolution, Points},
     },
 };
 
+use crate::keymap::{Action, Keymap};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+pub mod app;
+pub mod cache;
+pub mod check;
 pub mod cli;
+pub mod clipboard;
+pub mod config;
+pub mod dataset;
+pub mod diagnostics;
+pub mod export;
+pub mod feature;
+pub mod http;
+pub mod json_tree;
+pub mod keymap;
+pub mod plain;
+pub mod progress;
 pub mod projection;
+pub mod properties;
+pub mod query;
+pub mod s3;
+pub mod source;
+pub mod stats;
+pub mod wkt;
 
 pub struct ColumnsTableState {
     pub state: TableState,
+    /// The first row of the window drawn last frame
+    offset: usize,
 }
 
 impl ColumnsTableState {
     pub fn new() -> Self {
         Self {
             state: TableState::default().with_selected(Some(0)),
+            offset: 0,
         }
     }
 
     pub fn next(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i >= len - 1 {
@@ -39,6 +71,9 @@ impl ColumnsTableState {
     }
 
     pub fn previous(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -51,6 +86,43 @@ impl ColumnsTableState {
         };
         self.state.select(Some(i));
     }
+
+    /// Move the selection down by a page, stopping at the last row
+    pub fn page_down(&mut self, page_size: usize, len: usize) {
+        let i = self.state.selected().unwrap_or(0).saturating_add(page_size);
+        self.state.select(Some(i.min(len.saturating_sub(1))));
+    }
+
+    /// Move the selection up by a page, stopping at the first row
+    pub fn page_up(&mut self, page_size: usize) {
+        let i = self.state.selected().unwrap_or(0).saturating_sub(page_size);
+        self.state.select(Some(i));
+    }
+
+    pub fn first(&mut self) {
+        self.state.select(Some(0));
+    }
+
+    pub fn last(&mut self, len: usize) {
+        self.state.select(Some(len.saturating_sub(1)));
+    }
+
+    /// The rows to draw in a table `height` rows tall, so that only the rows on screen
+    /// are built. The window scrolls only as far as needed to keep the selection in view
+    pub fn visible_window(&mut self, len: usize, height: usize) -> std::ops::Range<usize> {
+        let selected = self
+            .state
+            .selected()
+            .unwrap_or(0)
+            .min(len.saturating_sub(1));
+        if selected < self.offset {
+            self.offset = selected;
+        } else if height > 0 && selected >= self.offset + height {
+            self.offset = selected + 1 - height;
+        }
+        self.offset = self.offset.min(len.saturating_sub(height));
+        self.offset..(self.offset + height).min(len)
+    }
 }
 
 impl Default for ColumnsTableState {
@@ -59,95 +131,1945 @@ impl Default for ColumnsTableState {
     }
 }
 
-pub fn make_map_with_bbox_overlay(map_title: &str, bbox: &projection::Bbox) -> impl Widget {
-    const MAX_LONGITUDE_RANGE: [f64; 2] = [-180.0, 180.0];
-    const MAX_LATITUDE_RANGE: [f64; 2] = [-90.0, 90.0];
+/// The cells of the Columns table, computed once when the header is loaded
+/// rather than on every frame
+#[derive(Default)]
+pub struct ColumnsTableData {
+    /// The title of each table column
+    pub headers: Vec<&'static str>,
+    /// Whether each table column is only shown in the extended view
+    extended_only: Vec<bool>,
+    /// The full values of each row, in file order, with a value for each table column
+    pub cells: Vec<Vec<String>>,
+    /// The values of each row truncated to fit within [`MAX_CELL_WIDTH`]
+    pub truncated_cells: Vec<Vec<String>>,
+    /// The width of each table column, including padding
+    pub widths: Vec<u16>,
+}
+
+impl ColumnsTableData {
+    pub fn new(columns: &[flatgeobuf::Column]) -> Self {
+        let fields: Vec<(Column<flatgeobuf::Column>, bool)> = vec![
+            (
+                Column {
+                    header: "Name",
+                    value: Box::new(|c| c.name().to_string()),
+                },
+                false,
+            ),
+            (
+                Column {
+                    header: "Title",
+                    value: Box::new(|c| c.title().unwrap_or("—").to_string()),
+                },
+                true,
+            ),
+            (
+                Column {
+                    header: "Type",
+                    value: Box::new(|c| format!("{:?}", c.type_())),
+                },
+                false,
+            ),
+            (
+                Column {
+                    header: "Description",
+                    value: Box::new(|c| c.description().unwrap_or("—").to_string()),
+                },
+                false,
+            ),
+            (
+                Column {
+                    header: "Nullable",
+                    value: Box::new(|c| c.nullable().to_string()),
+                },
+                false,
+            ),
+            (
+                Column {
+                    header: "Primary Key",
+                    value: Box::new(|c| c.primary_key().to_string()),
+                },
+                false,
+            ),
+            (
+                Column {
+                    header: "Unique",
+                    value: Box::new(|c| c.unique().to_string()),
+                },
+                false,
+            ),
+            (
+                Column {
+                    header: "Width",
+                    value: Box::new(|c| format_column_size(c.width())),
+                },
+                true,
+            ),
+            (
+                Column {
+                    header: "Precision",
+                    value: Box::new(|c| format_column_size(c.precision())),
+                },
+                true,
+            ),
+            (
+                Column {
+                    header: "Scale",
+                    value: Box::new(|c| format_column_size(c.scale())),
+                },
+                true,
+            ),
+            (
+                Column {
+                    header: "Metadata",
+                    value: Box::new(|c| c.metadata().unwrap_or("—").to_string()),
+                },
+                true,
+            ),
+        ];
+
+        let cells: Vec<Vec<String>> = columns
+            .iter()
+            .map(|c| fields.iter().map(|(field, _)| (field.value)(c)).collect())
+            .collect();
+        let truncated_cells = cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|value| truncate_with_ellipsis(value, MAX_CELL_WIDTH))
+                    .collect()
+            })
+            .collect();
+        let widths = fields
+            .iter()
+            .enumerate()
+            .map(|(i, (field, _))| {
+                // display width rather than bytes, so wide characters line up
+                let max_width = cells.iter().map(|row| row[i].width()).max().unwrap_or(0);
+                (field.header.width().max(max_width).min(MAX_CELL_WIDTH) + 2) as u16
+            })
+            .collect();
+
+        Self {
+            headers: fields.iter().map(|(field, _)| field.header).collect(),
+            extended_only: fields.iter().map(|&(_, extended)| extended).collect(),
+            cells,
+            truncated_cells,
+            widths,
+        }
+    }
+
+    /// The indices of the table columns which are shown
+    pub fn shown_fields(&self, extended: bool) -> Vec<usize> {
+        (0..self.headers.len())
+            .filter(|&i| extended || !self.extended_only[i])
+            .collect()
+    }
+}
+
+/// The styles used throughout the TUI, selected with `--theme`
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    /// The label half of an [`info_line`]
+    pub label: Style,
+    pub tabs: Style,
+    pub selected_tab: Style,
+    /// The focused row of a table
+    pub highlight: Style,
+    /// The part of a name matching the search query
+    pub search_match: Style,
+    /// The dataset source in the footer
+    pub source: Style,
+    /// Keybinding hints in the footer
+    pub hints: Style,
+    /// Transient messages in the footer
+    pub message: Style,
+    pub error: Style,
+    /// Keywords in pretty-printed WKT
+    pub keyword: Style,
+    /// The world map drawn behind the bounding box
+    pub map: Color,
+    /// The bounding box of the dataset on the map
+    pub bbox: Color,
+    /// A feature drawn over the bounding box
+    pub feature: Color,
+    /// The marker at the center of the bounding box
+    pub centroid: Color,
+    /// The points sampled from the features
+    pub sample: Color,
+    /// The lines of latitude and longitude on the map, and the equator and prime meridian
+    pub graticule: Color,
+    pub graticule_origin: Color,
+    /// What the map canvas draws its dots with
+    pub map_marker: Marker,
+    pub symbols: Symbols,
+}
+
+/// The non-alphanumeric characters drawn by the TUI, for terminals
+/// and fonts without good unicode coverage to swap out with `--ascii`
+#[derive(Clone, Copy, Debug)]
+pub struct Symbols {
+    pub scrollbar_track: &'static str,
+    pub scrollbar_thumb: &'static str,
+    pub scrollbar_begin: &'static str,
+    pub scrollbar_end: &'static str,
+    /// Shown before the focused row of a table
+    pub highlight: &'static str,
+    /// Frames of the loading animation
+    pub spinner: &'static [&'static str],
+    /// Markers for expanded and collapsed nodes of a tree
+    pub expanded: &'static str,
+    pub collapsed: &'static str,
+    /// Shown after the header of the column a table is sorted by
+    pub sort_ascending: &'static str,
+    pub sort_descending: &'static str,
+    /// Marks the center of the bounding box on the map
+    pub centroid: &'static str,
+    /// Shows a color in the map legend
+    pub swatch: &'static str,
+    /// The borders of the columns table printed by `--stdout`
+    pub table: line::Set<'static>,
+}
+
+impl Symbols {
+    pub fn unicode() -> Self {
+        Self {
+            scrollbar_track: scrollbar::VERTICAL.track,
+            scrollbar_thumb: scrollbar::VERTICAL.thumb,
+            scrollbar_begin: "↑",
+            scrollbar_end: "↓",
+            highlight: ">> ",
+            spinner: &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
+            expanded: "▾ ",
+            collapsed: "▸ ",
+            sort_ascending: "▲",
+            sort_descending: "▼",
+            centroid: "✕",
+            swatch: "■",
+            table: line::NORMAL,
+        }
+    }
+
+    pub fn ascii() -> Self {
+        Self {
+            scrollbar_track: "|",
+            scrollbar_thumb: "#",
+            scrollbar_begin: "^",
+            scrollbar_end: "v",
+            highlight: "> ",
+            spinner: &["|", "/", "-", "\\"],
+            expanded: "- ",
+            collapsed: "+ ",
+            sort_ascending: "^",
+            sort_descending: "v",
+            centroid: "X",
+            swatch: "#",
+            table: line::Set {
+                vertical: "|",
+                horizontal: "-",
+                top_right: "+",
+                top_left: "+",
+                bottom_right: "+",
+                bottom_left: "+",
+                vertical_left: "+",
+                vertical_right: "+",
+                horizontal_down: "+",
+                horizontal_up: "+",
+                cross: "+",
+            },
+        }
+    }
+}
+
+/// Whether the terminal is unlikely to be able to draw unicode symbols,
+/// judging by `TERM=dumb` or a locale which isn't UTF-8
+pub fn terminal_prefers_ascii() -> bool {
+    if std::env::var("TERM").is_ok_and(|term| term == "dumb") {
+        return true;
+    }
+    // the first of these which is set decides the character encoding
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .is_some_and(|locale| {
+            let locale = locale.to_lowercase();
+            !(locale.contains("utf-8") || locale.contains("utf8"))
+        })
+}
+
+/// A vertical scrollbar drawn with the theme's symbols
+pub fn make_scrollbar(theme: &Theme) -> Scrollbar<'static> {
+    let symbols = theme.symbols;
+    Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .symbols(scrollbar::Set {
+            track: symbols.scrollbar_track,
+            thumb: symbols.scrollbar_thumb,
+            begin: symbols.scrollbar_begin,
+            end: symbols.scrollbar_end,
+        })
+        .begin_symbol(Some(symbols.scrollbar_begin))
+        .end_symbol(Some(symbols.scrollbar_end))
+}
+
+impl Theme {
+    /// For terminals with a dark background
+    pub fn dark() -> Self {
+        Self {
+            label: Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+            tabs: Style::default().fg(Color::White),
+            selected_tab: Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::UNDERLINED),
+            highlight: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            search_match: Style::default()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            source: Style::default().add_modifier(Modifier::BOLD),
+            hints: Style::default().fg(Color::DarkGray),
+            message: Style::default().fg(Color::Yellow),
+            error: Style::default().fg(Color::Red),
+            keyword: Style::default().fg(Color::Cyan),
+            map: Color::Red,
+            bbox: Color::Green,
+            feature: Color::Cyan,
+            centroid: Color::Yellow,
+            sample: Color::LightMagenta,
+            graticule: Color::DarkGray,
+            graticule_origin: Color::Gray,
+            map_marker: Marker::Braille,
+            symbols: Symbols::unicode(),
+        }
+    }
+
+    /// For terminals with a light background, avoiding white and yellow text
+    pub fn light() -> Self {
+        Self {
+            label: Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            tabs: Style::default().fg(Color::Black),
+            selected_tab: Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::UNDERLINED),
+            highlight: Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+            search_match: Style::default()
+                .fg(Color::White)
+                .bg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            source: Style::default().add_modifier(Modifier::BOLD),
+            hints: Style::default().fg(Color::DarkGray),
+            message: Style::default().fg(Color::Magenta),
+            error: Style::default().fg(Color::Red),
+            keyword: Style::default().fg(Color::DarkGray),
+            map: Color::Red,
+            bbox: Color::Blue,
+            feature: Color::Magenta,
+            centroid: Color::DarkGray,
+            sample: Color::Cyan,
+            graticule: Color::Gray,
+            graticule_origin: Color::DarkGray,
+            map_marker: Marker::Braille,
+            symbols: Symbols::unicode(),
+        }
+    }
+
+    /// Only uses modifiers, for terminals with two colors
+    pub fn monochrome() -> Self {
+        Self {
+            label: Style::default().add_modifier(Modifier::BOLD),
+            tabs: Style::default(),
+            selected_tab: Style::default()
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::UNDERLINED),
+            highlight: Style::default().add_modifier(Modifier::REVERSED),
+            search_match: Style::default()
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::UNDERLINED),
+            source: Style::default().add_modifier(Modifier::BOLD),
+            hints: Style::default(),
+            message: Style::default().add_modifier(Modifier::BOLD),
+            error: Style::default().add_modifier(Modifier::BOLD),
+            keyword: Style::default().add_modifier(Modifier::ITALIC),
+            map: Color::Reset,
+            bbox: Color::Reset,
+            feature: Color::Reset,
+            centroid: Color::Reset,
+            sample: Color::Reset,
+            graticule: Color::Reset,
+            graticule_origin: Color::Reset,
+            map_marker: Marker::Braille,
+            symbols: Symbols::unicode(),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl std::str::FromStr for Theme {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "dark" => Ok(Self::dark()),
+            "light" => Ok(Self::light()),
+            "monochrome" => Ok(Self::monochrome()),
+            _ => Err(format!(
+                "unknown theme '{name}'; expected dark, light, or monochrome"
+            )),
+        }
+    }
+}
+
+/// The palette for the Map tab, selected with `--map-colors`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum MapColors {
+    /// Whatever the theme uses
+    #[default]
+    Theme,
+    /// Blue and orange, which can be told apart with red-green color blindness
+    ColorBlind,
+}
+
+impl std::str::FromStr for MapColors {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "theme" => Ok(Self::Theme),
+            "colorblind" => Ok(Self::ColorBlind),
+            _ => Err(format!(
+                "unknown map colors '{name}'; expected theme or colorblind"
+            )),
+        }
+    }
+}
+
+/// How the map canvas draws, selected with `--map-marker`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum MapMarker {
+    /// Braille patterns, the most detailed but poorly drawn by some fonts
+    #[default]
+    Braille,
+    /// Full blocks, which any font should draw well
+    Block,
+    Dot,
+    /// Half blocks, with twice the vertical detail of full blocks
+    Bar,
+}
+
+impl std::str::FromStr for MapMarker {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "braille" => Ok(Self::Braille),
+            "block" => Ok(Self::Block),
+            "dot" => Ok(Self::Dot),
+            "bar" => Ok(Self::Bar),
+            _ => Err(format!(
+                "unknown map marker '{name}'; expected braille, block, dot, or bar"
+            )),
+        }
+    }
+}
+
+impl Theme {
+    pub fn with_map_marker(self, map_marker: MapMarker) -> Self {
+        Self {
+            map_marker: match map_marker {
+                MapMarker::Braille => Marker::Braille,
+                MapMarker::Block => Marker::Block,
+                MapMarker::Dot => Marker::Dot,
+                MapMarker::Bar => Marker::HalfBlock,
+            },
+            ..self
+        }
+    }
+
+    pub fn with_map_colors(self, map_colors: MapColors) -> Self {
+        match map_colors {
+            MapColors::Theme => self,
+            // from the Okabe-Ito palette
+            MapColors::ColorBlind => Self {
+                map: Color::Rgb(0, 114, 178),
+                bbox: Color::Rgb(230, 159, 0),
+                feature: Color::Rgb(204, 121, 167),
+                centroid: Color::Rgb(213, 94, 0),
+                sample: Color::Rgb(86, 180, 233),
+                ..self
+            },
+        }
+    }
+}
+
+/// What part of the world the map shows, as the longitude and latitude at its center
+/// and how many degrees it spans across and down
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapView {
+    pub center: (f64, f64),
+    pub x_span: f64,
+    pub y_span: f64,
+}
+
+impl MapView {
+    pub const WORLD: Self = Self {
+        center: (0.0, 0.0),
+        x_span: 360.0,
+        y_span: 180.0,
+    };
+
+    /// Zooming in any further shows little but the dots of the canvas
+    const MIN_SPAN_DEGREES: f64 = 0.001;
+
+    /// A view of `bbox` with some padding, for a canvas drawn in `area`, borders
+    /// included. Terminal cells are about twice as tall as they are wide, so the
+    /// view is widened in one direction to keep the bbox from looking squashed
+    pub fn around(bbox: &projection::Bbox, area: Rect) -> Self {
+        const PADDING: f64 = 0.2;
+        // a point or a line still needs some room around it
+        const MIN_EXTENT_SPAN_DEGREES: f64 = 0.01;
+        let mut x_span = (bbox.width() * (1.0 + 2.0 * PADDING)).max(MIN_EXTENT_SPAN_DEGREES);
+        let mut y_span = (bbox.height() * (1.0 + 2.0 * PADDING)).max(MIN_EXTENT_SPAN_DEGREES);
+
+        let columns = f64::from(area.width.saturating_sub(2).max(1));
+        let rows = f64::from(area.height.saturating_sub(2).max(1));
+        let aspect = columns / (2.0 * rows);
+        if x_span / y_span < aspect {
+            x_span = y_span * aspect;
+        } else {
+            y_span = x_span / aspect;
+        }
+
+        let mut view = Self {
+            center: bbox.center(),
+            x_span,
+            y_span,
+        };
+        view.clamp();
+        view
+    }
+
+    /// Zoom in around the center when `factor` is above 1, or out when below
+    pub fn zoom(&mut self, factor: f64) {
+        // keep the aspect ratio unless one of the spans hits a limit
+        let factor = factor.min(self.x_span / Self::MIN_SPAN_DEGREES);
+        let factor = factor.min(self.y_span / Self::MIN_SPAN_DEGREES);
+        self.x_span /= factor;
+        self.y_span /= factor;
+        self.clamp();
+    }
+
+    /// Move the view by a fraction of its span, with positive fractions going east and north
+    pub fn pan(&mut self, x_fraction: f64, y_fraction: f64) {
+        self.center.0 += x_fraction * self.x_span;
+        self.center.1 += y_fraction * self.y_span;
+        self.clamp();
+    }
+
+    /// How far zoomed in the view is compared to the whole world
+    pub fn zoom_level(&self) -> f64 {
+        MapView::WORLD.x_span / self.x_span
+    }
+
+    /// Keep the view within the valid longitudes and latitudes
+    fn clamp(&mut self) {
+        self.x_span = self.x_span.min(Self::WORLD.x_span);
+        self.y_span = self.y_span.min(Self::WORLD.y_span);
+        let max_x = (Self::WORLD.x_span - self.x_span) / 2.0;
+        let max_y = (Self::WORLD.y_span - self.y_span) / 2.0;
+        self.center = (
+            self.center.0.clamp(-max_x, max_x),
+            self.center.1.clamp(-max_y, max_y),
+        );
+    }
+
+    pub fn bounds(&self) -> MapBounds {
+        let (x, y) = self.center;
+        MapBounds {
+            x: [x - self.x_span / 2.0, x + self.x_span / 2.0],
+            y: [y - self.y_span / 2.0, y + self.y_span / 2.0],
+        }
+    }
+
+    /// The center and zoom level, for the title of the map
+    pub fn description(&self) -> String {
+        format!(
+            "center {:.4}, {:.4}, zoom {:.1}x",
+            self.center.0,
+            self.center.1,
+            self.zoom_level()
+        )
+    }
+}
+
+/// The longitudes and latitudes spanned by the map canvas
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapBounds {
+    pub x: [f64; 2],
+    pub y: [f64; 2],
+}
+
+impl MapBounds {
+    /// The longitude and latitude at the center of a terminal cell on a canvas with these
+    /// bounds drawn in `area`, borders included. Cells outside the canvas are clamped to its edges
+    pub fn cell_to_lon_lat(&self, area: Rect, column: u16, row: u16) -> (f64, f64) {
+        let inner = Rect::new(
+            area.x + 1,
+            area.y + 1,
+            area.width.saturating_sub(2).max(1),
+            area.height.saturating_sub(2).max(1),
+        );
+        let column = column.clamp(inner.left(), inner.right() - 1);
+        let row = row.clamp(inner.top(), inner.bottom() - 1);
+        let x_fraction = (f64::from(column - inner.x) + 0.5) / f64::from(inner.width);
+        let y_fraction = (f64::from(row - inner.y) + 0.5) / f64::from(inner.height);
+        // rows count down from the top, where the latitude is highest
+        (
+            self.x[0] + x_fraction * (self.x[1] - self.x[0]),
+            self.y[1] - y_fraction * (self.y[1] - self.y[0]),
+        )
+    }
+}
+
+/// The spacing in degrees of the graticule lines for a view spanning `span` degrees:
+/// every 30 degrees across the whole world, and closer together as the map zooms in
+fn graticule_step(span: f64) -> f64 {
+    const STEPS: [f64; 14] = [
+        0.001, 0.002, 0.005, 0.01, 0.02, 0.05, 0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0,
+    ];
+    const MAX_LINES: f64 = 12.0;
+    STEPS
+        .into_iter()
+        .find(|step| span / step <= MAX_LINES)
+        .unwrap_or(30.0)
+}
+
+/// A graticule label like `60°N` or `120.5°W`, with as many decimals as `step` needs
+fn graticule_label(degrees: f64, step: f64, positive: char, negative: char) -> String {
+    let decimals = (-step.log10()).ceil().max(0.0) as usize;
+    let hemisphere = match degrees {
+        d if d > 0.0 => positive.to_string(),
+        d if d < 0.0 => negative.to_string(),
+        _ => String::new(),
+    };
+    format!("{:.decimals$}°{hemisphere}", degrees.abs())
+}
+
+/// How many decimals to show coordinates within `bbox` with: more the smaller it is,
+/// down to about a hundredth of its size
+pub fn coordinate_decimals(bbox: &projection::Bbox) -> usize {
+    let span = bbox.width().max(bbox.height());
+    if span > 0.0 {
+        (2.0 - span.log10().floor()).clamp(0.0, 6.0) as usize
+    } else {
+        6
+    }
+}
+
+/// A bbox for reading, like `x -124.5 to -114.1, y 32.5 to 42.0`, with as
+/// many decimals as [`coordinate_decimals`] gives for its size
+pub fn pretty_envelope(bbox: &projection::Bbox) -> String {
+    let decimals = coordinate_decimals(bbox);
+    format!(
+        "x {:.decimals$} to {:.decimals$}, y {:.decimals$} to {:.decimals$}",
+        bbox.xmin, bbox.xmax, bbox.ymin, bbox.ymax
+    )
+}
+
+/// A geometry type in words, like `Multi-polygon` for `MultiPolygon`, or `Any type` for
+/// `Unknown`, which allows features of any type
+pub fn pretty_geometry_type(geometry_type: flatgeobuf::GeometryType) -> String {
+    use flatgeobuf::GeometryType;
+    let name = match geometry_type {
+        GeometryType::Unknown => "Any type",
+        GeometryType::Point => "Point",
+        GeometryType::LineString => "Line string",
+        GeometryType::Polygon => "Polygon",
+        GeometryType::MultiPoint => "Multi-point",
+        GeometryType::MultiLineString => "Multi-line string",
+        GeometryType::MultiPolygon => "Multi-polygon",
+        GeometryType::GeometryCollection => "Geometry collection",
+        GeometryType::CircularString => "Circular string",
+        GeometryType::CompoundCurve => "Compound curve",
+        GeometryType::CurvePolygon => "Curve polygon",
+        GeometryType::MultiCurve => "Multi-curve",
+        GeometryType::MultiSurface => "Multi-surface",
+        GeometryType::Curve => "Curve",
+        GeometryType::Surface => "Surface",
+        GeometryType::PolyhedralSurface => "Polyhedral surface",
+        GeometryType::TIN => "Triangulated irregular network",
+        GeometryType::Triangle => "Triangle",
+        // a type newer than this version of flatgeobuf knows
+        other => return format!("{other:?}"),
+    };
+    name.to_string()
+}
+
+/// Where to print the coordinates of the bbox on a canvas with `bounds` drawn in `area`:
+/// the min corner at the bottom left and the max corner at the top right, or a single
+/// label in the middle if those would run into each other
+fn bbox_corner_labels(
+    bbox: &projection::Bbox,
+    bounds: MapBounds,
+    area: Rect,
+) -> Vec<(f64, f64, String)> {
+    let decimals = coordinate_decimals(bbox);
+    let min_label = format!("({:.decimals$}, {:.decimals$})", bbox.xmin, bbox.ymin);
+    let max_label = format!("({:.decimals$}, {:.decimals$})", bbox.xmax, bbox.ymax);
+
+    let columns_per_degree = f64::from(area.width.saturating_sub(2)) / (bounds.x[1] - bounds.x[0]);
+    let rows_per_degree = f64::from(area.height.saturating_sub(2)) / (bounds.y[1] - bounds.y[0]);
+    let rows_apart = bbox.height() * rows_per_degree;
+    let columns_apart = bbox.width() * columns_per_degree;
+    // both labels are printed rightwards from their corner
+    if rows_apart >= 1.0 || columns_apart > min_label.width() as f64 {
+        return vec![
+            (bbox.xmin, bbox.ymin, min_label),
+            (bbox.xmax, bbox.ymax, max_label),
+        ];
+    }
+
+    let label = format!(
+        "({:.decimals$}..{:.decimals$}, {:.decimals$}..{:.decimals$})",
+        bbox.xmin, bbox.xmax, bbox.ymin, bbox.ymax
+    );
+    let half_width_degrees = label.width() as f64 / 2.0 / columns_per_degree;
+    let (x, y) = bbox.center();
+    vec![(x - half_width_degrees, y, label)]
+}
+
+/// What is drawn over the bounding box, already in EPSG:4326
+#[derive(Clone, Copy)]
+pub struct MapOverlays<'a> {
+    /// The parts of the geometry of a feature
+    pub feature_parts: &'a [Vec<(f64, f64)>],
+    /// A point for each of a sample of the features
+    pub sample_points: &'a [(f64, f64)],
+}
+
+/// How the map is drawn, as chosen on the Map tab
+#[derive(Clone, Copy)]
+pub struct MapOptions {
+    pub resolution: MapResolution,
+    /// Whether lines of latitude and longitude are drawn
+    pub graticule: bool,
+}
+
+impl MapOptions {
+    /// The coastlines at high resolution are just noise once a terminal cell covers
+    /// a few degrees, as on small terminals, so those get the low resolution
+    pub fn auto_resolution(bounds: MapBounds, area: Rect) -> MapResolution {
+        const MAX_HIGH_DEGREES_PER_CELL: f64 = 2.0;
+        let degrees_per_cell =
+            (bounds.x[1] - bounds.x[0]) / f64::from(area.width.saturating_sub(2).max(1));
+        if degrees_per_cell > MAX_HIGH_DEGREES_PER_CELL {
+            MapResolution::Low
+        } else {
+            MapResolution::High
+        }
+    }
+}
+
+/// What each color on the map means, for only what is currently drawn
+pub fn map_legend(overlays: MapOverlays, options: MapOptions, theme: &Theme) -> Vec<Line<'static>> {
+    let swatch = theme.symbols.swatch;
+    let mut entries = vec![
+        (swatch, theme.map, "land"),
+        (swatch, theme.bbox, "extent"),
+        (theme.symbols.centroid, theme.centroid, "extent center"),
+    ];
+    if !overlays.feature_parts.is_empty() {
+        entries.push((swatch, theme.feature, "feature"));
+    }
+    if !overlays.sample_points.is_empty() {
+        entries.push((swatch, theme.sample, "sampled points"));
+    }
+    if options.graticule {
+        entries.push((swatch, theme.graticule, "graticule"));
+    }
+    entries
+        .into_iter()
+        .map(|(symbol, color, label)| {
+            Line::from(vec![
+                Span::styled(symbol, Style::default().fg(color)),
+                Span::raw(format!(" {label}")),
+            ])
+        })
+        .collect()
+}
+
+pub fn make_map_with_bbox_overlay(
+    map_title: &str,
+    area: Rect,
+    bounds: MapBounds,
+    bbox: &projection::Bbox,
+    overlays: MapOverlays,
+    options: MapOptions,
+    theme: &Theme,
+) -> impl Widget {
+    let (map_color, bbox_color, feature_color) = (theme.map, theme.bbox, theme.feature);
+    let sample_color = theme.sample;
+    let MapOverlays {
+        feature_parts,
+        sample_points,
+    } = overlays;
+    let centroid = bbox.center();
+    let (centroid_color, centroid_symbol) = (theme.centroid, theme.symbols.centroid);
+    let (graticule_color, origin_color) = (theme.graticule, theme.graticule_origin);
+
+    // the inside of the box is dotted as well, so that it stands out by more than its color;
+    // every 5 degrees on the world map, and as densely when zoomed in
+    let hatch_spacing = (bounds.x[1] - bounds.x[0]) / 72.0;
+    let hatch = |min: f64, max: f64| {
+        let start = (min / hatch_spacing).floor() as i64 + 1;
+        let end = (max / hatch_spacing).ceil() as i64;
+        (start..end).map(move |i| i as f64 * hatch_spacing)
+    };
+    // a bbox crossing the antimeridian is drawn as the two parts on either side of it
+    let pieces = bbox.split_at_antimeridian();
+    let hatch_points: Vec<(f64, f64)> = pieces
+        .iter()
+        .flat_map(|piece| {
+            hatch(piece.xmin, piece.xmax)
+                .flat_map(|x| hatch(piece.ymin, piece.ymax).map(move |y| (x, y)))
+        })
+        .collect();
+
+    let corner_labels = bbox_corner_labels(bbox, bounds, area);
+
+    // a bbox without any area would draw as little as nothing, so it is
+    // grown to at least a terminal cell across and down
+    let min_width = (bounds.x[1] - bounds.x[0]) / f64::from(area.width.saturating_sub(2).max(1));
+    let min_height = (bounds.y[1] - bounds.y[0]) / f64::from(area.height.saturating_sub(2).max(1));
+    let outlines: Vec<_> = pieces
+        .iter()
+        .map(|piece| {
+            let (center_x, center_y) = piece.center();
+            let (width, height) = (piece.width().max(min_width), piece.height().max(min_height));
+            ratatui::widgets::canvas::Rectangle {
+                x: center_x - width / 2.0,
+                y: center_y - height / 2.0,
+                width,
+                height,
+                color: bbox_color,
+            }
+        })
+        .collect();
+
+    let step = graticule_step((bounds.x[1] - bounds.x[0]).max(bounds.y[1] - bounds.y[0]));
+    let lines_within = move |[min, max]: [f64; 2]| {
+        let start = (min / step).ceil() as i64;
+        let end = (max / step).floor() as i64;
+        (start..=end).map(move |i| i as f64 * step)
+    };
+
     Canvas::default()
         .block(Block::default().borders(Borders::ALL).title(map_title))
-        .x_bounds(MAX_LONGITUDE_RANGE)
-        .y_bounds(MAX_LATITUDE_RANGE)
+        .marker(theme.map_marker)
+        .x_bounds(bounds.x)
+        .y_bounds(bounds.y)
         .paint(move |ctx| {
+            if options.graticule {
+                // under everything else, so it never hides the data
+                for x in lines_within(bounds.x) {
+                    ctx.draw(&ratatui::widgets::canvas::Line {
+                        x1: x,
+                        y1: bounds.y[0],
+                        x2: x,
+                        y2: bounds.y[1],
+                        color: if x == 0.0 {
+                            origin_color
+                        } else {
+                            graticule_color
+                        },
+                    });
+                }
+                for y in lines_within(bounds.y) {
+                    ctx.draw(&ratatui::widgets::canvas::Line {
+                        x1: bounds.x[0],
+                        y1: y,
+                        x2: bounds.x[1],
+                        y2: y,
+                        color: if y == 0.0 {
+                            origin_color
+                        } else {
+                            graticule_color
+                        },
+                    });
+                }
+            }
             // draw section that isn't included in the dataset
             ctx.draw(&Map {
-                color: Color::Red,
-                resolution: MapResolution::High,
+                color: map_color,
+                resolution: options.resolution,
+            });
+            // outline the section that contains the dataset
+            // to show it is included
+            for outline in &outlines {
+                ctx.draw(outline);
+            }
+            ctx.draw(&Points {
+                coords: &hatch_points,
+                color: bbox_color,
             });
-            // make all the section that contains the dataset
-            // enveloped in green to show it is included
-            ctx.draw(&ratatui::widgets::canvas::Rectangle {
-                x: bbox.xmin,
-                y: bbox.ymin,
-                width: bbox.xmax - bbox.xmin,
-                height: bbox.ymax - bbox.ymin,
-                color: Color::Green,
+            ctx.draw(&Points {
+                coords: sample_points,
+                color: sample_color,
             });
+            for part in feature_parts {
+                if let [point] = part.as_slice() {
+                    ctx.draw(&Points {
+                        coords: &[*point],
+                        color: feature_color,
+                    });
+                }
+                for pair in part.windows(2) {
+                    ctx.draw(&ratatui::widgets::canvas::Line {
+                        x1: pair[0].0,
+                        y1: pair[0].1,
+                        x2: pair[1].0,
+                        y2: pair[1].1,
+                        color: feature_color,
+                    });
+                }
+            }
+            // on top of the rest, since for tiny extents it is all that can be seen
+            ctx.print(
+                centroid.0,
+                centroid.1,
+                Span::styled(centroid_symbol, centroid_color),
+            );
+            for (x, y, label) in &corner_labels {
+                ctx.print(*x, *y, Span::styled(label.clone(), bbox_color));
+            }
+            if options.graticule {
+                // longitudes along the bottom edge and latitudes along the left
+                for x in lines_within(bounds.x) {
+                    ctx.print(
+                        x,
+                        bounds.y[0],
+                        Span::styled(graticule_label(x, step, 'E', 'W'), graticule_color),
+                    );
+                }
+                for y in lines_within(bounds.y) {
+                    ctx.print(
+                        bounds.x[0],
+                        y,
+                        Span::styled(graticule_label(y, step, 'N', 'S'), graticule_color),
+                    );
+                }
+            }
         })
 }
 
+/// Height of the tabs row at the top of the TUI, including its borders
+pub const TABS_HEIGHT: u16 = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Scroll {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum SelectedTab {
     Metadata,
     Columns,
+    Stats,
     Map,
 }
 
 impl SelectedTab {
-    pub fn next(self) -> Self {
-        match self {
-            Self::Metadata => Self::Columns,
-            Self::Columns => Self::Map,
-            Self::Map => Self::Metadata,
-        }
+    /// Every tab, in the order they are shown when the file has what each needs
+    pub const ALL: [Self; 4] = [Self::Metadata, Self::Columns, Self::Stats, Self::Map];
+
+    /// The tab after this one among `tabs`, wrapping around
+    pub fn next(self, tabs: &[Self]) -> Self {
+        let i = tabs
+            .iter()
+            .position(|&tab| tab == self)
+            .map_or(0, |i| i + 1);
+        tabs.get(i % tabs.len().max(1)).copied().unwrap_or(self)
     }
 
-    pub fn previous(self) -> Self {
+    /// The tab before this one among `tabs`, wrapping around
+    pub fn previous(self, tabs: &[Self]) -> Self {
+        let i = tabs.iter().position(|&tab| tab == self).unwrap_or(0);
+        tabs.get((i + tabs.len().max(1) - 1) % tabs.len().max(1))
+            .copied()
+            .unwrap_or(self)
+    }
+
+    pub fn title(self) -> &'static str {
         match self {
-            Self::Metadata => Self::Map,
-            Self::Columns => Self::Metadata,
-            Self::Map => Self::Columns,
+            Self::Metadata => "Metadata",
+            Self::Columns => "Columns",
+            Self::Stats => "Stats",
+            Self::Map => "Map",
         }
     }
 
-    pub fn titles() -> Vec<&'static str> {
-        vec!["Metadata", "Columns", "Map"]
+    /// The most important keybindings while this tab is focused, for the footer
+    pub fn key_hints(self, keymap: &Keymap) -> String {
+        use Action::*;
+        let hints: &[(&[Action], &str)] = match self {
+            Self::Metadata => &[
+                (&[PreviousTab, NextTab], "tabs"),
+                (&[ScrollDown, ScrollUp], "scroll"),
+                (&[Search], "search"),
+                (&[Select], "expand"),
+                (&[ToggleWkt], "wkt"),
+                (&[RawValues], "raw"),
+                (&[GoToFeature], "feature"),
+                (&[RequestLog], "requests"),
+                (&[Copy], "copy"),
+                (&[Reload], "reload"),
+                (&[Quit], "quit"),
+            ],
+            Self::Columns => &[
+                (&[PreviousTab, NextTab], "tabs"),
+                (&[ScrollDown, ScrollUp], "select"),
+                (&[Search], "search"),
+                (&[Sort], "sort"),
+                (&[CycleOrder], "order"),
+                (&[Select], "details"),
+                (&[Quit], "quit"),
+            ],
+            Self::Stats => &[
+                (&[PreviousTab, NextTab], "tabs"),
+                (&[ScrollDown, ScrollUp], "select"),
+                (&[Select], "scan/sizes"),
+                (&[Histogram], "histogram"),
+                (&[Copy], "copy json"),
+                (&[Reload], "reload"),
+                (&[Quit], "quit"),
+            ],
+            Self::Map => &[
+                (&[PreviousTab, NextTab], "tabs"),
+                (&[Copy], "copy bbox"),
+                (&[Query], "query"),
+                (&[ZoomExtent], "extent"),
+                (&[ZoomIn, ZoomOut], "zoom"),
+                (&[Graticule], "grid"),
+                (&[ToggleResolution], "resolution"),
+                (&[SamplePoints], "sample"),
+                (&[SwapAxes], "swap axes"),
+                (&[ScrollLeft, ScrollDown, ScrollUp, ScrollRight], "pan"),
+                (&[Reload], "reload"),
+                (&[Quit], "quit"),
+            ],
+        };
+        hints
+            .iter()
+            .map(|(actions, label)| {
+                let keys: Vec<String> = actions.iter().map(|&action| keymap.key(action)).collect();
+                format!("{} {label}", keys.join("/"))
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+
+    /// Find which of `tabs` has its title rendered at the given terminal column of the tabs row
+    pub fn at_column(tabs: &[Self], column: u16) -> Option<Self> {
+        // the tabs block has a one cell border on the left, and ratatui pads
+        // each title with a space on both sides and separates titles with a divider
+        let mut x: u16 = 1;
+        for &tab in tabs {
+            let width = tab.title().chars().count() as u16 + 2;
+            if (x..x + width).contains(&column) {
+                return Some(tab);
+            }
+            x += width + 1;
+        }
+        None
     }
 }
 
-pub fn make_tabs(selected_tab: SelectedTab) -> impl Widget {
-    let tabs_titles = SelectedTab::titles();
-    Tabs::new(tabs_titles)
-        .select(selected_tab as usize)
+pub fn make_tabs(tabs: &[SelectedTab], selected_tab: SelectedTab, theme: &Theme) -> impl Widget {
+    Tabs::new(tabs.iter().map(|tab| tab.title()))
+        .select(tabs.iter().position(|&tab| tab == selected_tab))
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .title("Header Categories"),
         )
-        .style(Style::default().fg(Color::White))
-        .highlight_style(
-            Style::default()
-                .fg(Color::Blue)
-                .add_modifier(Modifier::BOLD)
-                .add_modifier(Modifier::UNDERLINED),
-        )
+        .style(theme.tabs)
+        .highlight_style(theme.selected_tab)
+}
+
+/// Height of the status footer at the bottom of the TUI
+pub const FOOTER_HEIGHT: u16 = 1;
+
+/// Shorten `text` to at most `max_width` terminal cells by replacing its middle with an
+/// ellipsis, which keeps both the scheme/host and the file name of a long path or URL visible
+pub fn elide_middle(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+    let graphemes: Vec<&str> = text.graphemes(true).collect();
+    // one cell is taken by the ellipsis; the end gets any odd cell since it holds the file name
+    let budget = max_width - 1;
+    let mut head_budget = budget / 2;
+    let mut tail_budget = budget - head_budget;
+
+    let mut head = String::new();
+    for grapheme in &graphemes {
+        let width = grapheme.width();
+        if width > head_budget {
+            break;
+        }
+        head_budget -= width;
+        head.push_str(grapheme);
+    }
+    let mut tail = Vec::new();
+    for grapheme in graphemes.iter().rev() {
+        let width = grapheme.width();
+        if width > tail_budget {
+            break;
+        }
+        tail_budget -= width;
+        tail.push(*grapheme);
+    }
+    tail.reverse();
+    format!("{head}…{}", tail.concat())
 }
 
-pub fn info_line(label: &str, value: &str) -> Line<'static> {
+/// The footer shown on every frame: the source and dataset name on the left, and either a
+/// transient message or the keybindings for the focused tab on the right
+pub fn status_line(
+    source: &str,
+    dataset_name: &str,
+    hints: &str,
+    message: Option<&str>,
+    width: u16,
+    theme: &Theme,
+) -> Line<'static> {
+    let width = width as usize;
+    let (right, right_style) = match message {
+        Some(message) => (message, theme.message),
+        None => (hints, theme.hints),
+    };
+    let right = truncate_with_ellipsis(right, width);
+    // keep at least one space between the two halves
+    let available = width.saturating_sub(right.width() + 1);
+
+    let name = if dataset_name.is_empty() {
+        String::new()
+    } else {
+        format!(" · {dataset_name}")
+    };
+    let (source, name) = if source.width() + name.width() <= available {
+        (source.to_string(), name)
+    } else if name.width() < available / 2 {
+        (elide_middle(source, available - name.width()), name)
+    } else {
+        (elide_middle(source, available), String::new())
+    };
+
+    let padding = width.saturating_sub(source.width() + name.width() + right.width());
     Line::from(vec![
-        Span::styled(
-            format!("{label}: "),
-            Style::default()
-                .fg(Color::Green)
-                .add_modifier(Modifier::BOLD),
-        ),
+        Span::styled(source, theme.source),
+        Span::raw(name),
+        Span::raw(" ".repeat(padding)),
+        Span::styled(right, right_style),
+    ])
+}
+
+/// The plain text of a line, without any styling
+pub fn line_text(line: &Line) -> String {
+    line.spans
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect()
+}
+
+pub fn info_line(theme: &Theme, label: &str, value: &str) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!("{label}: "), theme.label),
         Span::raw(value.to_string()),
     ])
 }
 
+/// Label and value lines with the labels padded to the same width so that the values line
+/// up. Values too long for `width` are wrapped with the rest indented under the value
+pub fn info_lines(theme: &Theme, pairs: &[(&str, &str)], width: usize) -> Vec<Line<'static>> {
+    let label_width = pairs
+        .iter()
+        .map(|(label, _)| label.width())
+        .max()
+        .unwrap_or(0);
+    // the colon and a space follow each label
+    let value_column = label_width + 2;
+    let value_width = width.saturating_sub(value_column).max(1);
+
+    let mut lines = Vec::new();
+    for (label, value) in pairs {
+        let padding = " ".repeat(label_width - label.width());
+        let mut rows = wrap_text(value, value_width).into_iter();
+        lines.push(Line::from(vec![
+            Span::styled(format!("{label}:{padding} "), theme.label),
+            Span::raw(rows.next().unwrap_or_default()),
+        ]));
+        lines.extend(rows.map(|row| Line::from(format!("{}{row}", " ".repeat(value_column)))));
+    }
+    lines
+}
+
+/// Greedily wrap `text` at whitespace into rows of at most `width` terminal cells,
+/// splitting words which are too long to fit on a row of their own
+pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut rows = Vec::new();
+    let mut row = String::new();
+    for word in text.split_whitespace() {
+        let separator = usize::from(!row.is_empty());
+        if row.width() + separator + word.width() <= width {
+            if separator == 1 {
+                row.push(' ');
+            }
+            row.push_str(word);
+            continue;
+        }
+        if !row.is_empty() {
+            rows.push(std::mem::take(&mut row));
+        }
+        for grapheme in word.graphemes(true) {
+            if row.width() + grapheme.width() > width && !row.is_empty() {
+                rows.push(std::mem::take(&mut row));
+            }
+            row.push_str(grapheme);
+        }
+    }
+    if !row.is_empty() || rows.is_empty() {
+        rows.push(row);
+    }
+    rows
+}
+
+/// Find the byte range of the first case-insensitive occurrence of `needle` in `haystack`
+pub fn find_case_insensitive(haystack: &str, needle: &str) -> Option<std::ops::Range<usize>> {
+    if needle.is_empty() {
+        return None;
+    }
+    haystack.char_indices().find_map(|(start, _)| {
+        let mut chars = haystack[start..].char_indices();
+        for n in needle.chars() {
+            match chars.next() {
+                Some((_, c)) if c.to_lowercase().eq(n.to_lowercase()) => {}
+                _ => return None,
+            }
+        }
+        let end = chars.next().map_or(haystack.len(), |(i, _)| start + i);
+        Some(start..end)
+    })
+}
+
+/// Render `text` with the first case-insensitive match of `query` emphasized
+pub fn highlight_match(text: String, query: &str, theme: &Theme) -> Line<'static> {
+    match find_case_insensitive(&text, query) {
+        Some(range) => Line::from(vec![
+            Span::raw(text[..range.start].to_string()),
+            Span::styled(text[range.clone()].to_string(), theme.search_match),
+            Span::raw(text[range.end..].to_string()),
+        ]),
+        None => Line::from(text),
+    }
+}
+
+/// Emphasize the first case-insensitive match of `query` within each span of `line`,
+/// keeping the rest of the line's styling
+pub fn highlight_line_match(line: Line<'static>, query: &str, theme: &Theme) -> Line<'static> {
+    if query.is_empty() {
+        return line;
+    }
+    let spans = line
+        .spans
+        .into_iter()
+        .flat_map(|span| match find_case_insensitive(&span.content, query) {
+            Some(range) => {
+                let content = span.content.as_ref();
+                vec![
+                    Span::styled(content[..range.start].to_string(), span.style),
+                    Span::styled(
+                        content[range.clone()].to_string(),
+                        span.style.patch(theme.search_match),
+                    ),
+                    Span::styled(content[range.end..].to_string(), span.style),
+                ]
+            }
+            None => vec![span],
+        })
+        .collect::<Vec<_>>();
+    Line::from(spans)
+}
+
+/// State for an incremental `/` search over a list of names
+#[derive(Default)]
+pub struct SearchState {
+    pub query: String,
+    /// Whether the user is currently typing into the search prompt
+    pub editing: bool,
+    /// The selection to go back to if the search is cancelled
+    restore_selection: Option<usize>,
+}
+
+impl SearchState {
+    pub fn start(&mut self, current_selection: Option<usize>) {
+        self.query.clear();
+        self.editing = true;
+        self.restore_selection = current_selection;
+    }
+
+    pub fn confirm(&mut self) {
+        self.editing = false;
+    }
+
+    /// Abandon the search, returning the selection from before it started
+    pub fn cancel(&mut self) -> Option<usize> {
+        self.query.clear();
+        self.editing = false;
+        self.restore_selection.take()
+    }
+
+    /// Find the index of the first name matching the query, starting at `start`
+    /// and wrapping around the end of the list in the given direction
+    pub fn find(&self, names: &[impl AsRef<str>], start: usize, forward: bool) -> Option<usize> {
+        let len = names.len();
+        (0..len)
+            .map(|offset| {
+                if forward {
+                    (start + offset) % len
+                } else {
+                    (start + len - offset) % len
+                }
+            })
+            .find(|&i| find_case_insensitive(names[i].as_ref(), &self.query).is_some())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColumnSortField {
+    #[default]
+    FileOrder,
+    Name,
+    Type,
+    Nullable,
+}
+
+impl ColumnSortField {
+    pub fn next(self) -> Self {
+        match self {
+            Self::FileOrder => Self::Name,
+            Self::Name => Self::Type,
+            Self::Type => Self::Nullable,
+            Self::Nullable => Self::FileOrder,
+        }
+    }
+
+    /// The shorter cycle of `o`, between the orders most worth switching between
+    pub fn next_order(self) -> Self {
+        match self {
+            Self::FileOrder => Self::Name,
+            Self::Name => Self::Type,
+            Self::Type | Self::Nullable => Self::FileOrder,
+        }
+    }
+
+    /// The header of the Columns table column this sorts by, if any
+    pub fn header(self) -> Option<&'static str> {
+        match self {
+            Self::FileOrder => None,
+            Self::Name => Some("Name"),
+            Self::Type => Some("Type"),
+            Self::Nullable => Some("Nullable"),
+        }
+    }
+
+    pub fn from_header(header: &str) -> Option<Self> {
+        [Self::Name, Self::Type, Self::Nullable]
+            .into_iter()
+            .find(|field| field.header() == Some(header))
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::FileOrder => "file order",
+            Self::Name => "name",
+            Self::Type => "type",
+            Self::Nullable => "nullable",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ColumnSort {
+    pub field: ColumnSortField,
+    pub descending: bool,
+}
+
+impl ColumnSort {
+    /// Indices into `columns` in the order they should be displayed; the sort is
+    /// stable so columns which compare equal keep their file order
+    pub fn order(&self, columns: &[flatgeobuf::Column]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..columns.len()).collect();
+        order.sort_by(|&a_index, &b_index| {
+            let (a, b) = (&columns[a_index], &columns[b_index]);
+            let ordering = match self.field {
+                ColumnSortField::FileOrder => a_index.cmp(&b_index),
+                ColumnSortField::Name => a.name().to_lowercase().cmp(&b.name().to_lowercase()),
+                ColumnSortField::Type => {
+                    format!("{:?}", a.type_()).cmp(&format!("{:?}", b.type_()))
+                }
+                ColumnSortField::Nullable => a.nullable().cmp(&b.nullable()),
+            };
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        order
+    }
+
+    /// Whether the columns are displayed in some order other than how they appear in the file
+    pub fn is_active(&self) -> bool {
+        self.field != ColumnSortField::FileOrder || self.descending
+    }
+
+    /// What clicking the header of `field` does: sort by it, then reverse,
+    /// then go back to file order
+    pub fn toggle(&mut self, field: ColumnSortField) {
+        *self = match (self.field == field, self.descending) {
+            (false, _) => Self {
+                field,
+                descending: false,
+            },
+            (true, false) => Self {
+                field,
+                descending: true,
+            },
+            (true, true) => Self::default(),
+        };
+    }
+
+    fn arrow(&self, symbols: &Symbols) -> &'static str {
+        if self.descending {
+            symbols.sort_descending
+        } else {
+            symbols.sort_ascending
+        }
+    }
+
+    /// The Columns table header `header`, marked with the sort direction if sorted by it
+    pub fn header_label(&self, header: &str, symbols: &Symbols) -> String {
+        if self.field.header() != Some(header) {
+            return header.to_string();
+        }
+        format!("{header} {}", self.arrow(symbols))
+    }
+
+    /// The sort for the title of the Columns table, with the same arrow as the header
+    pub fn description(&self, symbols: &Symbols) -> String {
+        format!("sorted by {} {}", self.field.label(), self.arrow(symbols))
+    }
+}
+
+/// Widest a Columns table cell can be before its value is truncated
+pub const MAX_CELL_WIDTH: usize = 40;
+
+/// Shorten `text` to at most `max_width` terminal cells, marking the cut with an ellipsis.
+/// Widths are measured per grapheme so wide characters are never split
+pub fn truncate_with_ellipsis(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for grapheme in text.graphemes(true) {
+        let width = grapheme.width();
+        // leave room for the ellipsis
+        if used + width + 1 > max_width {
+            break;
+        }
+        truncated.push_str(grapheme);
+        used += width;
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// Format a column width/precision/scale, which flatgeobuf sets to -1 when unspecified
+pub fn format_column_size(value: i32) -> String {
+    if value < 0 {
+        "—".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Every field of a flatgeobuf column, one per line, for the column detail popup
+pub fn column_detail_lines(column: &flatgeobuf::Column, theme: &Theme) -> Vec<Line<'static>> {
+    vec![
+        info_line(theme, "Name", column.name()),
+        info_line(theme, "Title", column.title().unwrap_or_default()),
+        info_line(theme, "Type", &format!("{:?}", column.type_())),
+        info_line(
+            theme,
+            "Description",
+            column.description().unwrap_or_default(),
+        ),
+        info_line(theme, "Width", &format_column_size(column.width())),
+        info_line(theme, "Precision", &format_column_size(column.precision())),
+        info_line(theme, "Scale", &format_column_size(column.scale())),
+        info_line(theme, "Nullable", &column.nullable().to_string()),
+        info_line(theme, "Unique", &column.unique().to_string()),
+        info_line(theme, "Primary Key", &column.primary_key().to_string()),
+        info_line(theme, "Metadata", column.metadata().unwrap_or_default()),
+    ]
+}
+
+/// Given the width of every table column, find which columns starting at `offset`
+/// fit within `available` cells; at least one column is always included
+pub fn visible_column_range(
+    widths: &[u16],
+    offset: usize,
+    available: u16,
+    spacing: u16,
+) -> std::ops::Range<usize> {
+    let start = offset.min(widths.len().saturating_sub(1));
+    let mut used: u16 = 0;
+    let mut end = start;
+    for (i, width) in widths.iter().enumerate().skip(start) {
+        let needed = if i == start { *width } else { width + spacing };
+        if i > start && used.saturating_add(needed) > available {
+            break;
+        }
+        used = used.saturating_add(needed);
+        end = i + 1;
+    }
+    start..end
+}
+
 pub struct Column<'a, T> {
     pub header: &'a str,
     pub value: Box<dyn Fn(&T) -> String + 'a>,
 }
 
 pub fn is_remote_file(file: &str) -> bool {
-    file.starts_with("http://") || file.starts_with("https://")
+    source::Scheme::of(file) != source::Scheme::Local
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> flatgeobuf::FgbReader<std::io::BufReader<std::fs::File>> {
+        let file = std::fs::File::open("testdata/colorado_subset.fgb").unwrap();
+        flatgeobuf::FgbReader::open(std::io::BufReader::new(file)).unwrap()
+    }
+
+    /// A serialized header with a string column for each name and description
+    fn header_with_columns(columns: &[(&str, &str)]) -> Vec<u8> {
+        let mut fbb = flatbuffers::FlatBufferBuilder::new();
+        let columns: Vec<_> = columns
+            .iter()
+            .map(|(name, description)| {
+                let args = flatgeobuf::ColumnArgs {
+                    name: Some(fbb.create_string(name)),
+                    type_: flatgeobuf::ColumnType::String,
+                    description: Some(fbb.create_string(description)),
+                    ..Default::default()
+                };
+                flatgeobuf::Column::create(&mut fbb, &args)
+            })
+            .collect();
+        let columns = fbb.create_vector(&columns);
+        let header = flatgeobuf::Header::create(
+            &mut fbb,
+            &flatgeobuf::HeaderArgs {
+                columns: Some(columns),
+                ..Default::default()
+            },
+        );
+        fbb.finish(header, None);
+        fbb.finished_data().to_vec()
+    }
+
+    #[test]
+    fn elide_middle_keeps_both_ends() {
+        let url = "https://example.com/data/2026/counties.fgb";
+        assert_eq!(elide_middle(url, 100), url);
+        let elided = elide_middle(url, 21);
+        assert_eq!(elided, "https://ex…unties.fgb");
+        assert_eq!(elided.width(), 21);
+        assert_eq!(elide_middle(url, 1), "…");
+        assert_eq!(elide_middle(url, 0), "");
+    }
+
+    #[test]
+    fn elide_middle_never_splits_wide_characters() {
+        // each of these takes two cells, so an odd budget leaves a cell unused
+        let elided = elide_middle("東京都東京都東京都.fgb", 10);
+        assert!(elided.width() <= 10);
+        assert!(elided.starts_with("東京") && elided.ends_with(".fgb"));
+    }
+
+    #[test]
+    fn status_line_fills_the_width() {
+        let theme = Theme::dark();
+        let line = status_line("counties.fgb", "Counties", "q quit", None, 40, &theme);
+        let text = line_text(&line);
+        assert_eq!(text.width(), 40);
+        assert!(text.starts_with("counties.fgb · Counties"));
+        assert!(text.ends_with("q quit"));
+    }
+
+    #[test]
+    fn status_line_shows_a_message_instead_of_hints() {
+        let theme = Theme::dark();
+        let line = status_line("a.fgb", "", "q quit", Some("copied!"), 30, &theme);
+        let text = line_text(&line);
+        assert!(text.ends_with("copied!"));
+        assert!(!text.contains("quit"));
+    }
+
+    #[test]
+    fn status_line_elides_a_long_source_before_the_hints() {
+        let theme = Theme::dark();
+        let source = "https://example.com/a/very/long/path/to/some/counties.fgb";
+        let line = status_line(source, "Counties", "q quit", None, 40, &theme);
+        let text = line_text(&line);
+        assert_eq!(text.width(), 40);
+        assert!(text.starts_with("https://"));
+        assert!(text.contains("…"));
+        assert!(text.contains("ties.fgb · Counties"));
+        assert!(text.ends_with("q quit"));
+    }
+
+    #[test]
+    fn info_lines_align_values_after_multibyte_labels() {
+        let theme = Theme::dark();
+        let lines = info_lines(&theme, &[("Nom", "a"), ("Réseau", "b"), ("名前", "c")], 80);
+        let texts: Vec<String> = lines.iter().map(line_text).collect();
+        assert_eq!(texts, ["Nom:    a", "Réseau: b", "名前:   c"]);
+        // the values start in the same terminal column however many bytes the labels are
+        let columns: Vec<usize> = texts.iter().map(|text| text.width() - 1).collect();
+        assert!(columns.iter().all(|&column| column == 8));
+    }
+
+    #[test]
+    fn info_lines_indent_wrapped_values_under_the_value() {
+        let theme = Theme::dark();
+        let lines = info_lines(&theme, &[("Name", "one two three four")], 14);
+        let texts: Vec<String> = lines.iter().map(line_text).collect();
+        assert_eq!(texts, ["Name: one two", "      three", "      four"]);
+    }
+
+    #[test]
+    fn wrap_text_splits_words_too_long_for_a_row() {
+        assert_eq!(wrap_text("abcdefgh ij", 3), ["abc", "def", "gh", "ij"]);
+        assert_eq!(wrap_text("", 10), [""]);
+    }
+
+    #[test]
+    fn columns_table_widths_match_sizing_every_frame() {
+        let fgb = fixture();
+        let columns: Vec<flatgeobuf::Column> = fgb.header().columns().unwrap().iter().collect();
+        let data = ColumnsTableData::new(&columns);
+        assert_eq!(data.cells.len(), columns.len());
+        // as the table was sized before the cells were kept: the longest of the header and
+        // the values of every column, within the cap and padded. Every character here takes
+        // one cell, so counting them gives the display width
+        let value = |column: &flatgeobuf::Column, header: &str| match header {
+            "Name" => column.name().to_string(),
+            "Title" => column.title().unwrap_or("—").to_string(),
+            "Type" => format!("{:?}", column.type_()),
+            "Description" => column.description().unwrap_or("—").to_string(),
+            "Nullable" => column.nullable().to_string(),
+            "Primary Key" => column.primary_key().to_string(),
+            "Unique" => column.unique().to_string(),
+            "Width" => format_column_size(column.width()),
+            "Precision" => format_column_size(column.precision()),
+            "Scale" => format_column_size(column.scale()),
+            "Metadata" => column.metadata().unwrap_or("—").to_string(),
+            other => panic!("unexpected table column {other}"),
+        };
+        for (i, header) in data.headers.iter().enumerate() {
+            let longest = columns
+                .iter()
+                .map(|column| value(column, header).chars().count())
+                .max()
+                .unwrap_or(0);
+            let expected = (header.chars().count().max(longest).min(MAX_CELL_WIDTH) + 2) as u16;
+            assert_eq!(data.widths[i], expected, "width of {header}");
+        }
+        // the extended view adds Title, Width, Precision, Scale and Metadata
+        assert_eq!(data.shown_fields(false).len(), 6);
+        assert_eq!(data.shown_fields(true).len(), data.headers.len());
+    }
+
+    #[test]
+    fn visible_window_at_the_top_middle_and_bottom() {
+        let mut state = ColumnsTableState::new();
+        // at the top, the window starts at the first row
+        assert_eq!(state.visible_window(1500, 20), 0..20);
+        // moving within the window doesn't scroll it
+        state.page_down(10, 1500);
+        assert_eq!(state.visible_window(1500, 20), 0..20);
+        // moving past the bottom of the window scrolls it just far enough
+        state.page_down(15, 1500);
+        assert_eq!(state.visible_window(1500, 20), 6..26);
+        // moving back up above it scrolls it up to the selection
+        state.page_up(24);
+        assert_eq!(state.visible_window(1500, 20), 1..21);
+        // at the bottom, the window ends at the last row
+        state.last(1500);
+        assert_eq!(state.visible_window(1500, 20), 1480..1500);
+        // and wraps back to the top with the selection
+        state.next(1500);
+        assert_eq!(state.visible_window(1500, 20), 0..20);
+    }
+
+    #[test]
+    fn visible_window_shorter_than_the_table() {
+        let mut state = ColumnsTableState::new();
+        state.last(5);
+        assert_eq!(state.visible_window(5, 20), 0..5);
+        assert_eq!(state.visible_window(0, 20), 0..0);
+    }
+
+    #[test]
+    fn columns_table_lines_up_wide_characters() {
+        use ratatui::{Terminal, backend::TestBackend, layout::Constraint, widgets::Table};
+
+        let long_description = "東京都の区市町村の境界線を含むデータセットです。".repeat(2);
+        let header = header_with_columns(&[
+            ("name", "plain"),
+            ("名前", "日本語"),
+            ("🌍 area", &long_description),
+        ]);
+        let header = flatgeobuf::root_as_header(&header).unwrap();
+        let columns: Vec<flatgeobuf::Column> = header.columns().unwrap().iter().collect();
+        let data = ColumnsTableData::new(&columns);
+
+        // sized by the cells the values take rather than their bytes
+        assert_eq!(data.widths[0], "🌍 area".width() as u16 + 2);
+        assert_eq!(data.widths[3], MAX_CELL_WIDTH as u16 + 2);
+        let truncated = &data.truncated_cells[2][3];
+        assert!(truncated.ends_with('…'));
+        assert!(truncated.width() <= MAX_CELL_WIDTH);
+
+        let fields = data.shown_fields(false);
+        let rows = data
+            .truncated_cells
+            .iter()
+            .map(|row| ratatui::widgets::Row::new(fields.iter().map(|&i| row[i].clone())));
+        let widths = fields.iter().map(|&i| Constraint::Length(data.widths[i]));
+        let mut terminal = Terminal::new(TestBackend::new(160, 3)).unwrap();
+        terminal
+            .draw(|f| f.render_widget(Table::new(rows, widths).column_spacing(1), f.area()))
+            .unwrap();
+
+        // every row's Type cell starts in the same terminal column, whatever came before it
+        let buffer = terminal.backend().buffer();
+        let type_column = data.widths[0] + 1;
+        for y in 0..3 {
+            assert_eq!(buffer[(type_column, y)].symbol(), "S", "row {y}");
+            assert_eq!(buffer[(type_column - 1, y)].symbol(), " ", "row {y}");
+        }
+        // and the truncated description ends within its own cell
+        let description_column = type_column + data.widths[2] + 1;
+        let ellipsis = description_column + truncated.width() as u16 - 1;
+        assert_eq!(buffer[(ellipsis, 2)].symbol(), "…");
+        assert!(ellipsis < description_column + data.widths[3]);
+    }
+
+    #[test]
+    fn cell_to_lon_lat_maps_cells_inside_the_borders() {
+        let bounds = MapBounds {
+            x: [-180.0, 180.0],
+            y: [-90.0, 90.0],
+        };
+        // 36 columns and 18 rows inside the borders, so each cell is 10 degrees
+        let area = Rect::new(5, 2, 38, 20);
+        assert_eq!(bounds.cell_to_lon_lat(area, 6, 3), (-175.0, 85.0));
+        assert_eq!(bounds.cell_to_lon_lat(area, 41, 20), (175.0, -85.0));
+        let (lon, lat) = bounds.cell_to_lon_lat(area, 24, 12);
+        assert!((lon - 5.0).abs() < 1e-9 && (lat + 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cell_to_lon_lat_clamps_drags_outside_the_canvas() {
+        let bounds = MapBounds {
+            x: [-110.0, -100.0],
+            y: [35.0, 45.0],
+        };
+        let area = Rect::new(0, 0, 12, 12);
+        // the borders and beyond land on the nearest cell of the canvas
+        assert_eq!(
+            bounds.cell_to_lon_lat(area, 0, 0),
+            bounds.cell_to_lon_lat(area, 1, 1)
+        );
+        assert_eq!(
+            bounds.cell_to_lon_lat(area, 200, 200),
+            bounds.cell_to_lon_lat(area, 10, 10)
+        );
+        let (lon, lat) = bounds.cell_to_lon_lat(area, 200, 0);
+        assert_eq!((lon, lat), (-100.5, 44.5));
+    }
+
+    #[test]
+    fn map_view_zooms_around_the_center() {
+        let mut view = MapView {
+            center: (-105.0, 39.0),
+            x_span: 8.0,
+            y_span: 4.0,
+        };
+        view.zoom(2.0);
+        assert_eq!(view.center, (-105.0, 39.0));
+        assert_eq!((view.x_span, view.y_span), (4.0, 2.0));
+        assert_eq!(view.zoom_level(), 90.0);
+        view.zoom(0.25);
+        assert_eq!((view.x_span, view.y_span), (16.0, 8.0));
+        // zooming in stops at the smallest span, keeping the aspect ratio
+        view.zoom(1e9);
+        assert_eq!(view.y_span, MapView::MIN_SPAN_DEGREES);
+        assert!((view.x_span / view.y_span - 2.0).abs() < 1e-9);
+        // and zooming out stops at the whole world
+        view.zoom(1e-9);
+        assert_eq!(view, MapView::WORLD);
+    }
+
+    #[test]
+    fn map_view_pans_by_a_fraction_of_the_view() {
+        let mut view = MapView {
+            center: (-105.0, 39.0),
+            x_span: 8.0,
+            y_span: 4.0,
+        };
+        view.pan(0.25, -0.5);
+        assert_eq!(view.center, (-103.0, 37.0));
+        assert_eq!(
+            view.bounds(),
+            MapBounds {
+                x: [-107.0, -99.0],
+                y: [35.0, 39.0],
+            }
+        );
+        // panning stops with the edge of the view at the edge of the world
+        view.pan(100.0, 100.0);
+        assert_eq!(view.center, (176.0, 88.0));
+        assert_eq!(view.bounds().x[1], 180.0);
+        assert_eq!(view.bounds().y[1], 90.0);
+    }
+
+    #[test]
+    fn map_view_around_widens_for_the_terminal_aspect() {
+        let bbox = projection::Bbox::new(-109.0, 37.0, -102.0, 41.0);
+        // 80 columns by 20 rows inside the borders is twice as wide as it is tall
+        let view = MapView::around(&bbox, Rect::new(0, 0, 82, 22));
+        assert_eq!(view.center, (-105.5, 39.0));
+        assert!((view.x_span - 2.0 * view.y_span).abs() < 1e-9);
+        // the bbox fits with room to spare on every side
+        let bounds = view.bounds();
+        assert!(bounds.x[0] < bbox.xmin && bbox.xmax < bounds.x[1]);
+        assert!(bounds.y[0] < bbox.ymin && bbox.ymax < bounds.y[1]);
+    }
+
+    #[test]
+    fn envelopes_are_rounded_to_their_size() {
+        let envelope = |xmin, ymin, xmax, ymax| {
+            pretty_envelope(&projection::Bbox::new(xmin, ymin, xmax, ymax))
+        };
+        // about ten degrees across
+        assert_eq!(
+            envelope(-124.4821, 32.5288, -114.1315, 42.0095),
+            "x -124.5 to -114.1, y 32.5 to 42.0"
+        );
+        assert_eq!(
+            envelope(-109.0603, 36.9925, -102.0415, 41.0034),
+            "x -109.06 to -102.04, y 36.99 to 41.00"
+        );
+        assert_eq!(
+            envelope(-180.0, -90.0, 180.0, 90.0),
+            "x -180 to 180, y -90 to 90"
+        );
+        // a city block, in metres of a projected CRS and in degrees
+        assert_eq!(
+            envelope(500_000.25, 4_649_776.75, 500_120.75, 4_649_900.0),
+            "x 500000 to 500121, y 4649777 to 4649900"
+        );
+        assert_eq!(
+            envelope(-105.28514, 40.01492, -105.28379, 40.01589),
+            "x -105.28514 to -105.28379, y 40.01492 to 40.01589"
+        );
+        // a single point has no size to go by
+        assert_eq!(
+            envelope(2.5, 48.75, 2.5, 48.75),
+            "x 2.500000 to 2.500000, y 48.750000 to 48.750000"
+        );
+    }
+
+    #[test]
+    fn geometry_types_in_words() {
+        use flatgeobuf::GeometryType;
+        assert_eq!(pretty_geometry_type(GeometryType::Unknown), "Any type");
+        assert_eq!(
+            pretty_geometry_type(GeometryType::MultiPolygon),
+            "Multi-polygon"
+        );
+        assert_eq!(
+            pretty_geometry_type(GeometryType::TIN),
+            "Triangulated irregular network"
+        );
+        // each known type has its own words, rather than falling back to its name
+        let words: std::collections::HashSet<String> = GeometryType::ENUM_VALUES
+            .iter()
+            .map(|&geometry_type| pretty_geometry_type(geometry_type))
+            .collect();
+        assert_eq!(words.len(), GeometryType::ENUM_VALUES.len());
+        assert!(words.iter().all(|words| !words.contains("UNKNOWN")));
+        // a type newer than flatgeobuf knows is shown as it is
+        assert_eq!(pretty_geometry_type(GeometryType(200)), "<UNKNOWN 200>");
+    }
+
+    #[test]
+    fn sort_arrows_follow_the_symbols() {
+        let sort = ColumnSort {
+            field: ColumnSortField::Name,
+            descending: true,
+        };
+        let (unicode, ascii) = (Symbols::unicode(), Symbols::ascii());
+        assert_eq!(sort.description(&unicode), "sorted by name ▼");
+        assert_eq!(sort.description(&ascii), "sorted by name v");
+        assert_eq!(sort.header_label("Name", &ascii), "Name v");
+        assert_eq!(sort.header_label("Type", &ascii), "Type");
+        let sort = ColumnSort {
+            descending: false,
+            ..sort
+        };
+        assert_eq!(sort.description(&unicode), "sorted by name ▲");
+        assert_eq!(sort.description(&ascii), "sorted by name ^");
+    }
 }
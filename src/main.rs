@@ -1,373 +1,681 @@
 // Copyright 2025 Colton Loftus
 // SPDX-License-Identifier: Apache-2.0
 
-use bytesize::ByteSize;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    cursor::Show,
+    event::{self, DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use fgbdump::{
-    Column, ColumnsTableState, SelectedTab, cli::Args, info_line, is_remote_file,
-    make_map_with_bbox_overlay, make_tabs, projection::Bbox,
-};
-use flatgeobuf::{FgbReader, HttpFgbReader};
-use ratatui::{
-    Terminal,
-    backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
-    symbols::scrollbar,
-    text::Line,
-    widgets::{
-        Block, Borders, Cell, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState,
-        Table,
+    Symbols, Theme,
+    app::{App, ViewOptions},
+    cache, check,
+    cli::{
+        Args, BboxFormat, Cache, CacheCommand, CheckCommand, Command, ExportCommand,
+        FeatureCommand, FeatureFormat, FeaturesCommand, FeaturesFormat, ReprojectBbox,
+        StatsCommand, StatsFormat, StdoutFormat,
+    },
+    config::Config,
+    dataset::{self, Dataset, IndexLayout, OpenOptions},
+    diagnostics::{self, Failure},
+    export::{
+        CrsSchema, CsvWriter, FeatureSink, FgbFileWriter, FgbSchema, Filtered, GeojsonWriter,
+        NdjsonWriter, Reprojected,
     },
+    http::{HttpOptions, TransferStats},
+    json_tree::JsonTree,
+    keymap::Keymap,
+    plain, progress,
+    projection::{Reprojection, crs_label, describe_crs},
+    terminal_prefers_ascii,
 };
-use reqwest::header::CONTENT_LENGTH;
+use futures_util::{StreamExt, stream};
+use ratatui::{Terminal, backend::CrosstermBackend};
 use std::{
-    fs::File,
-    io::{BufReader, stdout},
+    io::{BufWriter, Stdout, Write, stdout},
+    path::Path,
+    process::ExitCode,
+    sync::{Arc, atomic::AtomicUsize},
+    time::{Duration, Instant},
 };
 
-#[tokio::main(flavor = "current_thread")]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Args = argh::from_env();
-
-    if is_remote_file(&args.file) {
-        // Remote file: use HTTP HEAD to get content length
-        let client = reqwest::Client::new();
-        let resp = client.head(&args.file).send().await?;
-        let content_length = resp
-            .headers()
-            .get(CONTENT_LENGTH)
-            .and_then(|val| val.to_str().ok())
-            .and_then(|s| s.parse::<u64>().ok());
+/// Where the bearer token is read from when `--bearer-token` isn't given
+const TOKEN_VAR: &str = "FGBDUMP_TOKEN";
 
-        let fgb = HttpFgbReader::open(&args.file).await?;
-        let header = fgb.header();
-        if args.stdout {
-            println!("{:#?}", header);
-            return Ok(());
-        }
-        render_header_tui(&header, content_length)?;
-    } else {
-        // Local file: use metadata to get size
-        let metadata = std::fs::metadata(&args.file)?;
-        let file_size = metadata.len();
-
-        let mut filein = BufReader::new(File::open(&args.file)?);
-        let fgb = FgbReader::open(&mut filein)?;
-        let header = fgb.header();
-        if args.stdout {
-            println!("{:#?}", header);
-            return Ok(());
-        }
-        render_header_tui(&header, Some(file_size))?;
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => diagnostics::report(failure),
     }
-
-    Ok(())
 }
 
-fn render_header_tui(
-    header: &flatgeobuf::Header,
-    byte_size: Option<u64>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    enable_raw_mode()?;
-    let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
-
-    let mut selected_tab = SelectedTab::Metadata;
-
-    // Scroll state for Metadata tab
-    let mut metadata_scroll: usize = 0;
-    let mut metadata_scroll_state = ScrollbarState::default();
-
-    // Extract bbox; if it fails, no map can be rendered
-    // unclear if in the future i might want to handle this more gracefully
-    let envelope = header
-        .envelope()
-        .ok_or("No bbox extent envelope found in the flatgeobuf metadata")?;
-    let bbox = Bbox::from_flatgeobuf_envelope(&envelope)?;
-
-    let crs = header.crs().ok_or(
-        "No crs data found in the flatgeobuf metadata; no map can be rendered".to_string(),
-    )?;
-
-    let src_proj_crs_string = format!(
-        "{}:{}",
-        crs.org()
-            .ok_or("No crs 'org' data found in the flatgeobuf metadata; no map can be rendered")?,
-        crs.code()
-    );
-
-    let mut columns_table_state = ColumnsTableState::new();
-    let mut columns_scroll_state = ScrollbarState::default();
-
-    loop {
-        terminal.draw(|f| {
-            let size = f.area();
-
-            let tabs = make_tabs(selected_tab);
-            f.render_widget(
-                tabs,
-                Rect {
-                    x: 0,
-                    y: 0,
-                    width: size.width,
-                    height: 3,
-                },
-            );
-
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([Constraint::Length(3), Constraint::Min(0)])
-                .split(size);
-
-            let content_area = chunks[1];
-
-            match selected_tab {
-                SelectedTab::Metadata => {
-                    let column_count = header.columns().map(|c| c.len()).unwrap_or(0);
-                    let envelope = header
-                        .envelope()
-                        .map_or("Undefined".to_string(), |e| format!("{:?}", e));
+async fn run() -> Result<(), Failure> {
+    let mut args: Args = argh::from_env();
+    if args.quiet && args.verbose {
+        return Err("--quiet and --verbose contradict each other; pass only one".into());
+    }
+    diagnostics::set_quiet(args.quiet);
+
+    // the rest open a file, so they need the options for that below
+    let command = match args.command.take() {
+        Some(Command::ReprojectBbox(command)) => return reproject_bbox(command),
+        Some(Command::Cache(Cache {
+            command: CacheCommand::Clear(_),
+        })) => return clear_cache(),
+        command => command,
+    };
+
+    if args.files.is_empty() && command.is_none() {
+        return Err("No files were given; pass one or more paths or URLs".into());
+    }
 
-                    let index_node_size = match header.index_node_size() {
-                        0 => "No Spatial Index".to_string(),
-                        _ => format!("{}", header.index_node_size()),
-                    };
+    let token = args
+        .bearer_token
+        .take()
+        .or_else(|| std::env::var(TOKEN_VAR).ok())
+        .filter(|token| !token.trim().is_empty());
+    if args.insecure {
+        diagnostics::warn(
+            "--insecure turns off TLS certificate verification, so the servers of remote \
+             files can't be trusted to be who they say",
+        );
+    }
+    let http = HttpOptions {
+        timeout: Duration::from_secs(args.timeout),
+        retries: args.retries,
+        // printing retries would garble the TUI
+        verbose: args.verbose && args.stdout,
+        proxy: args.proxy.take(),
+        cache_dir: if args.no_cache {
+            None
+        } else {
+            cache::default_dir()
+        },
+        aws_region: args.aws_region.take(),
+        insecure: args.insecure,
+        cacert: args.cacert.take(),
+        allow_full_download: args.allow_full_download,
+        ..Default::default()
+    }
+    .with_headers(std::mem::take(&mut args.header), token.as_deref())?;
+    let open_options = OpenOptions {
+        mmap: args.mmap,
+        http,
+    };
+    match command {
+        Some(Command::Feature(command)) => return print_feature(command, open_options).await,
+        Some(Command::Features(command)) => return print_features(command, open_options).await,
+        Some(Command::Export(command)) => return export(command, open_options).await,
+        Some(Command::Stats(command)) => return print_stats(command, open_options).await,
+        Some(Command::Check(command)) => return print_check(command, open_options).await,
+        _ => {}
+    }
+    if args.stdout {
+        // dropping the printing future cancels any request in flight
+        tokio::select! {
+            result = print_stdout(&args, &open_options) => return result,
+            _ = tokio::signal::ctrl_c() => {
+                let _ = stdout().flush();
+                return Err(Failure::interrupted("Interrupted; the output above is incomplete"));
+            }
+        }
+    }
 
-                    let byte_size_str = byte_size.map(|s| ByteSize(s).to_string());
-                    let mut lines = vec![
-                        info_line("Name", header.name().unwrap_or("")),
-                        info_line(
-                            "File Size",
-                            byte_size_str.as_deref().unwrap_or("Unknown File Size"),
-                        ),
-                        info_line("Description", header.description().unwrap_or("")),
-                        info_line("Features", &header.features_count().to_string()),
-                        info_line("Bounds", &envelope),
-                        info_line("Geometry Type", &format!("{:?}", header.geometry_type())),
-                        info_line("Columns", &column_count.to_string()),
-                        info_line("Spatial Index R-Tree Node Size", &index_node_size),
-                    ];
+    let config = Config::load(args.config.as_deref())?;
+    let keymap = Keymap::new(&config.keys)?;
 
-                    for line in [
-                        Line::default(),
-                        info_line("Has M Dimension", &header.has_m().to_string()),
-                        info_line("Has Z Dimension", &header.has_z().to_string()),
-                        info_line("Has T Dimension", &header.has_t().to_string()),
-                        info_line("Has TM Dimension", &header.has_tm().to_string()),
-                    ] {
-                        lines.push(line);
-                    }
+    let mut theme = args
+        .theme
+        .with_map_colors(args.map_colors)
+        .with_map_marker(args.map_marker);
+    if args.ascii || terminal_prefers_ascii() {
+        theme.symbols = Symbols::ascii();
+    }
+    let options = ViewOptions {
+        sample_size: args.sample_size,
+        crs_checks: !args.no_crs_checks,
+        concurrency: args.concurrency.max(1),
+        open: open_options,
+    };
+    render_tui(&args.files, theme, keymap, options, !args.no_mouse)
+}
 
-                    if let Some(crs) = header.crs() {
-                        lines.push(Line::default());
-                        lines.push(info_line("CRS Code", &crs.code().to_string()));
-                        lines.push(info_line("CRS Name", crs.name().unwrap_or_default()));
-                        lines.push(info_line(
-                            "CRS Code String",
-                            crs.code_string().unwrap_or_default(),
-                        ));
-                        lines.push(info_line(
-                            "CRS Description",
-                            crs.description().unwrap_or_default(),
-                        ));
-                        lines.push(info_line("CRS Organization", crs.org().unwrap_or_default()));
-                        lines.push(info_line("CRS WKT", crs.wkt().unwrap_or_default()));
-                    } else {
-                        lines.push(info_line("CRS", "Undefined"));
+/// Print the header of each file, along with what fgbdump works out from it
+async fn print_stdout(args: &Args, open_options: &OpenOptions) -> Result<(), Failure> {
+    // opened concurrently, but printed in the order given
+    let mut opened = stream::iter(&args.files)
+        .map(|file| async move {
+            let started = Instant::now();
+            let transfer = Arc::new(TransferStats::default());
+            let dataset = Dataset::open(file, transfer.clone(), open_options.clone()).await;
+            (file, dataset, transfer, started.elapsed())
+        })
+        .buffered(args.concurrency.max(1));
+    let mut failed = 0;
+    while let Some((file, dataset, transfer, elapsed)) = opened.next().await {
+        if args.files.len() > 1 {
+            println!("{file}:");
+        }
+        // one file failing to open doesn't stop the others from printing
+        let dataset = match dataset {
+            Ok(dataset) => dataset,
+            Err(e) => {
+                diagnostics::error(&e);
+                failed += 1;
+                continue;
+            }
+        };
+        // null rather than the 0 in the header when the header doesn't say
+        let features_count = match dataset.features_count() {
+            Some(count) => count.into(),
+            None if args.count => {
+                let progress = AtomicUsize::new(0);
+                let mut options = open_options.clone();
+                options.http.version = dataset.version.clone();
+                let scan = dataset::count_features(file, &progress, transfer.clone(), options);
+                let label = format!("Counting the features of {file}");
+                match progress::report(&label, None, &progress, scan).await {
+                    Ok(count) => count.into(),
+                    Err(e) => {
+                        diagnostics::error(&e);
+                        failed += 1;
+                        serde_json::Value::Null
                     }
+                }
+            }
+            None => serde_json::Value::Null,
+        };
+        let mut warnings = dataset.column_name_problems().warnings();
+        if !args.no_crs_checks {
+            warnings.extend(dataset.crs_warnings());
+        }
+        match args.format {
+            StdoutFormat::Pretty => {
+                let features = match &features_count {
+                    serde_json::Value::Null => "unknown (pass --count to count them)".to_string(),
+                    count => count.to_string(),
+                };
+                let border = if args.ascii || terminal_prefers_ascii() {
+                    Symbols::ascii().table
+                } else {
+                    Symbols::unicode().table
+                };
+                for line in plain::header_text(&dataset, &features, &warnings, &border) {
+                    println!("{line}");
+                }
+            }
+            StdoutFormat::Debug => print_debug(&dataset, &features_count, &warnings),
+        }
+        if args.verbose {
+            for note in dataset.resolve_crs().1 {
+                diagnostics::note(&note);
+            }
+        }
+        // on stderr so that stdout stays the same as without --stats
+        if args.stats && dataset.transfer.is_some() {
+            diagnostics::note(&format!(
+                "{file}: {} in {:.1}s",
+                transfer.summary(),
+                elapsed.as_secs_f64()
+            ));
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        _ => Err(format!("{failed} of {} files failed to open", args.files.len()).into()),
+    }
+}
 
-                    lines.push(Line::default());
-                    lines.push(info_line(
-                        "Custom Metadata",
-                        &format!("{:?}", header.metadata()),
-                    ));
-
-                    let max_scroll = lines.len();
-
-                    metadata_scroll = metadata_scroll.min(max_scroll);
-                    metadata_scroll_state = metadata_scroll_state
-                        .content_length(max_scroll + 1)
-                        .position(metadata_scroll);
-
-                    let body = Paragraph::new(lines)
-                        .wrap(ratatui::widgets::Wrap { trim: true }) // enable wrapping
-                        .scroll((metadata_scroll as u16, 0))
-                        .block(Block::default().borders(Borders::ALL).title("Metadata"));
-
-                    f.render_widget(body, content_area);
+/// The header as Rust debug output, followed by what fgbdump works out from it as JSON,
+/// for `--format debug`
+fn print_debug(dataset: &Dataset, features_count: &serde_json::Value, warnings: &[String]) {
+    let header = dataset.header();
+    println!("{:#?}", header);
+    // the debug output escapes the metadata as a string, which is unreadable for JSON
+    if let Some(tree) = header.metadata().and_then(JsonTree::parse) {
+        println!("metadata: {:#}", tree.value());
+    }
+    let crs_resolved = match dataset.crs() {
+        Ok(crs) => match describe_crs(&crs) {
+            Ok(info) => serde_json::json!({
+                "name": info.name,
+                "type": info.kind,
+                "units": info.units,
+            }),
+            Err(e) => e.to_string().into(),
+        },
+        Err(e) => e.into(),
+    };
+    println!("crs_resolved: {crs_resolved:#}");
+    println!("features_count: {features_count}");
+    let index = dataset
+        .index_layout()
+        .map_or(serde_json::Value::Null, IndexLayout::to_json);
+    println!("index: {index:#}");
+    println!("warnings: {:#}", serde_json::json!(warnings));
+    // approximate, from the envelope projected to EPSG:4326 on a spherical earth
+    match dataset.map_envelope() {
+        Ok((bbox, _)) => {
+            let (width, height) = bbox.approx_dimensions_km(bbox.center().1);
+            let envelope_km = serde_json::json!({
+                "width": width,
+                "height": height,
+                "area": bbox.approx_area_km2(),
+            });
+            println!("envelope_km: {envelope_km:#}");
+        }
+        // a file without an envelope has nothing to project
+        Err(e) if dataset.envelope().is_ok() => println!("envelope_km: {e}"),
+        Err(_) => {}
+    }
+}
 
-                    f.render_stateful_widget(
-                        Scrollbar::new(ScrollbarOrientation::VerticalRight)
-                            .symbols(scrollbar::VERTICAL)
-                            .begin_symbol(Some("↑"))
-                            .end_symbol(Some("↓")),
-                        content_area,
-                        &mut metadata_scroll_state,
-                    );
-                }
+fn reproject_bbox(command: ReprojectBbox) -> Result<(), Failure> {
+    let bbox = command
+        .bbox
+        .reproject(&command.from, &command.to, command.densify)
+        .map_err(|e| format!("Failed to project the bbox: {e}"))?;
+    match command.format {
+        BboxFormat::Plain => println!("{bbox}"),
+        BboxFormat::Json => println!(
+            "{:#}",
+            serde_json::json!({
+                "xmin": bbox.xmin,
+                "ymin": bbox.ymin,
+                "xmax": bbox.xmax,
+                "ymax": bbox.ymax,
+            })
+        ),
+        BboxFormat::Geojson => println!("{:#}", bbox.to_geojson()),
+    }
+    Ok(())
+}
 
-                SelectedTab::Columns => {
-                    let columns_data = header.columns().unwrap_or_default();
+async fn print_feature(command: FeatureCommand, options: OpenOptions) -> Result<(), Failure> {
+    let transfer = Arc::new(TransferStats::default());
+    let feature = dataset::feature_at(&command.file, command.index, transfer, options).await?;
+    match command.format {
+        FeatureFormat::Geojson => println!("{:#}", feature.to_geojson()),
+        FeatureFormat::Wkt => println!("{}", feature.wkt().unwrap_or_default()),
+        FeatureFormat::Properties => {
+            println!(
+                "{:#}",
+                serde_json::Value::from(feature.properties.to_json())
+            )
+        }
+    }
+    Ok(())
+}
 
-                    let total_rows = columns_data.len();
+async fn print_features(command: FeaturesCommand, options: OpenOptions) -> Result<(), Failure> {
+    let file = &command.file;
+    let transfer = Arc::new(TransferStats::default());
+    let progress = AtomicUsize::new(0);
+    let label = format!("Reading {file}");
+    // written as they're read, so that a large file doesn't have to fit in memory
+    let out = BufWriter::new(stdout().lock());
+    let geometry = !command.no_geometry;
+    let mut sink: Box<dyn FeatureSink> = match command.format {
+        FeaturesFormat::Ndjson => Box::new(NdjsonWriter::new(out, command.select, geometry)),
+        FeaturesFormat::Csv => Box::new(CsvWriter::new(out, command.select, geometry)),
+    };
+    let scan = async {
+        let dataset = Dataset::open(file, transfer.clone(), options.clone()).await?;
+        let mut options = options;
+        options.http.version = dataset.version.clone();
+        // how many a bbox selects isn't known until they've been read
+        let total = match command.bbox {
+            Some(_) => None,
+            None => dataset.features_count().map(|count| count as usize),
+        };
+        let read = dataset::read_features(
+            file,
+            command.bbox.as_ref(),
+            &mut *sink,
+            &progress,
+            transfer,
+            options,
+        );
+        progress::report(&label, total, &progress, read).await
+    };
+    scan.await?;
+    Ok(())
+}
 
-                    const TABLE_CHROME_ROWS: u16 = 3; // top border + header + bottom border
-                    let visible_rows =
-                        content_area.height.saturating_sub(TABLE_CHROME_ROWS) as usize;
+/// Removes the output of an export when dropped, unless kept, so that one which fails
+/// before anything of use is written doesn't leave an empty file behind
+struct PartialOutput<'a> {
+    path: &'a Path,
+    keep: bool,
+}
 
-                    let max_scroll = total_rows.saturating_sub(visible_rows);
+impl Drop for PartialOutput<'_> {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = std::fs::remove_file(self.path);
+        }
+    }
+}
 
-                    let selected = columns_table_state.state.selected().unwrap_or(0);
-                    let scroll_pos = selected.min(max_scroll);
+async fn export(command: ExportCommand, options: OpenOptions) -> Result<(), Failure> {
+    let file = &command.file;
+    let output = command.output.display();
+    let extension = command
+        .output
+        .extension()
+        .and_then(|extension| extension.to_str());
+    let fgb = extension.is_some_and(|extension| extension.eq_ignore_ascii_case("fgb"));
+    if fgb && command.ndjson {
+        return Err("--ndjson writes GeoJSONSeq, which can't go in a .fgb file".into());
+    }
+    // creating the file only if it doesn't exist leaves no gap for another to appear
+    let created = if command.force {
+        std::fs::File::create(&command.output)
+    } else {
+        std::fs::File::create_new(&command.output)
+    };
+    let out = match created {
+        Ok(out) => BufWriter::new(out),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            return Err(format!("{output} already exists; pass --force to overwrite it").into());
+        }
+        Err(e) => return Err(format!("Failed to create {output}: {e}").into()),
+    };
+    // declared before the writer, so that it's dropped after the writer closes the file
+    let mut partial = PartialOutput {
+        path: &command.output,
+        keep: false,
+    };
+    let transfer = Arc::new(TransferStats::default());
+    let opened = async {
+        let dataset = Dataset::open(file, transfer.clone(), options.clone()).await?;
+        // projected once for the whole export, so a CRS PROJ doesn't know fails now
+        let reprojection = match &command.to_crs {
+            Some(to) => {
+                let from = dataset.crs().map_err(|e| {
+                    format!("The CRS of {file} isn't known, so it can't be projected: {e}")
+                })?;
+                let reprojection = Reprojection::new(&from, to)
+                    .map_err(|e| format!("Failed to project to {}: {e}", crs_label(to)))?;
+                Some(reprojection)
+            }
+            None => None,
+        };
+        Ok::<_, String>((dataset, reprojection))
+    };
+    // nothing has been written if this fails, so the output is removed
+    let (dataset, reprojection) = opened.await?;
+    // the one column which identifies each feature, if the file has one
+    if !command.select.is_empty()
+        && let Some(columns) = dataset.header().columns()
+    {
+        for column in columns.iter().filter(|column| column.primary_key()) {
+            if !command.select.iter().any(|name| name == column.name()) {
+                diagnostics::warn(&format!(
+                    "{} is the primary key of {file} but isn't selected, so the features \
+                     written can't be matched with those in the file",
+                    column.name()
+                ));
+            }
+        }
+    }
+    let crs = match &reprojection {
+        Some(reprojection) => Some(reprojection.to.clone()),
+        None => dataset.crs().ok(),
+    };
+    let mut writer: Box<dyn FeatureSink> = if fgb {
+        let mut schema = FgbSchema::of(&dataset.header());
+        if let Some(reprojection) = &reprojection {
+            schema.crs = CrsSchema::named(&reprojection.to);
+        }
+        Box::new(FgbFileWriter::new(out, command.select, schema))
+    } else if command.ndjson {
+        Box::new(NdjsonWriter::new(out, command.select, true))
+    } else {
+        Box::new(GeojsonWriter::new(out, command.select, crs))
+    };
+    let mut filtered = Filtered::new(command.conditions, &mut *writer);
+    let mut reprojected;
+    let sink: &mut dyn FeatureSink = match reprojection {
+        Some(reprojection) => {
+            reprojected = Reprojected::new(reprojection, &mut filtered);
+            &mut reprojected
+        }
+        None => &mut filtered,
+    };
+    let mut options = options;
+    options.http.version = dataset.version.clone();
+    let total = match command.bbox {
+        Some(_) => None,
+        None => dataset.features_count().map(|count| count as usize),
+    };
+    let progress = AtomicUsize::new(0);
+    let label = format!("Exporting {file}");
+    let read = dataset::read_features(
+        file,
+        command.bbox.as_ref(),
+        sink,
+        &progress,
+        transfer,
+        options,
+    );
+    // GeoJSON which fails part way is left for whoever wants what was written, but
+    // FlatGeobuf is only written once every feature has been read
+    partial.keep = !fgb;
+    if let Err(e) = progress::report(&label, total, &progress, read).await {
+        let incomplete = if fgb { "not written" } else { "incomplete" };
+        return Err(format!("{e}\n{output} is {incomplete}").into());
+    }
+    partial.keep = true;
+    diagnostics::note(&format!("Wrote {} features to {output}", filtered.matched));
+    Ok(())
+}
 
-                    columns_scroll_state = columns_scroll_state
-                        .content_length(max_scroll + 1)
-                        .position(scroll_pos);
+async fn print_stats(command: StatsCommand, options: OpenOptions) -> Result<(), Failure> {
+    let file = &command.file;
+    let value_columns = (!command.column.is_empty()).then_some(&command.column[..]);
+    let transfer = Arc::new(TransferStats::default());
+    // shared with the threads reading a local file
+    let progress = Arc::new(AtomicUsize::new(0));
+    let threads = command
+        .threads
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, std::num::NonZero::get));
+    let label = format!("Reading {file}");
+    let range = match (command.histogram_min, command.histogram_max) {
+        (Some(min), Some(max)) if min <= max => Some((min, max)),
+        (None, None) => None,
+        _ => return Err("Give both --histogram-min and --histogram-max, in order".into()),
+    };
+    let scan = async {
+        // the header says how many features to expect, for the progress
+        let dataset = Dataset::open(file, transfer.clone(), options.clone()).await?;
+        let mut options = options;
+        options.http.version = dataset.version.clone();
+        let total = dataset.features_count().map(|count| count as usize);
+        if command.geometry_breakdown {
+            let scan = dataset::scan_sample(file, command.sample, &progress, transfer, options);
+            let total = command.sample.map_or(total, |sample| {
+                Some(total.map_or(sample, |total| total.min(sample)))
+            });
+            let types = progress::report(&label, total, &progress, scan)
+                .await?
+                .types;
+            return Ok::<_, String>(match command.format {
+                StatsFormat::Table => {
+                    let mut lines = vec![format!(
+                        "Geometry types of {}; the header says {:?}",
+                        types.read(),
+                        types.declared
+                    )];
+                    lines.extend(types.lines());
+                    lines
+                }
+                StatsFormat::Json => vec![format!("{:#}", types.to_json())],
+            });
+        }
+        match &command.histogram {
+            Some(column) => {
+                let bins = command.bins.max(1);
+                let scan = dataset::scan_histogram(
+                    file, column, bins, range, &progress, transfer, options,
+                );
+                let histogram = progress::report(&label, total, &progress, scan).await?;
+                Ok(histogram.text(command.log))
+            }
+            None => {
+                let scan = dataset::scan_stats_parallel(
+                    file,
+                    &dataset,
+                    value_columns,
+                    threads,
+                    &progress,
+                    transfer,
+                    options,
+                );
+                let stats = progress::report(&label, total, &progress, scan).await?;
+                Ok(match command.format {
+                    StatsFormat::Table => {
+                        std::iter::once(format!("{} features", stats.features))
+                            .chain(stats.table())
+                            .chain(std::iter::once(String::new()))
+                            .chain(stats.geometry_table())
+                            .chain(stats.sortedness.as_ref().map(|sortedness| {
+                                format!("Spatially sorted: {}", sortedness.text())
+                            }))
+                            .collect()
+                    }
+                    StatsFormat::Json => vec![format!("{:#}", stats.to_json())],
+                })
+            }
+        }
+    };
+    let lines: Vec<String> = scan.await?;
+    for line in lines {
+        println!("{line}");
+    }
+    Ok(())
+}
 
-                    let columns: Vec<Column<_>> = vec![
-                        Column {
-                            header: "Name",
-                            value: Box::new(|c: &flatgeobuf::Column| c.name().to_string()),
-                        },
-                        Column {
-                            header: "Type",
-                            value: Box::new(|c| format!("{:?}", c.type_())),
-                        },
-                        Column {
-                            header: "Description",
-                            value: Box::new(|c| c.description().unwrap_or("—").to_string()),
-                        },
-                        Column {
-                            header: "Nullable",
-                            value: Box::new(|c| c.nullable().to_string()),
-                        },
-                        Column {
-                            header: "Primary Key",
-                            value: Box::new(|c| c.primary_key().to_string()),
-                        },
-                        Column {
-                            header: "Unique",
-                            value: Box::new(|c| c.unique().to_string()),
-                        },
-                    ];
+async fn print_check(command: CheckCommand, options: OpenOptions) -> Result<(), Failure> {
+    let file = &command.file;
+    let transfer = Arc::new(TransferStats::default());
+    let progress = AtomicUsize::new(0);
+    let label = format!("Checking {file}");
+    let total = (!command.full).then_some(check::SAMPLE_FEATURES);
+    let scan = check::check(
+        file,
+        command.full,
+        command.lenient,
+        &progress,
+        transfer,
+        options,
+    );
+    let report = progress::report(&label, total, &progress, scan).await?;
+    for note in &report.notes {
+        println!("{file}: {note}");
+    }
+    for warning in &report.warnings {
+        println!("{file}: warning: {warning}");
+    }
+    if report.problems.is_empty() {
+        println!("{file}: no problems found in the header or {}", report.read);
+        return Ok(());
+    }
+    for problem in &report.problems {
+        println!("{file}: {problem}");
+    }
+    Err(Failure::reported())
+}
 
-                    let header_cells = columns
-                        .iter()
-                        .map(|c| Cell::from(c.header))
-                        .collect::<Vec<_>>();
+fn clear_cache() -> Result<(), Failure> {
+    let Some(dir) = cache::default_dir() else {
+        return Err("No cache directory; neither XDG_CACHE_HOME nor HOME is set".into());
+    };
+    let removed = cache::clear(&dir)
+        .map_err(|e| format!("Failed to clear the cache in {}: {e}", dir.display()))?;
+    println!("Removed {removed} cached headers from {}", dir.display());
+    Ok(())
+}
 
-                    let table_header = Row::new(header_cells).height(1);
+/// Undo the terminal setup done before entering the TUI
+fn restore_terminal(mouse_enabled: bool) -> std::io::Result<()> {
+    disable_raw_mode()?;
+    if mouse_enabled {
+        execute!(stdout(), DisableMouseCapture)?;
+    }
+    execute!(stdout(), LeaveAlternateScreen, Show)
+}
 
-                    let rows = columns_data.iter().map(|c| {
-                        let cells = columns
-                            .iter()
-                            .map(|col| Cell::from((col.value)(&c)))
-                            .collect::<Vec<_>>();
-                        Row::new(cells).height(1)
-                    });
+/// Restores the terminal when dropped, so that returning early with `?`
+/// doesn't leave the terminal in raw mode
+struct TerminalGuard {
+    mouse_enabled: bool,
+}
 
-                    let widths = columns
-                        .iter()
-                        .map(|col| {
-                            let max_len = columns_data
-                                .iter()
-                                .map(|c| (col.value)(&c).len())
-                                .max()
-                                .unwrap_or(0);
-                            Constraint::Length((col.header.len().max(max_len) + 2) as u16)
-                        })
-                        .collect::<Vec<_>>();
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = restore_terminal(self.mouse_enabled);
+    }
+}
 
-                    let table = Table::new(rows, &widths)
-                        .header(table_header)
-                        .block(Block::default().borders(Borders::ALL).title(format!(
-                            "Columns (Focused {} of {})",
-                            selected + 1,
-                            total_rows
-                        )))
-                        .row_highlight_style(
-                            Style::default()
-                                .fg(Color::Yellow)
-                                .add_modifier(Modifier::BOLD),
-                        )
-                        .highlight_symbol(">> ");
+fn render_tui(
+    sources: &[String],
+    theme: Theme,
+    keymap: Keymap,
+    options: ViewOptions,
+    mouse_enabled: bool,
+) -> Result<(), Failure> {
+    // a panic while in raw mode would otherwise leave the terminal unusable
+    let default_panic_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal(mouse_enabled);
+        default_panic_hook(info);
+    }));
+
+    // Ctrl-C is a key press in raw mode, but a SIGINT from elsewhere would otherwise
+    // kill fgbdump without restoring the terminal. The TUI may be blocked waiting for a
+    // key, so this can't wait for it to return, and exits from here
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            let _ = restore_terminal(mouse_enabled);
+            std::process::exit(diagnostics::INTERRUPTED.into());
+        }
+    });
 
-                    f.render_stateful_widget(table, content_area, &mut columns_table_state.state);
-                    f.render_stateful_widget(
-                        Scrollbar::new(ScrollbarOrientation::VerticalRight)
-                            .symbols(scrollbar::VERTICAL)
-                            .begin_symbol(Some("↑"))
-                            .end_symbol(Some("↓")),
-                        content_area,
-                        &mut columns_scroll_state,
-                    );
-                }
+    let guard = TerminalGuard { mouse_enabled };
+    enable_raw_mode()?;
+    let mut stdout = stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    if mouse_enabled {
+        execute!(stdout, EnableMouseCapture)?;
+    }
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
 
-                SelectedTab::Map => {
-                    let (bbox, message) = bbox
-                        .project_to_ratatui_map_crs(&src_proj_crs_string)
-                        // unclear if this should unwrap and panic or try and handle the error
-                        .unwrap_or_else(|e| {
-                            (
-                                Bbox::new(0.0, 0.0, 0.0, 0.0),
-                                format!("Failed to project bbox: {e}"),
-                            )
-                        });
-                    let canvas = make_map_with_bbox_overlay(&message, &bbox);
-                    f.render_widget(canvas, content_area);
-                }
-            }
-        })?;
+    // problems with the file are shown inside the TUI; only terminal
+    // errors end up here, and the guard restores the terminal before they are reported
+    let mut app = App::new(sources, theme, keymap, options);
+    run_app(&mut terminal, &mut app)?;
+    // restored before main reports anything
+    drop(guard);
+    if app.interrupted {
+        return Err(Failure::interrupted(""));
+    }
+    Ok(())
+}
 
-        if let Event::Key(KeyEvent {
-            code,
-            kind: KeyEventKind::Press,
-            modifiers,
-            ..
-        }) = event::read()?
-        {
-            match code {
-                KeyCode::Right => selected_tab = selected_tab.next(),
-                KeyCode::Left => selected_tab = selected_tab.previous(),
-                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => break,
-                KeyCode::Char('c')
-                    if modifiers.contains(crossterm::event::KeyModifiers::CONTROL) =>
-                {
-                    break;
-                }
-                KeyCode::Down | KeyCode::Char('j') => match selected_tab {
-                    SelectedTab::Metadata => {
-                        metadata_scroll = metadata_scroll.saturating_add(1);
-                        metadata_scroll_state = metadata_scroll_state.position(metadata_scroll);
-                    }
-                    SelectedTab::Columns => {
-                        columns_table_state.next(header.columns().unwrap_or_default().len());
-                    }
-                    _ => {}
-                },
-                KeyCode::Up | KeyCode::Char('k') => match selected_tab {
-                    SelectedTab::Metadata => {
-                        metadata_scroll = metadata_scroll.saturating_sub(1);
-                        metadata_scroll_state = metadata_scroll_state.position(metadata_scroll);
-                    }
-                    SelectedTab::Columns => {
-                        columns_table_state.previous(header.columns().unwrap_or_default().len());
-                    }
-                    _ => {}
-                },
-                _ => {}
-            }
+fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    app: &mut App,
+) -> Result<(), Box<dyn std::error::Error>> {
+    while !app.quit {
+        terminal.draw(|f| app.draw(f))?;
+        // while loading, wake up regularly to animate the spinner and check on the fetch
+        if !app.is_loading() || event::poll(Duration::from_millis(100))? {
+            app.handle_event(event::read()?);
         }
+        app.poll_loading();
     }
-
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    terminal.show_cursor()?;
     Ok(())
 }
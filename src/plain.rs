@@ -0,0 +1,304 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use bytesize::ByteSize;
+use ratatui::symbols::line;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    ColumnsTableData, dataset::Dataset, json_tree::JsonTree, pretty_envelope, pretty_geometry_type,
+    projection::describe_crs, wkt,
+};
+
+/// The header of `dataset` as plain text for `--stdout`, in the same sections as the
+/// Metadata tab: the metadata and CRS with their labels aligned, and the columns as a
+/// table drawn with `border`. `features` is how many features there are, or why that
+/// isn't known, and `warnings` go at the end
+pub fn header_text(
+    dataset: &Dataset,
+    features: &str,
+    warnings: &[String],
+    border: &line::Set,
+) -> Vec<String> {
+    let header = dataset.header();
+    let mut envelope = dataset
+        .envelope()
+        .map_or("Undefined".to_string(), |bbox| pretty_envelope(&bbox));
+    if dataset
+        .envelope()
+        .is_ok_and(|bbox| bbox.crosses_antimeridian())
+    {
+        envelope.push_str(" (crosses antimeridian)");
+    }
+    // approximate, from the envelope projected to EPSG:4326 on a spherical earth
+    let envelope_km = dataset.map_envelope().ok().map(|(bbox, _)| {
+        let (width, height) = bbox.approx_dimensions_km(bbox.center().1);
+        format!(
+            "{width:.1} km by {height:.1} km, {:.1} km²",
+            bbox.approx_area_km2()
+        )
+    });
+    let file_size = dataset
+        .byte_size
+        .map_or("Unknown File Size".to_string(), |size| {
+            ByteSize(size).to_string()
+        });
+    let columns: Vec<_> = header
+        .columns()
+        .map(|columns| columns.iter().collect())
+        .unwrap_or_default();
+    let column_count = columns.len().to_string();
+    let geometry_type = pretty_geometry_type(header.geometry_type());
+    let index_node_size = match header.index_node_size() {
+        0 => "No Spatial Index".to_string(),
+        size => size.to_string(),
+    };
+    let index_layout = dataset.index_layout().map(|layout| {
+        format!(
+            "{} in {} levels of {} nodes",
+            ByteSize(layout.bytes),
+            layout.levels,
+            layout.nodes
+        )
+    });
+
+    let mut pairs = vec![
+        ("Name", header.name().unwrap_or("")),
+        ("File Size", &file_size),
+        ("Description", header.description().unwrap_or("")),
+        ("Features", features),
+        ("Bounds", &envelope),
+    ];
+    if let Some(envelope_km) = &envelope_km {
+        pairs.push(("Approximate Extent", envelope_km));
+    }
+    pairs.extend([
+        ("Geometry Type", geometry_type.as_str()),
+        ("Columns", &column_count),
+        ("Spatial Index R-Tree Node Size", &index_node_size),
+    ]);
+    if let Some(index_layout) = &index_layout {
+        pairs.push(("Spatial Index Size", index_layout));
+    }
+    let mut lines = labeled(&pairs);
+
+    lines.push(String::new());
+    lines.extend(labeled(&[
+        ("Has M Dimension", &header.has_m().to_string()),
+        ("Has Z Dimension", &header.has_z().to_string()),
+        ("Has T Dimension", &header.has_t().to_string()),
+        ("Has TM Dimension", &header.has_tm().to_string()),
+    ]));
+
+    if !columns.is_empty() {
+        let data = ColumnsTableData::new(&columns);
+        let fields = data.shown_fields(false);
+        let headers: Vec<&str> = fields.iter().map(|&i| data.headers[i]).collect();
+        let rows: Vec<Vec<&str>> = data
+            .truncated_cells
+            .iter()
+            .map(|row| fields.iter().map(|&i| row[i].as_str()).collect())
+            .collect();
+        lines.push(String::new());
+        lines.extend(table(&headers, &rows, border));
+    }
+
+    lines.push(String::new());
+    match header.crs() {
+        Some(crs) => {
+            let resolved = match dataset.crs() {
+                Ok(crs) => match describe_crs(&crs) {
+                    Ok(info) => format!("{} ({}, {})", info.name, info.kind, info.units),
+                    Err(e) => e.to_string(),
+                },
+                Err(e) => e,
+            };
+            let wkt = crs.wkt().filter(|wkt| !wkt.is_empty());
+            lines.extend(labeled(&[
+                ("CRS Code", &crs.code().to_string()),
+                ("CRS Name", crs.name().unwrap_or_default()),
+                ("CRS Code String", crs.code_string().unwrap_or_default()),
+                ("CRS Description", crs.description().unwrap_or_default()),
+                ("CRS Organization", crs.org().unwrap_or_default()),
+                ("CRS Resolved", &resolved),
+                ("CRS WKT", if wkt.is_some() { "" } else { "Undefined" }),
+            ]));
+            if let Some(wkt) = wkt {
+                lines.extend(wkt::pretty_print(wkt).into_iter().map(|line| {
+                    format!(
+                        "{}{}{}",
+                        "  ".repeat(line.depth + 1),
+                        line.keyword,
+                        line.rest
+                    )
+                }));
+            }
+        }
+        None => lines.push("CRS: Undefined".to_string()),
+    }
+
+    lines.push(String::new());
+    match header.metadata().and_then(JsonTree::parse) {
+        Some(tree) => {
+            lines.push("Custom Metadata:".to_string());
+            lines.extend(format!("{:#}", tree.value()).lines().map(str::to_string));
+        }
+        None => lines.extend(labeled(&[(
+            "Custom Metadata",
+            header.metadata().unwrap_or("Undefined"),
+        )])),
+    }
+
+    if !warnings.is_empty() {
+        lines.push(String::new());
+        lines.extend(warnings.iter().map(|warning| format!("Warning: {warning}")));
+    }
+    lines
+}
+
+/// Label and value lines with the labels padded to the same width so that the values
+/// line up, as [`crate::info_lines`] does in the TUI but without wrapping
+fn labeled(pairs: &[(&str, &str)]) -> Vec<String> {
+    let label_width = pairs
+        .iter()
+        .map(|(label, _)| label.width())
+        .max()
+        .unwrap_or(0);
+    pairs
+        .iter()
+        .map(|(label, value)| {
+            let padding = " ".repeat(label_width - label.width());
+            format!("{label}:{padding} {value}").trim_end().to_string()
+        })
+        .collect()
+}
+
+/// `rows` under `headers` in a grid drawn with `border`, with each column as wide as
+/// its widest cell
+fn table(headers: &[&str], rows: &[Vec<&str>], border: &line::Set) -> Vec<String> {
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            rows.iter()
+                .map(|row| row[i].width())
+                .fold(header.width(), usize::max)
+        })
+        .collect();
+    let rule = |left: &str, middle: &str, right: &str| {
+        let segments: Vec<String> = widths
+            .iter()
+            .map(|width| border.horizontal.repeat(width + 2))
+            .collect();
+        format!("{left}{}{right}", segments.join(middle))
+    };
+    let row = |cells: &[&str]| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!(" {cell}{} ", " ".repeat(width - cell.width())))
+            .collect();
+        format!("{0}{1}{0}", border.vertical, cells.join(border.vertical))
+    };
+    let mut lines = vec![
+        rule(border.top_left, border.horizontal_down, border.top_right),
+        row(headers),
+        rule(border.vertical_right, border.cross, border.vertical_left),
+    ];
+    lines.extend(rows.iter().map(|cells| row(cells)));
+    lines.push(rule(
+        border.bottom_left,
+        border.horizontal_up,
+        border.bottom_right,
+    ));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Symbols, dataset::OpenOptions};
+
+    /// What `fgbdump --stdout --ascii testdata/colorado_subset.fgb` prints
+    const SNAPSHOT: &str = include_str!("../testdata/colorado_subset.txt");
+
+    async fn colorado(border: &line::Set<'_>) -> Vec<String> {
+        let dataset = Dataset::open(
+            "testdata/colorado_subset.fgb",
+            Default::default(),
+            OpenOptions::default(),
+        )
+        .await
+        .unwrap();
+        header_text(&dataset, "370", &[], border)
+    }
+
+    #[tokio::test]
+    async fn header_text_matches_the_snapshot() {
+        let lines = colorado(&Symbols::ascii().table).await;
+        let expected: Vec<&str> = SNAPSHOT.lines().collect();
+        assert_eq!(lines, expected);
+    }
+
+    #[tokio::test]
+    async fn the_columns_table_is_drawn_with_the_border_given() {
+        let ascii = colorado(&Symbols::ascii().table).await;
+        let unicode = colorado(&Symbols::unicode().table).await;
+        assert_eq!(ascii.len(), unicode.len());
+        let top = unicode
+            .iter()
+            .position(|line| line.starts_with('┌'))
+            .unwrap();
+        assert!(ascii[top].starts_with("+-"));
+        assert!(unicode[top + 1].starts_with("│ Name "));
+        // the same cells, only the borders differ
+        assert_eq!(
+            ascii[top + 1].replace('|', ""),
+            unicode[top + 1].replace('│', "")
+        );
+    }
+
+    #[tokio::test]
+    async fn warnings_come_last() {
+        let dataset = Dataset::open(
+            "testdata/colorado_subset.fgb",
+            Default::default(),
+            OpenOptions::default(),
+        )
+        .await
+        .unwrap();
+        let warnings = ["the CRS may be mislabeled".to_string()];
+        let lines = header_text(&dataset, "370", &warnings, &Symbols::ascii().table);
+        assert_eq!(
+            lines.last().map(String::as_str),
+            Some("Warning: the CRS may be mislabeled")
+        );
+    }
+
+    #[test]
+    fn labels_are_padded_so_values_line_up() {
+        let lines = labeled(&[("Name", "counties"), ("Description", ""), ("Features", "3")]);
+        // an empty value leaves no trailing space
+        assert_eq!(
+            lines,
+            ["Name:        counties", "Description:", "Features:    3"]
+        );
+    }
+
+    #[test]
+    fn table_columns_are_as_wide_as_their_widest_cell() {
+        let rows = vec![vec!["é", "1"], vec!["long name", "22"]];
+        let lines = table(&["Name", "N"], &rows, &Symbols::ascii().table);
+        assert_eq!(
+            lines,
+            [
+                "+-----------+----+",
+                "| Name      | N  |",
+                "+-----------+----+",
+                "| é         | 1  |",
+                "| long name | 22 |",
+                "+-----------+----+",
+            ]
+        );
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    io::{IsTerminal, Write, stderr},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::diagnostics;
+
+/// How often the progress line is redrawn on a terminal
+const REDRAW_EVERY: Duration = Duration::from_millis(200);
+
+/// How often a progress line is logged when stderr isn't a terminal, such as in CI logs
+const LOG_EVERY: Duration = Duration::from_secs(10);
+
+/// Run `scan`, reporting the features it has counted in `count` on stderr while it runs.
+/// On a terminal this is one line redrawn in place and cleared at the end; otherwise a
+/// line is logged now and then, and once more when the scan ends. Nothing goes to stdout,
+/// so the output of the scan can still be piped. `total` is the number of features the
+/// scan is expected to read, if known, for a percentage and an estimate of the time left.
+/// With `--quiet`, nothing is written at all
+pub async fn report<T>(
+    label: &str,
+    total: Option<usize>,
+    count: &AtomicUsize,
+    scan: impl Future<Output = T>,
+) -> T {
+    if diagnostics::quiet() {
+        return scan.await;
+    }
+    let reporter = Reporter {
+        label,
+        // the header says 0 features when it wasn't known
+        total: total.filter(|&total| total > 0),
+        started: Instant::now(),
+        terminal: stderr().is_terminal(),
+    };
+    let mut ticks = tokio::time::interval(if reporter.terminal {
+        REDRAW_EVERY
+    } else {
+        LOG_EVERY
+    });
+    // the first tick is immediate, and nothing has been read yet
+    ticks.tick().await;
+    let mut scan = std::pin::pin!(scan);
+    loop {
+        tokio::select! {
+            output = &mut scan => {
+                reporter.finish(count.load(Ordering::Relaxed));
+                return output;
+            }
+            _ = ticks.tick() => reporter.print(count.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+struct Reporter<'a> {
+    label: &'a str,
+    total: Option<usize>,
+    started: Instant,
+    terminal: bool,
+}
+
+impl Reporter<'_> {
+    fn print(&self, count: usize) {
+        let line = self.line(count);
+        let mut stderr = stderr().lock();
+        // a progress line which can't be written isn't worth failing the scan for
+        let _ = if self.terminal {
+            // clear whatever is left of a longer previous line
+            write!(stderr, "\r{line}\x1b[K")
+        } else {
+            writeln!(stderr, "{line}")
+        };
+        let _ = stderr.flush();
+    }
+
+    fn finish(&self, count: usize) {
+        let mut stderr = stderr().lock();
+        let _ = if self.terminal {
+            write!(stderr, "\r\x1b[K")
+        } else {
+            writeln!(
+                stderr,
+                "{}: {count} features in {:.1}s",
+                self.label,
+                self.started.elapsed().as_secs_f64()
+            )
+        };
+        let _ = stderr.flush();
+    }
+
+    /// Like `Scanning x.fgb: 12000 of 48000 features (25%), 4000/s, 9s left`
+    fn line(&self, count: usize) -> String {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let rate = if elapsed > 0.0 {
+            count as f64 / elapsed
+        } else {
+            0.0
+        };
+        let mut line = format!("{}: {count}", self.label);
+        if let Some(total) = self.total {
+            let percent = (count as f64 / total as f64 * 100.0).min(100.0);
+            line += &format!(" of {total} features ({percent:.0}%)");
+        } else {
+            line += " features";
+        }
+        line += &format!(", {rate:.0}/s");
+        if let Some(total) = self.total
+            && rate > 0.0
+        {
+            let left = total.saturating_sub(count) as f64 / rate;
+            line += &format!(", {} left", format_eta(left));
+        }
+        line
+    }
+}
+
+/// `left` seconds as `9s`, `3m 20s`, or `1h 05m`
+fn format_eta(left: f64) -> String {
+    let secs = left.round() as u64;
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
@@ -1,11 +1,281 @@
 // Copyright 2026 Colton Loftus
 // SPDX-License-Identifier: Apache-2.0
 
-use proj::Proj;
+#[cfg(feature = "proj")]
+use proj::{Proj as Transform, ProjCreateError as CreateError, ProjError as ConvertError};
+#[cfg(not(feature = "proj"))]
+use without_proj::{ConvertError, CreateError, Transform};
 
 pub const RATATUI_MAP_CRS: &str = "EPSG:4326";
 
-#[derive(Clone)]
+/// How many points along each edge of a bbox are projected to find its extent in another CRS
+pub const DEFAULT_POINTS_PER_EDGE: usize = 21;
+
+/// The mean radius of the earth
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// Whether data in `crs` can be drawn on the map as is. OGC:CRS84 is EPSG:4326
+/// with the longitude first, the order the map uses for both
+pub fn is_map_crs(crs: &str) -> bool {
+    matches!(crs, RATATUI_MAP_CRS | "OGC:CRS84")
+}
+
+/// A short name for a CRS to show in titles, since a CRS given as WKT
+/// would fill the screen; it is the name WKT starts with, like `PROJCS["NAD83 / UTM zone 15N",...`
+pub fn crs_label(crs: &str) -> &str {
+    match crs.split_once('[') {
+        Some((_, rest)) => rest
+            .strip_prefix('"')
+            .and_then(|rest| rest.split_once('"'))
+            .map_or("WKT CRS", |(name, _)| name),
+        None => crs,
+    }
+}
+
+/// Why a bbox couldn't be projected
+#[derive(Debug)]
+pub enum ProjectionError {
+    /// PROJ doesn't recognize the CRS, or can't transform between it and the target CRS
+    UnknownCrs { crs: String, source: CreateError },
+    /// The transform exists but failed for these coordinates, such as for points
+    /// outside the area the CRS is defined for
+    Conversion { crs: String, source: ConvertError },
+    /// Built without the `proj` feature, so only data already in the map CRS can be drawn
+    Unavailable { crs: String },
+}
+
+impl std::fmt::Display for ProjectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // an authority code rather than WKT
+            Self::UnknownCrs { crs, source } if crs_label(crs) == crs => {
+                write!(f, "{crs} not found in the PROJ database: {source}")
+            }
+            Self::UnknownCrs { crs, source } => {
+                write!(f, "unknown CRS {}: {source}", crs_label(crs))
+            }
+            Self::Conversion { crs, source } => {
+                write!(f, "failed to convert from {}: {source}", crs_label(crs))
+            }
+            Self::Unavailable { crs } => write!(
+                f,
+                "reprojection of {} unavailable — built without proj",
+                crs_label(crs)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProjectionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::UnknownCrs { source, .. } => Some(source),
+            Self::Conversion { source, .. } => Some(source),
+            Self::Unavailable { .. } => None,
+        }
+    }
+}
+
+/// Stand-ins for the PROJ types when built without the `proj` feature, which can't be
+/// created, so that the code using them still compiles and never gets to run
+#[cfg(not(feature = "proj"))]
+mod without_proj {
+    #[derive(Debug)]
+    pub enum Transform {}
+
+    #[derive(Debug)]
+    pub enum CreateError {}
+
+    #[derive(Debug)]
+    pub enum ConvertError {}
+
+    impl Transform {
+        pub fn convert(&self, _: (f64, f64)) -> Result<(f64, f64), ConvertError> {
+            match *self {}
+        }
+
+        pub fn convert_array<'a>(
+            &self,
+            _: &'a mut [(f64, f64)],
+        ) -> Result<&'a mut [(f64, f64)], ConvertError> {
+            match *self {}
+        }
+    }
+
+    impl std::fmt::Display for CreateError {
+        fn fmt(&self, _: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match *self {}
+        }
+    }
+
+    impl std::error::Error for CreateError {}
+
+    impl std::fmt::Display for ConvertError {
+        fn fmt(&self, _: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match *self {}
+        }
+    }
+
+    impl std::error::Error for ConvertError {}
+}
+
+/// A transform between two CRSs which takes and gives coordinates in the traditional GIS
+/// order of longitude then latitude, or easting then northing. EPSG defines latitude
+/// first for CRSs like EPSG:4326 and EPSG:4269, which would otherwise swap x and y;
+/// `new_known_crs` normalizes the axis order for visualization, so always go through here
+fn gis_order_transform(from: &str, to: &str) -> Result<Transform, ProjectionError> {
+    // the one of the two which isn't the map CRS, for errors
+    let crs = if is_map_crs(from) { to } else { from }.to_string();
+    #[cfg(feature = "proj")]
+    return Transform::new_known_crs(from, to, None).map_err(|source| {
+        // blame whichever of the two PROJ doesn't know, if only one
+        let crs = match (is_map_crs(from), is_map_crs(to)) {
+            (false, false) if Transform::new(from).is_ok() => to.to_string(),
+            _ => crs,
+        };
+        ProjectionError::UnknownCrs { crs, source }
+    });
+    #[cfg(not(feature = "proj"))]
+    return Err(ProjectionError::Unavailable { crs });
+}
+
+/// A transform from one CRS to another, created once and used for every position of an
+/// export rather than once per feature
+pub struct Reprojection {
+    /// `None` when both are the same CRS, which needs nothing done
+    transform: Option<Transform>,
+    pub from: String,
+    pub to: String,
+}
+
+impl Reprojection {
+    pub fn new(from: &str, to: &str) -> Result<Self, ProjectionError> {
+        let transform = if from == to || (is_map_crs(from) && is_map_crs(to)) {
+            None
+        } else {
+            Some(gis_order_transform(from, to)?)
+        };
+        Ok(Self {
+            transform,
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    }
+
+    /// Project one position in GIS order. Only x and y are projected, so anything
+    /// else the position has, like a Z, is left as it is
+    pub fn convert(&self, position: (f64, f64)) -> Result<(f64, f64), ProjectionError> {
+        match &self.transform {
+            Some(transform) => {
+                transform
+                    .convert(position)
+                    .map_err(|source| ProjectionError::Conversion {
+                        crs: self.from.clone(),
+                        source,
+                    })
+            }
+            None => Ok(position),
+        }
+    }
+}
+
+/// `crs` as a URN like `urn:ogc:def:crs:EPSG::3857` if it's an authority code, for
+/// naming it in GeoJSON, or as it is otherwise
+pub fn crs_urn(crs: &str) -> String {
+    match crs.split_once(':') {
+        Some((authority, code))
+            if !authority.is_empty()
+                && !code.is_empty()
+                && !crs.contains(char::is_whitespace)
+                && !code.contains(':') =>
+        {
+            format!("urn:ogc:def:crs:{authority}::{code}")
+        }
+        _ => crs.to_string(),
+    }
+}
+
+/// Check that `crs` can be projected to the ratatui map crs (EPSG:4326)
+pub fn check_crs(crs: &str) -> Result<(), ProjectionError> {
+    if is_map_crs(crs) {
+        return Ok(());
+    }
+    gis_order_transform(crs, RATATUI_MAP_CRS).map(|_| ())
+}
+
+/// What the PROJ database says about a CRS
+#[derive(Clone, Debug)]
+pub struct CrsInfo {
+    pub name: String,
+    /// `geographic`, `geocentric`, or `projected`
+    pub kind: &'static str,
+    pub units: String,
+}
+
+/// Look up `crs` in the PROJ database
+#[cfg(feature = "proj")]
+pub fn describe_crs(crs: &str) -> Result<CrsInfo, ProjectionError> {
+    let info = Transform::new(crs)
+        .map_err(|source| ProjectionError::UnknownCrs {
+            crs: crs.to_string(),
+            source,
+        })?
+        .proj_info();
+    // the PROJ string of the CRS, like `proj=utm zone=15 datum=NAD83 units=m no_defs`
+    let definition = info.definition.unwrap_or_default();
+    let param = |key: &str| {
+        definition
+            .split_whitespace()
+            .find_map(|param| param.trim_start_matches('+').strip_prefix(key))
+    };
+    let kind = match param("proj=") {
+        Some("longlat" | "latlong") => "geographic",
+        Some("geocent") => "geocentric",
+        _ => "projected",
+    };
+    let units = match (param("units="), kind) {
+        (Some(units), _) => units.to_string(),
+        (None, "geographic") => "degrees".to_string(),
+        (None, _) => "unknown".to_string(),
+    };
+    Ok(CrsInfo {
+        name: info
+            .description
+            .unwrap_or_else(|| crs_label(crs).to_string()),
+        kind,
+        units,
+    })
+}
+
+/// Without the PROJ database, only the map CRS is known
+#[cfg(not(feature = "proj"))]
+pub fn describe_crs(crs: &str) -> Result<CrsInfo, ProjectionError> {
+    if !is_map_crs(crs) {
+        return Err(ProjectionError::Unavailable {
+            crs: crs.to_string(),
+        });
+    }
+    Ok(CrsInfo {
+        name: "WGS 84".to_string(),
+        kind: "geographic",
+        units: "degrees".to_string(),
+    })
+}
+
+/// The longitudes and latitudes PROJ says `crs` is meant for, if it knows
+#[cfg(feature = "proj")]
+pub fn area_of_use(crs: &str) -> Option<Bbox> {
+    let (area, _name) = Transform::new(crs).ok()?.area_of_use().ok()?;
+    area.map(|area| Bbox::new(area.west, area.south, area.east, area.north))
+}
+
+/// Without the PROJ database, no area of use is known
+#[cfg(not(feature = "proj"))]
+pub fn area_of_use(_crs: &str) -> Option<Bbox> {
+    None
+}
+
+#[derive(Clone, Debug)]
 pub struct Bbox {
     pub xmin: f64,
     pub ymin: f64,
@@ -13,6 +283,32 @@ pub struct Bbox {
     pub ymax: f64,
 }
 
+/// Parses `xmin,ymin,xmax,ymax`
+impl std::str::FromStr for Bbox {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let values = text
+            .split(',')
+            .map(|value| value.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("invalid bbox '{text}': {e}"))?;
+        match values[..] {
+            [xmin, ymin, xmax, ymax] => Ok(Self::new(xmin, ymin, xmax, ymax)),
+            _ => Err(format!(
+                "invalid bbox '{text}'; expected four numbers as xmin,ymin,xmax,ymax"
+            )),
+        }
+    }
+}
+
+/// Formats as `xmin,ymin,xmax,ymax`
+impl std::fmt::Display for Bbox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},{},{}", self.xmin, self.ymin, self.xmax, self.ymax)
+    }
+}
+
 impl Bbox {
     pub fn new(xmin: f64, ymin: f64, xmax: f64, ymax: f64) -> Self {
         Self {
@@ -23,6 +319,197 @@ impl Bbox {
         }
     }
 
+    /// Check that the bbox is within the longitudes and latitudes of EPSG:4326
+    pub fn validate_geographic(&self) -> Result<(), String> {
+        for (label, value, limit) in [
+            ("xmin", self.xmin, 180.0),
+            ("ymin", self.ymin, 90.0),
+            ("xmax", self.xmax, 180.0),
+            ("ymax", self.ymax, 90.0),
+        ] {
+            if !(-limit..=limit).contains(&value) {
+                return Err(format!("{label} must be between -{limit} and {limit}"));
+            }
+        }
+        Ok(())
+    }
+
+    /// The bbox limited to the longitudes and latitudes of EPSG:4326, with values
+    /// which aren't numbers at all taken as reaching the limit
+    pub fn clamp_geographic(&self) -> Self {
+        let clamp = |value: f64, limit: f64, fallback: f64| {
+            if value.is_nan() {
+                fallback
+            } else {
+                value.clamp(-limit, limit)
+            }
+        };
+        Self::new(
+            clamp(self.xmin, 180.0, -180.0),
+            clamp(self.ymin, 90.0, -90.0),
+            clamp(self.xmax, 180.0, 180.0),
+            clamp(self.ymax, 90.0, 90.0),
+        )
+    }
+
+    /// Whether the bbox wraps around the antimeridian, which envelopes
+    /// show with an xmin further east than the xmax
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.xmin > self.xmax
+    }
+
+    /// How far the bbox spans east to west, the short way across the antimeridian if it crosses it
+    pub fn width(&self) -> f64 {
+        if self.crosses_antimeridian() {
+            self.xmax + 360.0 - self.xmin
+        } else {
+            self.xmax - self.xmin
+        }
+    }
+
+    pub fn height(&self) -> f64 {
+        self.ymax - self.ymin
+    }
+
+    pub fn area(&self) -> f64 {
+        self.width() * self.height()
+    }
+
+    /// Whether the point is within the bbox or on its edge
+    pub fn contains_point(&self, x: f64, y: f64) -> bool {
+        let within_x = if self.crosses_antimeridian() {
+            x >= self.xmin || x <= self.xmax
+        } else {
+            (self.xmin..=self.xmax).contains(&x)
+        };
+        within_x && (self.ymin..=self.ymax).contains(&y)
+    }
+
+    /// Whether the two bboxes share any area, or touch, either of them crossing the antimeridian
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.split_at_antimeridian().iter().any(|a| {
+            other.split_at_antimeridian().iter().any(|b| {
+                a.xmin <= b.xmax && b.xmin <= a.xmax && a.ymin <= b.ymax && b.ymin <= a.ymax
+            })
+        })
+    }
+
+    /// The smallest bbox around both, which crosses the antimeridian
+    /// if that is the shorter way to reach from one to the other
+    pub fn union(&self, other: &Self) -> Self {
+        let (ymin, ymax) = (self.ymin.min(other.ymin), self.ymax.max(other.ymax));
+        // from the west edge of one east to the east edge of the other, or around both
+        // without crossing, whichever is shortest of the ways which cover both
+        let covers = |outer: &Self, inner: &Self| {
+            outer.width() >= inner.width()
+                && outer.contains_point(inner.xmin, outer.ymin)
+                && outer.contains_point(inner.xmax, outer.ymin)
+        };
+        let mut candidates = vec![
+            Self::new(self.xmin, ymin, other.xmax, ymax),
+            Self::new(other.xmin, ymin, self.xmax, ymax),
+        ];
+        if !self.crosses_antimeridian() && !other.crosses_antimeridian() {
+            candidates.push(Self::new(
+                self.xmin.min(other.xmin),
+                ymin,
+                self.xmax.max(other.xmax),
+                ymax,
+            ));
+        }
+        candidates
+            .into_iter()
+            .filter(|candidate| covers(candidate, self) && covers(candidate, other))
+            .min_by(|a, b| a.width().total_cmp(&b.width()))
+            .unwrap_or(Self::new(-180.0, ymin, 180.0, ymax))
+    }
+
+    /// The bbox grown on every side by `fraction` of its width and height
+    pub fn expand_by_fraction(&self, fraction: f64) -> Self {
+        let (dx, dy) = (self.width() * fraction, self.height() * fraction);
+        Self::new(
+            self.xmin - dx,
+            self.ymin - dy,
+            self.xmax + dx,
+            self.ymax + dy,
+        )
+    }
+
+    /// The bbox as one or, if it crosses the antimeridian, two bboxes
+    /// which don't, on either side of it
+    pub fn split_at_antimeridian(&self) -> Vec<Self> {
+        if self.crosses_antimeridian() {
+            vec![
+                Self::new(self.xmin, self.ymin, 180.0, self.ymax),
+                Self::new(-180.0, self.ymin, self.xmax, self.ymax),
+            ]
+        } else {
+            vec![self.clone()]
+        }
+    }
+
+    /// The width and height in kilometers of a bbox in EPSG:4326, with the width
+    /// measured along `center_lat`. Treats the earth as a sphere, which is off by up to
+    /// about 0.5% from the ellipsoid, and measures the width along a parallel rather
+    /// than a great circle, so wide boxes far from the equator come out wider than the
+    /// shortest distance across them. Latitudes past the poles are taken as the poles
+    pub fn approx_dimensions_km(&self, center_lat: f64) -> (f64, f64) {
+        let km_per_degree = EARTH_RADIUS_KM.to_radians();
+        let (ymin, ymax) = (self.ymin.clamp(-90.0, 90.0), self.ymax.clamp(-90.0, 90.0));
+        let parallel_scale = center_lat.clamp(-90.0, 90.0).to_radians().cos();
+        (
+            self.width().min(360.0) * km_per_degree * parallel_scale,
+            (ymax - ymin) * km_per_degree,
+        )
+    }
+
+    /// The area in square kilometers of a bbox in EPSG:4326, on a spherical earth, which
+    /// is off by up to about 0.5% from the ellipsoid. Latitudes past the poles are
+    /// taken as the poles, and widths across the antimeridian go the short way round
+    pub fn approx_area_km2(&self) -> f64 {
+        let sin_lat = |lat: f64| lat.clamp(-90.0, 90.0).to_radians().sin();
+        let band = (sin_lat(self.ymax) - sin_lat(self.ymin)).abs();
+        EARTH_RADIUS_KM.powi(2) * self.width().min(360.0).to_radians() * band
+    }
+
+    /// Whether the bbox looks like it is in EPSG:4326 with latitude and longitude swapped,
+    /// with a y beyond the poles but every x within the range of latitudes
+    pub fn looks_axis_swapped(&self) -> bool {
+        let beyond_poles = self.ymin.abs() > 90.0 || self.ymax.abs() > 90.0;
+        beyond_poles && self.xmin.abs() <= 90.0 && self.xmax.abs() <= 90.0
+    }
+
+    /// The bbox read as if its x and y had been swapped
+    pub fn swapped_axes(&self) -> Self {
+        Self::new(self.ymin, self.xmin, self.ymax, self.xmax)
+    }
+
+    /// Whether the bbox has no area, because all of the data is at one point or along
+    /// a horizontal or vertical line
+    pub fn is_degenerate(&self) -> bool {
+        self.xmin == self.xmax || self.ymin == self.ymax
+    }
+
+    /// Describes the shape of a bbox without any area, or `None` if it has some
+    pub fn degenerate_kind(&self) -> Option<&'static str> {
+        match (self.xmin == self.xmax, self.ymin == self.ymax) {
+            (true, true) => Some("point extent"),
+            (false, true) => Some("zero-height extent"),
+            (true, false) => Some("zero-width extent"),
+            (false, false) => None,
+        }
+    }
+
+    /// The middle of the bbox, going the short way across the antimeridian if it crosses it
+    pub fn center(&self) -> (f64, f64) {
+        let y = (self.ymin + self.ymax) / 2.0;
+        if !self.crosses_antimeridian() {
+            return ((self.xmin + self.xmax) / 2.0, y);
+        }
+        let x = (self.xmin + self.xmax + 360.0) / 2.0;
+        (if x >= 180.0 { x - 360.0 } else { x }, y)
+    }
+
     /// Given the envelope from a flatgeobuf file which represents the geospatial extent,
     /// generate a bbox struct;
     pub fn from_flatgeobuf_envelope<'a>(
@@ -40,25 +527,545 @@ impl Bbox {
         })
     }
 
-    /// Project the bbox to the ratatui map crs (EPSG:4326)
+    /// Points spaced evenly along each edge of the bbox, `points_per_edge` to an edge
+    /// counting both corners, going around from the min corner
+    pub fn edge_points(&self, points_per_edge: usize) -> Vec<(f64, f64)> {
+        let steps = points_per_edge.max(2) - 1;
+        let corners = [
+            (self.xmin, self.ymin),
+            (self.xmax, self.ymin),
+            (self.xmax, self.ymax),
+            (self.xmin, self.ymax),
+            (self.xmin, self.ymin),
+        ];
+        corners
+            .windows(2)
+            .flat_map(|edge| {
+                let ((x0, y0), (x1, y1)) = (edge[0], edge[1]);
+                // each edge leaves off its last corner, which starts the next edge
+                (0..steps).map(move |i| {
+                    let t = i as f64 / steps as f64;
+                    (x0 + (x1 - x0) * t, y0 + (y1 - y0) * t)
+                })
+            })
+            .collect()
+    }
+
+    /// Project the bbox to the ratatui map crs (EPSG:4326), sampling
+    /// [`DEFAULT_POINTS_PER_EDGE`] points along each edge
     pub fn project_to_ratatui_map_crs(
         &self,
         source_crs: &str,
-    ) -> Result<(Self, String), proj::ProjError> {
-        if source_crs == RATATUI_MAP_CRS {
-            return Ok((
-                self.to_owned(),
-                format!("Extent of data in {RATATUI_MAP_CRS}"),
-            ));
-        }
+    ) -> Result<(Self, String), ProjectionError> {
+        self.project_to_ratatui_map_crs_densified(source_crs, DEFAULT_POINTS_PER_EDGE)
+    }
 
-        let src_to_ratatui_crs = Proj::new_known_crs(source_crs, RATATUI_MAP_CRS, None).unwrap();
-        let (new_xmin, new_ymin) = src_to_ratatui_crs.convert((self.xmin, self.ymin))?;
-        let (new_xmax, new_ymax) = src_to_ratatui_crs.convert((self.xmax, self.ymax))?;
+    /// Project the bbox to the ratatui map crs (EPSG:4326) as the bbox of `points_per_edge`
+    /// points projected from along each of its edges. Projecting only the corners would
+    /// miss how far the edges bow out in projections like polar stereographic
+    pub fn project_to_ratatui_map_crs_densified(
+        &self,
+        source_crs: &str,
+        points_per_edge: usize,
+    ) -> Result<(Self, String), ProjectionError> {
+        if is_map_crs(source_crs) {
+            return Ok((self.to_owned(), format!("Extent of data in {source_crs}")));
+        }
 
+        let projected = self.reproject(source_crs, RATATUI_MAP_CRS, points_per_edge)?;
         Ok((
-            Bbox::new(new_xmin, new_ymin, new_xmax, new_ymax),
-            format!("Extent of data in {source_crs} projected to {RATATUI_MAP_CRS}"),
+            projected,
+            format!(
+                "Extent of data in {} projected to {RATATUI_MAP_CRS}",
+                crs_label(source_crs)
+            ),
         ))
     }
+
+    /// Project the bbox from one CRS to another as the bbox of `points_per_edge` points
+    /// projected from along each of its edges
+    pub fn reproject(
+        &self,
+        from: &str,
+        to: &str,
+        points_per_edge: usize,
+    ) -> Result<Self, ProjectionError> {
+        if from == to || (is_map_crs(from) && is_map_crs(to)) {
+            return Ok(self.clone());
+        }
+        let transform = gis_order_transform(from, to)?;
+        let mut points = self.edge_points(points_per_edge);
+        transform
+            .convert_array(&mut points)
+            .map_err(|source| ProjectionError::Conversion {
+                crs: from.to_string(),
+                source,
+            })?;
+        Ok(points.iter().fold(
+            Bbox::new(
+                f64::INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::NEG_INFINITY,
+            ),
+            |bbox, &(x, y)| {
+                Bbox::new(
+                    bbox.xmin.min(x),
+                    bbox.ymin.min(y),
+                    bbox.xmax.max(x),
+                    bbox.ymax.max(y),
+                )
+            },
+        ))
+    }
+
+    /// The bbox as a GeoJSON polygon feature, going counterclockwise from the min corner
+    pub fn to_geojson(&self) -> serde_json::Value {
+        let ring: Vec<[f64; 2]> = [
+            (self.xmin, self.ymin),
+            (self.xmax, self.ymin),
+            (self.xmax, self.ymax),
+            (self.xmin, self.ymax),
+            (self.xmin, self.ymin),
+        ]
+        .into_iter()
+        .map(|(x, y)| [x, y])
+        .collect();
+        serde_json::json!({
+            "type": "Feature",
+            "bbox": [self.xmin, self.ymin, self.xmax, self.ymax],
+            "geometry": {
+                "type": "Polygon",
+                "coordinates": [ring],
+            },
+            "properties": {},
+        })
+    }
+}
+
+impl Bbox {
+    /// Project a bbox on the ratatui map (EPSG:4326) into `target_crs`
+    pub fn project_from_ratatui_map_crs(&self, target_crs: &str) -> Result<Self, String> {
+        if is_map_crs(target_crs) {
+            return Ok(self.to_owned());
+        }
+
+        let ratatui_to_target_crs = gis_order_transform(RATATUI_MAP_CRS, target_crs)
+            .map_err(|e| format!("Failed to project to {}: {e}", crs_label(target_crs)))?;
+        let convert = |position| {
+            ratatui_to_target_crs.convert(position).map_err(|e| {
+                format!(
+                    "Failed to project the bbox to {}: {e}",
+                    crs_label(target_crs)
+                )
+            })
+        };
+        let (new_xmin, new_ymin) = convert((self.xmin, self.ymin))?;
+        let (new_xmax, new_ymax) = convert((self.xmax, self.ymax))?;
+        Ok(Bbox::new(new_xmin, new_ymin, new_xmax, new_ymax))
+    }
+}
+
+/// Project the parts of a geometry to the ratatui map crs (EPSG:4326)
+pub fn project_parts_to_ratatui_map_crs(
+    parts: &[Vec<(f64, f64)>],
+    source_crs: &str,
+) -> Result<Vec<Vec<(f64, f64)>>, String> {
+    if is_map_crs(source_crs) {
+        return Ok(parts.to_vec());
+    }
+
+    let src_to_ratatui_crs = gis_order_transform(source_crs, RATATUI_MAP_CRS)
+        .map_err(|e| format!("Failed to project from {}: {e}", crs_label(source_crs)))?;
+    parts
+        .iter()
+        .map(|part| {
+            part.iter()
+                .map(|&position| {
+                    src_to_ratatui_crs
+                        .convert(position)
+                        .map_err(|e| format!("Failed to project a geometry: {e}"))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_crs_needs_no_projection() {
+        assert!(check_crs("EPSG:4326").is_ok());
+        assert!(check_crs("OGC:CRS84").is_ok());
+        let bbox = Bbox::new(-109.0, 37.0, -102.0, 41.0);
+        let (projected, title) = bbox.project_to_ratatui_map_crs("EPSG:4326").unwrap();
+        assert_eq!(projected.to_string(), bbox.to_string());
+        assert_eq!(title, "Extent of data in EPSG:4326");
+    }
+
+    #[test]
+    fn crs_label_shortens_wkt_to_its_name() {
+        assert_eq!(crs_label("EPSG:3857"), "EPSG:3857");
+        let wkt =
+            r#"PROJCS["NAD83 / UTM zone 15N",GEOGCS["NAD83",DATUM["North_American_Datum_1983"]]]"#;
+        assert_eq!(crs_label(wkt), "NAD83 / UTM zone 15N");
+        assert_eq!(crs_label("PROJCS[UNNAMED]"), "WKT CRS");
+    }
+
+    #[cfg(not(feature = "proj"))]
+    #[test]
+    fn other_crss_are_unavailable_without_proj() {
+        let error = check_crs("EPSG:3857").unwrap_err();
+        assert!(matches!(&error, ProjectionError::Unavailable { crs } if crs == "EPSG:3857"));
+        assert_eq!(
+            error.to_string(),
+            "reprojection of EPSG:3857 unavailable — built without proj"
+        );
+        let bbox = Bbox::new(0.0, 0.0, 1.0, 1.0);
+        assert!(bbox.project_to_ratatui_map_crs("EPSG:3857").is_err());
+    }
+
+    #[cfg(feature = "proj")]
+    #[test]
+    fn garbage_crs_is_an_unknown_crs_error() {
+        let bbox = Bbox::new(0.0, 0.0, 1.0, 1.0);
+        let error = bbox.project_to_ratatui_map_crs("EPSG:garbage").unwrap_err();
+        assert!(matches!(&error, ProjectionError::UnknownCrs { crs, .. } if crs == "EPSG:garbage"));
+        assert!(
+            error
+                .to_string()
+                .starts_with("EPSG:garbage not found in the PROJ database")
+        );
+        let error = check_crs(r#"PROJCS["Nowhere",NONSENSE]"#).unwrap_err();
+        assert!(error.to_string().starts_with("unknown CRS Nowhere"));
+    }
+
+    #[cfg(feature = "proj")]
+    #[test]
+    fn web_mercator_bbox_projects_to_longitudes_and_latitudes() {
+        // Colorado in web mercator
+        let bbox = Bbox::new(-12_140_503.7, 4_439_106.8, -11_359_040.8, 5_012_341.7);
+        let (projected, title) = bbox.project_to_ratatui_map_crs("EPSG:3857").unwrap();
+        assert_eq!(title, "Extent of data in EPSG:3857 projected to EPSG:4326");
+        for (value, expected) in [
+            (projected.xmin, -109.06),
+            (projected.ymin, 37.0),
+            (projected.xmax, -102.04),
+            (projected.ymax, 41.0),
+        ] {
+            assert!((value - expected).abs() < 1e-6, "{value} vs {expected}");
+        }
+    }
+
+    #[test]
+    fn center_of_a_bbox() {
+        let bbox = Bbox::new(-109.0, 37.0, -102.0, 41.0);
+        assert_eq!(bbox.center(), (-105.5, 39.0));
+        let point = Bbox::new(-105.0, 39.0, -105.0, 39.0);
+        assert_eq!(point.center(), (-105.0, 39.0));
+    }
+
+    #[test]
+    fn center_of_a_bbox_crossing_the_antimeridian() {
+        // Fiji, which reaches from 177°E past the antimeridian to 178°W
+        let fiji = Bbox::new(177.0, -21.0, -178.0, -12.0);
+        assert_eq!(fiji.center(), (179.5, -16.5));
+        // the center itself can be west of the antimeridian
+        let east_heavy = Bbox::new(179.0, -21.0, -170.0, -12.0);
+        assert_eq!(east_heavy.center(), (-175.5, -16.5));
+        // and right on it, which is written as the west side
+        let even = Bbox::new(170.0, 0.0, -170.0, 10.0);
+        assert_eq!(even.center(), (-180.0, 5.0));
+    }
+
+    #[test]
+    fn fiji_crossing_the_antimeridian() {
+        let fiji = Bbox::new(177.0, -21.0, -178.0, -12.0);
+        assert!(fiji.crosses_antimeridian());
+        assert_eq!(fiji.width(), 5.0);
+        assert_eq!(fiji.area(), 45.0);
+        assert!(fiji.contains_point(178.4, -18.1));
+        assert!(fiji.contains_point(-179.9, -16.5));
+        assert!(!fiji.contains_point(0.0, -16.5));
+        assert!(!fiji.contains_point(-170.0, -16.5));
+        let pieces: Vec<String> = fiji
+            .split_at_antimeridian()
+            .iter()
+            .map(Bbox::to_string)
+            .collect();
+        assert_eq!(pieces, ["177,-21,180,-12", "-180,-21,-178,-12"]);
+    }
+
+    #[test]
+    fn bboxes_on_either_side_of_the_antimeridian() {
+        let fiji = Bbox::new(177.0, -21.0, -178.0, -12.0);
+        let viti_levu = Bbox::new(177.2, -18.3, 178.8, -17.3);
+        let lau_islands = Bbox::new(-179.9, -19.5, -178.2, -17.0);
+        let samoa = Bbox::new(-172.8, -14.1, -171.4, -13.4);
+        assert!(fiji.intersects(&viti_levu) && viti_levu.intersects(&fiji));
+        assert!(fiji.intersects(&lau_islands));
+        assert!(!fiji.intersects(&samoa));
+        assert!(!viti_levu.intersects(&lau_islands));
+    }
+
+    #[test]
+    fn bbox_not_crossing_the_antimeridian() {
+        let colorado = Bbox::new(-109.06, 36.99, -102.04, 41.0);
+        assert!(!colorado.crosses_antimeridian());
+        assert!((colorado.width() - 7.02).abs() < 1e-9);
+        assert_eq!(colorado.split_at_antimeridian().len(), 1);
+        let whole_world = Bbox::new(-180.0, -90.0, 180.0, 90.0);
+        assert!(!whole_world.crosses_antimeridian());
+        assert_eq!(whole_world.width(), 360.0);
+    }
+
+    #[test]
+    fn edge_points_go_around_the_bbox() {
+        let bbox = Bbox::new(0.0, 0.0, 4.0, 2.0);
+        assert_eq!(
+            bbox.edge_points(3),
+            [
+                (0.0, 0.0),
+                (2.0, 0.0),
+                (4.0, 0.0),
+                (4.0, 1.0),
+                (4.0, 2.0),
+                (2.0, 2.0),
+                (0.0, 2.0),
+                (0.0, 1.0),
+            ]
+        );
+        // anything fewer than both corners is still the corners
+        assert_eq!(bbox.edge_points(0), bbox.edge_points(2));
+        assert_eq!(bbox.edge_points(2).len(), 4);
+        assert_eq!(bbox.edge_points(DEFAULT_POINTS_PER_EDGE).len(), 80);
+    }
+
+    #[cfg(feature = "proj")]
+    #[test]
+    fn densified_edges_find_more_of_a_polar_extent() {
+        // a square 2000 km across around the north pole, in NSIDC polar stereographic
+        let bbox = Bbox::new(-1_000_000.0, -1_000_000.0, 1_000_000.0, 1_000_000.0);
+        let corners = bbox.reproject("EPSG:3413", RATATUI_MAP_CRS, 2).unwrap();
+        let (densified, _) = bbox.project_to_ratatui_map_crs("EPSG:3413").unwrap();
+        // the corners are the furthest from the pole, so the middle of each edge reaches
+        // latitudes several degrees further north than any corner does
+        assert!(densified.ymax > corners.ymax + 3.0);
+        assert!((densified.ymin - corners.ymin).abs() < 1e-6);
+        // and going around the pole, the edges cover every longitude
+        assert!(densified.xmin < -170.0 && densified.xmax > 170.0);
+    }
+
+    #[test]
+    fn axis_swapped_envelopes() {
+        // Colorado with latitude first
+        let swapped = Bbox::new(37.0, -109.06, 41.0, -102.04);
+        assert!(swapped.looks_axis_swapped());
+        assert_eq!(swapped.swapped_axes().to_string(), "-109.06,37,-102.04,41");
+        assert!(!swapped.swapped_axes().looks_axis_swapped());
+        // latitudes within the poles can't tell
+        assert!(!Bbox::new(37.0, 39.0, 41.0, 40.0).looks_axis_swapped());
+        // nor can longitudes which couldn't be latitudes
+        assert!(!Bbox::new(-170.0, -100.0, 170.0, 100.0).looks_axis_swapped());
+    }
+
+    #[cfg(feature = "proj")]
+    #[test]
+    fn epsg_4269_projects_with_longitude_in_x() {
+        // EPSG defines NAD83 with latitude first, which the transform must not follow
+        let colorado = Bbox::new(-109.06, 36.99, -102.04, 41.0);
+        let (projected, _) = colorado.project_to_ratatui_map_crs("EPSG:4269").unwrap();
+        assert!((projected.xmin - -109.06).abs() < 0.001);
+        assert!((projected.xmax - -102.04).abs() < 0.001);
+        assert!((projected.ymin - 36.99).abs() < 0.001);
+        assert!((projected.ymax - 41.0).abs() < 0.001);
+        let position = Reprojection::new("EPSG:4269", "EPSG:4326")
+            .unwrap()
+            .convert((-105.0, 39.0))
+            .unwrap();
+        assert!((position.0 - -105.0).abs() < 0.001 && (position.1 - 39.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn describe_the_map_crs() {
+        let info = describe_crs("EPSG:4326").unwrap();
+        assert_eq!(info.kind, "geographic");
+        assert_eq!(info.units, "degrees");
+        assert!(info.name.contains("WGS 84"));
+    }
+
+    #[cfg(not(feature = "proj"))]
+    #[test]
+    fn describe_other_crss_without_proj() {
+        let error = describe_crs("EPSG:3857").unwrap_err();
+        assert!(matches!(error, ProjectionError::Unavailable { .. }));
+        assert!(area_of_use("EPSG:3857").is_none());
+    }
+
+    #[cfg(feature = "proj")]
+    #[test]
+    fn describe_a_known_projected_crs() {
+        let info = describe_crs("EPSG:3857").unwrap();
+        assert_eq!(info.name, "WGS 84 / Pseudo-Mercator");
+        assert_eq!(info.kind, "projected");
+        assert_eq!(info.units, "m");
+        let area = area_of_use("EPSG:3857").unwrap();
+        assert_eq!((area.xmin, area.xmax), (-180.0, 180.0));
+    }
+
+    #[cfg(feature = "proj")]
+    #[test]
+    fn describe_a_code_missing_from_the_proj_database() {
+        let error = describe_crs("EPSG:99999").unwrap_err();
+        assert!(matches!(&error, ProjectionError::UnknownCrs { crs, .. } if crs == "EPSG:99999"));
+        assert!(
+            error
+                .to_string()
+                .starts_with("EPSG:99999 not found in the PROJ database")
+        );
+    }
+
+    #[test]
+    fn bbox_dimensions() {
+        let bbox = Bbox::new(-109.0, 37.0, -102.0, 41.0);
+        assert_eq!((bbox.width(), bbox.height(), bbox.area()), (7.0, 4.0, 28.0));
+        let point = Bbox::new(-105.0, 39.0, -105.0, 39.0);
+        assert_eq!(point.area(), 0.0);
+        assert!(point.is_degenerate());
+        assert_eq!(point.degenerate_kind(), Some("point extent"));
+        let line = Bbox::new(-109.0, 39.0, -102.0, 39.0);
+        assert_eq!(line.degenerate_kind(), Some("zero-height extent"));
+        assert_eq!(bbox.degenerate_kind(), None);
+    }
+
+    #[test]
+    fn bboxes_intersect_when_they_overlap_or_touch() {
+        let colorado = Bbox::new(-109.0, 37.0, -102.0, 41.0);
+        let denver = Bbox::new(-105.1, 39.6, -104.6, 39.9);
+        let kansas = Bbox::new(-102.0, 37.0, -94.6, 40.0);
+        let utah = Bbox::new(-114.0, 37.0, -109.5, 42.0);
+        assert!(colorado.intersects(&denver) && denver.intersects(&colorado));
+        assert!(colorado.intersects(&kansas));
+        assert!(!colorado.intersects(&utah));
+        // a point or a line has no area but can still be within
+        let point = Bbox::new(-105.0, 39.0, -105.0, 39.0);
+        assert!(colorado.intersects(&point) && point.intersects(&colorado));
+        assert!(!utah.intersects(&point));
+        assert!(point.intersects(&point));
+    }
+
+    #[test]
+    fn bbox_contains_points_on_its_edges() {
+        let colorado = Bbox::new(-109.0, 37.0, -102.0, 41.0);
+        assert!(colorado.contains_point(-105.0, 39.0));
+        assert!(colorado.contains_point(-109.0, 37.0));
+        assert!(colorado.contains_point(-102.0, 41.0));
+        assert!(!colorado.contains_point(-101.9, 39.0));
+        assert!(!colorado.contains_point(-105.0, 41.1));
+        assert!(!colorado.contains_point(f64::NAN, 39.0));
+    }
+
+    #[test]
+    fn bbox_union() {
+        let denver = Bbox::new(-105.1, 39.6, -104.6, 39.9);
+        let pueblo = Bbox::new(-104.7, 38.2, -104.5, 38.35);
+        assert_eq!(denver.union(&pueblo).to_string(), "-105.1,38.2,-104.5,39.9");
+        // one within the other
+        let colorado = Bbox::new(-109.0, 37.0, -102.0, 41.0);
+        assert_eq!(colorado.union(&denver).to_string(), colorado.to_string());
+        assert_eq!(denver.union(&colorado).to_string(), colorado.to_string());
+    }
+
+    #[test]
+    fn bbox_union_across_the_antimeridian() {
+        let viti_levu = Bbox::new(177.2, -18.3, 178.8, -17.3);
+        let lau_islands = Bbox::new(-179.9, -19.5, -178.2, -17.0);
+        // the short way round is across the antimeridian, rather than around the world
+        let fiji = viti_levu.union(&lau_islands);
+        assert_eq!(fiji.to_string(), "177.2,-19.5,-178.2,-17");
+        assert!((fiji.width() - 4.6).abs() < 1e-9);
+        assert_eq!(lau_islands.union(&viti_levu).to_string(), fiji.to_string());
+        // and a box crossing it already takes in what it covers
+        assert_eq!(fiji.union(&viti_levu).to_string(), fiji.to_string());
+        let samoa = Bbox::new(-172.8, -14.1, -171.4, -13.4);
+        assert_eq!(fiji.union(&samoa).to_string(), "177.2,-19.5,-171.4,-13.4");
+    }
+
+    #[test]
+    fn bbox_expand_by_fraction() {
+        let bbox = Bbox::new(0.0, 10.0, 10.0, 30.0);
+        assert_eq!(bbox.expand_by_fraction(0.1).to_string(), "-1,8,11,32");
+        assert_eq!(bbox.expand_by_fraction(0.0).to_string(), bbox.to_string());
+        // nothing to grow a point by
+        let point = Bbox::new(5.0, 5.0, 5.0, 5.0);
+        assert_eq!(point.expand_by_fraction(0.5).to_string(), point.to_string());
+    }
+
+    /// Whether `value` is within 1% of `expected`
+    fn within_a_percent(value: f64, expected: f64) -> bool {
+        (value - expected).abs() <= expected.abs() * 0.01
+    }
+
+    #[test]
+    fn approx_dimensions_of_reference_boxes() {
+        // a degree of latitude, or of longitude along the equator, is about 111.2 km
+        let degree = Bbox::new(0.0, 0.0, 1.0, 1.0);
+        let (width, height) = degree.approx_dimensions_km(0.0);
+        assert!(within_a_percent(width, 111.2) && within_a_percent(height, 111.2));
+        // and half as much longitude at 60°
+        let (width, _) = degree.approx_dimensions_km(60.0);
+        assert!(within_a_percent(width, 55.6));
+        // Colorado is about 605 km wide along 39°N and 445 km tall
+        let colorado = Bbox::new(-109.05, 37.0, -102.05, 41.0);
+        let (width, height) = colorado.approx_dimensions_km(colorado.center().1);
+        assert!(within_a_percent(width, 605.0), "{width}");
+        assert!(within_a_percent(height, 445.0), "{height}");
+    }
+
+    #[test]
+    fn approx_area_of_reference_boxes() {
+        // Colorado is 269,601 km² by the census, its borders being very nearly its bbox
+        let colorado = Bbox::new(-109.05, 37.0, -102.05, 41.0);
+        assert!(within_a_percent(colorado.approx_area_km2(), 269_601.0));
+        // the surface of the earth is 510.1 million km²
+        let world = Bbox::new(-180.0, -90.0, 180.0, 90.0);
+        assert!(within_a_percent(world.approx_area_km2(), 510_072_000.0));
+        assert_eq!(Bbox::new(1.0, 1.0, 1.0, 2.0).approx_area_km2(), 0.0);
+    }
+
+    #[test]
+    fn approx_sizes_clamp_at_the_poles() {
+        // latitudes past the poles are taken as the poles
+        let past_the_poles = Bbox::new(-180.0, -100.0, 180.0, 100.0);
+        let world = Bbox::new(-180.0, -90.0, 180.0, 90.0);
+        assert_eq!(past_the_poles.approx_area_km2(), world.approx_area_km2());
+        assert_eq!(
+            past_the_poles.approx_dimensions_km(0.0).1,
+            world.approx_dimensions_km(0.0).1
+        );
+        // a parallel at the pole has no length, give or take rounding
+        let (width, _) = world.approx_dimensions_km(90.0);
+        assert!(width.abs() < 1e-6);
+        assert_eq!(
+            world.approx_dimensions_km(95.0),
+            world.approx_dimensions_km(90.0)
+        );
+        assert_eq!(
+            world.approx_dimensions_km(-95.0),
+            world.approx_dimensions_km(-90.0)
+        );
+    }
+
+    #[test]
+    fn approx_sizes_across_the_antimeridian() {
+        // 5° of longitude around Fiji, at 16.5°S
+        let fiji = Bbox::new(177.0, -21.0, -178.0, -12.0);
+        let (width, height) = fiji.approx_dimensions_km(fiji.center().1);
+        assert!(within_a_percent(width, 533.1), "{width}");
+        assert!(within_a_percent(height, 1000.8), "{height}");
+        let unwrapped = Bbox::new(177.0, -21.0, 182.0, -12.0);
+        assert_eq!(fiji.approx_area_km2(), unwrapped.approx_area_km2());
+    }
 }
@@ -0,0 +1,432 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use flatgeobuf::FgbFeature;
+use geozero::{ColumnValue, FeatureProperties, PropertyProcessor};
+use serde_json::{Map, Number, Value};
+use std::fmt;
+
+/// The value of a property, typed by the column it's in
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyValue {
+    /// Any of the signed and unsigned integer columns but `ULong`
+    Int(i64),
+    /// `ULong`, which can hold values beyond `i64`
+    UInt(u64),
+    /// `Float` or `Double`
+    Float(f64),
+    Bool(bool),
+    String(String),
+    /// As written in the file, which should be ISO 8601
+    DateTime(String),
+    /// As written in the file, which may not be valid JSON
+    Json(String),
+    Binary(Vec<u8>),
+}
+
+impl PropertyValue {
+    fn of(value: &ColumnValue) -> Self {
+        match *value {
+            ColumnValue::Byte(v) => Self::Int(v.into()),
+            ColumnValue::UByte(v) => Self::Int(v.into()),
+            ColumnValue::Bool(v) => Self::Bool(v),
+            ColumnValue::Short(v) => Self::Int(v.into()),
+            ColumnValue::UShort(v) => Self::Int(v.into()),
+            ColumnValue::Int(v) => Self::Int(v.into()),
+            ColumnValue::UInt(v) => Self::Int(v.into()),
+            ColumnValue::Long(v) => Self::Int(v),
+            ColumnValue::ULong(v) => Self::UInt(v),
+            ColumnValue::Float(v) => Self::Float(v.into()),
+            ColumnValue::Double(v) => Self::Float(v),
+            ColumnValue::String(v) => Self::String(v.to_string()),
+            ColumnValue::DateTime(v) => Self::DateTime(v.to_string()),
+            ColumnValue::Json(v) => Self::Json(v.to_string()),
+            ColumnValue::Binary(v) => Self::Binary(v.to_vec()),
+        }
+    }
+
+    /// The value as JSON: JSON columns are embedded as JSON rather than as a string,
+    /// binary values are hex, and NaN and infinity, which JSON can't hold, are null
+    pub fn to_json(&self) -> Value {
+        match self {
+            Self::Int(v) => Value::from(*v),
+            Self::UInt(v) => Value::from(*v),
+            Self::Float(v) => Number::from_f64(*v).map_or(Value::Null, Value::Number),
+            Self::Bool(v) => Value::from(*v),
+            Self::String(v) | Self::DateTime(v) => Value::from(v.as_str()),
+            // invalid JSON is still worth seeing as text
+            Self::Json(v) => serde_json::from_str(v).unwrap_or_else(|_| Value::from(v.as_str())),
+            Self::Binary(v) => Value::from(hex(v)),
+        }
+    }
+}
+
+/// As text for a table cell: strings without quotes, floats as in JSON but with NaN and
+/// infinity spelled out, and binary values as hex
+impl fmt::Display for PropertyValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Int(v) => write!(f, "{v}"),
+            Self::UInt(v) => write!(f, "{v}"),
+            Self::Float(v) => match Number::from_f64(*v) {
+                Some(number) => write!(f, "{number}"),
+                None => write!(f, "{v}"),
+            },
+            Self::Bool(v) => write!(f, "{v}"),
+            Self::String(v) | Self::DateTime(v) | Self::Json(v) => f.write_str(v),
+            Self::Binary(v) => f.write_str(&hex(v)),
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// The properties of a feature in header order, with `None` for the columns the feature
+/// has no value for. Columns keep their place even where names repeat
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Properties(pub Vec<(String, Option<PropertyValue>)>);
+
+impl Properties {
+    /// Decode the properties of `feature`, whose header has the columns `columns`
+    pub fn decode(feature: &FgbFeature, columns: &[String]) -> Result<Self, String> {
+        let mut properties = Self(columns.iter().map(|name| (name.clone(), None)).collect());
+        feature
+            .process_properties(&mut properties)
+            .map_err(|e| format!("Failed to decode the properties of a feature: {e}"))?;
+        Ok(properties)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = Option<&PropertyValue>> {
+        self.0.iter().map(|(_, value)| value.as_ref())
+    }
+
+    /// The value of the first column named `name`, if the feature has one
+    pub fn get(&self, name: &str) -> Option<&PropertyValue> {
+        self.0
+            .iter()
+            .find(|(other, _)| other == name)
+            .and_then(|(_, value)| value.as_ref())
+    }
+
+    /// As a JSON object, with null for missing values. Of columns with the same name,
+    /// only the last is kept, as JSON objects can't repeat keys
+    pub fn to_json(&self) -> Map<String, Value> {
+        self.0
+            .iter()
+            .map(|(name, value)| {
+                let value = value.as_ref().map_or(Value::Null, PropertyValue::to_json);
+                (name.clone(), value)
+            })
+            .collect()
+    }
+}
+
+impl PropertyProcessor for Properties {
+    fn property(
+        &mut self,
+        index: usize,
+        name: &str,
+        value: &ColumnValue,
+    ) -> geozero::error::Result<bool> {
+        let value = Some(PropertyValue::of(value));
+        match self.0.get_mut(index) {
+            Some(property) => property.1 = value,
+            // a column the header doesn't have is still worth seeing
+            None => self.0.push((name.to_string(), value)),
+        }
+        // keep going through the rest of the properties
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flatgeobuf::{ColumnType, FallibleStreamingIterator, FgbReader, FgbWriter, GeometryType};
+    use geozero::geojson::GeoJson;
+    use serde_json::json;
+
+    /// A column type with a value of it, what that decodes to, and that as JSON and as text
+    type Case = (
+        ColumnType,
+        ColumnValue<'static>,
+        PropertyValue,
+        Value,
+        &'static str,
+    );
+
+    /// A value of every column type
+    fn every_type() -> Vec<Case> {
+        use PropertyValue as P;
+        vec![
+            (
+                ColumnType::Byte,
+                ColumnValue::Byte(-8),
+                P::Int(-8),
+                json!(-8),
+                "-8",
+            ),
+            (
+                ColumnType::UByte,
+                ColumnValue::UByte(255),
+                P::Int(255),
+                json!(255),
+                "255",
+            ),
+            (
+                ColumnType::Bool,
+                ColumnValue::Bool(true),
+                P::Bool(true),
+                json!(true),
+                "true",
+            ),
+            (
+                ColumnType::Short,
+                ColumnValue::Short(i16::MIN),
+                P::Int(-32768),
+                json!(-32768),
+                "-32768",
+            ),
+            (
+                ColumnType::UShort,
+                ColumnValue::UShort(u16::MAX),
+                P::Int(65535),
+                json!(65535),
+                "65535",
+            ),
+            (
+                ColumnType::Int,
+                ColumnValue::Int(-7),
+                P::Int(-7),
+                json!(-7),
+                "-7",
+            ),
+            (
+                ColumnType::UInt,
+                ColumnValue::UInt(u32::MAX),
+                P::Int(4294967295),
+                json!(4294967295u32),
+                "4294967295",
+            ),
+            (
+                ColumnType::Long,
+                ColumnValue::Long(i64::MIN),
+                P::Int(i64::MIN),
+                json!(i64::MIN),
+                "-9223372036854775808",
+            ),
+            // beyond i64, so kept unsigned
+            (
+                ColumnType::ULong,
+                ColumnValue::ULong(u64::MAX),
+                P::UInt(u64::MAX),
+                json!(u64::MAX),
+                "18446744073709551615",
+            ),
+            (
+                ColumnType::Float,
+                ColumnValue::Float(1.5),
+                P::Float(1.5),
+                json!(1.5),
+                "1.5",
+            ),
+            (
+                ColumnType::Double,
+                ColumnValue::Double(-0.25),
+                P::Float(-0.25),
+                json!(-0.25),
+                "-0.25",
+            ),
+            (
+                ColumnType::String,
+                ColumnValue::String("Montezuma"),
+                P::String("Montezuma".into()),
+                json!("Montezuma"),
+                "Montezuma",
+            ),
+            (
+                ColumnType::Json,
+                ColumnValue::Json(r#"{"a":[1,2]}"#),
+                P::Json(r#"{"a":[1,2]}"#.into()),
+                json!({"a": [1, 2]}),
+                r#"{"a":[1,2]}"#,
+            ),
+            (
+                ColumnType::DateTime,
+                ColumnValue::DateTime("2026-10-16T12:00:00Z"),
+                P::DateTime("2026-10-16T12:00:00Z".into()),
+                json!("2026-10-16T12:00:00Z"),
+                "2026-10-16T12:00:00Z",
+            ),
+            (
+                ColumnType::Binary,
+                ColumnValue::Binary(&[0x00, 0xab, 0x10]),
+                P::Binary(vec![0x00, 0xab, 0x10]),
+                json!("00ab10"),
+                "00ab10",
+            ),
+        ]
+    }
+
+    fn column_name(column_type: ColumnType) -> String {
+        format!("{column_type:?}").to_lowercase()
+    }
+
+    /// A file with a column of every type and a point feature for each of `features`,
+    /// which lists the values each has as indices into [`every_type`], or past it for a
+    /// value of a column the header doesn't have
+    fn fixture(features: &[&[usize]]) -> Vec<u8> {
+        let types = every_type();
+        let mut fgb = FgbWriter::create("every_type", GeometryType::Point).unwrap();
+        for (column_type, ..) in &types {
+            fgb.add_column(&column_name(*column_type), *column_type, |_, _| {});
+        }
+        for values in features {
+            let point = GeoJson(r#"{"type": "Point", "coordinates": [-108.5, 37.3]}"#);
+            fgb.add_feature_geom(point, |feature| {
+                for &i in *values {
+                    // past the last column, which the header doesn't declare
+                    let (name, value) = match types.get(i) {
+                        Some((column_type, value, ..)) => (column_name(*column_type), value),
+                        None => ("extra".to_string(), &ColumnValue::Int(1)),
+                    };
+                    feature.property(i, &name, value).unwrap();
+                }
+            })
+            .unwrap();
+        }
+        let mut out = Vec::new();
+        fgb.write(&mut out).unwrap();
+        out
+    }
+
+    /// The properties of each feature of `fgb`
+    fn decode_all(fgb: &[u8]) -> Vec<Result<Properties, String>> {
+        let fgb = FgbReader::open(std::io::Cursor::new(fgb)).unwrap();
+        let columns: Vec<String> = fgb
+            .header()
+            .columns()
+            .unwrap()
+            .iter()
+            .map(|column| column.name().to_string())
+            .collect();
+        let mut features = fgb.select_all().unwrap();
+        let mut decoded = Vec::new();
+        while let Some(feature) = features.next().unwrap() {
+            decoded.push(Properties::decode(feature, &columns));
+        }
+        decoded
+    }
+
+    #[test]
+    fn every_column_type_decodes() {
+        let types = every_type();
+        let covered: Vec<ColumnType> = types.iter().map(|(column_type, ..)| *column_type).collect();
+        assert_eq!(covered, ColumnType::ENUM_VALUES);
+
+        let all: Vec<usize> = (0..types.len()).collect();
+        let decoded = decode_all(&fixture(&[&all]));
+        let properties = decoded[0].as_ref().unwrap();
+        for ((column_type, _, value, json, text), (name, decoded)) in
+            types.iter().zip(&properties.0)
+        {
+            assert_eq!(*name, column_name(*column_type));
+            assert_eq!(decoded.as_ref(), Some(value), "{name}");
+            assert_eq!(value.to_json(), *json, "{name}");
+            assert_eq!(value.to_string(), *text, "{name}");
+        }
+    }
+
+    #[test]
+    fn columns_a_feature_has_no_value_for_are_null() {
+        let decoded = decode_all(&fixture(&[&[], &[14, 0], &[5]]));
+        let properties: Vec<&Properties> = decoded.iter().map(|p| p.as_ref().unwrap()).collect();
+        let columns = every_type().len();
+
+        // none at all, yet every column of the header is still there
+        assert_eq!(properties[0].0.len(), columns);
+        assert!(properties[0].values().all(|value| value.is_none()));
+        assert!(properties[0].to_json().values().all(Value::is_null));
+
+        // written out of order, each lands in its own column
+        assert_eq!(properties[1].get("byte"), Some(&PropertyValue::Int(-8)));
+        assert_eq!(
+            properties[1].get("binary"),
+            Some(&PropertyValue::Binary(vec![0x00, 0xab, 0x10]))
+        );
+        assert_eq!(properties[1].values().flatten().count(), 2);
+
+        let json = properties[2].to_json();
+        assert_eq!(json.len(), columns);
+        assert_eq!(json["int"], json!(-7));
+        assert_eq!(json["string"], Value::Null);
+        assert_eq!(properties[2].get("string"), None);
+        let names: Vec<String> = every_type()
+            .iter()
+            .map(|(column_type, ..)| column_name(*column_type))
+            .collect();
+        assert!(properties[2].names().eq(names.iter().map(String::as_str)));
+    }
+
+    #[test]
+    fn a_value_for_a_column_the_header_doesnt_have() {
+        // the reader refuses a feature whose value is for a column past the last
+        let decoded = decode_all(&fixture(&[&[0], &[0, 15]]));
+        assert!(decoded[0].is_ok());
+        let error = decoded[1].as_ref().unwrap_err();
+        assert!(
+            error.starts_with("Failed to decode the properties of a feature: "),
+            "{error}"
+        );
+
+        // but one passed on anyway is kept after the header columns
+        let mut properties = Properties(vec![("name".to_string(), None)]);
+        properties
+            .property(3, "extra", &ColumnValue::Int(1))
+            .unwrap();
+        assert_eq!(
+            properties.0,
+            [
+                ("name".to_string(), None),
+                ("extra".to_string(), Some(PropertyValue::Int(1)))
+            ]
+        );
+    }
+
+    #[test]
+    fn repeated_column_names() {
+        let properties = Properties(vec![
+            (
+                "name".to_string(),
+                Some(PropertyValue::String("first".into())),
+            ),
+            (
+                "name".to_string(),
+                Some(PropertyValue::String("second".into())),
+            ),
+        ]);
+        assert_eq!(
+            properties.get("name"),
+            Some(&PropertyValue::String("first".into()))
+        );
+        assert_eq!(properties.to_json()["name"], json!("second"));
+        assert_eq!(properties.names().count(), 2);
+    }
+
+    #[test]
+    fn values_json_cannot_hold() {
+        assert_eq!(PropertyValue::Float(f64::NAN).to_json(), Value::Null);
+        assert_eq!(PropertyValue::Float(f64::INFINITY).to_json(), Value::Null);
+        assert_eq!(PropertyValue::Float(f64::NEG_INFINITY).to_string(), "-inf");
+        assert_eq!(PropertyValue::Float(f64::NAN).to_string(), "NaN");
+        // invalid JSON is kept as text
+        let json = PropertyValue::Json("{not json".to_string());
+        assert_eq!(json.to_json(), json!("{not json"));
+    }
+}
@@ -0,0 +1,254 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    sync::{Arc, atomic::AtomicUsize},
+    time::Instant,
+};
+use tokio::task::JoinHandle;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    ColumnsTableState, MAX_CELL_WIDTH,
+    dataset::{self, OpenOptions},
+    feature::Feature,
+    http::TransferStats,
+    projection::Bbox,
+    truncate_with_ellipsis,
+};
+
+/// The fields of the bbox form, in the order they are tabbed through
+pub const BBOX_FIELDS: [&str; 4] = ["xmin", "ymin", "xmax", "ymax"];
+
+/// The form for entering the bbox to query, in the CRS of the file
+pub struct BboxForm {
+    pub values: [String; 4],
+    /// Index into [`BBOX_FIELDS`] of the field being edited
+    pub focused: usize,
+    /// Why the entered bbox couldn't be queried, if it couldn't
+    pub error: Option<String>,
+    /// Whether the CRS of the file is EPSG:4326, so the bbox must be within its range
+    geographic: bool,
+}
+
+impl BboxForm {
+    /// A form pre-filled with `bbox`, which is usually the envelope of the file
+    pub fn new(bbox: Option<&Bbox>, geographic: bool) -> Self {
+        let values = match bbox {
+            Some(bbox) => [bbox.xmin, bbox.ymin, bbox.xmax, bbox.ymax].map(|v| v.to_string()),
+            None => Default::default(),
+        };
+        Self {
+            values,
+            focused: 0,
+            error: None,
+            geographic,
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        self.focused = (self.focused + 1) % BBOX_FIELDS.len();
+    }
+
+    pub fn previous_field(&mut self) {
+        self.focused = (self.focused + BBOX_FIELDS.len() - 1) % BBOX_FIELDS.len();
+    }
+
+    /// Type into the focused field, ignoring anything which can't be part of a number
+    pub fn push(&mut self, c: char) {
+        if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+            self.values[self.focused].push(c);
+        }
+    }
+
+    pub fn pop(&mut self) {
+        self.values[self.focused].pop();
+    }
+
+    /// Parse the entered bbox
+    pub fn bbox(&self) -> Result<Bbox, String> {
+        let mut parsed = [0.0; 4];
+        for ((value, label), parsed) in self.values.iter().zip(BBOX_FIELDS).zip(&mut parsed) {
+            *parsed = value
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| format!("{label} must be a number"))?;
+        }
+        let [xmin, ymin, xmax, ymax] = parsed;
+        if xmin > xmax {
+            return Err("xmin must not be greater than xmax".to_string());
+        }
+        if ymin > ymax {
+            return Err("ymin must not be greater than ymax".to_string());
+        }
+        let bbox = Bbox::new(xmin, ymin, xmax, ymax);
+        if self.geographic {
+            bbox.validate_geographic()?;
+        }
+        Ok(bbox)
+    }
+}
+
+/// The features found by a bbox query
+pub struct QueryResults {
+    pub bbox: Bbox,
+    pub features: Vec<Feature>,
+    /// The titles of the table columns: the geometry type, then each property
+    pub headers: Vec<String>,
+    /// The width of each table column, including padding
+    pub widths: Vec<u16>,
+    pub table: ColumnsTableState,
+    /// Whether the detail popup of the selected feature is shown
+    pub show_detail: bool,
+}
+
+impl QueryResults {
+    pub fn new(bbox: Bbox, features: Vec<Feature>) -> Self {
+        let mut headers = vec!["Geometry".to_string()];
+        if let Some(feature) = features.first() {
+            headers.extend(feature.properties.names().map(String::from));
+        }
+        // sized once here, since there can be far more features than fit on screen
+        let mut widths: Vec<usize> = headers.iter().map(|header| header.width()).collect();
+        for feature in &features {
+            for (width, cell) in widths.iter_mut().zip(Self::cells(feature)) {
+                *width = (*width).max(cell.width());
+            }
+        }
+        Self {
+            bbox,
+            features,
+            headers,
+            widths: widths
+                .into_iter()
+                .map(|width| (width.min(MAX_CELL_WIDTH) + 2) as u16)
+                .collect(),
+            table: ColumnsTableState::new(),
+            show_detail: false,
+        }
+    }
+
+    /// The table cells of a feature, truncated to fit within [`MAX_CELL_WIDTH`]
+    pub fn row(&self, i: usize) -> Vec<String> {
+        Self::cells(&self.features[i])
+            .map(|cell| truncate_with_ellipsis(&cell, MAX_CELL_WIDTH))
+            .collect()
+    }
+
+    fn cells(feature: &Feature) -> impl Iterator<Item = String> + '_ {
+        let geometry_type = feature.geometry["type"].as_str().unwrap_or("—").to_string();
+        std::iter::once(geometry_type).chain(
+            feature
+                .properties
+                .values()
+                .map(|value| value.map_or("null".to_string(), ToString::to_string)),
+        )
+    }
+
+    pub fn selected(&self) -> Option<&Feature> {
+        self.table
+            .state
+            .selected()
+            .and_then(|i| self.features.get(i))
+    }
+}
+
+/// The stages of a bbox query, from entering the bbox to browsing the results
+pub enum BboxQuery {
+    Form(BboxForm),
+    Running {
+        bbox: Bbox,
+        started: Instant,
+        /// The number of features read so far
+        progress: Arc<AtomicUsize>,
+        task: JoinHandle<Result<Vec<Feature>, String>>,
+    },
+    Results(QueryResults),
+}
+
+impl BboxQuery {
+    /// Start querying `source` in the background, recording any requests in `transfer`.
+    /// Must be called from within a tokio runtime
+    pub fn run(
+        source: &str,
+        bbox: Bbox,
+        transfer: Arc<TransferStats>,
+        options: OpenOptions,
+    ) -> Self {
+        let progress = Arc::new(AtomicUsize::new(0));
+        let task = tokio::spawn({
+            let source = source.to_string();
+            let bbox = bbox.clone();
+            let progress = progress.clone();
+            async move { dataset::select_bbox(&source, &bbox, &progress, transfer, options).await }
+        });
+        Self::Running {
+            bbox,
+            started: Instant::now(),
+            progress,
+            task,
+        }
+    }
+}
+
+/// The stages of going to a feature by its index, from the `:` prompt to the feature
+pub enum FeatureLookup {
+    Prompt {
+        input: String,
+        /// Why the entered index couldn't be looked up, if it couldn't
+        error: Option<String>,
+    },
+    Running {
+        index: usize,
+        started: Instant,
+        task: JoinHandle<Result<Feature, String>>,
+    },
+    Found {
+        index: usize,
+        feature: Feature,
+    },
+}
+
+impl FeatureLookup {
+    pub fn prompt() -> Self {
+        Self::Prompt {
+            input: String::new(),
+            error: None,
+        }
+    }
+
+    /// Parse the entered index, which must be less than `count` if the header says
+    /// how many features there are
+    pub fn index(input: &str, count: Option<usize>) -> Result<usize, String> {
+        let index = input
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| "the index must be a whole number, counting from 0".to_string())?;
+        match count {
+            Some(count) if index >= count => Err(format!(
+                "there are {count} features, from 0 to {}",
+                count - 1
+            )),
+            _ => Ok(index),
+        }
+    }
+
+    /// Start reading feature `index` of `source` in the background, recording any
+    /// requests in `transfer`. Must be called from within a tokio runtime
+    pub fn run(
+        source: &str,
+        index: usize,
+        transfer: Arc<TransferStats>,
+        options: OpenOptions,
+    ) -> Self {
+        let task = tokio::spawn({
+            let source = source.to_string();
+            async move { dataset::feature_at(&source, index, transfer, options).await }
+        });
+        Self::Running {
+            index,
+            started: Instant::now(),
+            task,
+        }
+    }
+}
@@ -0,0 +1,596 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http_range_client::{AsyncBufferedHttpRangeClient, AsyncHttpRangeClient, HttpError};
+use reqwest::{StatusCode, header::CONTENT_RANGE};
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+};
+
+use crate::{
+    dataset::OpenOptions,
+    diagnostics,
+    http::{CountingClient, FileVersion, HttpOptions, TransferStats},
+};
+
+#[cfg(any(feature = "gcs", feature = "azure", feature = "aws"))]
+use crate::http::file_changed;
+#[cfg(any(feature = "gcs", feature = "azure", feature = "aws"))]
+use object_store::{ClientConfigKey, GetOptions, ObjectStore, path::Path as ObjectPath};
+
+/// Somewhere the bytes of a flatgeobuf file can be read from a range at a time, which the
+/// async reader opens remote files through. Implement it to read files from other storage
+#[async_trait]
+pub trait RangeSource: Send + Sync {
+    /// `length` bytes starting at `offset`, or fewer if the file ends first
+    async fn read_range(&self, offset: u64, length: u64) -> Result<Bytes, String>;
+
+    /// The size of the whole file, if known so far
+    fn total_size(&self) -> Option<u64>;
+
+    /// Called once the header has been read, for sources which treat reading it
+    /// differently from reading features
+    fn finish_header(&self) {}
+
+    /// Which version of the file was read, for sources where it can be replaced while open
+    fn version(&self) -> Option<FileVersion> {
+        None
+    }
+}
+
+/// Where a file given on the command line is, from the scheme of its URL
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheme {
+    Local,
+    Http,
+    S3,
+    Gcs,
+    Azure,
+}
+
+impl Scheme {
+    pub fn of(source: &str) -> Self {
+        match source.split_once("://").map(|(scheme, _)| scheme) {
+            Some("http" | "https") => Self::Http,
+            Some("s3") => Self::S3,
+            Some("gs") => Self::Gcs,
+            Some("az" | "azure") => Self::Azure,
+            _ => Self::Local,
+        }
+    }
+}
+
+/// Open `source` as a [`RangeSource`], recording any requests in `transfer`. For remote
+/// files this makes the first request, which checks the header cache
+pub async fn open_range_source(
+    source: &str,
+    transfer: Arc<TransferStats>,
+    options: &OpenOptions,
+) -> Result<Arc<dyn RangeSource>, String> {
+    match Scheme::of(source) {
+        Scheme::Local => Ok(Arc::new(LocalSource::open(source)?)),
+        Scheme::S3 if cfg!(feature = "aws") => {
+            open_object_store(source, transfer, &options.http).await
+        }
+        Scheme::Http | Scheme::S3 => {
+            let mut client = CountingClient::new(transfer, &options.http)?;
+            let url = client.resolve(source).await?;
+            let content_length = client.content_length(&url).await?;
+            Ok(Arc::new(HttpSource {
+                client,
+                url,
+                content_length,
+                range_total: AtomicU64::new(0),
+                whole: Mutex::default(),
+                probed: AtomicBool::new(false),
+            }))
+        }
+        Scheme::Gcs | Scheme::Azure => open_object_store(source, transfer, &options.http).await,
+    }
+}
+
+/// Open a `gs://bucket/path`, `az://container/path` or `s3://bucket/path` URL, with the
+/// store configured from the environment the way the object_store crate documents, plus
+/// the timeout, `--proxy` and `--insecure` of `options`, and `--aws-region` for S3
+#[cfg(any(feature = "gcs", feature = "azure", feature = "aws"))]
+async fn open_object_store(
+    source: &str,
+    transfer: Arc<TransferStats>,
+    options: &HttpOptions,
+) -> Result<Arc<dyn RangeSource>, String> {
+    let key = source
+        .split_once("://")
+        .and_then(|(_, path)| path.split_once('/'))
+        .map(|(_, key)| key)
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| format!("Expected a bucket and path in {source}"))?;
+    let path = ObjectPath::from_url_path(key).map_err(|e| format!("Invalid path {key}: {e}"))?;
+    let mut client = vec![(
+        ClientConfigKey::Timeout,
+        format!("{}ms", options.timeout.as_millis()),
+    )];
+    if let Some(proxy) = &options.proxy {
+        client.push((ClientConfigKey::ProxyUrl, proxy.clone()));
+    }
+    if options.insecure {
+        client.push((
+            ClientConfigKey::AllowInvalidCertificates,
+            "true".to_string(),
+        ));
+    }
+    let store: Arc<dyn ObjectStore> = match Scheme::of(source) {
+        #[cfg(feature = "gcs")]
+        Scheme::Gcs => {
+            use object_store::gcp::{GoogleCloudStorageBuilder, GoogleConfigKey};
+            let builder = client.into_iter().fold(
+                GoogleCloudStorageBuilder::from_env().with_url(source),
+                |builder, (key, value)| builder.with_config(GoogleConfigKey::Client(key), value),
+            );
+            Arc::new(
+                builder
+                    .build()
+                    .map_err(|e| format!("Can't open {source}: {e}"))?,
+            )
+        }
+        #[cfg(feature = "azure")]
+        Scheme::Azure => {
+            use object_store::azure::{AzureConfigKey, MicrosoftAzureBuilder};
+            let builder = client.into_iter().fold(
+                MicrosoftAzureBuilder::from_env().with_url(source),
+                |builder, (key, value)| builder.with_config(AzureConfigKey::Client(key), value),
+            );
+            Arc::new(
+                builder
+                    .build()
+                    .map_err(|e| format!("Can't open {source}: {e}"))?,
+            )
+        }
+        #[cfg(feature = "aws")]
+        Scheme::S3 => {
+            use object_store::aws::{AmazonS3Builder, AmazonS3ConfigKey};
+            let bucket = source["s3://".len()..]
+                .split('/')
+                .next()
+                .unwrap_or_default();
+            let region = CountingClient::new(transfer.clone(), options)?
+                .s3_region(bucket)
+                .await?;
+            // path style by default, which works for buckets with dots in their names
+            let builder = client.into_iter().fold(
+                AmazonS3Builder::from_env()
+                    .with_url(source)
+                    .with_region(region),
+                |builder, (key, value)| builder.with_config(AmazonS3ConfigKey::Client(key), value),
+            );
+            Arc::new(
+                builder
+                    .build()
+                    .map_err(|e| format!("Can't open {source}: {e}"))?,
+            )
+        }
+        _ => return Err(without_feature(source)),
+    };
+    Ok(Arc::new(
+        ObjectStoreSource::open(store, path, source, transfer).await?,
+    ))
+}
+
+#[cfg(not(any(feature = "gcs", feature = "azure", feature = "aws")))]
+async fn open_object_store(
+    source: &str,
+    _transfer: Arc<TransferStats>,
+    _options: &HttpOptions,
+) -> Result<Arc<dyn RangeSource>, String> {
+    Err(without_feature(source))
+}
+
+/// Why `source` can't be opened by a build without the feature for its scheme
+fn without_feature(source: &str) -> String {
+    let feature = match Scheme::of(source) {
+        Scheme::Gcs => "gcs",
+        Scheme::S3 => "aws",
+        _ => "azure",
+    };
+    format!(
+        "{source} can't be opened: fgbdump was built without the {feature} feature; \
+         use a signed HTTPS URL for it instead"
+    )
+}
+
+/// The client for an async flatgeobuf reader on `source`
+pub fn range_client(source: Arc<dyn RangeSource>) -> AsyncBufferedHttpRangeClient<SourceClient> {
+    // the URL is only passed back to `SourceClient`, which already knows where to read
+    AsyncBufferedHttpRangeClient::with(SourceClient(source), "")
+}
+
+/// A file read with HTTP range requests
+struct HttpSource {
+    client: CountingClient,
+    url: String,
+    /// From the HEAD request made when opening it
+    content_length: Option<u64>,
+    /// From the Content-Range of the range requests, for servers which don't answer a
+    /// HEAD with the length; 0 until known
+    range_total: AtomicU64,
+    /// The whole file, from a server which ignored the range asked for, once allowed
+    /// with `--allow-full-download`
+    whole: Mutex<Option<Bytes>>,
+    /// Whether the first range request, which shows if the server supports them, was made
+    probed: AtomicBool,
+}
+
+#[async_trait]
+impl RangeSource for HttpSource {
+    async fn read_range(&self, offset: u64, length: u64) -> Result<Bytes, String> {
+        if let Some(whole) = &*self.whole.lock().unwrap() {
+            return Ok(slice_range(whole, offset, length));
+        }
+        let range = format!("bytes={offset}-{}", offset + length.max(1) - 1);
+        let result = self.client.fetch_range(&self.url, &range).await;
+        let first = !self.probed.swap(true, Ordering::Relaxed);
+        let (status, headers, bytes) = result.map_err(|e| {
+            let message = self.client.range_error("reading", &self.url, e);
+            if first && self.client.verbose() {
+                diagnostics::note(&format!("Probing range requests: {message}"));
+            }
+            message
+        })?;
+        if status == StatusCode::OK {
+            // only with --allow-full-download, or the request would have failed
+            if first && self.client.verbose() {
+                diagnostics::note(&format!(
+                    "{} ignores range requests; downloaded all {} bytes of it",
+                    self.url,
+                    bytes.len()
+                ));
+            }
+            self.range_total
+                .store(bytes.len() as u64, Ordering::Relaxed);
+            let slice = slice_range(&bytes, offset, length);
+            *self.whole.lock().unwrap() = Some(bytes);
+            return Ok(slice);
+        }
+        // `bytes 8-11/1234`, where the total may be `*` if unknown
+        let content_range = headers.get(CONTENT_RANGE).and_then(|val| val.to_str().ok());
+        let start = content_range
+            .and_then(|val| val.strip_prefix("bytes "))
+            .and_then(|val| val.split_once('-'))
+            .and_then(|(start, _)| start.parse::<u64>().ok());
+        if start != Some(offset) {
+            return Err(format!(
+                "{} answered a request for {range} with the wrong range: {}",
+                self.url,
+                content_range.unwrap_or("no Content-Range")
+            ));
+        }
+        if first && self.client.verbose() {
+            diagnostics::note(&format!(
+                "{} supports range requests: {range} returned {}",
+                self.url,
+                content_range.unwrap_or_default()
+            ));
+        }
+        if let Some(total) = content_range
+            .and_then(|val| val.rsplit_once('/'))
+            .and_then(|(_, total)| total.parse::<u64>().ok())
+        {
+            self.range_total.store(total, Ordering::Relaxed);
+        }
+        Ok(bytes)
+    }
+
+    fn total_size(&self) -> Option<u64> {
+        self.content_length
+            .or(Some(self.range_total.load(Ordering::Relaxed)).filter(|&total| total > 0))
+    }
+
+    fn finish_header(&self) {
+        self.client.finish_header();
+    }
+
+    fn version(&self) -> Option<FileVersion> {
+        self.client.version()
+    }
+}
+
+/// `length` bytes of `whole` from `offset`, or fewer if it ends first
+fn slice_range(whole: &Bytes, offset: u64, length: u64) -> Bytes {
+    let start = (offset as usize).min(whole.len());
+    let end = start.saturating_add(length as usize).min(whole.len());
+    whole.slice(start..end)
+}
+
+/// A file in cloud storage, read through the object_store crate
+#[cfg(any(feature = "gcs", feature = "azure", feature = "aws"))]
+pub struct ObjectStoreSource {
+    store: Arc<dyn ObjectStore>,
+    path: ObjectPath,
+    /// The URL it was opened from, for errors
+    url: String,
+    size: u64,
+    /// The ETag of the object when it was opened, which every read must still match
+    etag: Option<String>,
+    transfer: Arc<TransferStats>,
+}
+
+#[cfg(any(feature = "gcs", feature = "azure", feature = "aws"))]
+impl ObjectStoreSource {
+    /// Open the object at `path` in `store`, which `url` names, recording reads in
+    /// `transfer`
+    pub async fn open(
+        store: Arc<dyn ObjectStore>,
+        path: ObjectPath,
+        url: &str,
+        transfer: Arc<TransferStats>,
+    ) -> Result<Self, String> {
+        let meta = store
+            .head(&path)
+            .await
+            .map_err(|e| format!("Failed to open {url}: {e}"))?;
+        transfer.record(0);
+        Ok(Self {
+            store,
+            path,
+            url: url.to_string(),
+            size: meta.size,
+            etag: meta.e_tag,
+            transfer,
+        })
+    }
+}
+
+#[cfg(any(feature = "gcs", feature = "azure", feature = "aws"))]
+#[async_trait]
+impl RangeSource for ObjectStoreSource {
+    async fn read_range(&self, offset: u64, length: u64) -> Result<Bytes, String> {
+        let end = offset.saturating_add(length).min(self.size);
+        if offset >= end {
+            return Ok(Bytes::new());
+        }
+        let options = GetOptions {
+            range: Some((offset..end).into()),
+            if_match: self.etag.clone(),
+            ..Default::default()
+        };
+        let bytes = match self.store.get_opts(&self.path, options).await {
+            Ok(result) => result.bytes().await,
+            Err(e) => Err(e),
+        }
+        .map_err(|e| match e {
+            object_store::Error::Precondition { .. } => file_changed(&self.url),
+            e => format!("Failed to read {}: {e}", self.url),
+        })?;
+        self.transfer.record(bytes.len() as u64);
+        Ok(bytes)
+    }
+
+    fn total_size(&self) -> Option<u64> {
+        Some(self.size)
+    }
+}
+
+/// A file on disk, read a range at a time like a remote one
+pub struct LocalSource {
+    file: Mutex<File>,
+    size: Option<u64>,
+}
+
+impl LocalSource {
+    pub fn open(source: &str) -> Result<Self, String> {
+        let file = File::open(source).map_err(|e| format!("Failed to open {source}: {e}"))?;
+        let size = file.metadata().ok().map(|metadata| metadata.len());
+        Ok(Self {
+            file: Mutex::new(file),
+            size,
+        })
+    }
+}
+
+#[async_trait]
+impl RangeSource for LocalSource {
+    async fn read_range(&self, offset: u64, length: u64) -> Result<Bytes, String> {
+        let mut file = self.file.lock().unwrap();
+        let mut buf = Vec::new();
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| (&mut *file).take(length).read_to_end(&mut buf))
+            .map_err(|e| format!("Failed to read the file: {e}"))?;
+        Ok(buf.into())
+    }
+
+    fn total_size(&self) -> Option<u64> {
+        self.size
+    }
+}
+
+/// A [`RangeSource`] read as though the features from `chunk_start` on came straight
+/// after the index, so that a reader opened on it reads only from there, like the
+/// `ChunkReader` of local files
+pub struct ChunkSource {
+    inner: Arc<dyn RangeSource>,
+    /// Where the features start in the file, and so where the chunk starts in what's read
+    features_start: u64,
+    chunk_start: u64,
+}
+
+impl ChunkSource {
+    pub fn new(inner: Arc<dyn RangeSource>, features_start: u64, chunk_start: u64) -> Self {
+        Self {
+            inner,
+            features_start,
+            chunk_start,
+        }
+    }
+}
+
+#[async_trait]
+impl RangeSource for ChunkSource {
+    async fn read_range(&self, offset: u64, length: u64) -> Result<Bytes, String> {
+        let skipped = self.chunk_start - self.features_start;
+        if offset >= self.features_start {
+            return self.inner.read_range(offset + skipped, length).await;
+        }
+        // a read running on from the header and index into the features takes the rest
+        // from the chunk
+        let before = length.min(self.features_start - offset);
+        let start = self.inner.read_range(offset, before).await?;
+        if before == length || (start.len() as u64) < before {
+            return Ok(start);
+        }
+        let rest = self
+            .inner
+            .read_range(self.chunk_start, length - before)
+            .await?;
+        Ok([start, rest].concat().into())
+    }
+
+    fn total_size(&self) -> Option<u64> {
+        let skipped = self.chunk_start - self.features_start;
+        self.inner.total_size().map(|size| size - skipped)
+    }
+
+    fn version(&self) -> Option<FileVersion> {
+        self.inner.version()
+    }
+}
+
+/// Adapts a [`RangeSource`] to the client the flatgeobuf async reader expects
+pub struct SourceClient(Arc<dyn RangeSource>);
+
+#[async_trait]
+impl AsyncHttpRangeClient for SourceClient {
+    async fn get_range(&self, _url: &str, range: &str) -> http_range_client::Result<Bytes> {
+        let (start, end) = range
+            .strip_prefix("bytes=")
+            .and_then(|range| range.split_once('-'))
+            .and_then(|(start, end)| Some((start.parse::<u64>().ok()?, end.parse::<u64>().ok()?)))
+            .filter(|(start, end)| start <= end)
+            .ok_or_else(|| HttpError::HttpError(format!("unexpected range {range}")))?;
+        self.0
+            .read_range(start, end - start + 1)
+            .await
+            .map_err(HttpError::HttpError)
+    }
+
+    async fn head_response_header(
+        &self,
+        _url: &str,
+        _header: &str,
+    ) -> http_range_client::Result<Option<String>> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(feature = "gcs", feature = "azure", feature = "aws"))]
+    async fn in_memory(data: &[u8]) -> (Arc<dyn ObjectStore>, ObjectPath) {
+        let store = object_store::memory::InMemory::new();
+        let path = ObjectPath::from("dir/file.fgb");
+        store
+            .put(&path, Bytes::copy_from_slice(data).into())
+            .await
+            .unwrap();
+        (Arc::new(store), path)
+    }
+
+    #[cfg(any(feature = "gcs", feature = "azure", feature = "aws"))]
+    #[tokio::test]
+    async fn object_store_ranges() {
+        let (store, path) = in_memory(b"0123456789").await;
+        let transfer = Arc::new(TransferStats::default());
+        let source =
+            ObjectStoreSource::open(store, path, "gs://bucket/dir/file.fgb", transfer.clone())
+                .await
+                .unwrap();
+        assert_eq!(source.total_size(), Some(10));
+        assert_eq!(source.read_range(2, 3).await.unwrap(), &b"234"[..]);
+        // ranges running off the end are cut short, like an HTTP server would
+        assert_eq!(source.read_range(8, 10).await.unwrap(), &b"89"[..]);
+        assert!(source.read_range(10, 4).await.unwrap().is_empty());
+        assert_eq!(transfer.bytes(), 5);
+    }
+
+    #[cfg(any(feature = "gcs", feature = "azure", feature = "aws"))]
+    #[tokio::test]
+    async fn object_store_file_is_read_by_the_async_reader() {
+        let data = std::fs::read("testdata/colorado_subset.fgb").unwrap();
+        let (store, path) = in_memory(&data).await;
+        let source =
+            ObjectStoreSource::open(store, path, "gs://bucket/dir/file.fgb", Arc::default())
+                .await
+                .unwrap();
+        let fgb = flatgeobuf::HttpFgbReader::new(range_client(Arc::new(source)))
+            .await
+            .unwrap();
+        assert_eq!(fgb.header().features_count(), 370);
+        let mut features = fgb.select_all().await.unwrap();
+        let mut count = 0;
+        while features.next().await.unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 370);
+    }
+
+    #[cfg(any(feature = "gcs", feature = "azure", feature = "aws"))]
+    #[tokio::test]
+    async fn object_store_file_replaced_while_open() {
+        let store: Arc<dyn ObjectStore> = Arc::new(object_store::memory::InMemory::new());
+        let path = ObjectPath::from("file.fgb");
+        store
+            .put(&path, Bytes::from("before").into())
+            .await
+            .unwrap();
+        let source = ObjectStoreSource::open(
+            store.clone(),
+            path.clone(),
+            "az://container/file.fgb",
+            Arc::default(),
+        )
+        .await
+        .unwrap();
+        store
+            .put(&path, Bytes::from("after!").into())
+            .await
+            .unwrap();
+        let error = source.read_range(0, 6).await.unwrap_err();
+        assert!(error.contains(crate::http::FILE_CHANGED), "{error}");
+    }
+
+    #[cfg(not(feature = "gcs"))]
+    #[tokio::test]
+    async fn gcs_needs_its_feature() {
+        let error = open_range_source(
+            "gs://bucket/file.fgb",
+            Arc::default(),
+            &OpenOptions::default(),
+        )
+        .await
+        .err()
+        .unwrap();
+        assert!(error.contains("built without the gcs feature"), "{error}");
+    }
+
+    #[test]
+    fn schemes() {
+        for (source, scheme) in [
+            ("data/file.fgb", Scheme::Local),
+            ("C:\\data\\file.fgb", Scheme::Local),
+            ("https://example.com/file.fgb", Scheme::Http),
+            ("s3://bucket/file.fgb", Scheme::S3),
+            ("gs://bucket/file.fgb", Scheme::Gcs),
+            ("az://container/file.fgb", Scheme::Azure),
+            ("azure://container/file.fgb", Scheme::Azure),
+        ] {
+            assert_eq!(Scheme::of(source), scheme, "{source}");
+        }
+    }
+}
@@ -0,0 +1,1739 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use flatgeobuf::FgbFeature;
+use geozero::{ColumnValue, FeatureProperties, PropertyProcessor};
+use serde_json::{Value, json};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::OnceLock,
+};
+
+/// How many distinct values of a column are counted, beyond which further new values
+/// are only counted together, to bound memory on columns with a value for every feature
+const MAX_DISTINCT_VALUES: usize = 10_000;
+
+/// How many of the most frequent values of a column are reported
+pub const TOP_VALUES: usize = 10;
+
+/// What reading every feature found for one column
+#[derive(Clone, Debug)]
+pub struct ColumnStats {
+    pub name: String,
+    /// The type from the header, like `String`
+    pub column_type: String,
+    /// How many features have a value for the column; the rest are null
+    pub present: usize,
+    /// The smallest and largest values, for numeric and date-time columns
+    pub range: Option<Range>,
+    /// How many values of a floating point column are NaN, which are left out of `range`
+    pub nans: usize,
+    /// How many values are of a different type than the header gives the column, or are
+    /// date-times which can't be parsed, which are left out of `range` too
+    pub invalid: usize,
+    /// How often each value occurs, for string and small integer columns
+    pub values: Option<ValueCounts>,
+    kind: ValueKind,
+    /// The number of features read when this column was last counted, so that a
+    /// column repeated within a feature is only counted once
+    counted_at: usize,
+}
+
+/// The smallest and largest values of a column
+#[derive(Clone, Debug, PartialEq)]
+pub enum Range {
+    Integer {
+        min: i128,
+        max: i128,
+    },
+    Float {
+        min: f64,
+        max: f64,
+    },
+    /// As written in the file, compared by the instant they give
+    DateTime {
+        earliest: (i64, String),
+        latest: (i64, String),
+    },
+}
+
+impl Range {
+    /// The smallest and largest values, for printing
+    pub fn bounds(&self) -> (String, String) {
+        match self {
+            Self::Integer { min, max } => (min.to_string(), max.to_string()),
+            Self::Float { min, max } => (min.to_string(), max.to_string()),
+            Self::DateTime { earliest, latest } => (earliest.1.clone(), latest.1.clone()),
+        }
+    }
+
+    fn to_json(&self) -> (Value, Value) {
+        match self {
+            Self::Integer { min, max } => (json_integer(*min), json_integer(*max)),
+            Self::Float { min, max } => (json!(min), json!(max)),
+            Self::DateTime { earliest, latest } => (json!(earliest.1), json!(latest.1)),
+        }
+    }
+}
+
+/// Every integer column type fits in an i64 or a u64, which JSON numbers can hold
+fn json_integer(value: i128) -> Value {
+    i64::try_from(value)
+        .map(Value::from)
+        .or_else(|_| u64::try_from(value).map(Value::from))
+        .unwrap_or_else(|_| value.to_string().into())
+}
+
+/// How often each value of a column occurs, for as many distinct values as
+/// [`MAX_DISTINCT_VALUES`]
+#[derive(Clone, Debug, Default)]
+pub struct ValueCounts {
+    /// How often each value occurs, and how many values were counted before it was first
+    /// seen, which is the order merging adds them in
+    counts: HashMap<String, (usize, usize)>,
+    /// How many values weren't counted individually, once the limit was reached
+    pub other: usize,
+}
+
+impl ValueCounts {
+    fn add(&mut self, value: String) {
+        self.add_count(value, 1);
+    }
+
+    fn add_count(&mut self, value: String, count: usize) {
+        let seen = self.counts.len();
+        if let Some((counted, _)) = self.counts.get_mut(&value) {
+            *counted += count;
+        } else if seen < MAX_DISTINCT_VALUES {
+            self.counts.insert(value, (count, seen));
+        } else {
+            self.other += count;
+        }
+    }
+
+    /// Whether every value was counted individually, without reaching the limit
+    pub fn complete(&self) -> bool {
+        self.other == 0
+    }
+
+    /// Add the counts of the values which follow. New values are added in the order they
+    /// were first seen, so that the same are counted individually as reading in one pass,
+    /// as long as `other` is [`complete`](Self::complete); otherwise some of its values
+    /// which one pass would count may be among those it didn't
+    fn merge(&mut self, other: Self) {
+        let mut counts: Vec<(String, (usize, usize))> = other.counts.into_iter().collect();
+        counts.sort_unstable_by_key(|(_, (_, seen))| *seen);
+        for (value, (count, _)) in counts {
+            self.add_count(value, count);
+        }
+        self.other += other.other;
+    }
+
+    /// The `k` most frequent values, most frequent first and then in order
+    pub fn top(&self, k: usize) -> Vec<(&str, usize)> {
+        let mut top: Vec<(&str, usize)> = self
+            .counts
+            .iter()
+            .map(|(value, &(count, _))| (value.as_str(), count))
+            .collect();
+        top.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        top.truncate(k);
+        top
+    }
+
+    /// The `k` most frequent values, like `NY (14302), NJ (9118), …`
+    pub fn summary(&self, k: usize) -> String {
+        let top = self.top(k + 1);
+        let mut summary: Vec<String> = top
+            .iter()
+            .take(k)
+            .map(|(value, count)| format!("{value} ({count})"))
+            .collect();
+        if top.len() > k || self.other > 0 {
+            summary.push("…".to_string());
+        }
+        summary.join(", ")
+    }
+}
+
+/// What kind of values the header says a column has, for their range
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ValueKind {
+    Integer,
+    Float,
+    DateTime,
+    Other,
+}
+
+impl ValueKind {
+    fn of(column_type: &str) -> Self {
+        match column_type {
+            "Byte" | "UByte" | "Short" | "UShort" | "Int" | "UInt" | "Long" | "ULong" => {
+                Self::Integer
+            }
+            "Float" | "Double" => Self::Float,
+            "DateTime" => Self::DateTime,
+            _ => Self::Other,
+        }
+    }
+
+    /// Whether the most frequent values are counted by default
+    fn counts_values(column_type: &str) -> bool {
+        matches!(
+            column_type,
+            "String" | "Bool" | "Byte" | "UByte" | "Short" | "UShort"
+        )
+    }
+}
+
+/// Statistics about the features of a file, gathered in one pass over all of them
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    /// How many features were read
+    pub features: usize,
+    /// In header order
+    pub columns: Vec<ColumnStats>,
+    /// The vertices and sizes of all the features
+    pub geometry: GeometryStats,
+    /// The same by geometry type, like `MultiPolygon`, or `None` for features without
+    /// a geometry
+    pub geometry_types: BTreeMap<String, GeometryStats>,
+    /// Whether the features are in the order of the Hilbert curve, if the header has
+    /// an envelope to lay the curve over
+    pub sortedness: Option<Sortedness>,
+    /// The size of each feature, to see where in the file the largest are
+    pub sizes: FeatureSizes,
+    /// The geometry type from the header, which is `Unknown` when the features may be
+    /// of different types
+    header_geometry_type: flatgeobuf::GeometryType,
+}
+
+/// How many vertices the features have, and how many bytes they take up in the file
+#[derive(Clone, Debug, Default)]
+pub struct GeometryStats {
+    pub vertices: Summary,
+    /// Including the length prefix of each feature, so that the total is the size of
+    /// the features in the file
+    pub bytes: Summary,
+    /// How many features are a power of two bytes long, by the power, such that
+    /// `size_classes[10]` counts the features of 1024 to 2047 bytes
+    pub size_classes: Vec<usize>,
+}
+
+impl GeometryStats {
+    fn add(&mut self, vertices: u64, bytes: u64) {
+        self.vertices.add(vertices);
+        self.bytes.add(bytes);
+        let class = bytes.max(1).ilog2() as usize;
+        if self.size_classes.len() <= class {
+            self.size_classes.resize(class + 1, 0);
+        }
+        self.size_classes[class] += 1;
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.vertices.merge(other.vertices);
+        self.bytes.merge(other.bytes);
+        if self.size_classes.len() < other.size_classes.len() {
+            self.size_classes.resize(other.size_classes.len(), 0);
+        }
+        for (count, other) in self.size_classes.iter_mut().zip(other.size_classes) {
+            *count += other;
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "features": self.vertices.features,
+            "vertices": self.vertices.to_json(),
+            "bytes": self.bytes.to_json(),
+        })
+    }
+}
+
+/// The smallest, mean, largest and total of a number counted for each feature
+#[derive(Clone, Debug, Default)]
+pub struct Summary {
+    pub features: usize,
+    pub min: u64,
+    pub max: u64,
+    pub total: u64,
+}
+
+impl Summary {
+    fn add(&mut self, value: u64) {
+        if self.features == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.features += 1;
+        self.total += value;
+    }
+
+    fn merge(&mut self, other: Self) {
+        if self.features == 0 {
+            *self = other;
+        } else if other.features > 0 {
+            self.min = self.min.min(other.min);
+            self.max = self.max.max(other.max);
+            self.features += other.features;
+            self.total += other.total;
+        }
+    }
+
+    pub fn mean(&self) -> f64 {
+        match self.features {
+            0 => 0.0,
+            features => self.total as f64 / features as f64,
+        }
+    }
+
+    /// Like `min 4, mean 12.5, max 4096, total 125000`
+    pub fn text(&self) -> String {
+        format!(
+            "min {}, mean {:.1}, max {}, total {}",
+            self.min,
+            self.mean(),
+            self.max,
+            self.total
+        )
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "min": self.min,
+            "mean": self.mean(),
+            "max": self.max,
+            "total": self.total,
+        })
+    }
+}
+
+/// The size in bytes of each feature in file order, which takes four bytes a feature
+#[derive(Clone, Debug, Default)]
+pub struct FeatureSizes {
+    sizes: Vec<u32>,
+    /// Found the first time it's asked for, since that means sorting a copy of the sizes
+    median: OnceLock<Option<u32>>,
+}
+
+impl FeatureSizes {
+    fn add(&mut self, bytes: u64) {
+        // the length prefix is a u32, so only it can take a feature past u32::MAX
+        self.sizes.push(bytes.try_into().unwrap_or(u32::MAX));
+        self.median = OnceLock::new();
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.sizes.extend(other.sizes);
+        self.median = OnceLock::new();
+    }
+
+    pub fn len(&self) -> usize {
+        self.sizes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sizes.is_empty()
+    }
+
+    /// The middle size, or the smaller of the two middle sizes of an even number
+    pub fn median(&self) -> Option<u32> {
+        *self.median.get_or_init(|| {
+            let mut sorted = self.sizes.clone();
+            let middle = sorted.len().checked_sub(1)? / 2;
+            Some(*sorted.select_nth_unstable(middle).1)
+        })
+    }
+
+    /// The largest size in each of up to `count` runs of consecutive features, as even
+    /// as they can be, so that a few huge features stand out however many there are
+    pub fn buckets(&self, count: usize) -> Vec<u64> {
+        let count = count.min(self.sizes.len());
+        (0..count)
+            .map(|i| {
+                let start = i * self.sizes.len() / count;
+                let end = (i + 1) * self.sizes.len() / count;
+                self.sizes[start..end]
+                    .iter()
+                    .max()
+                    .map_or(0, |&size| size.into())
+            })
+            .collect()
+    }
+}
+
+impl Stats {
+    /// Statistics for the columns in `header`. The most frequent values are counted for
+    /// the columns named in `value_columns`, or by default for string and small integer
+    /// columns
+    pub fn new(
+        header: &flatgeobuf::Header,
+        value_columns: Option<&[String]>,
+    ) -> Result<Self, String> {
+        let columns: Vec<ColumnStats> = header
+            .columns()
+            .map(|columns| {
+                columns
+                    .iter()
+                    .map(|column| {
+                        let column_type = column.type_().variant_name().unwrap_or("Unknown");
+                        let counts_values = match value_columns {
+                            Some(names) => names.iter().any(|name| name == column.name()),
+                            None => ValueKind::counts_values(column_type),
+                        };
+                        ColumnStats {
+                            name: column.name().to_string(),
+                            column_type: column_type.to_string(),
+                            present: 0,
+                            range: None,
+                            nans: 0,
+                            invalid: 0,
+                            values: counts_values.then(ValueCounts::default),
+                            kind: ValueKind::of(column_type),
+                            counted_at: 0,
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        for name in value_columns.unwrap_or_default() {
+            if !columns.iter().any(|column| &column.name == name) {
+                return Err(format!("There is no column named {name}"));
+            }
+        }
+        Ok(Self {
+            features: 0,
+            columns,
+            geometry: GeometryStats::default(),
+            geometry_types: BTreeMap::new(),
+            sortedness: Sortedness::new(header),
+            sizes: FeatureSizes::default(),
+            header_geometry_type: header.geometry_type(),
+        })
+    }
+
+    /// Count the geometry and properties of another feature
+    pub fn add(&mut self, feature: &FgbFeature) -> Result<(), String> {
+        self.features += 1;
+        // the buffer of a feature is as long as its length prefix says, plus the prefix
+        let bytes = feature.fbs_feature()._tab.buf().len() as u64;
+        let vertices = feature.geometry().as_ref().map_or(0, vertex_count);
+        let geometry_type =
+            feature_geometry_type(self.header_geometry_type, feature.geometry().as_ref());
+        self.geometry.add(vertices, bytes);
+        self.sizes.add(bytes);
+        if let Some(sortedness) = &mut self.sortedness {
+            sortedness.add(feature.geometry().as_ref());
+        }
+        self.geometry_types
+            .entry(geometry_type)
+            .or_default()
+            .add(vertices, bytes);
+        let mut processor = StatsProcessor {
+            columns: &mut self.columns,
+            feature: self.features,
+        };
+        feature
+            .process_properties(&mut processor)
+            .map_err(|e| format!("Failed to decode the properties of a feature: {e}"))?;
+        Ok(())
+    }
+
+    /// Whether the most frequent values of every column were counted without reaching the
+    /// limit on distinct values, which merging the statistics which come before needs to
+    /// give the same as reading them all at once
+    pub fn values_complete(&self) -> bool {
+        self.columns
+            .iter()
+            .filter_map(|column| column.values.as_ref())
+            .all(ValueCounts::complete)
+    }
+
+    /// Add what was found reading the features which follow those read for `self`, such
+    /// that merging the statistics of consecutive runs of the features in order gives
+    /// the same as reading them all at once, as long as each run after the first has
+    /// [`values_complete`](Self::values_complete)
+    pub fn merge(&mut self, other: Self) {
+        self.features += other.features;
+        for (column, other) in self.columns.iter_mut().zip(other.columns) {
+            column.merge(other);
+        }
+        self.geometry.merge(other.geometry);
+        for (geometry_type, other) in other.geometry_types {
+            self.geometry_types
+                .entry(geometry_type)
+                .or_default()
+                .merge(other);
+        }
+        if let (Some(sortedness), Some(other)) = (&mut self.sortedness, other.sortedness) {
+            sortedness.merge(other);
+        }
+        self.sizes.merge(other.sizes);
+    }
+
+    /// How many of the features read have no value for `column`
+    pub fn nulls(&self, column: &ColumnStats) -> usize {
+        self.features.saturating_sub(column.present)
+    }
+
+    /// The percentage of the features read with no value for `column`
+    pub fn null_percent(&self, column: &ColumnStats) -> f64 {
+        match self.features {
+            0 => 0.0,
+            features => self.nulls(column) as f64 / features as f64 * 100.0,
+        }
+    }
+
+    /// Whether no feature has a value for `column`, which usually means something went
+    /// wrong writing the file
+    pub fn all_null(&self, column: &ColumnStats) -> bool {
+        self.features > 0 && column.present == 0
+    }
+
+    pub fn to_json(&self) -> Value {
+        let columns: Vec<Value> = self
+            .columns
+            .iter()
+            .map(|column| {
+                let (min, max) = column
+                    .range
+                    .as_ref()
+                    .map_or((Value::Null, Value::Null), Range::to_json);
+                json!({
+                    "name": column.name,
+                    "type": column.column_type,
+                    "nulls": self.nulls(column),
+                    "null_percent": self.null_percent(column),
+                    "min": min,
+                    "max": max,
+                    "nans": column.nans,
+                    "invalid": column.invalid,
+                    "top_values": column.values.as_ref().map(|values| {
+                        let top: Vec<Value> = values
+                            .top(TOP_VALUES)
+                            .into_iter()
+                            .map(|(value, count)| json!({ "value": value, "count": count }))
+                            .collect();
+                        json!({ "values": top, "other": values.other })
+                    }),
+                })
+            })
+            .collect();
+        let geometry_types: serde_json::Map<String, Value> = self
+            .geometry_types
+            .iter()
+            .map(|(name, stats)| (name.clone(), stats.to_json()))
+            .collect();
+        json!({
+            "features": self.features,
+            "columns": columns,
+            "geometry": self.geometry.to_json(),
+            "geometry_types": geometry_types,
+            "spatially_sorted": self.sortedness.as_ref().map(Sortedness::to_json),
+        })
+    }
+
+    /// The columns as an aligned plain text table, one line per column
+    pub fn table(&self) -> Vec<String> {
+        let rows: Vec<Vec<String>> = self
+            .columns
+            .iter()
+            .map(|column| {
+                let mut nulls =
+                    format!("{} ({:.1}%)", self.nulls(column), self.null_percent(column));
+                if self.all_null(column) {
+                    nulls.push_str(" all null");
+                }
+                let (min, max) = column
+                    .range
+                    .as_ref()
+                    .map_or_else(Default::default, Range::bounds);
+                vec![
+                    column.name.clone(),
+                    column.column_type.clone(),
+                    nulls,
+                    min,
+                    max,
+                    column
+                        .values
+                        .as_ref()
+                        .map(|values| values.summary(3))
+                        .unwrap_or_default(),
+                    column.notes(),
+                ]
+            })
+            .collect();
+        align(
+            &[
+                "Column",
+                "Type",
+                "Nulls",
+                "Min",
+                "Max",
+                "Top values",
+                "Notes",
+            ],
+            &rows,
+        )
+    }
+
+    /// The vertices and sizes of the features as an aligned plain text table, with a line
+    /// for all of them and then one for each geometry type when there's more than one
+    pub fn geometry_table(&self) -> Vec<String> {
+        let row = |name: &str, stats: &GeometryStats| {
+            vec![
+                name.to_string(),
+                stats.vertices.features.to_string(),
+                stats.vertices.min.to_string(),
+                format!("{:.1}", stats.vertices.mean()),
+                stats.vertices.max.to_string(),
+                stats.vertices.total.to_string(),
+                stats.bytes.min.to_string(),
+                format!("{:.1}", stats.bytes.mean()),
+                stats.bytes.max.to_string(),
+                stats.bytes.total.to_string(),
+            ]
+        };
+        let mut rows = vec![row("All", &self.geometry)];
+        if self.geometry_types.len() > 1 {
+            rows.extend(
+                self.geometry_types
+                    .iter()
+                    .map(|(name, stats)| row(name, stats)),
+            );
+        }
+        align(
+            &[
+                "Geometry",
+                "Features",
+                "Min vertices",
+                "Mean",
+                "Max",
+                "Total",
+                "Min bytes",
+                "Mean",
+                "Max",
+                "Total",
+            ],
+            &rows,
+        )
+    }
+}
+
+/// How many features are of each geometry type, to see what a file whose header says
+/// `Unknown` actually holds, or whether a file holds other types than its header says
+#[derive(Clone, Debug, Default)]
+pub struct GeometryTypes {
+    /// The geometry type from the header
+    pub declared: flatgeobuf::GeometryType,
+    /// By geometry type, like `MultiPolygon`, or `None` for features without a geometry
+    pub counts: BTreeMap<String, usize>,
+    /// How many features were read
+    pub features: usize,
+    /// Whether there were more features than were read
+    pub sampled: bool,
+}
+
+impl GeometryTypes {
+    pub fn new(header: &flatgeobuf::Header) -> Self {
+        Self {
+            declared: header.geometry_type(),
+            ..Default::default()
+        }
+    }
+
+    /// Count a feature with `geometry`, if it has one
+    pub fn add(&mut self, geometry: Option<&flatgeobuf::Geometry>) {
+        self.features += 1;
+        *self
+            .counts
+            .entry(feature_geometry_type(self.declared, geometry))
+            .or_default() += 1;
+    }
+
+    /// The types by how many features are of them, most first
+    pub fn ranked(&self) -> Vec<(&str, usize)> {
+        let mut ranked: Vec<(&str, usize)> = self
+            .counts
+            .iter()
+            .map(|(name, &count)| (name.as_str(), count))
+            .collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        ranked
+    }
+
+    /// Like `Point 12034, MultiPolygon 88`
+    pub fn summary(&self) -> String {
+        let parts: Vec<String> = self
+            .ranked()
+            .into_iter()
+            .map(|(name, count)| format!("{name} {count}"))
+            .collect();
+        parts.join(", ")
+    }
+
+    /// Which features, like `the first 1000 features` when only some were read
+    pub fn read(&self) -> String {
+        if self.sampled {
+            format!("the first {} features", self.features)
+        } else {
+            format!("all {} features", self.features)
+        }
+    }
+
+    /// The types of features other than the one the header gives, if it gives one.
+    /// Features without a geometry aren't counted, since any type allows them
+    pub fn mismatched(&self) -> Vec<(&str, usize)> {
+        if self.declared == flatgeobuf::GeometryType::Unknown {
+            return Vec::new();
+        }
+        let declared = format!("{:?}", self.declared);
+        self.ranked()
+            .into_iter()
+            .filter(|&(name, _)| name != declared && name != "None")
+            .collect()
+    }
+
+    /// A line for each type, like `Point: 12034 (93.1%)`
+    pub fn lines(&self) -> Vec<String> {
+        let width = self.counts.keys().map(String::len).max().unwrap_or(0) + 1;
+        self.ranked()
+            .into_iter()
+            .map(|(name, count)| {
+                let percent = count as f64 / self.features.max(1) as f64 * 100.0;
+                format!("{:width$} {count} ({percent:.1}%)", format!("{name}:"))
+            })
+            .collect()
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "declared": format!("{:?}", self.declared),
+            "features": self.features,
+            "sampled": self.sampled,
+            "counts": self.counts,
+        })
+    }
+}
+
+/// What reading the first features of a file in order shows about them, which is
+/// enough to tell about a huge file without reading all of it
+#[derive(Clone, Debug, Default)]
+pub struct Sample {
+    pub types: GeometryTypes,
+    pub sortedness: Option<Sortedness>,
+}
+
+impl Sample {
+    pub fn new(header: &flatgeobuf::Header) -> Self {
+        Self {
+            types: GeometryTypes::new(header),
+            sortedness: Sortedness::new(header),
+        }
+    }
+
+    pub fn add(&mut self, feature: &FgbFeature) {
+        self.add_geometry(feature.geometry().as_ref());
+    }
+
+    /// Add a feature with `geometry`, if it has one, for features decoded by hand
+    pub fn add_geometry(&mut self, geometry: Option<&flatgeobuf::Geometry>) {
+        self.types.add(geometry);
+        if let Some(sortedness) = &mut self.sortedness {
+            sortedness.add(geometry);
+        }
+    }
+}
+
+/// How close the features are to being in the order of the Hilbert curve over the
+/// envelope, as writers sort them so that features near each other are near each other
+/// in the file too, which readers fetching ranges of a file without an index rely on.
+/// flatgeobuf and GDAL write them from the end of the curve back, so either direction
+/// counts
+#[derive(Clone, Debug)]
+pub struct Sortedness {
+    /// The envelope from the header, as min x, min y, max x and max y
+    extent: [f64; 4],
+    /// The position on the curve of the first feature with a geometry, for merging
+    first: Option<u32>,
+    /// The position on the curve of the last feature with a geometry
+    previous: Option<u32>,
+    /// Pairs of features with geometries which follow each other, ignoring any features
+    /// without one in between
+    pub pairs: usize,
+    /// Of those, the ones whose second feature is no earlier on the curve than the first
+    pub ascending: usize,
+    /// And the ones whose second feature is no later on the curve than the first
+    pub descending: usize,
+}
+
+impl Sortedness {
+    /// `None` if the header has no envelope
+    pub fn new(header: &flatgeobuf::Header) -> Option<Self> {
+        let envelope = header.envelope()?;
+        if envelope.len() < 4 {
+            return None;
+        }
+        Some(Self {
+            extent: [
+                envelope.get(0),
+                envelope.get(1),
+                envelope.get(2),
+                envelope.get(3),
+            ],
+            first: None,
+            previous: None,
+            pairs: 0,
+            ascending: 0,
+            descending: 0,
+        })
+    }
+
+    /// Add the next feature, which is left out of the pairs without a geometry
+    pub fn add(&mut self, geometry: Option<&flatgeobuf::Geometry>) {
+        let Some(bbox) = geometry.and_then(geometry_bbox) else {
+            return;
+        };
+        let [min_x, min_y, max_x, max_y] = self.extent;
+        // the center of the feature as a fraction of the way across the envelope, as
+        // writers place features on the curve
+        let scale = |value: f64, min: f64, max: f64| {
+            let fraction = if max > min {
+                (value - min) / (max - min)
+            } else {
+                0.0
+            };
+            (fraction.clamp(0.0, 1.0) * f64::from(HILBERT_MAX)) as u32
+        };
+        let code = hilbert(
+            scale((bbox[0] + bbox[2]) / 2.0, min_x, max_x),
+            scale((bbox[1] + bbox[3]) / 2.0, min_y, max_y),
+        );
+        if let Some(previous) = self.previous {
+            self.add_pair(previous, code);
+        }
+        self.first = self.first.or(Some(code));
+        self.previous = Some(code);
+    }
+
+    fn add_pair(&mut self, previous: u32, code: u32) {
+        self.pairs += 1;
+        if code >= previous {
+            self.ascending += 1;
+        }
+        if code <= previous {
+            self.descending += 1;
+        }
+    }
+
+    /// Add the pairs of the features which follow, counting the pair either side of
+    /// where one run of features ends and the next begins
+    fn merge(&mut self, other: Self) {
+        if let (Some(previous), Some(first)) = (self.previous, other.first) {
+            self.add_pair(previous, first);
+        }
+        self.pairs += other.pairs;
+        self.ascending += other.ascending;
+        self.descending += other.descending;
+        self.first = self.first.or(other.first);
+        self.previous = other.previous.or(self.previous);
+    }
+
+    /// Whether more pairs go back along the curve than forward
+    pub fn descending(&self) -> bool {
+        self.descending > self.ascending
+    }
+
+    /// The fraction of pairs in order, in whichever direction more are, or `None`
+    /// without any pairs
+    pub fn score(&self) -> Option<f64> {
+        let ordered = self.ascending.max(self.descending);
+        (self.pairs > 0).then(|| ordered as f64 / self.pairs as f64)
+    }
+
+    /// `yes`, `mostly`, or `no`; features in no particular order score about a half
+    pub fn verdict(&self) -> &'static str {
+        match self.score() {
+            Some(score) if score >= 0.95 => "yes",
+            Some(score) if score >= 0.75 => "mostly",
+            _ => "no",
+        }
+    }
+
+    /// Like `mostly (0.87 of 999 adjacent pairs in Hilbert order)`, or `in reverse
+    /// Hilbert order` when they go back along the curve
+    pub fn text(&self) -> String {
+        let order = if self.descending() {
+            "reverse Hilbert order"
+        } else {
+            "Hilbert order"
+        };
+        match self.score() {
+            Some(score) => format!(
+                "{} ({score:.2} of {} adjacent pairs in {order})",
+                self.verdict(),
+                self.pairs
+            ),
+            None => "unknown (fewer than two features with geometries)".to_string(),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "verdict": self.score().map(|_| self.verdict()),
+            "score": self.score(),
+            "direction": self
+                .score()
+                .map(|_| if self.descending() { "descending" } else { "ascending" }),
+            "pairs": self.pairs,
+        })
+    }
+}
+
+/// The largest coordinate on either axis of the Hilbert curve, as in the reference
+/// implementation
+const HILBERT_MAX: u32 = (1 << 16) - 1;
+
+/// The position of `x` and `y`, each from 0 to [`HILBERT_MAX`], along the Hilbert curve,
+/// computed as in the reference implementation so that sorted files score fully
+fn hilbert(x: u32, y: u32) -> u32 {
+    let mut a = x ^ y;
+    let mut b = 0xFFFF ^ a;
+    let mut c = 0xFFFF ^ (x | y);
+    let mut d = x & (y ^ 0xFFFF);
+
+    let mut aa = a | (b >> 1);
+    let mut bb = (a >> 1) ^ a;
+    let mut cc = ((c >> 1) ^ (b & (d >> 1))) ^ c;
+    let mut dd = ((a & (c >> 1)) ^ (d >> 1)) ^ d;
+
+    a = aa;
+    b = bb;
+    c = cc;
+    d = dd;
+    aa = (a & (a >> 2)) ^ (b & (b >> 2));
+    bb = (a & (b >> 2)) ^ (b & ((a ^ b) >> 2));
+    cc ^= (a & (c >> 2)) ^ (b & (d >> 2));
+    dd ^= (b & (c >> 2)) ^ ((a ^ b) & (d >> 2));
+
+    a = aa;
+    b = bb;
+    c = cc;
+    d = dd;
+    aa = (a & (a >> 4)) ^ (b & (b >> 4));
+    bb = (a & (b >> 4)) ^ (b & ((a ^ b) >> 4));
+    cc ^= (a & (c >> 4)) ^ (b & (d >> 4));
+    dd ^= (b & (c >> 4)) ^ ((a ^ b) & (d >> 4));
+
+    a = aa;
+    b = bb;
+    c = cc;
+    d = dd;
+    cc ^= (a & (c >> 8)) ^ (b & (d >> 8));
+    dd ^= (b & (c >> 8)) ^ ((a ^ b) & (d >> 8));
+
+    a = cc ^ (cc >> 1);
+    b = dd ^ (dd >> 1);
+
+    let mut i0 = x ^ y;
+    let mut i1 = b | (0xFFFF ^ (i0 | a));
+
+    i0 = (i0 | (i0 << 8)) & 0x00FF00FF;
+    i0 = (i0 | (i0 << 4)) & 0x0F0F0F0F;
+    i0 = (i0 | (i0 << 2)) & 0x33333333;
+    i0 = (i0 | (i0 << 1)) & 0x55555555;
+
+    i1 = (i1 | (i1 << 8)) & 0x00FF00FF;
+    i1 = (i1 | (i1 << 4)) & 0x0F0F0F0F;
+    i1 = (i1 | (i1 << 2)) & 0x33333333;
+    i1 = (i1 | (i1 << 1)) & 0x55555555;
+
+    (i1 << 1) | i0
+}
+
+/// The bounding box of `geometry` and its parts as min x, min y, max x and max y, or
+/// `None` if it has no positions
+fn geometry_bbox(geometry: &flatgeobuf::Geometry) -> Option<[f64; 4]> {
+    let mut bbox: Option<[f64; 4]> = None;
+    let mut extend = |other: [f64; 4]| {
+        bbox = Some(match bbox {
+            None => other,
+            Some([min_x, min_y, max_x, max_y]) => [
+                min_x.min(other[0]),
+                min_y.min(other[1]),
+                max_x.max(other[2]),
+                max_y.max(other[3]),
+            ],
+        });
+    };
+    if let Some(xy) = geometry.xy() {
+        for i in 0..xy.len() / 2 {
+            let (x, y) = (xy.get(i * 2), xy.get(i * 2 + 1));
+            extend([x, y, x, y]);
+        }
+    }
+    for part in geometry.parts().iter().flatten() {
+        if let Some(part) = geometry_bbox(&part) {
+            extend(part);
+        }
+    }
+    bbox
+}
+
+/// The geometry type of a feature with `geometry`, or `None` if it has none. The features of a
+/// file whose header gives a type usually leave theirs unset, and are of that type
+fn feature_geometry_type(
+    declared: flatgeobuf::GeometryType,
+    geometry: Option<&flatgeobuf::Geometry>,
+) -> String {
+    match geometry.map(|geometry| geometry.type_()) {
+        None => "None".to_string(),
+        Some(flatgeobuf::GeometryType::Unknown) => format!("{declared:?}"),
+        Some(geometry_type) => format!("{geometry_type:?}"),
+    }
+}
+
+/// `rows` under `header`, with each column as wide as its widest cell
+fn align(header: &[&str], rows: &[Vec<String>]) -> Vec<String> {
+    let header: Vec<String> = header.iter().map(|cell| cell.to_string()).collect();
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            std::iter::once(&header)
+                .chain(rows)
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    std::iter::once(&header)
+        .chain(rows)
+        .map(|row| {
+            let line: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{cell:width$}"))
+                .collect();
+            line.join("  ").trim_end().to_string()
+        })
+        .collect()
+}
+
+/// The number of positions in `geometry`, including those of the parts of multi
+/// geometries and collections
+fn vertex_count(geometry: &flatgeobuf::Geometry) -> u64 {
+    let own = geometry.xy().map_or(0, |xy| xy.len() / 2) as u64;
+    let parts: u64 = geometry.parts().map_or(0, |parts| {
+        parts.iter().map(|part| vertex_count(&part)).sum()
+    });
+    own + parts
+}
+
+impl ColumnStats {
+    /// Whether the column holds integers or floats
+    pub fn is_numeric(&self) -> bool {
+        matches!(self.kind, ValueKind::Integer | ValueKind::Float)
+    }
+
+    /// The NaN and invalid values, if there were any, like `3 NaN, 1 invalid`
+    pub fn notes(&self) -> String {
+        let mut notes = Vec::new();
+        if self.nans > 0 {
+            notes.push(format!("{} NaN", self.nans));
+        }
+        if self.invalid > 0 {
+            notes.push(format!("{} invalid", self.invalid));
+        }
+        notes.join(", ")
+    }
+
+    /// Add what was found for the same column in the features which follow
+    fn merge(&mut self, other: Self) {
+        self.present += other.present;
+        self.nans += other.nans;
+        self.invalid += other.invalid;
+        self.range = match (self.range.take(), other.range) {
+            (Some(Range::Integer { min, max }), Some(Range::Integer { min: a, max: b })) => {
+                Some(Range::Integer {
+                    min: min.min(a),
+                    max: max.max(b),
+                })
+            }
+            (Some(Range::Float { min, max }), Some(Range::Float { min: a, max: b })) => {
+                Some(Range::Float {
+                    min: min.min(a),
+                    max: max.max(b),
+                })
+            }
+            (
+                Some(Range::DateTime { earliest, latest }),
+                Some(Range::DateTime {
+                    earliest: other_earliest,
+                    latest: other_latest,
+                }),
+            ) => Some(Range::DateTime {
+                // the first of equal instants is kept, as when reading in one pass
+                earliest: if other_earliest.0 < earliest.0 {
+                    other_earliest
+                } else {
+                    earliest
+                },
+                latest: if other_latest.0 > latest.0 {
+                    other_latest
+                } else {
+                    latest
+                },
+            }),
+            (range, other) => range.or(other),
+        };
+        if let (Some(values), Some(other)) = (&mut self.values, other.values) {
+            values.merge(other);
+        }
+    }
+
+    /// Count `value` among the most frequent values, and widen the range to include it or
+    /// count it as NaN or invalid
+    fn add_value(&mut self, value: &ColumnValue) {
+        if let Some(values) = &mut self.values {
+            match *value {
+                ColumnValue::String(v) => values.add(v.to_string()),
+                ColumnValue::Bool(v) => values.add(v.to_string()),
+                ColumnValue::Byte(v) => values.add(v.to_string()),
+                ColumnValue::UByte(v) => values.add(v.to_string()),
+                ColumnValue::Short(v) => values.add(v.to_string()),
+                ColumnValue::UShort(v) => values.add(v.to_string()),
+                ColumnValue::Int(v) => values.add(v.to_string()),
+                ColumnValue::UInt(v) => values.add(v.to_string()),
+                ColumnValue::Long(v) => values.add(v.to_string()),
+                ColumnValue::ULong(v) => values.add(v.to_string()),
+                ColumnValue::Float(v) => values.add(v.to_string()),
+                ColumnValue::Double(v) => values.add(v.to_string()),
+                ColumnValue::DateTime(v) => values.add(v.to_string()),
+                // too long to list
+                ColumnValue::Json(_) | ColumnValue::Binary(_) => {}
+            }
+        }
+        let integer: Option<i128> = match *value {
+            ColumnValue::Byte(v) => Some(v.into()),
+            ColumnValue::UByte(v) => Some(v.into()),
+            ColumnValue::Short(v) => Some(v.into()),
+            ColumnValue::UShort(v) => Some(v.into()),
+            ColumnValue::Int(v) => Some(v.into()),
+            ColumnValue::UInt(v) => Some(v.into()),
+            ColumnValue::Long(v) => Some(v.into()),
+            ColumnValue::ULong(v) => Some(v.into()),
+            _ => None,
+        };
+        let float = match *value {
+            ColumnValue::Float(v) => Some(f64::from(v)),
+            ColumnValue::Double(v) => Some(v),
+            _ => None,
+        };
+        match (self.kind, integer, float, value) {
+            (ValueKind::Integer, Some(v), _, _) => {
+                self.range = Some(match self.range {
+                    Some(Range::Integer { min, max }) => Range::Integer {
+                        min: min.min(v),
+                        max: max.max(v),
+                    },
+                    _ => Range::Integer { min: v, max: v },
+                });
+            }
+            (ValueKind::Float, _, Some(v), _) if v.is_nan() => self.nans += 1,
+            (ValueKind::Float, _, Some(v), _) => {
+                self.range = Some(match self.range {
+                    Some(Range::Float { min, max }) => Range::Float {
+                        min: min.min(v),
+                        max: max.max(v),
+                    },
+                    _ => Range::Float { min: v, max: v },
+                });
+            }
+            (ValueKind::DateTime, _, _, ColumnValue::DateTime(text)) => {
+                let Some(instant) = parse_datetime(text) else {
+                    self.invalid += 1;
+                    return;
+                };
+                let value = (instant, text.to_string());
+                match &mut self.range {
+                    Some(Range::DateTime { earliest, latest }) => {
+                        if instant < earliest.0 {
+                            *earliest = value;
+                        } else if instant > latest.0 {
+                            *latest = value;
+                        }
+                    }
+                    range => {
+                        *range = Some(Range::DateTime {
+                            earliest: value.clone(),
+                            latest: value,
+                        })
+                    }
+                }
+            }
+            (ValueKind::Other, _, _, _) => {}
+            // which shouldn't happen, but a scan shouldn't fail over it when it does
+            _ => self.invalid += 1,
+        }
+    }
+}
+
+/// Milliseconds since 1970 for an ISO 8601 date-time like `2024-03-01T12:30:00.5+01:00`,
+/// or a date like `2024-03-01`. Without an offset the time is taken as UTC
+fn parse_datetime(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let (date, time) = match text.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (text, None),
+    };
+    // a leading minus is a year before 1 BC
+    let (sign, date) = match date.strip_prefix('-') {
+        Some(date) => (-1, date),
+        None => (1, date),
+    };
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = sign * parts.next()?.parse::<i64>().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let mut millis = days_from_civil(year, month, day) * 86_400_000;
+    if let Some(time) = time {
+        let (clock, offset) = if let Some(clock) = time.strip_suffix(['Z', 'z']) {
+            (clock, 0)
+        } else if let Some(i) = time.rfind(['+', '-']) {
+            let (clock, offset) = time.split_at(i);
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let (hours, minutes) = offset[1..].split_once(':').unwrap_or((&offset[1..], "0"));
+            let minutes = hours.parse::<i64>().ok()? * 60 + minutes.parse::<i64>().ok()?;
+            (clock, sign * minutes * 60_000)
+        } else {
+            (time, 0)
+        };
+        let mut fields = clock.splitn(3, ':');
+        let hours: i64 = fields.next()?.parse().ok()?;
+        let minutes: i64 = fields.next().unwrap_or("0").parse().ok()?;
+        let seconds: f64 = fields.next().unwrap_or("0").parse().ok()?;
+        if hours > 24 || minutes > 59 || !(0.0..61.0).contains(&seconds) {
+            return None;
+        }
+        millis += (hours * 60 + minutes) * 60_000 + (seconds * 1000.0).round() as i64 - offset;
+    }
+    Some(millis)
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar, from
+/// <http://howardhinnant.github.io/date_algorithms.html>
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Counts which columns each feature has a value for, and takes in their values
+struct StatsProcessor<'a> {
+    columns: &'a mut [ColumnStats],
+    /// The number of features read, including this one
+    feature: usize,
+}
+
+impl PropertyProcessor for StatsProcessor<'_> {
+    fn property(
+        &mut self,
+        idx: usize,
+        _name: &str,
+        value: &ColumnValue,
+    ) -> geozero::error::Result<bool> {
+        if let Some(column) = self.columns.get_mut(idx)
+            && column.counted_at != self.feature
+        {
+            column.present += 1;
+            column.counted_at = self.feature;
+            column.add_value(value);
+        }
+        // keep going through the rest of the properties
+        Ok(false)
+    }
+}
+
+/// How many values of a numeric column fall in each of a number of equal ranges
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    pub column: String,
+    pub min: f64,
+    pub max: f64,
+    pub counts: Vec<usize>,
+    /// Features without a value for the column
+    pub nulls: usize,
+    pub nans: usize,
+    /// Values outside `min` to `max`, when those were given rather than observed
+    pub outside: usize,
+}
+
+impl Histogram {
+    pub fn new(column: &str, min: f64, max: f64, bins: usize) -> Self {
+        Self {
+            column: column.to_string(),
+            min,
+            max,
+            counts: vec![0; bins.max(1)],
+            nulls: 0,
+            nans: 0,
+            outside: 0,
+        }
+    }
+
+    /// A histogram of `values`, from their smallest to their largest
+    pub fn of_values(column: &str, values: &[f64], bins: usize) -> Self {
+        let finite = values.iter().filter(|value| !value.is_nan());
+        let min = finite.clone().copied().reduce(f64::min).unwrap_or(0.0);
+        let max = finite.copied().reduce(f64::max).unwrap_or(0.0);
+        let mut histogram = Self::new(column, min, max, bins);
+        for &value in values {
+            histogram.add(value);
+        }
+        histogram
+    }
+
+    pub fn add(&mut self, value: f64) {
+        if value.is_nan() {
+            self.nans += 1;
+        } else if value < self.min || value > self.max {
+            self.outside += 1;
+        } else {
+            let bins = self.counts.len();
+            // the last bin includes the maximum; with a single value everything is in the first
+            let bin = match self.max - self.min {
+                width if width > 0.0 => ((value - self.min) / width * bins as f64) as usize,
+                _ => 0,
+            };
+            self.counts[bin.min(bins - 1)] += 1;
+        }
+    }
+
+    /// The lower and upper edges of bin `i`
+    pub fn edges(&self, i: usize) -> (f64, f64) {
+        let width = (self.max - self.min) / self.counts.len() as f64;
+        let upper = if i + 1 == self.counts.len() {
+            self.max
+        } else {
+            self.min + width * (i + 1) as f64
+        };
+        (self.min + width * i as f64, upper)
+    }
+
+    /// The range of bin `i`, like `[10, 20)`, or `[90, 100]` for the last
+    pub fn label(&self, i: usize) -> String {
+        let (lower, upper) = self.edges(i);
+        let close = if i + 1 == self.counts.len() { ']' } else { ')' };
+        format!("[{}, {}{close}", format_edge(lower), format_edge(upper))
+    }
+
+    /// The length of the bar for `count`, out of `width` for the largest count. With `log`
+    /// the lengths follow the logarithm of the counts, so that a long tail of small
+    /// counts still shows next to a large one
+    pub fn bar_length(&self, count: usize, width: usize, log: bool) -> usize {
+        let largest = self.counts.iter().copied().max().unwrap_or(0);
+        if largest == 0 {
+            return 0;
+        }
+        let fraction = if log {
+            (count as f64).ln_1p() / (largest as f64).ln_1p()
+        } else {
+            count as f64 / largest as f64
+        };
+        (fraction * width as f64).round() as usize
+    }
+
+    /// What was left out, like `12 nulls, 3 NaN excluded`, if anything was
+    pub fn excluded(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.nulls > 0 {
+            parts.push(format!("{} nulls", self.nulls));
+        }
+        if self.nans > 0 {
+            parts.push(format!("{} NaN", self.nans));
+        }
+        if self.outside > 0 {
+            parts.push(format!("{} outside the range", self.outside));
+        }
+        (!parts.is_empty()).then(|| format!("{} excluded", parts.join(", ")))
+    }
+
+    /// A line for each bin with its range, its count, and a bar of `#`, then what was
+    /// left out
+    pub fn text(&self, log: bool) -> Vec<String> {
+        const BAR_WIDTH: usize = 50;
+        let labels: Vec<String> = (0..self.counts.len()).map(|i| self.label(i)).collect();
+        let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+        let count_width = self
+            .counts
+            .iter()
+            .map(|count| count.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let mut lines: Vec<String> = labels
+            .iter()
+            .zip(&self.counts)
+            .map(|(label, &count)| {
+                let bar = "#".repeat(self.bar_length(count, BAR_WIDTH, log));
+                format!("{label:label_width$}  {count:>count_width$}  {bar}")
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+        lines.extend(self.excluded());
+        lines
+    }
+}
+
+/// A bin edge with at most four decimals, without trailing zeros
+fn format_edge(value: f64) -> String {
+    let text = format!("{value:.4}");
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// The value of the column at `index` in `feature` as a number, or `None` if the feature
+/// has no value for it. Values which aren't numbers are NaN
+pub fn feature_number(feature: &FgbFeature, index: usize) -> Result<Option<f64>, String> {
+    let mut processor = NumberProcessor { index, value: None };
+    feature
+        .process_properties(&mut processor)
+        .map_err(|e| format!("Failed to decode the properties of a feature: {e}"))?;
+    Ok(processor.value)
+}
+
+/// Takes the value of one column as a number
+struct NumberProcessor {
+    index: usize,
+    value: Option<f64>,
+}
+
+impl PropertyProcessor for NumberProcessor {
+    fn property(
+        &mut self,
+        idx: usize,
+        _name: &str,
+        value: &ColumnValue,
+    ) -> geozero::error::Result<bool> {
+        if idx != self.index {
+            return Ok(false);
+        }
+        self.value = Some(match *value {
+            ColumnValue::Byte(v) => v.into(),
+            ColumnValue::UByte(v) => v.into(),
+            ColumnValue::Short(v) => v.into(),
+            ColumnValue::UShort(v) => v.into(),
+            ColumnValue::Int(v) => v.into(),
+            ColumnValue::UInt(v) => v.into(),
+            ColumnValue::Long(v) => v as f64,
+            ColumnValue::ULong(v) => v as f64,
+            ColumnValue::Float(v) => v.into(),
+            ColumnValue::Double(v) => v,
+            _ => f64::NAN,
+        });
+        // that was the only value needed
+        Ok(true)
+    }
+}
+
+/// The index of the column `name` in `header`, if it is numeric
+pub fn numeric_column(header: &flatgeobuf::Header, name: &str) -> Result<usize, String> {
+    let columns = header.columns().unwrap_or_default();
+    let (index, column) = columns
+        .iter()
+        .enumerate()
+        .find(|(_, column)| column.name() == name)
+        .ok_or_else(|| format!("There is no column named {name}"))?;
+    match ValueKind::of(column.type_().variant_name().unwrap_or_default()) {
+        ValueKind::Integer | ValueKind::Float => Ok(index),
+        _ => Err(format!("{name} isn't a numeric column")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flatgeobuf::{FallibleStreamingIterator, FgbReader};
+
+    /// Read every feature of `testdata/colorado_subset.fgb` into `add`, after making what
+    /// they're added to from the header with `new`
+    fn read_fixture<T>(
+        new: impl FnOnce(&flatgeobuf::Header) -> T,
+        mut add: impl FnMut(&mut T, &FgbFeature),
+    ) -> T {
+        let file = std::fs::File::open("testdata/colorado_subset.fgb").unwrap();
+        let fgb = FgbReader::open(std::io::BufReader::new(file)).unwrap();
+        let mut state = new(&fgb.header());
+        let mut features = fgb.select_all().unwrap();
+        while let Some(feature) = features.next().unwrap() {
+            add(&mut state, feature);
+        }
+        state
+    }
+
+    #[test]
+    fn files_sorted_from_the_end_of_the_curve_back_are_sorted() {
+        // written by flatgeobuf, which sorts features in descending Hilbert order
+        let sortedness = read_fixture(
+            |header| Sortedness::new(header).unwrap(),
+            |sortedness, feature| sortedness.add(feature.geometry().as_ref()),
+        );
+        assert_eq!(sortedness.pairs, 369);
+        assert_eq!(sortedness.verdict(), "yes");
+        assert!(sortedness.descending());
+        assert_eq!(
+            sortedness.text(),
+            "yes (1.00 of 369 adjacent pairs in reverse Hilbert order)"
+        );
+        assert_eq!(sortedness.to_json()["direction"], "descending");
+    }
+
+    #[test]
+    fn sortedness_counts_both_directions() {
+        let mut sortedness = Sortedness {
+            extent: [0.0, 0.0, 1.0, 1.0],
+            first: None,
+            previous: None,
+            pairs: 0,
+            ascending: 0,
+            descending: 0,
+        };
+        for (previous, code) in [(1, 2), (2, 2), (2, 5), (5, 3)] {
+            sortedness.add_pair(previous, code);
+        }
+        assert_eq!(
+            (
+                sortedness.pairs,
+                sortedness.ascending,
+                sortedness.descending
+            ),
+            (4, 3, 2)
+        );
+        assert_eq!(sortedness.score(), Some(0.75));
+        assert_eq!(sortedness.verdict(), "mostly");
+        assert!(!sortedness.descending());
+    }
+
+    #[test]
+    fn datetimes_with_offsets_and_dates() {
+        assert_eq!(parse_datetime("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_datetime("1970-01-01"), Some(0));
+        assert_eq!(parse_datetime("1970-01-02"), Some(86_400_000));
+        // without an offset, UTC
+        assert_eq!(parse_datetime("1970-01-01T01:00:00"), Some(3_600_000));
+        assert_eq!(parse_datetime("1970-01-01 01:00"), Some(3_600_000));
+        // the same instant, written with different offsets
+        let instant = parse_datetime("2024-03-01T12:30:00Z").unwrap();
+        assert_eq!(parse_datetime("2024-03-01T13:30:00+01:00"), Some(instant));
+        assert_eq!(parse_datetime("2024-03-01T07:30:00-05:00"), Some(instant));
+        assert_eq!(parse_datetime("2024-03-01T07:30:00-05"), Some(instant));
+        assert_eq!(
+            parse_datetime("2024-03-01T12:30:00.5z"),
+            Some(instant + 500)
+        );
+        // a leap day, and a year before 1 BC
+        assert_eq!(
+            parse_datetime("2024-03-01").unwrap() - parse_datetime("2024-02-28").unwrap(),
+            2 * 86_400_000
+        );
+        assert!(parse_datetime("-0001-01-01").unwrap() < parse_datetime("0001-01-01").unwrap());
+    }
+
+    #[test]
+    fn datetimes_which_cant_be_parsed() {
+        for text in [
+            "",
+            "yesterday",
+            "2024",
+            "2024-03",
+            "2024-13-01",
+            "2024-03-32",
+            "2024-03-01T25:00",
+            "2024-03-01T12:60",
+            "2024-03-01T12:30:00+one",
+            "2024-03-01Tnoon",
+        ] {
+            assert_eq!(parse_datetime(text), None, "{text}");
+        }
+    }
+
+    /// The statistics of a column of `column_type` which has had `values`
+    fn column_of(column_type: &str, values: &[ColumnValue]) -> ColumnStats {
+        let mut column = ColumnStats {
+            name: "column".to_string(),
+            column_type: column_type.to_string(),
+            present: 0,
+            range: None,
+            nans: 0,
+            invalid: 0,
+            values: ValueKind::counts_values(column_type).then(ValueCounts::default),
+            kind: ValueKind::of(column_type),
+            counted_at: 0,
+        };
+        for value in values {
+            column.add_value(value);
+        }
+        column
+    }
+
+    #[test]
+    fn nans_and_invalid_values_are_left_out_of_the_range() {
+        let column = column_of(
+            "Double",
+            &[
+                ColumnValue::Double(2.5),
+                ColumnValue::Double(f64::NAN),
+                ColumnValue::Double(-1.0),
+                ColumnValue::Double(f64::NAN),
+                // not the type the header gives
+                ColumnValue::String("3"),
+            ],
+        );
+        assert_eq!(
+            column.range,
+            Some(Range::Float {
+                min: -1.0,
+                max: 2.5
+            })
+        );
+        assert_eq!((column.nans, column.invalid), (2, 1));
+        assert_eq!(column.notes(), "2 NaN, 1 invalid");
+
+        let column = column_of(
+            "DateTime",
+            &[
+                ColumnValue::DateTime("2024-03-01T12:00:00+01:00"),
+                ColumnValue::DateTime("not a date"),
+                ColumnValue::DateTime("2024-03-01T11:30:00Z"),
+                ColumnValue::DateTime("2023-12-31"),
+            ],
+        );
+        // compared by instant, but kept as written
+        assert_eq!(
+            column.range.as_ref().map(Range::bounds),
+            Some(("2023-12-31".to_string(), "2024-03-01T11:30:00Z".to_string()))
+        );
+        assert_eq!((column.nans, column.invalid), (0, 1));
+        assert_eq!(column.notes(), "1 invalid");
+
+        let column = column_of("Int", &[ColumnValue::Int(7), ColumnValue::Int(-3)]);
+        assert_eq!(column.range, Some(Range::Integer { min: -3, max: 7 }));
+        assert_eq!(column.notes(), "");
+    }
+
+    #[test]
+    fn histogram_bins_include_their_lower_edge_and_the_last_its_upper() {
+        let mut histogram = Histogram::new("value", 0.0, 10.0, 5);
+        for value in [0.0, 1.999, 2.0, 5.0, 9.999, 10.0, -0.1, 10.1, f64::NAN] {
+            histogram.add(value);
+        }
+        assert_eq!(histogram.counts, [2, 1, 1, 0, 2]);
+        assert_eq!((histogram.outside, histogram.nans), (2, 1));
+        assert_eq!(histogram.edges(0), (0.0, 2.0));
+        assert_eq!(histogram.edges(4), (8.0, 10.0));
+        assert_eq!(histogram.label(0), "[0, 2)");
+        assert_eq!(histogram.label(4), "[8, 10]");
+        histogram.nulls = 3;
+        assert_eq!(
+            histogram.excluded().as_deref(),
+            Some("3 nulls, 1 NaN, 2 outside the range excluded")
+        );
+    }
+
+    #[test]
+    fn histograms_of_values_span_them() {
+        let histogram = Histogram::of_values("value", &[3.0, f64::NAN, 1.0, 2.0], 4);
+        assert_eq!((histogram.min, histogram.max), (1.0, 3.0));
+        assert_eq!(histogram.counts, [1, 0, 1, 1]);
+        assert_eq!(histogram.label(1), "[1.5, 2)");
+        assert_eq!(histogram.nans, 1);
+        // a single value goes in the first bin
+        let histogram = Histogram::of_values("value", &[4.0, 4.0], 3);
+        assert_eq!(histogram.counts, [2, 0, 0]);
+        assert_eq!(histogram.label(2), "[4, 4]");
+    }
+
+    #[test]
+    fn histogram_bars_on_a_log_scale() {
+        let histogram = Histogram {
+            counts: vec![1000, 10, 0, 1],
+            ..Histogram::new("value", 0.0, 4.0, 4)
+        };
+        let bars = |log| -> Vec<usize> {
+            histogram
+                .counts
+                .iter()
+                .map(|&count| histogram.bar_length(count, 50, log))
+                .collect()
+        };
+        assert_eq!(bars(false), [50, 1, 0, 0]);
+        // ln(1 + count) / ln(1001), so that the tail still shows
+        assert_eq!(bars(true), [50, 17, 0, 5]);
+        assert_eq!(
+            histogram.text(true),
+            [
+                "[0, 1)  1000  ##################################################",
+                "[1, 2)    10  #################",
+                "[2, 3)     0",
+                "[3, 4]     1  #####",
+            ]
+        );
+    }
+
+    #[test]
+    fn value_counts_merge_over_the_limit_as_one_pass_counts() {
+        // a run with most of the distinct values counted, and one after it with as many
+        // again, half of them new, which go past the limit together
+        let mut first = Vec::new();
+        first.extend(std::iter::repeat_n("common".to_string(), 50));
+        first.extend(std::iter::repeat_n("early".to_string(), 20));
+        first.extend((0..8_000).map(|i| format!("v{i}")));
+        let mut second = Vec::new();
+        second.extend(std::iter::repeat_n("late".to_string(), 40));
+        second.extend((4_000..12_000).map(|i| format!("v{i}")));
+        second.extend(std::iter::repeat_n("common".to_string(), 30));
+
+        let count = |values: &[String]| {
+            let mut counts = ValueCounts::default();
+            for value in values {
+                counts.add(value.clone());
+            }
+            counts
+        };
+        let one_pass = count(&[first.clone(), second.clone()].concat());
+        let mut merged = count(&first);
+        let second = count(&second);
+        assert!(merged.complete() && second.complete());
+        merged.merge(second);
+
+        // 8002 values before the second run, which adds `late` and then v8000 to v9996
+        // before the limit, leaving v9997 to v11999 uncounted
+        assert_eq!(one_pass.other, 2_003);
+        assert_eq!(merged.other, one_pass.other);
+        assert!(!merged.complete());
+        let top = merged.top(5);
+        assert_eq!(
+            top,
+            [
+                ("common", 80),
+                ("late", 40),
+                ("early", 20),
+                ("v4000", 2),
+                ("v4001", 2)
+            ]
+        );
+        assert_eq!(top, one_pass.top(5));
+        assert_eq!(merged.top(usize::MAX), one_pass.top(usize::MAX));
+        assert_eq!(merged.summary(2), "common (80), late (40), …");
+    }
+}
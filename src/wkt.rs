@@ -0,0 +1,143 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+/// One line of pretty-printed WKT: a keyword, its opening bracket, and
+/// everything up to the next nested keyword
+#[derive(Debug, PartialEq)]
+pub struct WktLine {
+    /// How many brackets the keyword is nested in
+    pub depth: usize,
+    pub keyword: String,
+    pub rest: String,
+}
+
+/// Split WKT onto one line per keyword, tracking bracket nesting so the lines can be
+/// indented. This only tokenizes the text, so it works for WKT1 and WKT2 alike and
+/// tolerates invalid WKT rather than rejecting it
+pub fn pretty_print(wkt: &str) -> Vec<WktLine> {
+    let mut lines: Vec<WktLine> = Vec::new();
+    let mut depth = 0;
+    let mut word = String::new();
+
+    // anything which doesn't start a keyword goes on the end of the latest line
+    let push_rest = |lines: &mut Vec<WktLine>, depth: usize, text: &str| match lines.last_mut() {
+        Some(line) => line.rest.push_str(text),
+        None => lines.push(WktLine {
+            depth,
+            keyword: String::new(),
+            rest: text.to_string(),
+        }),
+    };
+
+    let mut chars = wkt.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                word.push('"');
+                // quotes inside a string are escaped by doubling them
+                while let Some(c) = chars.next() {
+                    word.push(c);
+                    if c == '"' {
+                        if chars.peek() == Some(&'"') {
+                            word.push('"');
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+            }
+            '[' | '(' => {
+                lines.push(WktLine {
+                    depth,
+                    keyword: std::mem::take(&mut word),
+                    rest: c.to_string(),
+                });
+                depth += 1;
+            }
+            ']' | ')' | ',' => {
+                word.push(c);
+                push_rest(&mut lines, depth, &word);
+                word.clear();
+                if c != ',' {
+                    depth = depth.saturating_sub(1);
+                }
+            }
+            // whitespace between tokens isn't significant
+            c if c.is_whitespace() => {}
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        push_rest(&mut lines, depth, &word);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each line as its indentation, keyword and the rest, the way the Metadata tab shows them
+    fn lines(wkt: &str) -> Vec<String> {
+        pretty_print(wkt)
+            .into_iter()
+            .map(|line| format!("{}{}{}", "  ".repeat(line.depth), line.keyword, line.rest))
+            .collect()
+    }
+
+    #[test]
+    fn keywords_go_on_lines_of_their_own_by_nesting() {
+        let wkt = r#"GEOGCRS["WGS 84",
+            DATUM["World Geodetic System 1984", ELLIPSOID["WGS 84",6378137,298.257223563]],
+            CS[ellipsoidal,2],
+            ID["EPSG",4326]]"#;
+        assert_eq!(
+            lines(wkt),
+            [
+                r#"GEOGCRS["WGS 84","#,
+                r#"  DATUM["World Geodetic System 1984","#,
+                r#"    ELLIPSOID["WGS 84",6378137,298.257223563]],"#,
+                "  CS[ellipsoidal,2],",
+                r#"  ID["EPSG",4326]]"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn brackets_and_quotes_inside_strings_are_text() {
+        let wkt = r#"PROJCS["NAD83 / UTM [zone 13N] ""north""",UNIT["metre",1]]"#;
+        assert_eq!(
+            lines(wkt),
+            [
+                r#"PROJCS["NAD83 / UTM [zone 13N] ""north""","#,
+                r#"  UNIT["metre",1]]"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn parentheses_nest_like_brackets() {
+        assert_eq!(
+            lines("GEOGCS(\"WGS 84\",PRIMEM(\"Greenwich\",0))"),
+            ["GEOGCS(\"WGS 84\",", "  PRIMEM(\"Greenwich\",0))"]
+        );
+    }
+
+    #[test]
+    fn invalid_wkt_is_tolerated() {
+        // text before any keyword, and more closing brackets than opening ones
+        assert_eq!(
+            pretty_print("EPSG:4326"),
+            [WktLine {
+                depth: 0,
+                keyword: String::new(),
+                rest: "EPSG:4326".to_string(),
+            }]
+        );
+        assert_eq!(lines("A[1]]],B[2"), ["A[1]]],", "B[2"]);
+        // an unterminated string takes the rest of the text
+        assert_eq!(lines(r#"A["open,B[1]"#), [r#"A["open,B[1]"#]);
+        assert!(pretty_print("").is_empty());
+    }
+}
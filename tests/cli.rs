@@ -0,0 +1,469 @@
+// Copyright 2026 Colton Loftus
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    io::{Read, Write},
+    process::{Command, Output},
+    sync::{Arc, mpsc::Receiver},
+    time::{Duration, Instant},
+};
+
+fn fgbdump(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_fgbdump"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn reproject_bbox_between_the_map_crss() {
+    let bbox = "-109.06,36.99,-102.04,41";
+    let output = fgbdump(&[
+        "reproject-bbox",
+        "--bbox",
+        bbox,
+        "--from",
+        "EPSG:4326",
+        "--to",
+        "OGC:CRS84",
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{bbox}\n"));
+}
+
+#[test]
+fn reproject_bbox_formats() {
+    let args = |format| {
+        fgbdump(&[
+            "reproject-bbox",
+            "--bbox",
+            "1,2,3,4",
+            "--from",
+            "EPSG:4326",
+            "--to",
+            "EPSG:4326",
+            "--format",
+            format,
+        ])
+    };
+    let json: serde_json::Value = serde_json::from_str(&stdout(&args("json"))).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({"xmin": 1.0, "ymin": 2.0, "xmax": 3.0, "ymax": 4.0})
+    );
+    let geojson: serde_json::Value = serde_json::from_str(&stdout(&args("geojson"))).unwrap();
+    assert_eq!(geojson["geometry"]["type"], "Polygon");
+    assert_eq!(
+        geojson["geometry"]["coordinates"][0],
+        serde_json::json!([[1.0, 2.0], [3.0, 2.0], [3.0, 4.0], [1.0, 4.0], [1.0, 2.0]])
+    );
+    let output = args("xml");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("unknown format 'xml'"));
+}
+
+#[test]
+fn reproject_bbox_rejects_a_malformed_bbox() {
+    let output = fgbdump(&[
+        "reproject-bbox",
+        "--bbox",
+        "1,2,3",
+        "--from",
+        "EPSG:4326",
+        "--to",
+        "EPSG:4326",
+    ]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("expected four numbers as xmin,ymin,xmax,ymax"));
+}
+
+#[test]
+fn reproject_bbox_errors_in_one_line() {
+    let output = fgbdump(&[
+        "reproject-bbox",
+        "--bbox",
+        "0,0,1,1",
+        "--from",
+        "EPSG:garbage",
+        "--to",
+        "EPSG:4326",
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = stderr(&output);
+    assert!(stderr.starts_with("Failed to project the bbox: "));
+    assert_eq!(stderr.lines().count(), 1, "{stderr}");
+}
+
+#[cfg(feature = "proj")]
+#[test]
+fn reproject_bbox_from_web_mercator() {
+    let output = fgbdump(&[
+        "reproject-bbox",
+        "--bbox",
+        "-12140503.7,4439106.8,-11359040.8,5012341.7",
+        "--from",
+        "EPSG:3857",
+        "--to",
+        "EPSG:4326",
+        "--format",
+        "json",
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    for (key, expected) in [
+        ("xmin", -109.06),
+        ("ymin", 37.0),
+        ("xmax", -102.04),
+        ("ymax", 41.0),
+    ] {
+        let value = json[key].as_f64().unwrap();
+        assert!((value - expected).abs() < 1e-6, "{key}: {value}");
+    }
+}
+
+/// A stand-in for a server or proxy which answers every request with a range of `file`,
+/// recording each request it's sent
+fn range_server(file: &'static str) -> (std::net::SocketAddr, Receiver<String>) {
+    slow_range_server(file, Duration::ZERO)
+}
+
+/// Like [`range_server`], waiting `delay` before each response and answering requests for
+/// paths with `missing` in them with a 404. Connections are answered at the same time
+fn slow_range_server(
+    file: &'static str,
+    delay: Duration,
+) -> (std::net::SocketAddr, Receiver<String>) {
+    let data = Arc::new(std::fs::read(file).unwrap());
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, requests) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let (data, sender) = (data.clone(), sender.clone());
+            std::thread::spawn(move || {
+                let mut stream = stream.unwrap();
+                let request = read_request(&mut stream);
+                std::thread::sleep(delay);
+                let _ = stream.write_all(&respond(&request, &data));
+                let _ = sender.send(request);
+            });
+        }
+    });
+    (address, requests)
+}
+
+fn read_request(stream: &mut impl Read) -> String {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.ends_with(b"\r\n\r\n") {
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => request.extend_from_slice(&buf[..read]),
+        }
+    }
+    String::from_utf8(request).unwrap()
+}
+
+/// The response to `request` for a range of `data`, or all of it without a range
+fn respond(request: &str, data: &[u8]) -> Vec<u8> {
+    if request
+        .lines()
+        .next()
+        .is_some_and(|line| line.contains("missing"))
+    {
+        return b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            .to_vec();
+    }
+    let range = request.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        let (start, end) = name
+            .eq_ignore_ascii_case("range")
+            .then(|| value.trim().strip_prefix("bytes="))??
+            .split_once('-')?;
+        let start: usize = start.parse().ok()?;
+        let end = end
+            .parse::<usize>()
+            .map_or(data.len() - 1, |end| end.min(data.len() - 1));
+        Some(start..end + 1)
+    });
+    match range {
+        Some(range) => {
+            let mut response = format!(
+                "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\n\
+                 Content-Length: {}\r\n{VALIDATORS}Connection: close\r\n\r\n",
+                range.start,
+                range.end - 1,
+                data.len(),
+                range.len()
+            )
+            .into_bytes();
+            response.extend_from_slice(&data[range]);
+            response
+        }
+        None => format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nAccept-Ranges: bytes\r\n\
+             {VALIDATORS}Connection: close\r\n\r\n",
+            data.len()
+        )
+        .into_bytes(),
+    }
+}
+
+/// The ETag and Last-Modified headers of the file [`range_server`] serves
+const VALIDATORS: &str = "ETag: \"fgbdump\"\r\nLast-Modified: Fri, 16 Oct 2026 00:00:00 GMT\r\n";
+
+#[test]
+fn remote_files_are_read_through_the_proxy() {
+    let (address, requests) = range_server("testdata/colorado_subset.fgb");
+    let proxy = format!("http://user:pass@{address}");
+    // a host which can't resolve, so the file can only come through the proxy
+    let url = "http://fgbdump.invalid/colorado_subset.fgb";
+    let output = fgbdump(&["--stdout", "--no-cache", "--proxy", &proxy, url]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Features:                       370"));
+
+    let requests: Vec<String> = requests.try_iter().collect();
+    assert!(!requests.is_empty());
+    for request in requests {
+        let request_line = request.lines().next().unwrap();
+        // proxies are sent the whole URL, not just the path
+        assert!(
+            request_line.ends_with(&format!("{url} HTTP/1.1")),
+            "{request_line}"
+        );
+        // with the credentials from the proxy URL, base64 encoded
+        assert!(
+            request
+                .to_lowercase()
+                .contains("proxy-authorization: basic dxnlcjpwyxnz"),
+            "{request}"
+        );
+    }
+}
+
+#[cfg(feature = "gcs")]
+#[test]
+fn gcs_files_are_read_through_object_store() {
+    let (address, requests) = range_server("testdata/colorado_subset.fgb");
+    // how the object_store crate is pointed at an emulator, without signing in
+    let key = format!(
+        r#"{{"gcs_base_url": "http://{address}", "disable_oauth": true,
+        "client_email": "", "private_key": "", "private_key_id": ""}}"#
+    );
+    let output = Command::new(env!("CARGO_BIN_EXE_fgbdump"))
+        .args([
+            "--stdout",
+            "--no-cache",
+            "gs://bucket/dir/colorado_subset.fgb",
+        ])
+        .env("GOOGLE_SERVICE_ACCOUNT_KEY", key)
+        .env("GOOGLE_ALLOW_HTTP", "true")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Features:                       370"));
+    for request in requests.try_iter() {
+        let request_line = request.lines().next().unwrap();
+        // object names are percent encoded whole, slashes included
+        assert!(
+            request_line.contains(" /bucket/dir%2Fcolorado%5Fsubset%2Efgb "),
+            "{request_line}"
+        );
+    }
+}
+
+#[cfg(feature = "azure")]
+#[test]
+fn azure_files_are_read_through_object_store() {
+    let (address, requests) = range_server("testdata/colorado_subset.fgb");
+    let output = Command::new(env!("CARGO_BIN_EXE_fgbdump"))
+        .args([
+            "--stdout",
+            "--no-cache",
+            "az://container/dir/colorado_subset.fgb",
+        ])
+        .env("AZURE_STORAGE_USE_EMULATOR", "true")
+        .env("AZURITE_BLOB_STORAGE_URL", format!("http://{address}"))
+        .env("AZURE_ALLOW_HTTP", "true")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Features:                       370"));
+    for request in requests.try_iter() {
+        let request_line = request.lines().next().unwrap();
+        // the emulator's account comes first in the path
+        assert!(
+            request_line.contains(" /devstoreaccount1/container/dir/colorado_subset.fgb "),
+            "{request_line}"
+        );
+    }
+}
+
+#[test]
+fn files_are_opened_concurrently_in_order() {
+    const DELAY: Duration = Duration::from_millis(200);
+    let (address, _requests) = slow_range_server("testdata/colorado_subset.fgb", DELAY);
+    let urls: Vec<String> = ["a", "b", "missing", "c", "d"]
+        .iter()
+        .map(|name| format!("http://{address}/{name}.fgb"))
+        .collect();
+    let run = |urls: &[String], concurrency: &str| {
+        let started = Instant::now();
+        let mut args = vec!["--stdout", "--no-cache", "--concurrency", concurrency];
+        args.extend(urls.iter().map(String::as_str));
+        let output = fgbdump(&args);
+        (output, started.elapsed())
+    };
+
+    let (one, single) = run(&urls[..1], "8");
+    assert!(one.status.success(), "{}", stderr(&one));
+
+    let (output, concurrent) = run(&urls, "8");
+    // the missing file is reported, but doesn't stop the others
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("missing.fgb"),
+        "{}",
+        stderr(&output)
+    );
+    let stdout = stdout(&output);
+    let printed: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("http://"))
+        .collect();
+    // each under its URL in the order given, the missing file with nothing under it
+    let expected: Vec<String> = urls.iter().map(|url| format!("{url}:")).collect();
+    assert_eq!(printed, expected);
+    assert_eq!(
+        stdout
+            .matches("Features:                       370")
+            .count(),
+        4
+    );
+
+    let (_, serial) = run(&urls, "1");
+    assert!(serial > single * 3, "{serial:?} against {single:?} for one");
+    // about as long as the slowest file, rather than as long as all of them, measured
+    // against reading them one at a time since other tests run alongside this one
+    assert!(
+        concurrent * 2 < serial,
+        "{concurrent:?} against {serial:?} one at a time"
+    );
+}
+
+#[test]
+fn export_writes_flatgeobuf_with_the_selected_columns() {
+    use flatgeobuf::{FallibleStreamingIterator, FgbReader};
+
+    let output = std::env::temp_dir().join(format!("fgbdump-export-{}.fgb", std::process::id()));
+    let _ = std::fs::remove_file(&output);
+    let run = fgbdump(&[
+        "export",
+        "--file",
+        "testdata/colorado_subset.fgb",
+        "--output",
+        output.to_str().unwrap(),
+        "--select",
+        "geoconnex_url",
+        "--select",
+        "Catchment_featureid",
+    ]);
+    assert!(run.status.success(), "{}", stderr(&run));
+
+    let read = |path: &std::path::Path| {
+        let fgb = FgbReader::open(std::fs::File::open(path).unwrap()).unwrap();
+        let header = fgb.header();
+        let columns: Vec<String> = header
+            .columns()
+            .unwrap()
+            .iter()
+            .map(|column| format!("{} {:?}", column.name(), column.type_()))
+            .collect();
+        let crs = header.crs().map(|crs| crs.code());
+        let mut ids = Vec::new();
+        let mut features = fgb.select_all_seq().unwrap();
+        while let Some(feature) = features.next().unwrap() {
+            let id: f64 =
+                geozero::FeatureProperties::property(feature, "Catchment_featureid").unwrap();
+            ids.push(id as u64);
+        }
+        ids.sort();
+        (columns, crs, ids)
+    };
+    let (columns, crs, ids) = read(&output);
+    let (_, original_crs, original_ids) =
+        read(std::path::Path::new("testdata/colorado_subset.fgb"));
+    std::fs::remove_file(&output).unwrap();
+    // in the order of the file, and with their types
+    assert_eq!(
+        columns,
+        ["Catchment_featureid Double", "geoconnex_url String"]
+    );
+    assert_eq!(crs, original_crs);
+    assert_eq!(ids.len(), 370);
+    assert_eq!(ids, original_ids);
+}
+
+#[test]
+fn an_export_which_fails_leaves_no_output() {
+    let output = std::env::temp_dir().join(format!("fgbdump-failed-{}.json", std::process::id()));
+    let export = |file: &str| {
+        fgbdump(&[
+            "export",
+            "--file",
+            file,
+            "--output",
+            output.to_str().unwrap(),
+        ])
+    };
+    let _ = std::fs::remove_file(&output);
+    let run = export("testdata/missing.fgb");
+    assert_eq!(run.status.code(), Some(1));
+    assert_eq!(stderr(&run).lines().count(), 1, "{}", stderr(&run));
+    assert!(!output.exists());
+
+    // nor touches a file already there
+    std::fs::write(&output, "kept").unwrap();
+    let run = export("testdata/colorado_subset.fgb");
+    let kept = std::fs::read_to_string(&output).unwrap();
+    std::fs::remove_file(&output).unwrap();
+    assert_eq!(run.status.code(), Some(1));
+    assert_eq!(
+        stderr(&run),
+        format!(
+            "{} already exists; pass --force to overwrite it\n",
+            output.display()
+        )
+    );
+    assert_eq!(kept, "kept");
+}
+
+#[test]
+fn stdout_prints_readable_sections() {
+    let output = fgbdump(&["--stdout", "--ascii", "testdata/colorado_subset.fgb"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        std::fs::read_to_string("testdata/colorado_subset.txt").unwrap()
+    );
+
+    // the raw header, for anyone who relied on it
+    let output = fgbdump(&[
+        "--stdout",
+        "--format",
+        "debug",
+        "testdata/colorado_subset.fgb",
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = stdout(&output);
+    assert!(stdout.starts_with("Header {"), "{stdout}");
+    assert!(stdout.contains("index_node_size: 16"), "{stdout}");
+}